@@ -0,0 +1,166 @@
+//! Admin interface for operations that are triggered by the network rather
+//! than by a UE or gNB.
+//!
+//! Commands are sent as single-line text datagrams to the admin address, and
+//! each one is answered with `OK` or `ERROR <reason>`:
+//!
+//! ```text
+//! deregister <AMF_UE_NGAP_ID> [reregister] [cause <5GMM cause>]
+//! reset <gNB ID> [<AMF_UE_NGAP_ID>...]
+//! update-configuration
+//! ```
+
+use log::{debug, info, warn};
+use std::net::UdpSocket;
+use std::str::FromStr;
+
+use crate::{config, ngap_handlers, store};
+
+#[cfg(test)]
+mod tests;
+
+const BUFFER_LEN: usize = 256;
+
+/// A command received on the admin interface.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// De-register a UE, optionally asking it to register again
+    Deregister {
+        amf_ue_ngap_id: u64,
+        re_registration_required: bool,
+        cause: Option<nas::MobilityManagementCause>,
+    },
+    /// Reset the NG interface towards a gNB, or only the connections of the
+    /// given UEs through it
+    Reset {
+        gnb_id: u32,
+        amf_ue_ngap_ids: Vec<u64>,
+    },
+    /// Send the configuration of the AMF to every gNB
+    UpdateConfiguration,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("deregister") => {
+                let amf_ue_ngap_id = words
+                    .next()
+                    .ok_or("missing AMF_UE_NGAP_ID")?
+                    .parse()
+                    .map_err(|_| "invalid AMF_UE_NGAP_ID")?;
+                let mut re_registration_required = false;
+                let mut cause = None;
+                while let Some(word) = words.next() {
+                    match word {
+                        "reregister" => re_registration_required = true,
+                        "cause" => {
+                            let value = words.next().ok_or("missing 5GMM cause")?;
+                            let value = value.parse().map_err(|_| "invalid 5GMM cause")?;
+                            cause = Some(
+                                nas::MobilityManagementCause::from_u8(value)
+                                    .ok_or("unknown 5GMM cause")?,
+                            );
+                        }
+                        other => return Err(format!("unknown option {}", other)),
+                    }
+                }
+                Ok(Command::Deregister {
+                    amf_ue_ngap_id,
+                    re_registration_required,
+                    cause,
+                })
+            }
+            Some("reset") => {
+                let gnb_id = words
+                    .next()
+                    .ok_or("missing gNB ID")?
+                    .parse()
+                    .map_err(|_| "invalid gNB ID")?;
+                let amf_ue_ngap_ids = words
+                    .map(|word| word.parse().map_err(|_| "invalid AMF_UE_NGAP_ID"))
+                    .collect::<Result<_, _>>()?;
+                Ok(Command::Reset {
+                    gnb_id,
+                    amf_ue_ngap_ids,
+                })
+            }
+            Some("update-configuration") => match words.next() {
+                Some(other) => Err(format!("unknown option {}", other)),
+                None => Ok(Command::UpdateConfiguration),
+            },
+            Some(other) => Err(format!("unknown command {}", other)),
+            None => Err("empty command".to_string()),
+        }
+    }
+}
+
+/// Carry out a command, returning the NGAP messages it sends.
+pub fn execute(
+    config: &config::CoreKubeConfig,
+    store: &store::Store,
+    command: Command,
+) -> Result<Vec<ngap_handlers::NGAPResponse>, String> {
+    match command {
+        Command::Deregister {
+            amf_ue_ngap_id,
+            re_registration_required,
+            cause,
+        } => ngap_handlers::deregister_ue(store, amf_ue_ngap_id, re_registration_required, cause)
+            .ok_or_else(|| format!("unknown UE {}", amf_ue_ngap_id)),
+        Command::Reset {
+            gnb_id,
+            amf_ue_ngap_ids,
+        } => {
+            let gnb = store
+                .find_gnb_by_id(gnb_id)
+                .ok_or_else(|| format!("unknown gNB {}", gnb_id))?;
+            ngap_handlers::reset_gnb(config, store, &gnb.address, &amf_ue_ngap_ids)
+                .ok_or_else(|| format!("no given UE is connected through gNB {}", gnb_id))
+        }
+        Command::UpdateConfiguration => Ok(ngap_handlers::update_amf_configuration(config, store)),
+    }
+}
+
+/// Serve admin commands from `socket`, sending the resulting NGAP messages
+/// to the gNBs through `ngap_socket`.
+pub fn run(
+    config: &crate::reload::ConfigHandle,
+    store: &store::Store,
+    socket: &UdpSocket,
+    ngap_socket: &UdpSocket,
+) {
+    loop {
+        let mut buf = [0; BUFFER_LEN];
+        let (size, src) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) => {
+                warn!("Admin interface could not receive: {}", e);
+                continue;
+            }
+        };
+        let line = String::from_utf8_lossy(&buf[..size]);
+        debug!("Admin command from {}: {}", src, line.trim());
+
+        let result = line
+            .parse()
+            .and_then(|command| execute(&config.load(), store, command));
+        let reply = match result {
+            Ok(responses) => {
+                let responses = crate::encode_responses(responses);
+                crate::send_responses(store, ngap_socket, None, responses);
+                "OK".to_string()
+            }
+            Err(reason) => {
+                info!("Admin command failed: {}", reason);
+                format!("ERROR {}", reason)
+            }
+        };
+        if let Err(e) = socket.send_to(reply.as_bytes(), src) {
+            warn!("Admin interface could not reply to {}: {}", src, e);
+        }
+    }
+}
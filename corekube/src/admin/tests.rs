@@ -0,0 +1,99 @@
+use super::*;
+use bitvec::field::BitField;
+
+#[test]
+fn test_parse_deregister() {
+    assert_eq!(
+        "deregister 7".parse(),
+        Ok(Command::Deregister {
+            amf_ue_ngap_id: 7,
+            re_registration_required: false,
+            cause: None,
+        })
+    );
+    assert_eq!(
+        "deregister 7 reregister cause 9\n".parse(),
+        Ok(Command::Deregister {
+            amf_ue_ngap_id: 7,
+            re_registration_required: true,
+            cause: Some(nas::MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED),
+        })
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    assert!("".parse::<Command>().is_err());
+    assert!("reboot".parse::<Command>().is_err());
+    assert!("deregister".parse::<Command>().is_err());
+    assert!("deregister 7 cause".parse::<Command>().is_err());
+    assert!("deregister 7 cause 4".parse::<Command>().is_err());
+    assert!("deregister 7 soon".parse::<Command>().is_err());
+}
+
+#[test]
+fn test_execute_unknown_ue() {
+    let store = store::Store::default();
+    let command = Command::Deregister {
+        amf_ue_ngap_id: 7,
+        re_registration_required: false,
+        cause: None,
+    };
+    assert!(execute(&config::CoreKubeConfig::default(), &store, command).is_err());
+}
+
+#[test]
+fn test_parse_reset() {
+    assert_eq!(
+        "reset 1".parse(),
+        Ok(Command::Reset {
+            gnb_id: 1,
+            amf_ue_ngap_ids: vec![],
+        })
+    );
+    assert_eq!(
+        "reset 1 7 8".parse(),
+        Ok(Command::Reset {
+            gnb_id: 1,
+            amf_ue_ngap_ids: vec![7, 8],
+        })
+    );
+    assert!("reset".parse::<Command>().is_err());
+    assert!("reset 1 all".parse::<Command>().is_err());
+}
+
+#[test]
+fn test_execute_reset() {
+    let config = config::CoreKubeConfig::default();
+    let store = store::Store::default();
+    let gnb = crate::tests::test_target_gnb();
+    let gnb_id = gnb.gnb_id.load_be::<u32>();
+    store.put_gnb(gnb);
+
+    let command = Command::Reset {
+        gnb_id,
+        amf_ue_ngap_ids: vec![],
+    };
+    assert_eq!(execute(&config, &store, command).map(|r| r.len()), Ok(1));
+
+    // A gNB that is not known, or UEs that are not connected through it
+    let command = Command::Reset {
+        gnb_id: gnb_id + 1,
+        amf_ue_ngap_ids: vec![],
+    };
+    assert!(execute(&config, &store, command).is_err());
+    let command = Command::Reset {
+        gnb_id,
+        amf_ue_ngap_ids: vec![7],
+    };
+    assert!(execute(&config, &store, command).is_err());
+}
+
+#[test]
+fn test_parse_update_configuration() {
+    assert_eq!(
+        "update-configuration".parse(),
+        Ok(Command::UpdateConfiguration)
+    );
+    assert!("update-configuration now".parse::<Command>().is_err());
+}
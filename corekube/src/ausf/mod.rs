@@ -0,0 +1,77 @@
+//! AUSF-lite: 5G AKA of the subscribers in the configuration, see TS 33.501
+//! section 6.1.3.2.
+//!
+//! The AMF plays the part of the SEAF, AUSF and UDM at once, so it checks the
+//! RES* of the UE against XRES* itself rather than through HXRES*. SQNs are
+//! generated as in TS 33.102 Annex C.3.2 with IND always 0, and are kept in
+//! the store so that every worker continues the same sequence.
+
+use log::{error, info};
+use nas::aka::{self, AuthenticationVector, Milenage};
+
+use crate::config::CoreKubeConfig;
+use crate::store::Store;
+
+#[cfg(test)]
+mod tests;
+
+/// SEQ is the SQN without its 5 bit IND.
+const SQN_STEP: u64 = 1 << 5;
+
+fn milenage(config: &CoreKubeConfig, supi: &str) -> Option<Milenage> {
+    let Some(subscriber) = config.subscribers.get(supi) else {
+        info!("No subscription for {}", supi);
+        return None;
+    };
+    Some(Milenage::new(subscriber.k, subscriber.opc))
+}
+
+/// Generate a fresh authentication vector for a subscriber, or `None` if
+/// there is no subscription for the SUPI.
+pub fn authentication_vector(
+    config: &CoreKubeConfig,
+    store: &Store,
+    supi: &str,
+) -> Option<AuthenticationVector> {
+    let milenage = milenage(config, supi)?;
+    let mut rand = [0u8; 16];
+    if let Err(e) = getrandom::getrandom(&mut rand) {
+        error!("Could not generate RAND: {}", e);
+        return None;
+    }
+    let sqn = store.advance_sqn(supi, SQN_STEP, aka::MAX_SQN);
+    Some(AuthenticationVector::generate(
+        &milenage,
+        sqn,
+        rand,
+        &aka::serving_network_name(config.mcc.into(), config.mnc.into()),
+    ))
+}
+
+/// Take over the SQN of the UE from the AUTS it sent in answer to the
+/// challenge `rand`, so that the next authentication vector is accepted.
+/// Returns whether AUTS was valid.
+pub fn resynchronise(
+    config: &CoreKubeConfig,
+    store: &Store,
+    supi: &str,
+    rand: &[u8; 16],
+    auts: &[u8; 14],
+) -> bool {
+    let Some(milenage) = milenage(config, supi) else {
+        return false;
+    };
+    let Some(sqn_ms) = aka::resynchronise(&milenage, rand, auts) else {
+        info!("Invalid AUTS from {}", supi);
+        return false;
+    };
+    info!("Re-synchronised SQN of {} to {:#x}", supi, sqn_ms);
+    store.set_sqn(supi, sqn_ms);
+    true
+}
+
+/// Derive KAMF from the vector of a challenge the UE answered correctly.
+pub fn kamf(vector: &AuthenticationVector, supi: &str) -> [u8; 32] {
+    // Keys are bound to the IMSI alone, as a string of digits
+    vector.kamf(supi.strip_prefix("imsi-").unwrap_or(supi))
+}
@@ -0,0 +1,67 @@
+use super::*;
+use crate::config::SubscriberConfig;
+
+const SUPI: &str = "imsi-208930000000001";
+
+/// The configuration of test set 1 of TS 35.207 as subscriber [`SUPI`].
+fn test_config() -> CoreKubeConfig {
+    let mut config = CoreKubeConfig::default();
+    config.subscribers.insert(
+        SUPI.to_string(),
+        SubscriberConfig {
+            k: hex::decode("465b5ce8b199b49faa5f0a2ee238a6bc")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            opc: hex::decode("cd63cb71954a9f4e48a5994e37a02baf")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        },
+    );
+    config
+}
+
+/// The SQN a UE gets from the AUTN of a vector.
+fn sqn_of(config: &CoreKubeConfig, vector: &AuthenticationVector) -> u64 {
+    let (_, _, _, ak) = milenage(config, SUPI).unwrap().f2345(&vector.rand);
+    let mut sqn = [0u8; 8];
+    for (i, octet) in sqn[2..].iter_mut().enumerate() {
+        *octet = vector.autn[i] ^ ak[i];
+    }
+    u64::from_be_bytes(sqn)
+}
+
+#[test]
+fn test_authentication_vector() {
+    let config = test_config();
+    let store = Store::default();
+
+    let first = authentication_vector(&config, &store, SUPI).unwrap();
+    let second = authentication_vector(&config, &store, SUPI).unwrap();
+    assert_ne!(first.rand, second.rand);
+    assert_eq!(sqn_of(&config, &first), SQN_STEP);
+    assert_eq!(sqn_of(&config, &second), 2 * SQN_STEP);
+
+    assert!(authentication_vector(&config, &store, "imsi-208930000000002").is_none());
+}
+
+#[test]
+fn test_resynchronise() {
+    let config = test_config();
+    let store = Store::default();
+    let vector = authentication_vector(&config, &store, SUPI).unwrap();
+
+    // The UE is ahead at SQN 0x1000
+    let milenage = milenage(&config, SUPI).unwrap();
+    let sqn_ms = [0, 0, 0, 0, 0x10, 0x00];
+    let ak = milenage.f5_star(&vector.rand);
+    let concealed_sqn: [u8; 6] = std::array::from_fn(|i| sqn_ms[i] ^ ak[i]);
+    let mac_s = milenage.f1_star(&vector.rand, &sqn_ms, &[0; 2]);
+    let auts: [u8; 14] = [&concealed_sqn[..], &mac_s].concat().try_into().unwrap();
+
+    assert!(!resynchronise(&config, &store, SUPI, &[0; 16], &auts));
+    assert!(resynchronise(&config, &store, SUPI, &vector.rand, &auts));
+    let vector = authentication_vector(&config, &store, SUPI).unwrap();
+    assert_eq!(sqn_of(&config, &vector), 0x1000 + SQN_STEP);
+}
@@ -1,10 +1,21 @@
 use bitvec::prelude::*;
+use nas::fgmm::Snssai;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::store::NasTimer;
 
 /// Configuration for the Core
 pub struct CoreKubeConfig {
     pub bind_addr: String,
     pub bind_port: u16,
     pub multithreaded: bool,
+    /// Address of the admin interface, see [`crate::admin`], or None to
+    /// disable it
+    pub admin_addr: Option<SocketAddr>,
     pub amf_name: String,
     pub amf_region_id: BitVec<u8, Msb0>,
     pub amf_set_id: BitVec<u8, Msb0>,
@@ -12,7 +23,216 @@ pub struct CoreKubeConfig {
     pub mcc: u8,
     pub mnc: u8,
     pub relative_amf_capacity: u8,
-    pub sst: Vec<u8>,
+    pub nssai: NssaiConfig,
+    pub ue_ambr_downlink: u64,
+    pub ue_ambr_uplink: u64,
+    pub session_ambr_downlink: u64,
+    pub session_ambr_uplink: u64,
+    /// Set to run the SMF-lite, which makes PDU sessions possible
+    pub smf: Option<SmfConfig>,
+    pub paging_strategy: PagingStrategy,
+    /// UE specific PagingDRX sent when paging, or None to let each gNB use
+    /// its default
+    pub paging_drx: Option<u8>,
+    /// Set to reject registrations and service requests because of
+    /// congestion, asking the UEs to back off for this many seconds
+    pub nas_congestion_backoff: Option<u32>,
+    pub nas_timers: NasTimerConfig,
+    pub overload: OverloadConfig,
+    /// Subscribers that can be authenticated, by SUPI
+    pub subscribers: HashMap<String, SubscriberConfig>,
+}
+
+/// The long-term key and OPc of a subscriber, for 5G AKA with MILENAGE, see
+/// [`crate::ausf`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubscriberConfig {
+    pub k: [u8; 16],
+    pub opc: [u8; 16],
+}
+
+/// Network slices of the PLMN and of its subscribers, see [`crate::nssf`].
+/// S-NSSAIs of subscriptions are values of the HPLMN of the subscriber.
+pub struct NssaiConfig {
+    /// S-NSSAIs the AMF serves, advertised to the gNBs in NG Setup
+    pub supported: Vec<Snssai>,
+    /// Subscribed S-NSSAIs by SUPI
+    pub subscriptions: HashMap<String, Vec<SubscribedSnssai>>,
+    /// Subscribed S-NSSAIs of UEs without a subscription of their own
+    pub default_subscription: Vec<SubscribedSnssai>,
+    /// How S-NSSAIs of the HPLMN of roaming UEs are mapped to S-NSSAIs of
+    /// this PLMN. HPLMN S-NSSAIs without a mapping are not available.
+    pub roaming_mapping: Vec<SnssaiMapping>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubscribedSnssai {
+    pub snssai: Snssai,
+    /// Whether the S-NSSAI is used when the UE does not request any
+    pub default: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnssaiMapping {
+    pub hplmn: Snssai,
+    pub serving: Snssai,
+}
+
+impl Default for NssaiConfig {
+    fn default() -> Self {
+        NssaiConfig {
+            supported: vec![Snssai::default()],
+            subscriptions: HashMap::new(),
+            default_subscription: vec![SubscribedSnssai {
+                snssai: Snssai::default(),
+                default: true,
+            }],
+            roaming_mapping: vec![],
+        }
+    }
+}
+
+/// Values of the 5GMM timers run by the AMF, see TS 24.501 section 10.2.
+pub struct NasTimerConfig {
+    pub t3550: Duration,
+    pub t3560: Duration,
+    pub t3570: Duration,
+    pub mobile_reachable: Duration,
+    pub implicit_deregistration: Duration,
+    /// Retransmissions of a NAS message before its procedure is aborted,
+    /// which happens at the fifth expiry of its timer
+    pub max_retransmissions: u8,
+    /// How often the store is polled for expired timers
+    pub poll_interval: Duration,
+}
+
+impl NasTimerConfig {
+    pub fn value(&self, timer: NasTimer) -> Duration {
+        match timer {
+            NasTimer::T3550 => self.t3550,
+            NasTimer::T3560 => self.t3560,
+            NasTimer::T3570 => self.t3570,
+            NasTimer::MobileReachable => self.mobile_reachable,
+            NasTimer::ImplicitDeregistration => self.implicit_deregistration,
+        }
+    }
+}
+
+impl Default for NasTimerConfig {
+    fn default() -> Self {
+        // The reachability timers default to 4 minutes more than the 54
+        // minutes of the periodic registration timer T3512
+        NasTimerConfig {
+            t3550: Duration::from_secs(6),
+            t3560: Duration::from_secs(6),
+            t3570: Duration::from_secs(6),
+            mobile_reachable: Duration::from_secs(58 * 60),
+            implicit_deregistration: Duration::from_secs(58 * 60),
+            max_retransmissions: 4,
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Thresholds of the overload control of the AMF, see [`crate::load`]. An
+/// overload starts when either start threshold is reached, and stops once
+/// the load is back under both stop thresholds.
+pub struct OverloadConfig {
+    /// Messages received but not handled yet
+    pub start_queue_depth: usize,
+    pub stop_queue_depth: usize,
+    /// Smoothed time from receiving a message to having handled it
+    pub start_latency: Duration,
+    pub stop_latency: Duration,
+    /// What the gNBs are asked to do during an overload
+    pub action: OverloadAction,
+    /// Percentage of signalling traffic the gNBs are asked to shed
+    pub traffic_load_reduction: u8,
+    /// Relative AMF capacity advertised during an overload, if lower than
+    /// the configured one
+    pub relative_amf_capacity: u8,
+    /// How often the load is checked
+    pub check_interval: Duration,
+}
+
+impl Default for OverloadConfig {
+    fn default() -> Self {
+        OverloadConfig {
+            start_queue_depth: 1000,
+            stop_queue_depth: 500,
+            start_latency: Duration::from_millis(100),
+            stop_latency: Duration::from_millis(20),
+            action: OverloadAction::RejectNonEmergencyMoData,
+            traffic_load_reduction: 50,
+            relative_amf_capacity: 32,
+            check_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Which requests the gNBs reject during an overload of the AMF, see TS
+/// 38.413 section 9.3.1.105.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverloadAction {
+    /// RRC connection establishments for non-emergency mobile originated
+    /// data transfer
+    RejectNonEmergencyMoData,
+    /// RRC connection establishments for signalling
+    RejectSignalling,
+    /// Everything but emergency sessions and mobile terminated services
+    PermitEmergencyAndMobileTerminatedOnly,
+    /// Everything but high priority sessions and mobile terminated services
+    PermitHighPriorityAndMobileTerminatedOnly,
+}
+
+/// Which gNBs are asked to page a CM-IDLE UE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PagingStrategy {
+    /// Every gNB serving the registration area of the UE
+    RegistrationArea,
+    /// Only the gNB that last served the UE, then the whole registration
+    /// area if the UE does not answer
+    LastGNBFirst,
+}
+
+/// Configuration for the SMF-lite, see [`crate::smf`].
+pub struct SmfConfig {
+    /// Address of the N4 interface, also used as the PFCP Node ID
+    pub pfcp_bind_addr: Ipv4Addr,
+    /// Requests may be sent from any port, 0 picks a free one
+    pub pfcp_bind_port: u16,
+    pub upf_addr: SocketAddr,
+    /// The DNNs that can be requested. The first one is used if the UE does
+    /// not ask for a specific DNN.
+    pub dnns: Vec<DnnConfig>,
+}
+
+/// A data network served by the SMF-lite, with the pool that UE IP addresses
+/// are allocated from and the QoS of its default flow.
+#[derive(Clone)]
+pub struct DnnConfig {
+    pub dnn: String,
+    pub ue_pool_start: Ipv4Addr,
+    pub ue_pool_size: u32,
+    pub five_qi: u8,
+    pub arp_priority_level: u8,
+}
+
+impl Default for SmfConfig {
+    fn default() -> Self {
+        SmfConfig {
+            pfcp_bind_addr: Ipv4Addr::LOCALHOST,
+            pfcp_bind_port: 0,
+            upf_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, pfcp::PFCP_PORT)),
+            dnns: vec![DnnConfig {
+                dnn: "internet".to_string(),
+                ue_pool_start: Ipv4Addr::new(10, 45, 0, 2),
+                ue_pool_size: 65533,
+                five_qi: 9,
+                arp_priority_level: 8,
+            }],
+        }
+    }
 }
 
 impl Default for CoreKubeConfig {
@@ -21,6 +241,7 @@ impl Default for CoreKubeConfig {
             bind_addr: "0.0.0.0".to_string(),
             bind_port: 9977,
             multithreaded: true,
+            admin_addr: None,
             amf_name: "CoreKubeRS_5G_Worker".to_string(),
             amf_region_id: bitvec![u8, Msb0; 0, 0, 0, 0, 0, 0, 1, 0],
             amf_set_id: bitvec![u8, Msb0; 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
@@ -28,7 +249,516 @@ impl Default for CoreKubeConfig {
             mcc: 208,
             mnc: 93,
             relative_amf_capacity: 255,
-            sst: vec![1],
+            nssai: NssaiConfig::default(),
+            ue_ambr_downlink: 1_000_000_000,
+            ue_ambr_uplink: 1_000_000_000,
+            session_ambr_downlink: 1_000_000_000,
+            session_ambr_uplink: 1_000_000_000,
+            smf: None,
+            paging_strategy: PagingStrategy::LastGNBFirst,
+            paging_drx: None,
+            nas_congestion_backoff: None,
+            nas_timers: NasTimerConfig::default(),
+            overload: OverloadConfig::default(),
+            subscribers: HashMap::new(),
+        }
+    }
+}
+
+/// The settings that can be given in a configuration file, see
+/// [`CoreKubeConfig::from_toml`]. Settings that are left out keep their
+/// default value.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    bind_addr: Option<String>,
+    bind_port: Option<u16>,
+    multithreaded: Option<bool>,
+    admin_addr: Option<SocketAddr>,
+    amf_name: Option<String>,
+    amf_region_id: Option<u32>,
+    amf_set_id: Option<u32>,
+    amf_pointer: Option<u32>,
+    mcc: Option<u8>,
+    mnc: Option<u8>,
+    relative_amf_capacity: Option<u8>,
+    supported_nssai: Option<Vec<SnssaiFile>>,
+    nssai_subscriptions: Option<Vec<NssaiSubscriptionFile>>,
+    default_subscription: Option<Vec<SubscribedSnssaiFile>>,
+    roaming_mapping: Option<Vec<SnssaiMappingFile>>,
+    smf: Option<SmfFile>,
+    paging_strategy: Option<PagingStrategyFile>,
+    paging_drx: Option<u8>,
+    nas_congestion_backoff: Option<u32>,
+    nas_timers: Option<NasTimerFile>,
+    overload: Option<OverloadFile>,
+    subscribers: Option<Vec<SubscriberFile>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SnssaiFile {
+    sst: u8,
+    sd: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubscribedSnssaiFile {
+    sst: u8,
+    sd: Option<u32>,
+    #[serde(default)]
+    default: bool,
+}
+
+/// The subscribed S-NSSAIs of a SUPI.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NssaiSubscriptionFile {
+    supi: String,
+    snssais: Vec<SubscribedSnssaiFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SnssaiMappingFile {
+    hplmn: SnssaiFile,
+    serving: SnssaiFile,
+}
+
+/// The SMF-lite, which is enabled by giving this section.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SmfFile {
+    pfcp_bind_addr: Option<Ipv4Addr>,
+    pfcp_bind_port: Option<u16>,
+    upf_addr: Option<SocketAddr>,
+    dnns: Option<Vec<DnnFile>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DnnFile {
+    dnn: String,
+    ue_pool_start: Ipv4Addr,
+    ue_pool_size: u32,
+    five_qi: Option<u8>,
+    arp_priority_level: Option<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PagingStrategyFile {
+    RegistrationArea,
+    LastGnbFirst,
+}
+
+/// The NAS timers in seconds, and the poll interval in milliseconds.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NasTimerFile {
+    t3550: Option<u64>,
+    t3560: Option<u64>,
+    t3570: Option<u64>,
+    mobile_reachable: Option<u64>,
+    implicit_deregistration: Option<u64>,
+    max_retransmissions: Option<u8>,
+    poll_interval_ms: Option<u64>,
+}
+
+/// The overload thresholds, with times in milliseconds.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverloadFile {
+    start_queue_depth: Option<usize>,
+    stop_queue_depth: Option<usize>,
+    start_latency_ms: Option<u64>,
+    stop_latency_ms: Option<u64>,
+    action: Option<OverloadActionFile>,
+    traffic_load_reduction: Option<u8>,
+    relative_amf_capacity: Option<u8>,
+    check_interval_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OverloadActionFile {
+    RejectNonEmergencyMoData,
+    RejectSignalling,
+    PermitEmergencyAndMobileTerminatedOnly,
+    PermitHighPriorityAndMobileTerminatedOnly,
+}
+
+/// A subscriber, with either its OPc or the OP it is derived from, in hex.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubscriberFile {
+    supi: String,
+    k: String,
+    opc: Option<String>,
+    op: Option<String>,
+}
+
+fn snssai(sst: u8, sd: Option<u32>) -> Result<Snssai, String> {
+    let sd = match sd {
+        Some(sd) if sd >> 24 != 0 => return Err(format!("SD {:#x} does not fit in 24 bits", sd)),
+        Some(sd) => Some(sd.to_be_bytes()[1..].try_into().unwrap()),
+        None => None,
+    };
+    Ok(Snssai { sst, sd })
+}
+
+fn subscribed_snssais(snssais: Vec<SubscribedSnssaiFile>) -> Result<Vec<SubscribedSnssai>, String> {
+    snssais
+        .into_iter()
+        .map(|subscribed| {
+            Ok(SubscribedSnssai {
+                snssai: snssai(subscribed.sst, subscribed.sd)?,
+                default: subscribed.default,
+            })
+        })
+        .collect()
+}
+
+/// A 128 bit key given in hex.
+fn key(name: &str, value: &str) -> Result<[u8; 16], String> {
+    hex::decode(value)
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| format!("{} must be 32 hex digits", name))
+}
+
+/// The value of an identifier as a bit string of the given length.
+fn bits(name: &str, value: u32, len: usize) -> Result<BitVec<u8, Msb0>, String> {
+    if value >> len != 0 {
+        return Err(format!("{} does not fit in {} bits", name, len));
+    }
+    let mut bits = bitvec![u8, Msb0; 0; len];
+    bits.store_be(value);
+    Ok(bits)
+}
+
+/// Whether a SUPI is `imsi-` followed by the 5 to 15 digits of an IMSI,
+/// which is the only type of SUPI that 5G AKA is implemented for.
+fn is_imsi_supi(supi: &str) -> bool {
+    supi.strip_prefix("imsi-").is_some_and(|imsi| {
+        (5..=15).contains(&imsi.len()) && imsi.bytes().all(|digit| digit.is_ascii_digit())
+    })
+}
+
+impl CoreKubeConfig {
+    /// Read the configuration from a TOML file, see [`Self::from_toml`].
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::from_toml(&contents)
+    }
+
+    /// Parse a configuration in TOML, for instance:
+    ///
+    /// ```toml
+    /// amf_name = "amf1"
+    /// amf_set_id = 1
+    /// relative_amf_capacity = 128
+    /// supported_nssai = [{ sst = 1 }, { sst = 2, sd = 0x000001 }]
+    /// default_subscription = [{ sst = 1, default = true }]
+    /// paging_strategy = "registration_area"
+    ///
+    /// [smf]
+    /// upf_addr = "10.0.0.2:8805"
+    /// dnns = [{ dnn = "internet", ue_pool_start = "10.45.0.2", ue_pool_size = 254 }]
+    ///
+    /// [nas_timers]
+    /// t3550 = 6
+    /// poll_interval_ms = 200
+    ///
+    /// [[subscribers]]
+    /// supi = "imsi-208930000000001"
+    /// k = "465b5ce8b199b49faa5f0a2ee238a6bc"
+    /// opc = "cd63cb71954a9f4e48a5994e37a02baf"
+    /// ```
+    ///
+    /// The configuration is validated before it is returned.
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+        let mut config = CoreKubeConfig::default();
+
+        if let Some(bind_addr) = file.bind_addr {
+            config.bind_addr = bind_addr;
+        }
+        if let Some(bind_port) = file.bind_port {
+            config.bind_port = bind_port;
+        }
+        if let Some(multithreaded) = file.multithreaded {
+            config.multithreaded = multithreaded;
+        }
+        if file.admin_addr.is_some() {
+            config.admin_addr = file.admin_addr;
+        }
+        if let Some(amf_name) = file.amf_name {
+            config.amf_name = amf_name;
+        }
+        if let Some(amf_region_id) = file.amf_region_id {
+            config.amf_region_id = bits("amf_region_id", amf_region_id, 8)?;
+        }
+        if let Some(amf_set_id) = file.amf_set_id {
+            config.amf_set_id = bits("amf_set_id", amf_set_id, 10)?;
+        }
+        if let Some(amf_pointer) = file.amf_pointer {
+            config.amf_pointer = bits("amf_pointer", amf_pointer, 6)?;
+        }
+        if let Some(mcc) = file.mcc {
+            config.mcc = mcc;
         }
+        if let Some(mnc) = file.mnc {
+            config.mnc = mnc;
+        }
+        if let Some(relative_amf_capacity) = file.relative_amf_capacity {
+            config.relative_amf_capacity = relative_amf_capacity;
+        }
+        if let Some(supported_nssai) = file.supported_nssai {
+            config.nssai.supported = supported_nssai
+                .into_iter()
+                .map(|supported| snssai(supported.sst, supported.sd))
+                .collect::<Result<_, String>>()?;
+        }
+        if let Some(subscriptions) = file.nssai_subscriptions {
+            config.nssai.subscriptions = subscriptions
+                .into_iter()
+                .map(|subscription| {
+                    Ok((subscription.supi, subscribed_snssais(subscription.snssais)?))
+                })
+                .collect::<Result<_, String>>()?;
+        }
+        if let Some(default_subscription) = file.default_subscription {
+            config.nssai.default_subscription = subscribed_snssais(default_subscription)?;
+        }
+        if let Some(roaming_mapping) = file.roaming_mapping {
+            config.nssai.roaming_mapping = roaming_mapping
+                .into_iter()
+                .map(|mapping| {
+                    Ok(SnssaiMapping {
+                        hplmn: snssai(mapping.hplmn.sst, mapping.hplmn.sd)?,
+                        serving: snssai(mapping.serving.sst, mapping.serving.sd)?,
+                    })
+                })
+                .collect::<Result<_, String>>()?;
+        }
+        if let Some(smf) = file.smf {
+            let mut smf_config = SmfConfig::default();
+            if let Some(pfcp_bind_addr) = smf.pfcp_bind_addr {
+                smf_config.pfcp_bind_addr = pfcp_bind_addr;
+            }
+            if let Some(pfcp_bind_port) = smf.pfcp_bind_port {
+                smf_config.pfcp_bind_port = pfcp_bind_port;
+            }
+            if let Some(upf_addr) = smf.upf_addr {
+                smf_config.upf_addr = upf_addr;
+            }
+            if let Some(dnns) = smf.dnns {
+                let default_dnn = &smf_config.dnns[0];
+                smf_config.dnns = dnns
+                    .into_iter()
+                    .map(|dnn| DnnConfig {
+                        dnn: dnn.dnn,
+                        ue_pool_start: dnn.ue_pool_start,
+                        ue_pool_size: dnn.ue_pool_size,
+                        five_qi: dnn.five_qi.unwrap_or(default_dnn.five_qi),
+                        arp_priority_level: dnn
+                            .arp_priority_level
+                            .unwrap_or(default_dnn.arp_priority_level),
+                    })
+                    .collect();
+            }
+            config.smf = Some(smf_config);
+        }
+        if let Some(paging_strategy) = file.paging_strategy {
+            config.paging_strategy = match paging_strategy {
+                PagingStrategyFile::RegistrationArea => PagingStrategy::RegistrationArea,
+                PagingStrategyFile::LastGnbFirst => PagingStrategy::LastGNBFirst,
+            };
+        }
+        if file.paging_drx.is_some() {
+            config.paging_drx = file.paging_drx;
+        }
+        if file.nas_congestion_backoff.is_some() {
+            config.nas_congestion_backoff = file.nas_congestion_backoff;
+        }
+        if let Some(nas_timers) = file.nas_timers {
+            let timers = &mut config.nas_timers;
+            let seconds = [
+                (&mut timers.t3550, nas_timers.t3550),
+                (&mut timers.t3560, nas_timers.t3560),
+                (&mut timers.t3570, nas_timers.t3570),
+                (&mut timers.mobile_reachable, nas_timers.mobile_reachable),
+                (
+                    &mut timers.implicit_deregistration,
+                    nas_timers.implicit_deregistration,
+                ),
+            ];
+            for (timer, value) in seconds {
+                if let Some(value) = value {
+                    *timer = Duration::from_secs(value);
+                }
+            }
+            if let Some(max_retransmissions) = nas_timers.max_retransmissions {
+                timers.max_retransmissions = max_retransmissions;
+            }
+            if let Some(poll_interval) = nas_timers.poll_interval_ms {
+                timers.poll_interval = Duration::from_millis(poll_interval);
+            }
+        }
+        if let Some(overload) = file.overload {
+            let overload_config = &mut config.overload;
+            if let Some(start_queue_depth) = overload.start_queue_depth {
+                overload_config.start_queue_depth = start_queue_depth;
+            }
+            if let Some(stop_queue_depth) = overload.stop_queue_depth {
+                overload_config.stop_queue_depth = stop_queue_depth;
+            }
+            if let Some(start_latency) = overload.start_latency_ms {
+                overload_config.start_latency = Duration::from_millis(start_latency);
+            }
+            if let Some(stop_latency) = overload.stop_latency_ms {
+                overload_config.stop_latency = Duration::from_millis(stop_latency);
+            }
+            if let Some(action) = overload.action {
+                overload_config.action = match action {
+                    OverloadActionFile::RejectNonEmergencyMoData => {
+                        OverloadAction::RejectNonEmergencyMoData
+                    }
+                    OverloadActionFile::RejectSignalling => OverloadAction::RejectSignalling,
+                    OverloadActionFile::PermitEmergencyAndMobileTerminatedOnly => {
+                        OverloadAction::PermitEmergencyAndMobileTerminatedOnly
+                    }
+                    OverloadActionFile::PermitHighPriorityAndMobileTerminatedOnly => {
+                        OverloadAction::PermitHighPriorityAndMobileTerminatedOnly
+                    }
+                };
+            }
+            if let Some(traffic_load_reduction) = overload.traffic_load_reduction {
+                overload_config.traffic_load_reduction = traffic_load_reduction;
+            }
+            if let Some(relative_amf_capacity) = overload.relative_amf_capacity {
+                overload_config.relative_amf_capacity = relative_amf_capacity;
+            }
+            if let Some(check_interval) = overload.check_interval_ms {
+                overload_config.check_interval = Duration::from_millis(check_interval);
+            }
+        }
+        if let Some(subscribers) = file.subscribers {
+            config.subscribers = subscribers
+                .into_iter()
+                .map(|subscriber| {
+                    let k = key("k", &subscriber.k)?;
+                    let opc = match (subscriber.opc, subscriber.op) {
+                        (Some(opc), None) => key("opc", &opc)?,
+                        (None, Some(op)) => nas::aka::Milenage::opc(&k, &key("op", &op)?),
+                        _ => {
+                            return Err(format!(
+                                "subscriber {} needs either opc or op",
+                                subscriber.supi
+                            ))
+                        }
+                    };
+                    Ok((subscriber.supi, SubscriberConfig { k, opc }))
+                })
+                .collect::<Result<_, String>>()?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the values that the types of the configuration do not restrict
+    /// enough, such as those that NGAP limits in size.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.amf_name.is_empty() || self.amf_name.len() > 150 {
+            return Err("amf_name must have 1 to 150 characters".to_string());
+        }
+        if self.amf_region_id.len() != 8 || self.amf_set_id.len() != 10 {
+            return Err("the AMF Region ID and AMF Set ID must have 8 and 10 bits".to_string());
+        }
+        if self.amf_pointer.len() != 6 {
+            return Err("the AMF Pointer must have 6 bits".to_string());
+        }
+        // The S-NSSAIs are advertised in the SliceSupportList of NG Setup
+        if self.nssai.supported.is_empty() || self.nssai.supported.len() > 1024 {
+            return Err("1 to 1024 S-NSSAIs must be supported".to_string());
+        }
+        let overload = &self.overload;
+        if overload.stop_queue_depth > overload.start_queue_depth
+            || overload.stop_latency > overload.start_latency
+        {
+            return Err(
+                "overload stop thresholds must not exceed the start thresholds".to_string(),
+            );
+        }
+        if !(1..=99).contains(&overload.traffic_load_reduction) {
+            return Err("the traffic load reduction must be 1 to 99 percent".to_string());
+        }
+        if overload.check_interval.is_zero() || self.nas_timers.poll_interval.is_zero() {
+            return Err("the overload check and timer poll intervals must not be 0".to_string());
+        }
+        if let Some(smf) = &self.smf {
+            if smf.dnns.is_empty() {
+                return Err("the SMF-lite must serve at least one DNN".to_string());
+            }
+        }
+        for dnn in self.smf.iter().flat_map(|smf| &smf.dnns) {
+            if !(1..=15).contains(&dnn.arp_priority_level) {
+                return Err(format!(
+                    "the ARP priority level of DNN {} must be 1 to 15",
+                    dnn.dnn
+                ));
+            }
+            // Addresses are allocated as offsets from the start of the pool
+            if u64::from(u32::from(dnn.ue_pool_start)) + u64::from(dnn.ue_pool_size) > 1 << 32 {
+                return Err(format!(
+                    "the UE pool of DNN {} runs past 255.255.255.255",
+                    dnn.dnn
+                ));
+            }
+        }
+        if let Some(supi) = self.subscribers.keys().find(|supi| !is_imsi_supi(supi)) {
+            return Err(format!("SUPI {} is not an IMSI", supi));
+        }
+        if let Some(paging_drx) = self.paging_drx {
+            if paging_drx > 3 {
+                return Err(format!("unknown PagingDRX {}", paging_drx));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a new configuration only differs from this one in
+    /// settings that can change while the worker runs. The sockets and
+    /// threads are set up once, when the worker starts.
+    pub fn check_reloadable(&self, new: &CoreKubeConfig) -> Result<(), String> {
+        if self.bind_addr != new.bind_addr
+            || self.bind_port != new.bind_port
+            || self.multithreaded != new.multithreaded
+            || self.admin_addr != new.admin_addr
+            || self.smf.is_some() != new.smf.is_some()
+        {
+            return Err(
+                "the listening addresses, threading and SMF-lite can only change on restart"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the gNBs have to be told about a new configuration, because
+    /// it changes what the AMF advertises in NG Setup.
+    pub fn ng_configuration_differs(&self, new: &CoreKubeConfig) -> bool {
+        self.amf_name != new.amf_name
+            || self.amf_region_id != new.amf_region_id
+            || self.amf_set_id != new.amf_set_id
+            || self.amf_pointer != new.amf_pointer
+            || self.mcc != new.mcc
+            || self.mnc != new.mnc
+            || self.relative_amf_capacity != new.relative_amf_capacity
+            || self.nssai.supported != new.nssai.supported
     }
 }
@@ -0,0 +1,98 @@
+//! Load of the worker, used for overload control, see TS 23.501 section
+//! 5.19.5.2.
+//!
+//! The load is measured as the number of messages received but not handled
+//! yet, and as the time from receiving a message to having handled it,
+//! smoothed over recent messages. Whether the worker is overloaded only
+//! changes when it is checked against the thresholds of the configuration,
+//! so that the gNBs are told about every change exactly once.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::config::OverloadConfig;
+
+#[cfg(test)]
+mod tests;
+
+/// Weight of the latest latency in the smoothed latency, as a power of two.
+const LATENCY_SMOOTHING_SHIFT: u32 = 3;
+
+/// A change of the overload state found by [`LoadMonitor::check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadChange {
+    OverloadStarted,
+    OverloadStopped,
+}
+
+#[derive(Default)]
+pub struct LoadMonitor {
+    queue_depth: AtomicUsize,
+    /// Smoothed latency in microseconds
+    latency: AtomicU64,
+    overloaded: AtomicBool,
+}
+
+impl LoadMonitor {
+    /// Count a message that was just received, returning the time to pass
+    /// to [`Self::handled`] once it is handled.
+    pub fn received(&self) -> Instant {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+        Instant::now()
+    }
+
+    /// Count a message received at `received` as handled.
+    pub fn handled(&self, received: Instant) {
+        self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+        let sample = received.elapsed().as_micros() as u64;
+        // An exponentially weighted moving average, kept without a lock
+        let _ = self
+            .latency
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |latency| {
+                Some(
+                    latency - (latency >> LATENCY_SMOOTHING_SHIFT)
+                        + (sample >> LATENCY_SMOOTHING_SHIFT),
+                )
+            });
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+
+    pub fn latency(&self) -> Duration {
+        Duration::from_micros(self.latency.load(Ordering::Relaxed))
+    }
+
+    pub fn is_overloaded(&self) -> bool {
+        self.overloaded.load(Ordering::Relaxed)
+    }
+
+    /// Check the load against the thresholds, returning whether an overload
+    /// started or stopped since the last check.
+    pub fn check(&self, config: &OverloadConfig) -> Option<LoadChange> {
+        let queue_depth = self.queue_depth();
+        let latency = self.latency();
+        let overloaded = queue_depth >= config.start_queue_depth || latency >= config.start_latency;
+        let recovered = queue_depth <= config.stop_queue_depth && latency <= config.stop_latency;
+
+        // Only the thread that changes the state reports the change
+        if overloaded
+            && self
+                .overloaded
+                .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(LoadChange::OverloadStarted)
+        } else if recovered
+            && self
+                .overloaded
+                .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(LoadChange::OverloadStopped)
+        } else {
+            None
+        }
+    }
+}
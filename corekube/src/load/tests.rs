@@ -0,0 +1,55 @@
+use super::*;
+
+fn test_config() -> OverloadConfig {
+    OverloadConfig {
+        start_queue_depth: 3,
+        stop_queue_depth: 1,
+        start_latency: Duration::from_millis(100),
+        stop_latency: Duration::from_millis(20),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_queue_depth_overload() {
+    let config = test_config();
+    let monitor = LoadMonitor::default();
+
+    let received: Vec<_> = (0..3).map(|_| monitor.received()).collect();
+    assert_eq!(monitor.queue_depth(), 3);
+    assert_eq!(monitor.check(&config), Some(LoadChange::OverloadStarted));
+    assert!(monitor.is_overloaded());
+    // The change is only reported once
+    assert_eq!(monitor.check(&config), None);
+
+    // Between the thresholds, the overload goes on
+    monitor.handled(received[0]);
+    assert_eq!(monitor.check(&config), None);
+    assert!(monitor.is_overloaded());
+
+    monitor.handled(received[1]);
+    assert_eq!(monitor.check(&config), Some(LoadChange::OverloadStopped));
+    assert!(!monitor.is_overloaded());
+    assert_eq!(monitor.check(&config), None);
+}
+
+#[test]
+fn test_latency_overload() {
+    let config = test_config();
+    let monitor = LoadMonitor::default();
+
+    // A single slow message moves the smoothed latency by an eighth
+    monitor.received();
+    monitor.handled(Instant::now() - Duration::from_secs(1));
+    assert!(monitor.latency() >= Duration::from_millis(100));
+    assert_eq!(monitor.queue_depth(), 0);
+    assert_eq!(monitor.check(&config), Some(LoadChange::OverloadStarted));
+
+    // Fast messages bring it back down over time
+    for _ in 0..20 {
+        let received = monitor.received();
+        monitor.handled(received);
+    }
+    assert!(monitor.latency() <= Duration::from_millis(20));
+    assert_eq!(monitor.check(&config), Some(LoadChange::OverloadStopped));
+}
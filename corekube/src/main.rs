@@ -1,13 +1,22 @@
+use arc_swap::ArcSwap;
 use asn1_codecs::{aper::AperCodec, PerCodecData};
 use flexi_logger::Logger;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use ngap_asn1 as ngap;
 use std::net::UdpSocket;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
 
+mod admin;
+mod ausf;
 mod config;
+mod load;
 mod ngap_handlers;
+mod nssf;
+mod reload;
+mod smf;
+mod store;
 
 #[cfg(test)]
 mod tests;
@@ -21,9 +30,26 @@ fn main() {
         .start()
         .expect("could not start logger");
 
-    // Load the default configuration. We wrap it in an atomic reference to
-    // allow sharing it between threads.
-    let config: Arc<config::CoreKubeConfig> = Arc::new(Default::default());
+    // Load the configuration from the file given as the first argument, or
+    // the default configuration. We wrap it in a handle that is shared
+    // between threads and can be swapped when the file is reloaded.
+    let config_path = std::env::args().nth(1).map(PathBuf::from);
+    let config = match &config_path {
+        Some(path) => config::CoreKubeConfig::from_file(path)
+            .unwrap_or_else(|e| panic!("invalid configuration: {}", e)),
+        None => Default::default(),
+    };
+    let config_handle: Arc<reload::ConfigHandle> = Arc::new(ArcSwap::from_pointee(config));
+    let config = config_handle.load_full();
+
+    // The UE and gNB contexts are shared between threads in the same way,
+    // along with the SMF-lite if PDU sessions are enabled.
+    let store: Arc<store::Store> = Arc::new(match &config.smf {
+        Some(smf_config) => store::Store::with_smf(
+            smf::Smf::start(smf_config).expect("could not set up PFCP association"),
+        ),
+        None => Default::default(),
+    });
 
     info!("Running corekube-rs...");
     info!("Listening on {}:{}", config.bind_addr, config.bind_port);
@@ -33,6 +59,57 @@ fn main() {
         Err(e) => panic!("couldn't bind socket: {}", e),
     };
 
+    // The admin interface sends to gNBs through the same socket
+    if let Some(admin_addr) = config.admin_addr {
+        let admin_socket = UdpSocket::bind(admin_addr).expect("couldn't bind admin socket");
+        let ngap_socket = socket.try_clone().expect("couldn't clone the socket");
+        let config_handle = Arc::clone(&config_handle);
+        let store = Arc::clone(&store);
+        info!("Admin interface listening on {}", admin_addr);
+        thread::spawn(move || admin::run(&config_handle, &store, &admin_socket, &ngap_socket));
+    }
+
+    // Changes to the configuration file are picked up without a restart
+    if let Some(path) = config_path {
+        let config_handle = Arc::clone(&config_handle);
+        let store = Arc::clone(&store);
+        let ngap_socket = socket.try_clone().expect("couldn't clone the socket");
+        info!(
+            "Reloading the configuration on SIGHUP or changes to {}",
+            path.display()
+        );
+        thread::spawn(move || reload::run(&config_handle, &store, &path, &ngap_socket));
+    }
+
+    // NAS timers are kept in the store, so expired ones are found by polling
+    // it. Each one is taken by a single poller, so more could run.
+    {
+        let config_handle = Arc::clone(&config_handle);
+        let store = Arc::clone(&store);
+        let ngap_socket = socket.try_clone().expect("couldn't clone the socket");
+        thread::spawn(move || loop {
+            thread::sleep(config_handle.load().nas_timers.poll_interval);
+            let config = config_handle.load_full();
+            let responses =
+                ngap_handlers::fire_expired_timers(&config, &store, ngap_handlers::now());
+            send_responses(&store, &ngap_socket, None, encode_responses(responses));
+        });
+    }
+
+    // The load is checked in the same way, telling the gNBs when the worker
+    // is overloaded
+    {
+        let config_handle = Arc::clone(&config_handle);
+        let store = Arc::clone(&store);
+        let ngap_socket = socket.try_clone().expect("couldn't clone the socket");
+        thread::spawn(move || loop {
+            thread::sleep(config_handle.load().overload.check_interval);
+            let config = config_handle.load_full();
+            let responses = ngap_handlers::check_overload(&config, &store);
+            send_responses(&store, &ngap_socket, None, encode_responses(responses));
+        });
+    }
+
     loop {
         let mut buf = [0; BUFFER_LEN];
         let (size, src) = socket
@@ -42,16 +119,23 @@ fn main() {
         // Clone the socket to pass it to the thread
         let socket_clone = socket.try_clone().expect("couldn't clone the socket");
 
-        // Clone the reference to the config
-        let config = Arc::clone(&config);
+        // Take the current config, which the message is handled with even if
+        // it is reloaded meanwhile, and clone the reference to the store
+        let config = config_handle.load_full();
+        let store = Arc::clone(&store);
+
+        // The message counts towards the load until it is handled
+        let received = store.load().received();
 
         // Start a new thread for each received packet
         if config.multithreaded {
             thread::spawn(move || {
-                process_message(&*config, socket_clone, &mut buf, size, src);
+                process_message(&*config, &*store, socket_clone, &mut buf, size, src);
+                store.load().handled(received);
             });
         } else {
-            process_message(&*config, socket_clone, &mut buf, size, src);
+            process_message(&*config, &*store, socket_clone, &mut buf, size, src);
+            store.load().handled(received);
         }
     }
 }
@@ -59,6 +143,7 @@ fn main() {
 /// Handle the client UDP packet.
 fn process_message(
     config: &config::CoreKubeConfig,
+    store: &store::Store,
     socket: UdpSocket,
     buf: &mut [u8; BUFFER_LEN],
     size: usize,
@@ -80,23 +165,71 @@ fn process_message(
     let (frontend_id, buf) = buf.split_at_mut(4);
     debug!("frontend_id: {:?}", frontend_id);
 
-    let responses = ngap_handler_entrypoint(config, buf);
+    let gnb = store::GNBAddress {
+        frontend: src,
+        frontend_id: [
+            frontend_id[0],
+            frontend_id[1],
+            frontend_id[2],
+            frontend_id[3],
+        ],
+    };
+
+    let responses = ngap_handler_entrypoint(config, store, &gnb, buf);
+    send_responses(store, &socket, Some(&gnb), responses);
+}
 
-    // Send the responses back to the client
-    for mut return_buf in responses {
-        // Append the frontend ID and the SCTP stream ID to the response buffer
+/// Send each response to the gNB it is for, prepending the frontend ID and
+/// the SCTP stream ID to the response buffer. Responses to the sender can
+/// only be sent if there is one.
+fn send_responses(
+    store: &store::Store,
+    socket: &UdpSocket,
+    sender: Option<&store::GNBAddress>,
+    responses: Vec<ngap_handlers::ByteResponse>,
+) {
+    for return_buf in responses {
+        let Some(destination) = resolve_destination(store, sender, &return_buf.destination) else {
+            warn!(
+                "Dropping response to unknown gNB {:?}",
+                return_buf.destination
+            );
+            continue;
+        };
         let return_buf = [
-            &mut *frontend_id,
-            &mut vec![return_buf.sctp_stream],
-            &mut return_buf.buf,
+            &destination.frontend_id[..],
+            &[return_buf.sctp_stream],
+            &return_buf.buf,
         ]
         .concat();
-        socket.send_to(&return_buf, src).unwrap();
+        socket.send_to(&return_buf, destination.frontend).unwrap();
+    }
+}
+
+/// Find the association of the gNB a response is for. Other gNBs than the
+/// sender must have completed NG Setup, otherwise there is no frontend
+/// known to reach them.
+fn resolve_destination(
+    store: &store::Store,
+    sender: Option<&store::GNBAddress>,
+    destination: &ngap_handlers::Destination,
+) -> Option<store::GNBAddress> {
+    match destination {
+        ngap_handlers::Destination::Sender => sender.copied(),
+        ngap_handlers::Destination::Association(address) => {
+            store.get_gnb(address).map(|gnb| gnb.address)
+        }
+        ngap_handlers::Destination::GlobalGNBID {
+            plmn_identity,
+            gnb_id,
+        } => store.find_gnb(plmn_identity, gnb_id).map(|gnb| gnb.address),
     }
 }
 
 fn ngap_handler_entrypoint(
     config: &config::CoreKubeConfig,
+    store: &store::Store,
+    gnb: &store::GNBAddress,
     buf: &[u8],
 ) -> Vec<ngap_handlers::ByteResponse> {
     // This is a placeholder for the NGAP handler
@@ -104,26 +237,36 @@ fn ngap_handler_entrypoint(
     debug!("NGAP: {:?}", buf);
 
     let mut codec_data = PerCodecData::from_slice_aper(&buf);
-    let ngap_pdu = ngap::NGAP_PDU::aper_decode(&mut codec_data).expect("Error decoding NGAP PDU");
+    let ngap_pdu = match ngap::NGAP_PDU::aper_decode(&mut codec_data) {
+        Ok(ngap_pdu) => ngap_pdu,
+        Err(e) => {
+            warn!("Error decoding NGAP PDU from gNB {:?}: {}", gnb, e);
+            return encode_responses(ngap_handlers::handle_undecodable_pdu(buf));
+        }
+    };
 
     let responses = match ngap_pdu {
         ngap::NGAP_PDU::InitiatingMessage(init_msg) => {
-            ngap_initiating_message_handler(config, init_msg)
+            ngap_initiating_message_handler(config, store, gnb, init_msg)
         }
         ngap::NGAP_PDU::SuccessfulOutcome(success_outcome) => {
-            info!("SuccessfulOutcome: {:?}", success_outcome);
-            vec![]
+            ngap_successful_outcome_handler(config, store, gnb, success_outcome)
         }
         ngap::NGAP_PDU::UnsuccessfulOutcome(unsuccess_outcome) => {
-            info!("UnsuccessfulOutcome: {:?}", unsuccess_outcome);
-            vec![]
+            ngap_unsuccessful_outcome_handler(config, store, gnb, unsuccess_outcome)
         }
     };
 
-    // Encode each NGAP response to a ByteResponse using the APER codec
+    encode_responses(responses)
+}
+
+/// Encode each NGAP response to a ByteResponse using the APER codec.
+fn encode_responses(
+    responses: Vec<ngap_handlers::NGAPResponse>,
+) -> Vec<ngap_handlers::ByteResponse> {
     let mut codec_data = PerCodecData::default();
     responses
-        .iter()
+        .into_iter()
         .map(|resp| {
             resp.ngap_pdu
                 .aper_encode(&mut codec_data)
@@ -132,6 +275,7 @@ fn ngap_handler_entrypoint(
             ngap_handlers::ByteResponse {
                 sctp_stream: resp.sctp_stream,
                 buf,
+                destination: resp.destination,
             }
         })
         .collect()
@@ -139,23 +283,180 @@ fn ngap_handler_entrypoint(
 
 fn ngap_initiating_message_handler(
     config: &config::CoreKubeConfig,
+    store: &store::Store,
+    gnb: &store::GNBAddress,
     init_msg: ngap::InitiatingMessage,
 ) -> Vec<ngap_handlers::NGAPResponse> {
     trace!("Handling NGAP message of type InitiaingMessage");
 
-    match init_msg.value {
+    let notifications = match ngap_handlers::check_initiating_message(&init_msg) {
+        Ok(notifications) => notifications,
+        Err(rejection) => return rejection,
+    };
+
+    let mut responses = match init_msg.value {
         ngap::InitiatingMessageValue::Id_NGSetup(ng_setup) => {
-            ngap_handlers::handle_setup_request(config, ng_setup)
+            ngap_handlers::handle_setup_request(config, store, gnb, ng_setup)
         }
         ngap::InitiatingMessageValue::Id_InitialUEMessage(ue_msg) => {
-            ngap_handlers::handle_initial_ue_message(config, ue_msg)
+            ngap_handlers::handle_initial_ue_message(config, store, gnb, ue_msg)
         }
         ngap::InitiatingMessageValue::Id_UplinkNASTransport(nas_transport) => {
-            ngap_handlers::handle_uplink_nas_transport(config, nas_transport)
+            ngap_handlers::handle_uplink_nas_transport(config, store, gnb, nas_transport)
+        }
+        ngap::InitiatingMessageValue::Id_UEContextReleaseRequest(release_request) => {
+            ngap_handlers::handle_ue_context_release_request(config, store, gnb, release_request)
+        }
+        ngap::InitiatingMessageValue::Id_PDUSessionResourceModifyIndication(modify_indication) => {
+            ngap_handlers::handle_pdu_session_resource_modify_indication(
+                config,
+                store,
+                gnb,
+                modify_indication,
+            )
+        }
+        ngap::InitiatingMessageValue::Id_PDUSessionResourceNotify(notify) => {
+            ngap_handlers::handle_pdu_session_resource_notify(config, store, gnb, notify)
+        }
+        ngap::InitiatingMessageValue::Id_HandoverPreparation(handover_required) => {
+            ngap_handlers::handle_handover_required(config, store, gnb, handover_required)
+        }
+        ngap::InitiatingMessageValue::Id_HandoverNotification(handover_notify) => {
+            ngap_handlers::handle_handover_notify(config, store, gnb, handover_notify)
+        }
+        ngap::InitiatingMessageValue::Id_HandoverCancel(handover_cancel) => {
+            ngap_handlers::handle_handover_cancel(config, store, gnb, handover_cancel)
+        }
+        ngap::InitiatingMessageValue::Id_PathSwitchRequest(path_switch_request) => {
+            ngap_handlers::handle_path_switch_request(config, store, gnb, path_switch_request)
+        }
+        ngap::InitiatingMessageValue::Id_NGReset(ng_reset) => {
+            ngap_handlers::handle_ng_reset(config, store, gnb, ng_reset)
+        }
+        ngap::InitiatingMessageValue::Id_RANConfigurationUpdate(update) => {
+            ngap_handlers::handle_ran_configuration_update(config, store, gnb, update)
+        }
+        ngap::InitiatingMessageValue::Id_ErrorIndication(error_indication) => {
+            ngap_handlers::handle_error_indication(config, store, gnb, error_indication)
         }
         unhandled => {
             info!("Unknown InitiatingMessage: {:?}", unhandled);
-            vec![]
+            ngap_handlers::handle_unknown_procedure(
+                init_msg.procedure_code.0,
+                ngap::TriggeringMessage::INITIATING_MESSAGE,
+                init_msg.criticality.0,
+            )
         }
-    }
+    };
+    responses.extend(notifications);
+    responses
+}
+
+fn ngap_successful_outcome_handler(
+    config: &config::CoreKubeConfig,
+    store: &store::Store,
+    gnb: &store::GNBAddress,
+    success_outcome: ngap::SuccessfulOutcome,
+) -> Vec<ngap_handlers::NGAPResponse> {
+    trace!("Handling NGAP message of type SuccessfulOutcome");
+
+    let notifications = match ngap_handlers::check_successful_outcome(&success_outcome) {
+        Ok(notifications) => notifications,
+        Err(rejection) => return rejection,
+    };
+
+    let mut responses = match success_outcome.value {
+        ngap::SuccessfulOutcomeValue::Id_InitialContextSetup(ics_response) => {
+            ngap_handlers::handle_initial_context_setup_response(config, store, gnb, ics_response)
+        }
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceSetup(setup_response) => {
+            ngap_handlers::handle_pdu_session_resource_setup_response(
+                config,
+                store,
+                gnb,
+                setup_response,
+            )
+        }
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceModify(modify_response) => {
+            ngap_handlers::handle_pdu_session_resource_modify_response(
+                config,
+                store,
+                gnb,
+                modify_response,
+            )
+        }
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceRelease(release_response) => {
+            ngap_handlers::handle_pdu_session_resource_release_response(
+                config,
+                store,
+                gnb,
+                release_response,
+            )
+        }
+        ngap::SuccessfulOutcomeValue::Id_UEContextRelease(release_complete) => {
+            ngap_handlers::handle_ue_context_release_complete(config, store, gnb, release_complete)
+        }
+        ngap::SuccessfulOutcomeValue::Id_HandoverResourceAllocation(request_ack) => {
+            ngap_handlers::handle_handover_request_acknowledge(config, store, gnb, request_ack)
+        }
+        ngap::SuccessfulOutcomeValue::Id_NGReset(reset_ack) => {
+            ngap_handlers::handle_ng_reset_acknowledge(config, store, gnb, reset_ack)
+        }
+        ngap::SuccessfulOutcomeValue::Id_AMFConfigurationUpdate(update_ack) => {
+            ngap_handlers::handle_amf_configuration_update_acknowledge(
+                config, store, gnb, update_ack,
+            )
+        }
+        unhandled => {
+            info!("Unknown SuccessfulOutcome: {:?}", unhandled);
+            ngap_handlers::handle_unknown_procedure(
+                success_outcome.procedure_code.0,
+                ngap::TriggeringMessage::SUCCESSFUL_OUTCOME,
+                success_outcome.criticality.0,
+            )
+        }
+    };
+    responses.extend(notifications);
+    responses
+}
+
+fn ngap_unsuccessful_outcome_handler(
+    config: &config::CoreKubeConfig,
+    store: &store::Store,
+    gnb: &store::GNBAddress,
+    unsuccess_outcome: ngap::UnsuccessfulOutcome,
+) -> Vec<ngap_handlers::NGAPResponse> {
+    trace!("Handling NGAP message of type UnsuccessfulOutcome");
+
+    let notifications = match ngap_handlers::check_unsuccessful_outcome(&unsuccess_outcome) {
+        Ok(notifications) => notifications,
+        Err(rejection) => return rejection,
+    };
+
+    let mut responses = match unsuccess_outcome.value {
+        ngap::UnsuccessfulOutcomeValue::Id_InitialContextSetup(ics_failure) => {
+            ngap_handlers::handle_initial_context_setup_failure(config, store, gnb, ics_failure)
+        }
+        ngap::UnsuccessfulOutcomeValue::Id_HandoverResourceAllocation(failure) => {
+            ngap_handlers::handle_handover_failure(config, store, gnb, failure)
+        }
+        ngap::UnsuccessfulOutcomeValue::Id_AMFConfigurationUpdate(update_failure) => {
+            ngap_handlers::handle_amf_configuration_update_failure(
+                config,
+                store,
+                gnb,
+                update_failure,
+            )
+        }
+        unhandled => {
+            info!("Unknown UnsuccessfulOutcome: {:?}", unhandled);
+            ngap_handlers::handle_unknown_procedure(
+                unsuccess_outcome.procedure_code.0,
+                ngap::TriggeringMessage::UNSUCCESSFULL_OUTCOME,
+                unsuccess_outcome.criticality.0,
+            )
+        }
+    };
+    responses.extend(notifications);
+    responses
 }
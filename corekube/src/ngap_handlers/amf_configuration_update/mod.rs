@@ -0,0 +1,127 @@
+use log::{error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::overload::relative_amf_capacity;
+use super::setup_request::{build_plmn_support_list, build_served_guami_list};
+use super::{Destination, NGAPResponse};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct AMFConfigurationUpdateAcknowledgeIEs
+        from AMFConfigurationUpdateAcknowledge(
+            AMFConfigurationUpdateAcknowledgeProtocolIEs_EntryValue
+        )
+    {
+        mandatory {}
+        optional {
+            failed_list: Id_AMF_TNLAssociationFailedToSetupList(ngap::TNLAssociationList),
+        }
+    }
+}
+
+protocol_ies! {
+    struct AMFConfigurationUpdateFailureIEs
+        from AMFConfigurationUpdateFailure(AMFConfigurationUpdateFailureProtocolIEs_EntryValue)
+    {
+        mandatory {}
+        optional {
+            cause: Id_Cause(ngap::Cause),
+            time_to_wait: Id_TimeToWait(ngap::TimeToWait),
+        }
+    }
+}
+
+/// Push the configuration of the AMF to every gNB that completed NG Setup,
+/// see TS 38.413 section 8.7.3. This is how the gNBs learn about changes to
+/// the served GUAMIs, the supported PLMNs and slices, or the relative
+/// capacity of the AMF, which is lowered during an overload.
+pub fn update_amf_configuration(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+) -> Vec<NGAPResponse> {
+    let gnbs = store.gnbs();
+    info!("Sending the AMF configuration to {} gNB(s)", gnbs.len());
+    let relative_amf_capacity = relative_amf_capacity(config, store);
+    gnbs.into_iter()
+        .map(|gnb| NGAPResponse {
+            sctp_stream: 0,
+            ngap_pdu: build_amf_configuration_update(config, relative_amf_capacity),
+            destination: Destination::Association(gnb.address),
+        })
+        .collect()
+}
+
+pub fn handle_amf_configuration_update_acknowledge(
+    _config: &crate::config::CoreKubeConfig,
+    _store: &Store,
+    gnb: &GNBAddress,
+    update_ack: ngap::AMFConfigurationUpdateAcknowledge,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type AMFConfigurationUpdateAcknowledge");
+
+    let AMFConfigurationUpdateAcknowledgeIEs { failed_list } =
+        match AMFConfigurationUpdateAcknowledgeIEs::try_from(update_ack) {
+            Ok(ies) => ies,
+            Err(e) => {
+                error!("{}", e);
+                return vec![];
+            }
+        };
+    if let Some(failed_list) = failed_list {
+        warn!(
+            "gNB {:?} failed to set up TNL associations: {:?}",
+            gnb, failed_list
+        );
+    }
+
+    info!("gNB {:?} acknowledged the AMF configuration update", gnb);
+    vec![]
+}
+
+pub fn handle_amf_configuration_update_failure(
+    _config: &crate::config::CoreKubeConfig,
+    _store: &Store,
+    gnb: &GNBAddress,
+    update_failure: ngap::AMFConfigurationUpdateFailure,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type AMFConfigurationUpdateFailure");
+
+    let AMFConfigurationUpdateFailureIEs {
+        cause,
+        time_to_wait,
+    } = match AMFConfigurationUpdateFailureIEs::try_from(update_failure) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+
+    warn!(
+        "gNB {:?} rejected the AMF configuration update with cause {:?}, time to wait {:?}",
+        gnb, cause, time_to_wait
+    );
+    vec![]
+}
+
+fn build_amf_configuration_update(
+    config: &crate::config::CoreKubeConfig,
+    relative_amf_capacity: u8,
+) -> ngap::NGAP_PDU {
+    trace!("Building AMFConfigurationUpdate");
+
+    use ngap::AMFConfigurationUpdateProtocolIEs_EntryValue as Value;
+    message::<ngap::AMFConfigurationUpdate>()
+        .ie(Value::Id_AMFName(ngap::AMFName(config.amf_name.to_owned())))
+        .ie(Value::Id_ServedGUAMIList(build_served_guami_list(config)))
+        .ie(Value::Id_RelativeAMFCapacity(ngap::RelativeAMFCapacity(
+            relative_amf_capacity,
+        )))
+        .ie(Value::Id_PLMNSupportList(build_plmn_support_list(config)))
+        .build()
+}
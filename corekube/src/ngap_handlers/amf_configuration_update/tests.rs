@@ -0,0 +1,58 @@
+use super::*;
+
+#[test]
+fn test_update_amf_configuration() {
+    let mut config = crate::config::CoreKubeConfig::default();
+    config.relative_amf_capacity = 10;
+    let store = Store::default();
+    assert!(update_amf_configuration(&config, &store).is_empty());
+
+    let target = crate::tests::test_target_gnb();
+    store.put_gnb(target.clone());
+    let result = update_amf_configuration(&config, &store);
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(target.address)
+    );
+
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_AMFConfigurationUpdate(update),
+        ..
+    }) = &result[0].ngap_pdu
+    else {
+        panic!("Response is not an AMFConfigurationUpdate");
+    };
+    assert!(update.protocol_i_es.0.iter().any(|protocol_ie| matches!(
+        protocol_ie.value,
+        ngap::AMFConfigurationUpdateProtocolIEs_EntryValue::Id_RelativeAMFCapacity(
+            ngap::RelativeAMFCapacity(10)
+        )
+    )));
+}
+
+#[test]
+fn test_amf_configuration_update_failure() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    let failure = ngap::AMFConfigurationUpdateFailure {
+        protocol_i_es: ngap::AMFConfigurationUpdateFailureProtocolIEs(vec![
+            ngap::AMFConfigurationUpdateFailureProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::AMFConfigurationUpdateFailureProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::Misc(ngap::CauseMisc(ngap::CauseMisc::UNSPECIFIED)),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_amf_configuration_update_failure(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        failure,
+    );
+    assert!(result.is_empty());
+}
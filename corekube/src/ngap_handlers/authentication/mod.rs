@@ -0,0 +1,379 @@
+//! Identification, authentication and security mode control of a UE that
+//! registers without a NAS security context the AMF knows, see TS 24.501
+//! sections 5.4.1.3, 5.4.2 and 5.4.3.
+//!
+//! The UE is identified by its SUCI, asking for it with an Identity Request
+//! if needed, and authenticated with 5G AKA, see [`crate::ausf`]. The KAMF
+//! this yields keys the NAS security context that the Security Mode Command
+//! takes into use. The Registration Request is held in the UE context
+//! meanwhile, and accepted once the Security Mode Complete comes in.
+//!
+//! A UE that registers for emergency services is not identified if it did
+//! not send a SUCI, and is given a security context with the null
+//! algorithms if it can not be authenticated.
+
+use log::{debug, error, info, trace};
+use nas::fgmm::{
+    AuthenticationFailure, AuthenticationReject, AuthenticationRequest, AuthenticationResponse,
+    IdentityRequest, IdentityResponse, RegistrationReject, RegistrationRequest,
+    SecurityModeCommand, REGISTRATION_TYPE_EMERGENCY,
+};
+use nas::security::SecurityContext;
+use nas::MobilityManagementCause;
+use ngap_asn1 as ngap;
+
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::nas_timers::guard_dl_nas_message;
+use super::ue_context_release::build_ue_context_release_command;
+use super::uplink_nas_transport::send_mobility_management_status;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::ausf;
+use crate::store::{GNBAddress, RMState, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+/// ngKSI value meaning that the UE has no NAS security context.
+const NGKSI_NO_KEY: u8 = 0x07;
+
+/// Start the registration of a UE that could not be identified by a
+/// 5G-GUTI, creating a context for it that is kept until it is registered
+/// or released.
+pub(super) fn authenticate_new_ue(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    ran_ue_ngap_id: u32,
+    tai: TAI,
+    request: RegistrationRequest,
+) -> Vec<NGAPResponse> {
+    let mut ue = UEContext::new(store.allocate_amf_ue_ngap_id(), ran_ue_ngap_id, *gnb);
+    ue.rm_state = RMState::Deregistered;
+    ue.tai = Some(tai);
+
+    let suci = request.suci.clone();
+    ue.pending_registration = Some(request);
+    let responses = match suci.map(|suci| (suci.supi(), suci)) {
+        Some((Some(supi), _)) => {
+            ue.supi = Some(supi);
+            authenticate(config, store, &mut ue)
+        }
+        _ if is_emergency(&ue) => accept_unauthenticated_emergency(config, store, &mut ue),
+        Some((None, suci)) => {
+            info!(
+                "SUCI protection scheme {} is not supported",
+                suci.protection_scheme
+            );
+            reject_registration(&mut ue, MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED)
+        }
+        None => {
+            debug!("Asking UE {} for its SUCI", ue.amf_ue_ngap_id);
+            send_plain(config, store, &mut ue, IdentityRequest.encode())
+        }
+    };
+    store.put_ue(ue);
+    responses
+}
+
+/// Challenge a UE whose SUPI is known with a new authentication vector.
+fn authenticate(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+) -> Vec<NGAPResponse> {
+    let Some(supi) = ue.supi.as_deref() else {
+        return vec![];
+    };
+    let Some(vector) = ausf::authentication_vector(config, store, supi) else {
+        if is_emergency(ue) {
+            return accept_unauthenticated_emergency(config, store, ue);
+        }
+        return reject_registration(ue, MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED);
+    };
+    debug!("Authenticating UE {} as {}", ue.amf_ue_ngap_id, supi);
+
+    let request = AuthenticationRequest {
+        ngksi: new_ngksi(ue),
+        rand: vector.rand,
+        autn: vector.autn,
+    };
+    ue.authentication = Some(vector);
+    send_plain(config, store, ue, request.encode())
+}
+
+/// Whether the UE is registering for emergency services.
+fn is_emergency(ue: &UEContext) -> bool {
+    ue.pending_registration
+        .as_ref()
+        .is_some_and(|request| request.registration_type == REGISTRATION_TYPE_EMERGENCY)
+}
+
+/// Let a UE that registers for emergency services but can not be
+/// authenticated go on with the null algorithms, see TS 33.501 section
+/// 10.2.2. The SUPI it claimed is not used.
+fn accept_unauthenticated_emergency(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+) -> Vec<NGAPResponse> {
+    info!(
+        "UE {} registers for emergency services without authentication",
+        ue.amf_ue_ngap_id
+    );
+    ue.supi = None;
+    ue.authentication = None;
+    let security = SecurityContext::null(new_ngksi(ue), ue_security_capability(ue));
+    send_security_mode_command(config, store, ue, security)
+}
+
+/// The ngKSI of the NAS security context being set up, which has to differ
+/// from the one of any context the UE still has.
+fn new_ngksi(ue: &UEContext) -> u8 {
+    match ue
+        .pending_registration
+        .as_ref()
+        .map(|request| request.ngksi & 0x07)
+    {
+        Some(current) if current != NGKSI_NO_KEY => (current + 1) % NGKSI_NO_KEY,
+        _ => 0,
+    }
+}
+
+/// Handle the SUCI that a UE sent in answer to the Identity Request.
+pub(super) fn handle_identity_response(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type IdentityResponse");
+
+    if ue.supi.is_some() || ue.pending_registration.is_none() {
+        info!("Unexpected Identity Response from UE {}", ue.amf_ue_ngap_id);
+        return vec![];
+    }
+    let Some(response) = IdentityResponse::decode(nas_message) else {
+        error!("Could not decode Identity Response");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+
+    match response.suci.and_then(|suci| suci.supi()) {
+        Some(supi) => {
+            ue.supi = Some(supi);
+            authenticate(config, store, ue)
+        }
+        None => {
+            info!("UE {} did not send a usable SUCI", ue.amf_ue_ngap_id);
+            reject_registration(ue, MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED)
+        }
+    }
+}
+
+/// Check the RES* of the UE, and take the NAS security context it
+/// authenticates into use with a Security Mode Command.
+pub(super) fn handle_authentication_response(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type AuthenticationResponse");
+
+    let (Some(vector), Some(supi)) = (ue.authentication.take(), ue.supi.clone()) else {
+        info!(
+            "Authentication Response from UE {} without a challenge",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    };
+    let Some(response) = AuthenticationResponse::decode(nas_message) else {
+        error!("Could not decode Authentication Response");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+    if response.res_star != Some(vector.xres_star) {
+        info!("Authentication of UE {} failed", ue.amf_ue_ngap_id);
+        if is_emergency(ue) {
+            return accept_unauthenticated_emergency(config, store, ue);
+        }
+        return release(
+            ue,
+            Some(AuthenticationReject.encode()),
+            ngap::CauseNas::AUTHENTICATION_FAILURE,
+        );
+    }
+    info!("UE {} authenticated as {}", ue.amf_ue_ngap_id, supi);
+
+    let Some(security) = SecurityContext::new(
+        new_ngksi(ue),
+        ausf::kamf(&vector, &supi),
+        ue_security_capability(ue),
+    ) else {
+        return reject_registration(
+            ue,
+            MobilityManagementCause::UE_SECURITY_CAPABILITIES_MISMATCH,
+        );
+    };
+    send_security_mode_command(config, store, ue, security)
+}
+
+/// The UE security capability the UE sent in its Registration Request.
+fn ue_security_capability(ue: &UEContext) -> Vec<u8> {
+    ue.pending_registration
+        .as_ref()
+        .and_then(|request| request.ue_security_capability.clone())
+        .unwrap_or_default()
+}
+
+/// Take a new NAS security context into use with a Security Mode Command.
+fn send_security_mode_command(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    mut security: SecurityContext,
+) -> Vec<NGAPResponse> {
+    // The UE is asked for its Registration Request again, as it could only
+    // send the cleartext IEs of it without NAS security
+    let command = SecurityModeCommand {
+        ciphering_algorithm: security.ciphering_algorithm as u8,
+        integrity_algorithm: security.integrity_algorithm as u8,
+        ngksi: security.ngksi,
+        replayed_ue_security_capability: security.ue_security_capability.clone(),
+        request_initial_nas_message: true,
+    }
+    .encode();
+    let nas_pdu = security.protect(
+        &command,
+        nas::SecurityHeader::IntegrityProtectedWithNewSecurityContext,
+    );
+    ue.security = Some(security);
+    guard_dl_nas_message(config, store, ue, command);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Handle a UE that did not accept the challenge, challenging it again if
+/// it only had a different SQN.
+pub(super) fn handle_authentication_failure(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type AuthenticationFailure");
+
+    let (Some(vector), Some(supi)) = (ue.authentication.take(), ue.supi.clone()) else {
+        info!(
+            "Authentication Failure from UE {} without a challenge",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    };
+    let Some(failure) = AuthenticationFailure::decode(nas_message) else {
+        error!("Could not decode Authentication Failure");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+    info!(
+        "UE {} rejected the challenge with cause {:?}",
+        ue.amf_ue_ngap_id, failure.cause
+    );
+
+    if let (MobilityManagementCause::SYNCH_FAILURE, Some(auts)) = (failure.cause, failure.auts) {
+        if ausf::resynchronise(config, store, &supi, &vector.rand, &auts) {
+            return authenticate(config, store, ue);
+        }
+    }
+    if is_emergency(ue) {
+        return accept_unauthenticated_emergency(config, store, ue);
+    }
+    release(ue, None, ngap::CauseNas::AUTHENTICATION_FAILURE)
+}
+
+/// Give up on a UE that did not accept the NAS security context.
+pub(super) fn handle_security_mode_reject(ue: &mut UEContext) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type SecurityModeReject");
+
+    if ue.rm_state == RMState::Registered {
+        info!(
+            "Unexpected Security Mode Reject from UE {}",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    }
+    info!("UE {} rejected security mode control", ue.amf_ue_ngap_id);
+    ue.security = None;
+    release(ue, None, ngap::CauseNas::UNSPECIFIED)
+}
+
+/// Send a plain NAS message to a UE without a NAS security context, keeping
+/// it for retransmission if it is answered.
+fn send_plain(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: Vec<u8>,
+) -> Vec<NGAPResponse> {
+    let ngap_pdu = build_downlink_nas_transport(ue, nas_message.clone());
+    guard_dl_nas_message(config, store, ue, nas_message);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Sender,
+    }]
+}
+
+/// Reject the Registration Request of a UE that is not registered yet.
+///
+/// A UE that can not be identified or has no subscription is rejected with
+/// a cause that lets it try again later. Illegal UE would make it consider
+/// its USIM invalid until it is switched off, see TS 24.501 section
+/// 5.5.1.2.5.
+fn reject_registration(ue: &mut UEContext, cause: MobilityManagementCause) -> Vec<NGAPResponse> {
+    let reject = RegistrationReject {
+        cause,
+        t3346: None,
+        rejected_nssai: vec![],
+    };
+    release(ue, Some(reject.encode()), ngap::CauseNas::NORMAL_RELEASE)
+}
+
+/// Release the signalling connection of a UE that is not registered, after
+/// sending it a last plain NAS message if there is one. Its context goes
+/// with the connection.
+fn release(ue: &mut UEContext, nas_message: Option<Vec<u8>>, cause: u8) -> Vec<NGAPResponse> {
+    ue.pending_registration = None;
+    ue.authentication = None;
+    ue.last_dl_nas = None;
+
+    let mut responses: Vec<NGAPResponse> = nas_message
+        .into_iter()
+        .map(|nas_message| NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_downlink_nas_transport(ue, nas_message),
+            destination: Destination::Sender,
+        })
+        .collect();
+    responses.push(NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_ue_context_release_command(
+            ue.amf_ue_ngap_id,
+            Some(ue.ran_ue_ngap_id),
+            ngap::Cause::Nas(ngap::CauseNas(cause)),
+        ),
+        destination: Destination::Sender,
+    });
+    responses
+}
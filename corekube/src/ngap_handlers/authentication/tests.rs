@@ -0,0 +1,368 @@
+use super::*;
+use crate::config::SubscriberConfig;
+use crate::ngap_handlers::registration::handle_initial_registration_request;
+use crate::ngap_handlers::uplink_nas_transport::handle_uplink_nas_transport;
+use nas::aka::{AuthenticationVector, Milenage};
+use nas::fgmm::REGISTRATION_TYPE_INITIAL;
+
+const K: &str = "465b5ce8b199b49faa5f0a2ee238a6bc";
+const OPC: &str = "cd63cb71954a9f4e48a5994e37a02baf";
+
+/// SUCI of IMSI 208930000000001 with the null scheme.
+const SUCI: [u8; 13] = [
+    0x01, 0x02, 0xf8, 0x39, 0xf0, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// A configuration with test set 1 of TS 35.207 as the subscription of
+/// IMSI 208930000000001.
+fn test_config() -> crate::config::CoreKubeConfig {
+    let mut config = crate::config::CoreKubeConfig::default();
+    config.subscribers.insert(
+        "imsi-208930000000001".to_string(),
+        SubscriberConfig {
+            k: hex::decode(K).unwrap().try_into().unwrap(),
+            opc: hex::decode(OPC).unwrap().try_into().unwrap(),
+        },
+    );
+    config
+}
+
+fn test_tai() -> TAI {
+    TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, 0x01],
+    }
+}
+
+/// A Registration Request of the given type without a NAS security
+/// context, with the SUCI if given.
+fn build_registration_request(registration_type: u8, suci: Option<&[u8]>) -> Vec<u8> {
+    let identity: &[u8] = suci.unwrap_or(&[0x00]);
+    [
+        &[
+            0x7E,
+            0x00,
+            0x41,
+            0x78 | registration_type,
+            0x00,
+            identity.len() as u8,
+        ][..],
+        identity,
+        &[0x2E, 0x02, 0x80, 0x20],
+    ]
+    .concat()
+}
+
+fn register(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    suci: Option<&[u8]>,
+) -> Vec<NGAPResponse> {
+    let plain = build_registration_request(suci);
+    handle_initial_registration_request(
+        config,
+        store,
+        &crate::tests::test_gnb(),
+        10,
+        test_tai(),
+        &plain,
+        &plain,
+    )
+}
+
+fn uplink(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    amf_ue_ngap_id: u64,
+    nas_pdu: Vec<u8>,
+) -> Vec<NGAPResponse> {
+    let uplink_nas = ngap::UplinkNASTransport {
+        protocol_i_es: ngap::UplinkNASTransportProtocolIEs(vec![
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(amf_ue_ngap_id),
+                ),
+            },
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_NAS_PDU),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_NAS_PDU(ngap::NAS_PDU(
+                    nas_pdu,
+                )),
+            },
+        ]),
+    };
+    handle_uplink_nas_transport(config, store, &crate::tests::test_gnb(), uplink_nas)
+}
+
+/// The AMF_UE_NGAP_ID and NAS PDU of a DownlinkNASTransport.
+fn downlink_nas(response: &NGAPResponse) -> (u64, Vec<u8>) {
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(transport),
+        ..
+    }) = &response.ngap_pdu
+    else {
+        panic!("Not a DownlinkNASTransport");
+    };
+    let mut amf_ue_ngap_id = None;
+    let mut nas_pdu = None;
+    for protocol_ie in &transport.protocol_i_es.0 {
+        match &protocol_ie.value {
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(id) => {
+                amf_ue_ngap_id = Some(id.0)
+            }
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_NAS_PDU(pdu) => {
+                nas_pdu = Some(pdu.0.clone())
+            }
+            _ => {}
+        }
+    }
+    (amf_ue_ngap_id.unwrap(), nas_pdu.unwrap())
+}
+
+fn is_release(response: &NGAPResponse) -> bool {
+    matches!(
+        &response.ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(init_msg)
+            if init_msg.procedure_code.0 == ngap::ID_UE_CONTEXT_RELEASE
+    )
+}
+
+/// Answer an Authentication Request the way the UE would, returning the
+/// vector it computed.
+fn answer_challenge(request: &[u8]) -> AuthenticationVector {
+    assert_eq!(request[..3], [0x7E, 0x00, 0x56]);
+    let rand: [u8; 16] = request[8..24].try_into().unwrap();
+    let autn = &request[26..42];
+
+    let milenage = Milenage::new(
+        hex::decode(K).unwrap().try_into().unwrap(),
+        hex::decode(OPC).unwrap().try_into().unwrap(),
+    );
+    let (_, _, _, ak) = milenage.f2345(&rand);
+    let mut sqn = [0u8; 8];
+    for (i, octet) in sqn[2..].iter_mut().enumerate() {
+        *octet = autn[i] ^ ak[i];
+    }
+    let vector = AuthenticationVector::generate(
+        &milenage,
+        u64::from_be_bytes(sqn),
+        rand,
+        "5G:mnc093.mcc208.3gppnetwork.org",
+    );
+    assert_eq!(vector.autn, autn);
+    vector
+}
+
+fn authentication_response(res_star: &[u8; 16]) -> Vec<u8> {
+    [&[0x7E, 0x00, 0x57, 0x2D, 0x10][..], res_star].concat()
+}
+
+#[test]
+fn test_authentication_and_security_mode_control() {
+    let config = test_config();
+    let store = Store::default();
+
+    let result = register(&config, &store, REGISTRATION_TYPE_INITIAL, Some(&SUCI));
+    assert_eq!(result.len(), 1);
+    let (amf_ue_ngap_id, request) = downlink_nas(&result[0]);
+    let ue = store.get_ue(amf_ue_ngap_id).unwrap();
+    assert_eq!(ue.supi.as_deref(), Some("imsi-208930000000001"));
+    assert_eq!(ue.rm_state, RMState::Deregistered);
+    assert!(ue.security.is_none());
+    assert!(store
+        .timer_due(amf_ue_ngap_id, crate::store::NasTimer::T3560)
+        .is_some());
+
+    // The UE has no NAS security context, so the new one gets ngKSI 0
+    assert_eq!(request[3], 0x00);
+    let vector = answer_challenge(&request);
+    let result = uplink(
+        &config,
+        &store,
+        amf_ue_ngap_id,
+        authentication_response(&vector.xres_star),
+    );
+    assert_eq!(result.len(), 1);
+
+    // The Security Mode Command is integrity protected with the new context,
+    // and asks for the Registration Request again
+    let (_, command) = downlink_nas(&result[0]);
+    let kamf = vector.kamf("208930000000001");
+    let knas_int = nas::security::derive_nas_key(&kamf, 0x02, 2);
+    assert_eq!(command[..2], [0x7E, 0x03]);
+    assert_eq!(
+        command[2..6],
+        nas::security::nia2(&knas_int, 0, 0, 1, &command[6..])
+    );
+    // NEA0 and NIA2, ngKSI 0 and the capability of the UE replayed
+    assert_eq!(command[7..14], [0x7E, 0x00, 0x5D, 0x02, 0x00, 0x02, 0x80]);
+
+    // The Security Mode Complete carries the Registration Request, after
+    // which the registration is accepted
+    let container = build_registration_request(REGISTRATION_TYPE_INITIAL, Some(&SUCI));
+    let mut payload = vec![0x00, 0x7E, 0x00, 0x5E, 0x71, 0x00, container.len() as u8];
+    payload.extend_from_slice(&container);
+    let mac = nas::security::nia2(&knas_int, 0, 0, 0, &payload);
+    let complete = [&[0x7E, 0x03][..], &mac, &payload].concat();
+    let result = uplink(&config, &store, amf_ue_ngap_id, complete);
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_InitialContextSetup(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(amf_ue_ngap_id).unwrap();
+    assert_eq!(ue.rm_state, RMState::Registered);
+    assert!(ue.tmsi.is_some());
+    assert!(ue.pending_registration.is_none());
+    assert!(ue.authentication.is_none());
+    assert_eq!(ue.security.unwrap().kamf, kamf);
+}
+
+#[test]
+fn test_authentication_failure() {
+    let config = test_config();
+    let store = Store::default();
+
+    let result = register(&config, &store, REGISTRATION_TYPE_INITIAL, Some(&SUCI));
+    let (amf_ue_ngap_id, request) = downlink_nas(&result[0]);
+    let mut vector = answer_challenge(&request);
+    vector.xres_star[0] ^= 0x01;
+
+    // A wrong RES* is rejected, and the signalling connection released
+    let result = uplink(
+        &config,
+        &store,
+        amf_ue_ngap_id,
+        authentication_response(&vector.xres_star),
+    );
+    assert_eq!(result.len(), 2);
+    assert_eq!(downlink_nas(&result[0]).1, vec![0x7E, 0x00, 0x58]);
+    assert!(is_release(&result[1]));
+    assert!(store
+        .get_ue(amf_ue_ngap_id)
+        .unwrap()
+        .pending_registration
+        .is_none());
+}
+
+#[test]
+fn test_synch_failure() {
+    let config = test_config();
+    let store = Store::default();
+
+    let result = register(&config, &store, REGISTRATION_TYPE_INITIAL, Some(&SUCI));
+    let (amf_ue_ngap_id, request) = downlink_nas(&result[0]);
+    let rand: [u8; 16] = request[8..24].try_into().unwrap();
+
+    // The UE is ahead at SQN 0x1000, and is challenged again above it
+    let milenage = Milenage::new(
+        hex::decode(K).unwrap().try_into().unwrap(),
+        hex::decode(OPC).unwrap().try_into().unwrap(),
+    );
+    let sqn_ms = [0, 0, 0, 0, 0x10, 0x00];
+    let ak = milenage.f5_star(&rand);
+    let concealed_sqn: [u8; 6] = std::array::from_fn(|i| sqn_ms[i] ^ ak[i]);
+    let mac_s = milenage.f1_star(&rand, &sqn_ms, &[0; 2]);
+    let failure = [
+        &[0x7E, 0x00, 0x59, 0x15, 0x30, 0x0E][..],
+        &concealed_sqn,
+        &mac_s,
+    ]
+    .concat();
+    let result = uplink(&config, &store, amf_ue_ngap_id, failure);
+    assert_eq!(result.len(), 1);
+    let (_, request) = downlink_nas(&result[0]);
+    answer_challenge(&request);
+    assert_ne!(request[8..24], rand);
+}
+
+#[test]
+fn test_identity_request() {
+    let config = test_config();
+    let store = Store::default();
+
+    // Without a SUCI the UE is asked for it
+    let result = register(&config, &store, REGISTRATION_TYPE_INITIAL, None);
+    assert_eq!(result.len(), 1);
+    let (amf_ue_ngap_id, request) = downlink_nas(&result[0]);
+    assert_eq!(request, vec![0x7E, 0x00, 0x5B, 0x01]);
+    assert!(store
+        .timer_due(amf_ue_ngap_id, crate::store::NasTimer::T3570)
+        .is_some());
+
+    let response = [&[0x7E, 0x00, 0x5C, 0x00, 0x0D][..], &SUCI].concat();
+    let result = uplink(&config, &store, amf_ue_ngap_id, response);
+    assert_eq!(result.len(), 1);
+    let (_, request) = downlink_nas(&result[0]);
+    answer_challenge(&request);
+    assert_eq!(
+        store.get_ue(amf_ue_ngap_id).unwrap().supi.as_deref(),
+        Some("imsi-208930000000001")
+    );
+}
+
+#[test]
+fn test_unknown_subscriber() {
+    let store = Store::default();
+
+    let result = register(
+        &crate::config::CoreKubeConfig::default(),
+        &store,
+        REGISTRATION_TYPE_INITIAL,
+        Some(&SUCI),
+    );
+    assert_eq!(result.len(), 2);
+    assert_eq!(downlink_nas(&result[0]).1, vec![0x7E, 0x00, 0x44, 0x6F]);
+    assert!(is_release(&result[1]));
+}
+
+#[test]
+fn test_unauthenticated_emergency_registration() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    // A UE without a SUCI is not asked for it, and gets the null algorithms
+    let result = register(&config, &store, REGISTRATION_TYPE_EMERGENCY, None);
+    assert_eq!(result.len(), 1);
+    let (amf_ue_ngap_id, command) = downlink_nas(&result[0]);
+    assert_eq!(command[..6], [0x7E, 0x03, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(command[7..12], [0x7E, 0x00, 0x5D, 0x00, 0x00]);
+
+    let payload = [0x00, 0x7E, 0x00, 0x5E];
+    let complete = [&[0x7E, 0x03, 0x00, 0x00, 0x00, 0x00][..], &payload].concat();
+    let result = uplink(&config, &store, amf_ue_ngap_id, complete);
+    assert_eq!(result.len(), 1);
+    let ue = store.get_ue(amf_ue_ngap_id).unwrap();
+    assert_eq!(ue.rm_state, RMState::Registered);
+    assert_eq!(ue.supi, None);
+}
+
+#[test]
+fn test_emergency_registration_of_unknown_subscriber() {
+    let store = Store::default();
+
+    let result = register(
+        &crate::config::CoreKubeConfig::default(),
+        &store,
+        REGISTRATION_TYPE_EMERGENCY,
+        Some(&SUCI),
+    );
+    assert_eq!(result.len(), 1);
+    let (amf_ue_ngap_id, command) = downlink_nas(&result[0]);
+    assert_eq!(command[7..12], [0x7E, 0x00, 0x5D, 0x00, 0x00]);
+    assert_eq!(store.get_ue(amf_ue_ngap_id).unwrap().supi, None);
+}
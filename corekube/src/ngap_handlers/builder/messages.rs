@@ -0,0 +1,495 @@
+//! The ProtocolIEs of each message the AMF sends as listed in TS 38.413
+//! section 9.2, with their ID and criticality, and the procedure the
+//! message belongs to.
+
+use ngap_asn1 as ngap;
+
+use super::{Message, ProtocolIE};
+
+outgoing_message! {
+    AMFConfigurationUpdate(
+        AMFConfigurationUpdateProtocolIEs,
+        AMFConfigurationUpdateProtocolIEs_Entry,
+        AMFConfigurationUpdateProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_AMFConfigurationUpdate,
+        ID_AMF_CONFIGURATION_UPDATE,
+        REJECT,
+    ),
+    {
+        Id_AMFName => (ID_AMF_NAME, REJECT),
+        Id_ServedGUAMIList => (ID_SERVED_GUAMI_LIST, REJECT),
+        Id_RelativeAMFCapacity => (ID_RELATIVE_AMF_CAPACITY, IGNORE),
+        Id_PLMNSupportList => (ID_PLMN_SUPPORT_LIST, REJECT),
+        Id_AMF_TNLAssociationToAddList => (ID_AMF_TNL_ASSOCIATION_TO_ADD_LIST, IGNORE),
+        Id_AMF_TNLAssociationToRemoveList => (ID_AMF_TNL_ASSOCIATION_TO_REMOVE_LIST, IGNORE),
+        Id_AMF_TNLAssociationToUpdateList => (ID_AMF_TNL_ASSOCIATION_TO_UPDATE_LIST, IGNORE),
+    }
+}
+
+outgoing_message! {
+    DownlinkNASTransport(
+        DownlinkNASTransportProtocolIEs,
+        DownlinkNASTransportProtocolIEs_Entry,
+        DownlinkNASTransportProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_DownlinkNASTransport,
+        ID_DOWNLINK_NAS_TRANSPORT,
+        IGNORE,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_OldAMF => (ID_OLD_AMF, REJECT),
+        Id_RANPagingPriority => (ID_RAN_PAGING_PRIORITY, IGNORE),
+        Id_NAS_PDU => (ID_NAS_PDU, REJECT),
+        Id_MobilityRestrictionList => (ID_MOBILITY_RESTRICTION_LIST, IGNORE),
+        Id_IndexToRFSP => (ID_INDEX_TO_RFSP, IGNORE),
+        Id_UEAggregateMaximumBitRate => (ID_UE_AGGREGATE_MAXIMUM_BIT_RATE, IGNORE),
+        Id_AllowedNSSAI => (ID_ALLOWED_NSSAI, REJECT),
+    }
+}
+
+outgoing_message! {
+    ErrorIndication(
+        ErrorIndicationProtocolIEs,
+        ErrorIndicationProtocolIEs_Entry,
+        ErrorIndicationProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_ErrorIndication,
+        ID_ERROR_INDICATION,
+        IGNORE,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    HandoverCancelAcknowledge(
+        HandoverCancelAcknowledgeProtocolIEs,
+        HandoverCancelAcknowledgeProtocolIEs_Entry,
+        HandoverCancelAcknowledgeProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_HandoverCancel,
+        ID_HANDOVER_CANCEL,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    HandoverCommand(
+        HandoverCommandProtocolIEs,
+        HandoverCommandProtocolIEs_Entry,
+        HandoverCommandProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_HandoverPreparation,
+        ID_HANDOVER_PREPARATION,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_HandoverType => (ID_HANDOVER_TYPE, REJECT),
+        Id_NASSecurityParametersFromNGRAN => (ID_NAS_SECURITY_PARAMETERS_FROM_NGRAN, REJECT),
+        Id_PDUSessionResourceHandoverList => (ID_PDU_SESSION_RESOURCE_HANDOVER_LIST, IGNORE),
+        Id_PDUSessionResourceToReleaseListHOCmd => (ID_PDU_SESSION_RESOURCE_TO_RELEASE_LIST_HO_CMD, IGNORE),
+        Id_TargetToSource_TransparentContainer => (ID_TARGET_TO_SOURCE_TRANSPARENT_CONTAINER, REJECT),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    HandoverPreparationFailure(
+        HandoverPreparationFailureProtocolIEs,
+        HandoverPreparationFailureProtocolIEs_Entry,
+        HandoverPreparationFailureProtocolIEs_EntryValue,
+    ),
+    UnsuccessfulOutcome(
+        UnsuccessfulOutcomeValue::Id_HandoverPreparation,
+        ID_HANDOVER_PREPARATION,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    HandoverRequest(
+        HandoverRequestProtocolIEs,
+        HandoverRequestProtocolIEs_Entry,
+        HandoverRequestProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_HandoverResourceAllocation,
+        ID_HANDOVER_RESOURCE_ALLOCATION,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_HandoverType => (ID_HANDOVER_TYPE, REJECT),
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_UEAggregateMaximumBitRate => (ID_UE_AGGREGATE_MAXIMUM_BIT_RATE, REJECT),
+        Id_CoreNetworkAssistanceInformationForInactive => (ID_CORE_NETWORK_ASSISTANCE_INFORMATION_FOR_INACTIVE, IGNORE),
+        Id_UESecurityCapabilities => (ID_UE_SECURITY_CAPABILITIES, REJECT),
+        Id_SecurityContext => (ID_SECURITY_CONTEXT, REJECT),
+        Id_NewSecurityContextInd => (ID_NEW_SECURITY_CONTEXT_IND, REJECT),
+        Id_NASC => (ID_NASC, REJECT),
+        Id_PDUSessionResourceSetupListHOReq => (ID_PDU_SESSION_RESOURCE_SETUP_LIST_HO_REQ, REJECT),
+        Id_AllowedNSSAI => (ID_ALLOWED_NSSAI, REJECT),
+        Id_TraceActivation => (ID_TRACE_ACTIVATION, IGNORE),
+        Id_MaskedIMEISV => (ID_MASKED_IMEISV, IGNORE),
+        Id_SourceToTarget_TransparentContainer => (ID_SOURCE_TO_TARGET_TRANSPARENT_CONTAINER, REJECT),
+        Id_MobilityRestrictionList => (ID_MOBILITY_RESTRICTION_LIST, IGNORE),
+        Id_LocationReportingRequestType => (ID_LOCATION_REPORTING_REQUEST_TYPE, IGNORE),
+        Id_RRCInactiveTransitionReportRequest => (ID_RRC_INACTIVE_TRANSITION_REPORT_REQUEST, IGNORE),
+        Id_GUAMI => (ID_GUAMI, REJECT),
+        Id_RedirectionVoiceFallback => (ID_REDIRECTION_VOICE_FALLBACK, IGNORE),
+        Id_CNAssistedRANTuning => (ID_CN_ASSISTED_RAN_TUNING, IGNORE),
+    }
+}
+
+outgoing_message! {
+    InitialContextSetupRequest(
+        InitialContextSetupRequestProtocolIEs,
+        InitialContextSetupRequestProtocolIEs_Entry,
+        InitialContextSetupRequestProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_InitialContextSetup,
+        ID_INITIAL_CONTEXT_SETUP,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_OldAMF => (ID_OLD_AMF, REJECT),
+        Id_UEAggregateMaximumBitRate => (ID_UE_AGGREGATE_MAXIMUM_BIT_RATE, REJECT),
+        Id_CoreNetworkAssistanceInformationForInactive => (ID_CORE_NETWORK_ASSISTANCE_INFORMATION_FOR_INACTIVE, IGNORE),
+        Id_GUAMI => (ID_GUAMI, REJECT),
+        Id_PDUSessionResourceSetupListCxtReq => (ID_PDU_SESSION_RESOURCE_SETUP_LIST_CXT_REQ, REJECT),
+        Id_AllowedNSSAI => (ID_ALLOWED_NSSAI, REJECT),
+        Id_UESecurityCapabilities => (ID_UE_SECURITY_CAPABILITIES, REJECT),
+        Id_SecurityKey => (ID_SECURITY_KEY, REJECT),
+        Id_TraceActivation => (ID_TRACE_ACTIVATION, IGNORE),
+        Id_MobilityRestrictionList => (ID_MOBILITY_RESTRICTION_LIST, IGNORE),
+        Id_UERadioCapability => (ID_UE_RADIO_CAPABILITY, IGNORE),
+        Id_IndexToRFSP => (ID_INDEX_TO_RFSP, IGNORE),
+        Id_MaskedIMEISV => (ID_MASKED_IMEISV, IGNORE),
+        Id_NAS_PDU => (ID_NAS_PDU, IGNORE),
+        Id_EmergencyFallbackIndicator => (ID_EMERGENCY_FALLBACK_INDICATOR, REJECT),
+        Id_RRCInactiveTransitionReportRequest => (ID_RRC_INACTIVE_TRANSITION_REPORT_REQUEST, IGNORE),
+        Id_UERadioCapabilityForPaging => (ID_UE_RADIO_CAPABILITY_FOR_PAGING, IGNORE),
+        Id_RedirectionVoiceFallback => (ID_REDIRECTION_VOICE_FALLBACK, IGNORE),
+        Id_LocationReportingRequestType => (ID_LOCATION_REPORTING_REQUEST_TYPE, IGNORE),
+        Id_CNAssistedRANTuning => (ID_CN_ASSISTED_RAN_TUNING, IGNORE),
+    }
+}
+
+outgoing_message! {
+    NGReset(
+        NGResetProtocolIEs,
+        NGResetProtocolIEs_Entry,
+        NGResetProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_NGReset,
+        ID_NG_RESET,
+        REJECT,
+    ),
+    {
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_ResetType => (ID_RESET_TYPE, REJECT),
+    }
+}
+
+outgoing_message! {
+    NGResetAcknowledge(
+        NGResetAcknowledgeProtocolIEs,
+        NGResetAcknowledgeProtocolIEs_Entry,
+        NGResetAcknowledgeProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_NGReset,
+        ID_NG_RESET,
+        REJECT,
+    ),
+    {
+        Id_UE_associatedLogicalNG_connectionList => (ID_UE_ASSOCIATED_LOGICAL_NG_CONNECTION_LIST, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    NGSetupFailure(
+        NGSetupFailureProtocolIEs,
+        NGSetupFailureProtocolIEs_Entry,
+        NGSetupFailureProtocolIEs_EntryValue,
+    ),
+    UnsuccessfulOutcome(
+        UnsuccessfulOutcomeValue::Id_NGSetup,
+        ID_NG_SETUP,
+        REJECT,
+    ),
+    {
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_TimeToWait => (ID_TIME_TO_WAIT, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    NGSetupResponse(
+        NGSetupResponseProtocolIEs,
+        NGSetupResponseProtocolIEs_Entry,
+        NGSetupResponseProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_NGSetup,
+        ID_NG_SETUP,
+        REJECT,
+    ),
+    {
+        Id_AMFName => (ID_AMF_NAME, REJECT),
+        Id_ServedGUAMIList => (ID_SERVED_GUAMI_LIST, REJECT),
+        Id_RelativeAMFCapacity => (ID_RELATIVE_AMF_CAPACITY, IGNORE),
+        Id_PLMNSupportList => (ID_PLMN_SUPPORT_LIST, REJECT),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+        Id_UERetentionInformation => (ID_UE_RETENTION_INFORMATION, IGNORE),
+    }
+}
+
+outgoing_message! {
+    OverloadStart(
+        OverloadStartProtocolIEs,
+        OverloadStartProtocolIEs_Entry,
+        OverloadStartProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_OverloadStart,
+        ID_OVERLOAD_START,
+        IGNORE,
+    ),
+    {
+        Id_AMFOverloadResponse => (ID_AMF_OVERLOAD_RESPONSE, REJECT),
+        Id_AMFTrafficLoadReductionIndication => (ID_AMF_TRAFFIC_LOAD_REDUCTION_INDICATION, IGNORE),
+        Id_OverloadStartNSSAIList => (ID_OVERLOAD_START_NSSAI_LIST, IGNORE),
+    }
+}
+
+outgoing_message! {
+    PDUSessionResourceModifyConfirm(
+        PDUSessionResourceModifyConfirmProtocolIEs,
+        PDUSessionResourceModifyConfirmProtocolIEs_Entry,
+        PDUSessionResourceModifyConfirmProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_PDUSessionResourceModifyIndication,
+        ID_PDU_SESSION_RESOURCE_MODIFY_INDICATION,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_PDUSessionResourceModifyListModCfm => (ID_PDU_SESSION_RESOURCE_MODIFY_LIST_MOD_CFM, IGNORE),
+        Id_PDUSessionResourceFailedToModifyListModCfm => (ID_PDU_SESSION_RESOURCE_FAILED_TO_MODIFY_LIST_MOD_CFM, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    PDUSessionResourceModifyRequest(
+        PDUSessionResourceModifyRequestProtocolIEs,
+        PDUSessionResourceModifyRequestProtocolIEs_Entry,
+        PDUSessionResourceModifyRequestProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_PDUSessionResourceModify,
+        ID_PDU_SESSION_RESOURCE_MODIFY,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_RANPagingPriority => (ID_RAN_PAGING_PRIORITY, IGNORE),
+        Id_PDUSessionResourceModifyListModReq => (ID_PDU_SESSION_RESOURCE_MODIFY_LIST_MOD_REQ, REJECT),
+    }
+}
+
+outgoing_message! {
+    PDUSessionResourceReleaseCommand(
+        PDUSessionResourceReleaseCommandProtocolIEs,
+        PDUSessionResourceReleaseCommandProtocolIEs_Entry,
+        PDUSessionResourceReleaseCommandProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_PDUSessionResourceRelease,
+        ID_PDU_SESSION_RESOURCE_RELEASE,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_RANPagingPriority => (ID_RAN_PAGING_PRIORITY, IGNORE),
+        Id_NAS_PDU => (ID_NAS_PDU, IGNORE),
+        Id_PDUSessionResourceToReleaseListRelCmd => (ID_PDU_SESSION_RESOURCE_TO_RELEASE_LIST_REL_CMD, REJECT),
+    }
+}
+
+outgoing_message! {
+    PDUSessionResourceSetupRequest(
+        PDUSessionResourceSetupRequestProtocolIEs,
+        PDUSessionResourceSetupRequestProtocolIEs_Entry,
+        PDUSessionResourceSetupRequestProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_PDUSessionResourceSetup,
+        ID_PDU_SESSION_RESOURCE_SETUP,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, REJECT),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, REJECT),
+        Id_RANPagingPriority => (ID_RAN_PAGING_PRIORITY, IGNORE),
+        Id_NAS_PDU => (ID_NAS_PDU, REJECT),
+        Id_PDUSessionResourceSetupListSUReq => (ID_PDU_SESSION_RESOURCE_SETUP_LIST_SU_REQ, REJECT),
+        Id_UEAggregateMaximumBitRate => (ID_UE_AGGREGATE_MAXIMUM_BIT_RATE, IGNORE),
+    }
+}
+
+outgoing_message! {
+    Paging(
+        PagingProtocolIEs,
+        PagingProtocolIEs_Entry,
+        PagingProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_Paging,
+        ID_PAGING,
+        IGNORE,
+    ),
+    {
+        Id_UEPagingIdentity => (ID_UE_PAGING_IDENTITY, IGNORE),
+        Id_PagingDRX => (ID_PAGING_DRX, IGNORE),
+        Id_TAIListForPaging => (ID_TAI_LIST_FOR_PAGING, IGNORE),
+        Id_PagingPriority => (ID_PAGING_PRIORITY, IGNORE),
+        Id_UERadioCapabilityForPaging => (ID_UE_RADIO_CAPABILITY_FOR_PAGING, IGNORE),
+        Id_PagingOrigin => (ID_PAGING_ORIGIN, IGNORE),
+        Id_AssistanceDataForPaging => (ID_ASSISTANCE_DATA_FOR_PAGING, IGNORE),
+    }
+}
+
+outgoing_message! {
+    PathSwitchRequestAcknowledge(
+        PathSwitchRequestAcknowledgeProtocolIEs,
+        PathSwitchRequestAcknowledgeProtocolIEs_Entry,
+        PathSwitchRequestAcknowledgeProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_PathSwitchRequest,
+        ID_PATH_SWITCH_REQUEST,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_UESecurityCapabilities => (ID_UE_SECURITY_CAPABILITIES, REJECT),
+        Id_SecurityContext => (ID_SECURITY_CONTEXT, REJECT),
+        Id_NewSecurityContextInd => (ID_NEW_SECURITY_CONTEXT_IND, REJECT),
+        Id_PDUSessionResourceSwitchedList => (ID_PDU_SESSION_RESOURCE_SWITCHED_LIST, IGNORE),
+        Id_PDUSessionResourceReleasedListPSAck => (ID_PDU_SESSION_RESOURCE_RELEASED_LIST_PS_ACK, IGNORE),
+        Id_AllowedNSSAI => (ID_ALLOWED_NSSAI, REJECT),
+        Id_CoreNetworkAssistanceInformationForInactive => (ID_CORE_NETWORK_ASSISTANCE_INFORMATION_FOR_INACTIVE, IGNORE),
+        Id_RRCInactiveTransitionReportRequest => (ID_RRC_INACTIVE_TRANSITION_REPORT_REQUEST, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+        Id_RedirectionVoiceFallback => (ID_REDIRECTION_VOICE_FALLBACK, IGNORE),
+        Id_CNAssistedRANTuning => (ID_CN_ASSISTED_RAN_TUNING, IGNORE),
+    }
+}
+
+outgoing_message! {
+    PathSwitchRequestFailure(
+        PathSwitchRequestFailureProtocolIEs,
+        PathSwitchRequestFailureProtocolIEs_Entry,
+        PathSwitchRequestFailureProtocolIEs_EntryValue,
+    ),
+    UnsuccessfulOutcome(
+        UnsuccessfulOutcomeValue::Id_PathSwitchRequest,
+        ID_PATH_SWITCH_REQUEST,
+        REJECT,
+    ),
+    {
+        Id_AMF_UE_NGAP_ID => (ID_AMF_UE_NGAP_ID, IGNORE),
+        Id_RAN_UE_NGAP_ID => (ID_RAN_UE_NGAP_ID, IGNORE),
+        Id_PDUSessionResourceReleasedListPSFail => (ID_PDU_SESSION_RESOURCE_RELEASED_LIST_PS_FAIL, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    RANConfigurationUpdateAcknowledge(
+        RANConfigurationUpdateAcknowledgeProtocolIEs,
+        RANConfigurationUpdateAcknowledgeProtocolIEs_Entry,
+        RANConfigurationUpdateAcknowledgeProtocolIEs_EntryValue,
+    ),
+    SuccessfulOutcome(
+        SuccessfulOutcomeValue::Id_RANConfigurationUpdate,
+        ID_RAN_CONFIGURATION_UPDATE,
+        REJECT,
+    ),
+    {
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    RANConfigurationUpdateFailure(
+        RANConfigurationUpdateFailureProtocolIEs,
+        RANConfigurationUpdateFailureProtocolIEs_Entry,
+        RANConfigurationUpdateFailureProtocolIEs_EntryValue,
+    ),
+    UnsuccessfulOutcome(
+        UnsuccessfulOutcomeValue::Id_RANConfigurationUpdate,
+        ID_RAN_CONFIGURATION_UPDATE,
+        REJECT,
+    ),
+    {
+        Id_Cause => (ID_CAUSE, IGNORE),
+        Id_TimeToWait => (ID_TIME_TO_WAIT, IGNORE),
+        Id_CriticalityDiagnostics => (ID_CRITICALITY_DIAGNOSTICS, IGNORE),
+    }
+}
+
+outgoing_message! {
+    UEContextReleaseCommand(
+        UEContextReleaseCommandProtocolIEs,
+        UEContextReleaseCommandProtocolIEs_Entry,
+        UEContextReleaseCommandProtocolIEs_EntryValue,
+    ),
+    InitiatingMessage(
+        InitiatingMessageValue::Id_UEContextRelease,
+        ID_UE_CONTEXT_RELEASE,
+        REJECT,
+    ),
+    {
+        Id_UE_NGAP_IDs => (ID_UE_NGAP_I_DS, REJECT),
+        Id_Cause => (ID_CAUSE, IGNORE),
+    }
+}
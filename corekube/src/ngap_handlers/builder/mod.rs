@@ -0,0 +1,116 @@
+//! Builders for the NGAP messages the AMF sends. The ID and criticality of
+//! each ProtocolIE follow from the variant of its value, and the procedure
+//! code and criticality from the message, so they can not mismatch:
+//!
+//! ```ignore
+//! use ngap::DownlinkNASTransportProtocolIEs_EntryValue as Value;
+//!
+//! message::<ngap::DownlinkNASTransport>()
+//!     .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(amf_ue_ngap_id)))
+//!     .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(ran_ue_ngap_id)))
+//!     .ie(Value::Id_NAS_PDU(ngap::NAS_PDU(nas_pdu)))
+//!     .build()
+//! ```
+//!
+//! The ProtocolIEs are sent in the order they are added.
+
+use ngap_asn1 as ngap;
+
+#[cfg(test)]
+mod tests;
+
+/// A ProtocolIE entry whose ID and criticality follow from its value.
+pub(super) trait ProtocolIE {
+    type Value;
+
+    fn new(value: Self::Value) -> Self;
+}
+
+/// A message the AMF sends, with the procedure it belongs to.
+pub(super) trait Message {
+    type ProtocolIE: ProtocolIE;
+
+    fn new(protocol_ies: Vec<Self::ProtocolIE>) -> Self;
+
+    fn into_pdu(self) -> ngap::NGAP_PDU;
+}
+
+type Value<M> = <<M as Message>::ProtocolIE as ProtocolIE>::Value;
+
+pub(super) struct MessageBuilder<M: Message> {
+    protocol_ies: Vec<M::ProtocolIE>,
+}
+
+/// Start building a message of type `M`.
+pub(super) fn message<M: Message>() -> MessageBuilder<M> {
+    MessageBuilder {
+        protocol_ies: vec![],
+    }
+}
+
+impl<M: Message> MessageBuilder<M> {
+    pub(super) fn ie(mut self, value: Value<M>) -> Self {
+        self.protocol_ies
+            .push(<M::ProtocolIE as ProtocolIE>::new(value));
+        self
+    }
+
+    /// Add a ProtocolIE only if there is a value for it.
+    pub(super) fn optional_ie(self, value: Option<Value<M>>) -> Self {
+        match value {
+            Some(value) => self.ie(value),
+            None => self,
+        }
+    }
+
+    pub(super) fn build(self) -> ngap::NGAP_PDU {
+        M::new(self.protocol_ies).into_pdu()
+    }
+}
+
+macro_rules! outgoing_message {
+    (
+        $message:ident($protocol_ies:ident, $entry:ident, $entry_value:ident $(,)?),
+        $pdu:ident($pdu_value:ident::$procedure:ident, $procedure_code:ident, $criticality:ident $(,)?),
+        {
+            $($value:ident => ($id:ident, $ie_criticality:ident)),* $(,)?
+        }
+    ) => {
+        impl ProtocolIE for ngap::$entry {
+            type Value = ngap::$entry_value;
+
+            fn new(value: Self::Value) -> Self {
+                let (id, criticality) = match &value {
+                    $(ngap::$entry_value::$value(_) => {
+                        (ngap::$id, ngap::Criticality::$ie_criticality)
+                    })*
+                };
+                ngap::$entry {
+                    id: ngap::ProtocolIE_ID(id),
+                    criticality: ngap::Criticality(criticality),
+                    value,
+                }
+            }
+        }
+
+        impl Message for ngap::$message {
+            type ProtocolIE = ngap::$entry;
+
+            fn new(protocol_ies: Vec<Self::ProtocolIE>) -> Self {
+                ngap::$message {
+                    protocol_i_es: ngap::$protocol_ies(protocol_ies),
+                }
+            }
+
+            fn into_pdu(self) -> ngap::NGAP_PDU {
+                ngap::NGAP_PDU::$pdu(ngap::$pdu {
+                    procedure_code: ngap::ProcedureCode(ngap::$procedure_code),
+                    criticality: ngap::Criticality(ngap::Criticality::$criticality),
+                    value: ngap::$pdu_value::$procedure(self),
+                })
+            }
+        }
+    };
+}
+
+mod messages;
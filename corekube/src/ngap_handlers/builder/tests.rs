@@ -0,0 +1,68 @@
+use super::*;
+
+#[test]
+fn test_message_builder() {
+    use ngap::UEContextReleaseCommandProtocolIEs_EntryValue as Value;
+    let pdu = message::<ngap::UEContextReleaseCommand>()
+        .ie(Value::Id_UE_NGAP_IDs(ngap::UE_NGAP_IDs::AMF_UE_NGAP_ID(
+            ngap::AMF_UE_NGAP_ID(1),
+        )))
+        .ie(Value::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::UNSPECIFIED,
+        ))))
+        .build();
+
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) = pdu else {
+        panic!("PDU is not an InitiatingMessage");
+    };
+    assert_eq!(init_msg.procedure_code.0, ngap::ID_UE_CONTEXT_RELEASE);
+    assert_eq!(init_msg.criticality.0, ngap::Criticality::REJECT);
+    let ngap::InitiatingMessageValue::Id_UEContextRelease(command) = init_msg.value else {
+        panic!("PDU is not a UEContextReleaseCommand");
+    };
+    let ies: Vec<_> = command
+        .protocol_i_es
+        .0
+        .iter()
+        .map(|ie| (ie.id.0, ie.criticality.0))
+        .collect();
+    assert_eq!(
+        ies,
+        vec![
+            (ngap::ID_UE_NGAP_I_DS, ngap::Criticality::REJECT),
+            (ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+        ]
+    );
+    assert_eq!(
+        command.protocol_i_es.0[1].value,
+        Value::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::UNSPECIFIED
+        )))
+    );
+}
+
+#[test]
+fn test_optional_ie() {
+    use ngap::NGSetupFailureProtocolIEs_EntryValue as Value;
+    let pdu = message::<ngap::NGSetupFailure>()
+        .ie(Value::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::UNSPECIFIED,
+        ))))
+        .optional_ie(None)
+        .optional_ie(Some(Value::Id_TimeToWait(ngap::TimeToWait(
+            ngap::TimeToWait::V10S,
+        ))))
+        .build();
+
+    let ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+        procedure_code,
+        value: ngap::UnsuccessfulOutcomeValue::Id_NGSetup(failure),
+        ..
+    }) = pdu
+    else {
+        panic!("PDU is not an NGSetupFailure");
+    };
+    assert_eq!(procedure_code.0, ngap::ID_NG_SETUP);
+    let ids: Vec<_> = failure.protocol_i_es.0.iter().map(|ie| ie.id.0).collect();
+    assert_eq!(ids, vec![ngap::ID_CAUSE, ngap::ID_TIME_TO_WAIT]);
+}
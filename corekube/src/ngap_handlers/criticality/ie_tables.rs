@@ -0,0 +1,216 @@
+//! The ProtocolIEs of each received message as listed in TS 38.413 section
+//! 9.2, with the criticality of the mandatory ones. Conditional IEs are
+//! checked as optional.
+
+use ngap_asn1 as ngap;
+
+use super::{mandatory, optional, IeSpec};
+
+pub(super) const NG_SETUP_REQUEST: &[IeSpec] = &[
+    mandatory(ngap::ID_GLOBAL_RAN_NODE_ID, ngap::Criticality::REJECT),
+    optional(ngap::ID_RAN_NODE_NAME),
+    mandatory(ngap::ID_SUPPORTED_TA_LIST, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_DEFAULT_PAGING_DRX, ngap::Criticality::IGNORE),
+    optional(ngap::ID_UE_RETENTION_INFORMATION),
+];
+
+pub(super) const INITIAL_UE_MESSAGE: &[IeSpec] = &[
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_NAS_PDU, ngap::Criticality::REJECT),
+    mandatory(
+        ngap::ID_USER_LOCATION_INFORMATION,
+        ngap::Criticality::REJECT,
+    ),
+    mandatory(ngap::ID_RRC_ESTABLISHMENT_CAUSE, ngap::Criticality::IGNORE),
+    optional(ngap::ID_FIVE_G_S_TMSI),
+    optional(ngap::ID_AMF_SET_ID),
+    optional(ngap::ID_UE_CONTEXT_REQUEST),
+    optional(ngap::ID_ALLOWED_NSSAI),
+    optional(ngap::ID_SOURCE_TO_TARGET_AMF_INFORMATION_REROUTE),
+    optional(ngap::ID_SELECTED_PLMN_IDENTITY),
+];
+
+pub(super) const UPLINK_NAS_TRANSPORT: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_NAS_PDU, ngap::Criticality::REJECT),
+    mandatory(
+        ngap::ID_USER_LOCATION_INFORMATION,
+        ngap::Criticality::IGNORE,
+    ),
+];
+
+pub(super) const UE_CONTEXT_RELEASE_REQUEST: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_LIST_CXT_REL_REQ),
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+];
+
+pub(super) const PDU_SESSION_RESOURCE_MODIFY_INDICATION: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(
+        ngap::ID_PDU_SESSION_RESOURCE_MODIFY_LIST_MOD_IND,
+        ngap::Criticality::REJECT,
+    ),
+];
+
+pub(super) const PDU_SESSION_RESOURCE_NOTIFY: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_NOTIFY_LIST),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_RELEASED_LIST_NOT),
+    optional(ngap::ID_USER_LOCATION_INFORMATION),
+];
+
+pub(super) const HANDOVER_REQUIRED: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_HANDOVER_TYPE, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_TARGET_ID, ngap::Criticality::REJECT),
+    optional(ngap::ID_DIRECT_FORWARDING_PATH_AVAILABILITY),
+    mandatory(
+        ngap::ID_PDU_SESSION_RESOURCE_LIST_HO_RQD,
+        ngap::Criticality::REJECT,
+    ),
+    mandatory(
+        ngap::ID_SOURCE_TO_TARGET_TRANSPARENT_CONTAINER,
+        ngap::Criticality::REJECT,
+    ),
+];
+
+pub(super) const HANDOVER_NOTIFY: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(
+        ngap::ID_USER_LOCATION_INFORMATION,
+        ngap::Criticality::IGNORE,
+    ),
+];
+
+pub(super) const HANDOVER_CANCEL: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+];
+
+pub(super) const PATH_SWITCH_REQUEST: &[IeSpec] = &[
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(ngap::ID_SOURCE_AMF_UE_NGAP_ID, ngap::Criticality::REJECT),
+    mandatory(
+        ngap::ID_USER_LOCATION_INFORMATION,
+        ngap::Criticality::IGNORE,
+    ),
+    mandatory(ngap::ID_UE_SECURITY_CAPABILITIES, ngap::Criticality::IGNORE),
+    mandatory(
+        ngap::ID_PDU_SESSION_RESOURCE_TO_BE_SWITCHED_DL_LIST,
+        ngap::Criticality::REJECT,
+    ),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_PS_REQ),
+];
+
+pub(super) const NG_RESET: &[IeSpec] = &[
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RESET_TYPE, ngap::Criticality::REJECT),
+];
+
+pub(super) const RAN_CONFIGURATION_UPDATE: &[IeSpec] = &[
+    optional(ngap::ID_RAN_NODE_NAME),
+    optional(ngap::ID_SUPPORTED_TA_LIST),
+    optional(ngap::ID_DEFAULT_PAGING_DRX),
+    optional(ngap::ID_GLOBAL_RAN_NODE_ID),
+    optional(ngap::ID_NGRAN_TNL_ASSOCIATION_TO_REMOVE_LIST),
+];
+
+pub(super) const INITIAL_CONTEXT_SETUP_RESPONSE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_SETUP_LIST_CXT_RES),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_CXT_RES),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const PDU_SESSION_RESOURCE_SETUP_RESPONSE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_SETUP_LIST_SU_RES),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_SU_RES),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const PDU_SESSION_RESOURCE_MODIFY_RESPONSE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_MODIFY_LIST_MOD_RES),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_MODIFY_LIST_MOD_RES),
+    optional(ngap::ID_USER_LOCATION_INFORMATION),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const PDU_SESSION_RESOURCE_RELEASE_RESPONSE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(
+        ngap::ID_PDU_SESSION_RESOURCE_RELEASED_LIST_REL_RES,
+        ngap::Criticality::IGNORE,
+    ),
+    optional(ngap::ID_USER_LOCATION_INFORMATION),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const UE_CONTEXT_RELEASE_COMPLETE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    optional(ngap::ID_USER_LOCATION_INFORMATION),
+    optional(ngap::ID_INFO_ON_RECOMMENDED_CELLS_AND_RAN_NODES_FOR_PAGING),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_LIST_CXT_REL_CPL),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const HANDOVER_REQUEST_ACKNOWLEDGE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(
+        ngap::ID_PDU_SESSION_RESOURCE_ADMITTED_LIST,
+        ngap::Criticality::IGNORE,
+    ),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_HO_ACK),
+    mandatory(
+        ngap::ID_TARGET_TO_SOURCE_TRANSPARENT_CONTAINER,
+        ngap::Criticality::REJECT,
+    ),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const NG_RESET_ACKNOWLEDGE: &[IeSpec] = &[
+    optional(ngap::ID_UE_ASSOCIATED_LOGICAL_NG_CONNECTION_LIST),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const AMF_CONFIGURATION_UPDATE_ACKNOWLEDGE: &[IeSpec] = &[
+    optional(ngap::ID_AMF_TNL_ASSOCIATION_SETUP_LIST),
+    optional(ngap::ID_AMF_TNL_ASSOCIATION_FAILED_TO_SETUP_LIST),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const INITIAL_CONTEXT_SETUP_FAILURE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_RAN_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    optional(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_CXT_FAIL),
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const HANDOVER_FAILURE: &[IeSpec] = &[
+    mandatory(ngap::ID_AMF_UE_NGAP_ID, ngap::Criticality::IGNORE),
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
+
+pub(super) const AMF_CONFIGURATION_UPDATE_FAILURE: &[IeSpec] = &[
+    mandatory(ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+    optional(ngap::ID_TIME_TO_WAIT),
+    optional(ngap::ID_CRITICALITY_DIAGNOSTICS),
+];
@@ -0,0 +1,560 @@
+//! Checks of the ProtocolIEs of received messages against the presence and
+//! criticality rules of TS 38.413 section 10. The handlers only see messages
+//! that passed the checks, or whose errors may be ignored.
+//!
+//! A ProtocolIE that is not in the table of its message is not comprehended,
+//! and is reported with the criticality it was received with. An IE whose ID
+//! is not in the ASN.1 of its message at all usually fails to decode
+//! instead, and the PDU is answered by [`handle_undecodable_pdu`]. A missing
+//! mandatory IE is reported with the criticality it has in the
+//! specification:
+//! - reject: the procedure is rejected with its failure message, or with an
+//!   ErrorIndication if there is none. A response is not processed at all.
+//! - ignore and notify: the message is handled and an ErrorIndication is
+//!   sent afterwards.
+//! - ignore: the message is handled.
+//!
+//! The handlers take the mandatory IEs whose criticality is ignore as
+//! optional, and carry on without them, see TS 38.413 section 10.3.5. A
+//! Cause is taken to be [`missing_cause`]. Only an IE that identifies the UE
+//! a response is about can not be done without, and such a response is
+//! dropped.
+
+use log::{error, warn};
+use ngap_asn1 as ngap;
+
+use super::error_indication::error_indication;
+use super::ran_configuration_update::build_ran_configuration_update_failure;
+use super::setup_request::build_setup_failure;
+use super::{Destination, NGAPResponse};
+
+mod ie_tables;
+
+#[cfg(test)]
+mod tests;
+
+/// An entry of the table of ProtocolIEs of a message.
+pub(super) struct IeSpec {
+    id: u16,
+    criticality: u8,
+    mandatory: bool,
+}
+
+pub(super) const fn mandatory(id: u16, criticality: u8) -> IeSpec {
+    IeSpec {
+        id,
+        criticality,
+        mandatory: true,
+    }
+}
+
+/// An optional IE is never reported, so its criticality is not needed.
+pub(super) const fn optional(id: u16) -> IeSpec {
+    IeSpec {
+        id,
+        criticality: ngap::Criticality::IGNORE,
+        mandatory: false,
+    }
+}
+
+/// An IE that is reported in the CriticalityDiagnostics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct IeError {
+    criticality: u8,
+    id: u16,
+    type_of_error: u8,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum IeCheck {
+    Passed,
+    Notify(Vec<IeError>),
+    Reject(Vec<IeError>),
+}
+
+/// The procedure of a received message, as reported in the
+/// CriticalityDiagnostics.
+struct Procedure {
+    code: u8,
+    triggering_message: u8,
+    criticality: u8,
+}
+
+/// A received message whose ProtocolIEs are checked against a table.
+trait ReceivedMessage {
+    const IES: &'static [IeSpec];
+
+    /// The ID and criticality of each ProtocolIE in the message.
+    fn protocol_ies(&self) -> Vec<(u16, u8)>;
+
+    /// The UE NGAP IDs of a UE-associated message, to address the
+    /// ErrorIndication to the same UE.
+    fn ue_ngap_ids(&self) -> (Option<u64>, Option<u32>) {
+        (None, None)
+    }
+}
+
+macro_rules! received_message {
+    ($message:ident, $table:ident) => {
+        impl ReceivedMessage for ngap::$message {
+            const IES: &'static [IeSpec] = ie_tables::$table;
+
+            fn protocol_ies(&self) -> Vec<(u16, u8)> {
+                self.protocol_i_es
+                    .0
+                    .iter()
+                    .map(|ie| (ie.id.0, ie.criticality.0))
+                    .collect()
+            }
+        }
+    };
+    ($message:ident, $table:ident, $value:ident $(, amf: $amf:ident)? $(, ran: $ran:ident)?) => {
+        impl ReceivedMessage for ngap::$message {
+            const IES: &'static [IeSpec] = ie_tables::$table;
+
+            fn protocol_ies(&self) -> Vec<(u16, u8)> {
+                self.protocol_i_es
+                    .0
+                    .iter()
+                    .map(|ie| (ie.id.0, ie.criticality.0))
+                    .collect()
+            }
+
+            fn ue_ngap_ids(&self) -> (Option<u64>, Option<u32>) {
+                let mut ue_ngap_ids = (None, None);
+                for protocol_ie in &self.protocol_i_es.0 {
+                    match &protocol_ie.value {
+                        $(ngap::$value::$amf(id) => ue_ngap_ids.0 = Some(id.0),)?
+                        $(ngap::$value::$ran(id) => ue_ngap_ids.1 = Some(id.0),)?
+                        _ => {}
+                    }
+                }
+                ue_ngap_ids
+            }
+        }
+    };
+}
+
+received_message!(NGSetupRequest, NG_SETUP_REQUEST);
+received_message!(
+    InitialUEMessage,
+    INITIAL_UE_MESSAGE,
+    InitialUEMessageProtocolIEs_EntryValue,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    UplinkNASTransport,
+    UPLINK_NAS_TRANSPORT,
+    UplinkNASTransportProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    UEContextReleaseRequest,
+    UE_CONTEXT_RELEASE_REQUEST,
+    UEContextReleaseRequestProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    PDUSessionResourceModifyIndication,
+    PDU_SESSION_RESOURCE_MODIFY_INDICATION,
+    PDUSessionResourceModifyIndicationProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    PDUSessionResourceNotify,
+    PDU_SESSION_RESOURCE_NOTIFY,
+    PDUSessionResourceNotifyProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    HandoverRequired,
+    HANDOVER_REQUIRED,
+    HandoverRequiredProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    HandoverNotify,
+    HANDOVER_NOTIFY,
+    HandoverNotifyProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    HandoverCancel,
+    HANDOVER_CANCEL,
+    HandoverCancelProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+// The gNB only knows the UE by the AMF_UE_NGAP_ID of the source AMF
+received_message!(
+    PathSwitchRequest,
+    PATH_SWITCH_REQUEST,
+    PathSwitchRequestProtocolIEs_EntryValue,
+    amf: Id_SourceAMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(NGReset, NG_RESET);
+received_message!(RANConfigurationUpdate, RAN_CONFIGURATION_UPDATE);
+received_message!(
+    InitialContextSetupResponse,
+    INITIAL_CONTEXT_SETUP_RESPONSE,
+    InitialContextSetupResponseProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    PDUSessionResourceSetupResponse,
+    PDU_SESSION_RESOURCE_SETUP_RESPONSE,
+    PDUSessionResourceSetupResponseProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    PDUSessionResourceModifyResponse,
+    PDU_SESSION_RESOURCE_MODIFY_RESPONSE,
+    PDUSessionResourceModifyResponseProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    PDUSessionResourceReleaseResponse,
+    PDU_SESSION_RESOURCE_RELEASE_RESPONSE,
+    PDUSessionResourceReleaseResponseProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    UEContextReleaseComplete,
+    UE_CONTEXT_RELEASE_COMPLETE,
+    UEContextReleaseCompleteProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    HandoverRequestAcknowledge,
+    HANDOVER_REQUEST_ACKNOWLEDGE,
+    HandoverRequestAcknowledgeProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(NGResetAcknowledge, NG_RESET_ACKNOWLEDGE);
+received_message!(
+    AMFConfigurationUpdateAcknowledge,
+    AMF_CONFIGURATION_UPDATE_ACKNOWLEDGE
+);
+received_message!(
+    InitialContextSetupFailure,
+    INITIAL_CONTEXT_SETUP_FAILURE,
+    InitialContextSetupFailureProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID,
+    ran: Id_RAN_UE_NGAP_ID
+);
+received_message!(
+    HandoverFailure,
+    HANDOVER_FAILURE,
+    HandoverFailureProtocolIEs_EntryValue,
+    amf: Id_AMF_UE_NGAP_ID
+);
+received_message!(
+    AMFConfigurationUpdateFailure,
+    AMF_CONFIGURATION_UPDATE_FAILURE
+);
+
+/// Check the ProtocolIEs of an InitiatingMessage. Returns the ErrorIndication
+/// to send once the message is handled, or, if the procedure is rejected,
+/// the responses to send instead of handling it.
+pub fn check_initiating_message(
+    init_msg: &ngap::InitiatingMessage,
+) -> Result<Vec<NGAPResponse>, Vec<NGAPResponse>> {
+    let procedure = Procedure {
+        code: init_msg.procedure_code.0,
+        triggering_message: ngap::TriggeringMessage::INITIATING_MESSAGE,
+        criticality: init_msg.criticality.0,
+    };
+
+    let (check, ue_ngap_ids) = match &init_msg.value {
+        ngap::InitiatingMessageValue::Id_NGSetup(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_InitialUEMessage(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_UplinkNASTransport(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_UEContextReleaseRequest(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_PDUSessionResourceModifyIndication(message) => {
+            check_message(message)
+        }
+        ngap::InitiatingMessageValue::Id_PDUSessionResourceNotify(message) => {
+            check_message(message)
+        }
+        ngap::InitiatingMessageValue::Id_HandoverPreparation(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_HandoverNotification(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_HandoverCancel(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_PathSwitchRequest(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_NGReset(message) => check_message(message),
+        ngap::InitiatingMessageValue::Id_RANConfigurationUpdate(message) => check_message(message),
+        // Not checked, notably an ErrorIndication is never answered with
+        // another one
+        _ => return Ok(vec![]),
+    };
+
+    match check {
+        IeCheck::Passed => Ok(vec![]),
+        IeCheck::Notify(errors) => Ok(notify(&procedure, &errors, ue_ngap_ids)),
+        IeCheck::Reject(errors) => {
+            warn!(
+                "Rejecting procedure {} because of the IEs {:?}",
+                procedure.code, errors
+            );
+            let cause = abstract_syntax_error(ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_REJECT);
+            let diagnostics = build_criticality_diagnostics(&procedure, &errors);
+            // Only the procedures the AMF handles that have a failure
+            // message can be rejected with it
+            let ngap_pdu = match init_msg.value {
+                ngap::InitiatingMessageValue::Id_NGSetup(_) => {
+                    build_setup_failure(cause, Some(diagnostics))
+                }
+                ngap::InitiatingMessageValue::Id_RANConfigurationUpdate(_) => {
+                    build_ran_configuration_update_failure(cause, Some(diagnostics))
+                }
+                _ => return Err(vec![error_indication(ue_ngap_ids, cause, diagnostics)]),
+            };
+            Err(vec![NGAPResponse {
+                sctp_stream: 0,
+                ngap_pdu,
+                destination: Destination::Sender,
+            }])
+        }
+    }
+}
+
+/// Check the ProtocolIEs of a SuccessfulOutcome, see
+/// [`check_initiating_message`]. A rejected response is not answered.
+pub fn check_successful_outcome(
+    success_outcome: &ngap::SuccessfulOutcome,
+) -> Result<Vec<NGAPResponse>, Vec<NGAPResponse>> {
+    let procedure = Procedure {
+        code: success_outcome.procedure_code.0,
+        triggering_message: ngap::TriggeringMessage::SUCCESSFUL_OUTCOME,
+        criticality: success_outcome.criticality.0,
+    };
+
+    let (check, ue_ngap_ids) = match &success_outcome.value {
+        ngap::SuccessfulOutcomeValue::Id_InitialContextSetup(message) => check_message(message),
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceSetup(message) => check_message(message),
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceModify(message) => {
+            check_message(message)
+        }
+        ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceRelease(message) => {
+            check_message(message)
+        }
+        ngap::SuccessfulOutcomeValue::Id_UEContextRelease(message) => check_message(message),
+        ngap::SuccessfulOutcomeValue::Id_HandoverResourceAllocation(message) => {
+            check_message(message)
+        }
+        ngap::SuccessfulOutcomeValue::Id_NGReset(message) => check_message(message),
+        ngap::SuccessfulOutcomeValue::Id_AMFConfigurationUpdate(message) => check_message(message),
+        _ => return Ok(vec![]),
+    };
+
+    check_response(&procedure, check, ue_ngap_ids)
+}
+
+/// Check the ProtocolIEs of an UnsuccessfulOutcome, see
+/// [`check_initiating_message`]. A rejected response is not answered.
+pub fn check_unsuccessful_outcome(
+    unsuccess_outcome: &ngap::UnsuccessfulOutcome,
+) -> Result<Vec<NGAPResponse>, Vec<NGAPResponse>> {
+    let procedure = Procedure {
+        code: unsuccess_outcome.procedure_code.0,
+        triggering_message: ngap::TriggeringMessage::UNSUCCESSFULL_OUTCOME,
+        criticality: unsuccess_outcome.criticality.0,
+    };
+
+    let (check, ue_ngap_ids) = match &unsuccess_outcome.value {
+        ngap::UnsuccessfulOutcomeValue::Id_InitialContextSetup(message) => check_message(message),
+        ngap::UnsuccessfulOutcomeValue::Id_HandoverResourceAllocation(message) => {
+            check_message(message)
+        }
+        ngap::UnsuccessfulOutcomeValue::Id_AMFConfigurationUpdate(message) => {
+            check_message(message)
+        }
+        _ => return Ok(vec![]),
+    };
+
+    check_response(&procedure, check, ue_ngap_ids)
+}
+
+/// Handle a message of a procedure the AMF does not implement, see TS 38.413
+/// section 10.2. Unless the procedure may be ignored, the gNB is told with
+/// an ErrorIndication.
+pub fn handle_unknown_procedure(
+    procedure_code: u8,
+    triggering_message: u8,
+    criticality: u8,
+) -> Vec<NGAPResponse> {
+    let cause = match criticality {
+        ngap::Criticality::REJECT => ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_REJECT,
+        ngap::Criticality::NOTIFY => ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_IGNORE_AND_NOTIFY,
+        _ => return vec![],
+    };
+    let procedure = Procedure {
+        code: procedure_code,
+        triggering_message,
+        criticality,
+    };
+
+    vec![error_indication(
+        (None, None),
+        abstract_syntax_error(cause),
+        build_criticality_diagnostics(&procedure, &[]),
+    )]
+}
+
+/// Answer a PDU that can not be decoded with an ErrorIndication, see TS
+/// 38.413 section 10.2. The procedure is reported when the start of the PDU
+/// is intact, but nothing can be known about the UE it was sent for.
+pub fn handle_undecodable_pdu(buf: &[u8]) -> Vec<NGAPResponse> {
+    // In APER, the choice of NGAP-PDU is in the first octet, followed by
+    // the procedure code and the criticality
+    let diagnostics = match buf {
+        [pdu_type, code, criticality, ..] => ngap::CriticalityDiagnostics {
+            procedure_code: Some(ngap::ProcedureCode(*code)),
+            triggering_message: Some(ngap::TriggeringMessage((pdu_type >> 5) & 0x03)),
+            procedure_criticality: Some(ngap::Criticality(criticality >> 6)),
+            i_es_criticality_diagnostics: None,
+            ie_extensions: None,
+        },
+        _ => ngap::CriticalityDiagnostics {
+            procedure_code: None,
+            triggering_message: None,
+            procedure_criticality: None,
+            i_es_criticality_diagnostics: None,
+            ie_extensions: None,
+        },
+    };
+
+    vec![error_indication(
+        (None, None),
+        abstract_syntax_error(ngap::CauseProtocol::TRANSFER_SYNTAX_ERROR),
+        diagnostics,
+    )]
+}
+
+fn check_message<M: ReceivedMessage>(message: &M) -> (IeCheck, (Option<u64>, Option<u32>)) {
+    (
+        check_ies(&message.protocol_ies(), M::IES),
+        message.ue_ngap_ids(),
+    )
+}
+
+/// Compare the ProtocolIEs of a message with its table, keeping the errors
+/// that are reported.
+fn check_ies(protocol_ies: &[(u16, u8)], table: &[IeSpec]) -> IeCheck {
+    let not_understood = protocol_ies
+        .iter()
+        .filter(|&&(id, _)| !table.iter().any(|ie| ie.id == id))
+        .map(|&(id, criticality)| IeError {
+            criticality,
+            id,
+            type_of_error: ngap::TypeOfError::NOT_UNDERSTOOD,
+        });
+    let missing = table
+        .iter()
+        .filter(|ie| ie.mandatory && !protocol_ies.iter().any(|&(id, _)| id == ie.id))
+        .map(|ie| IeError {
+            criticality: ie.criticality,
+            id: ie.id,
+            type_of_error: ngap::TypeOfError::MISSING,
+        });
+    let errors: Vec<_> = not_understood
+        .chain(missing)
+        .filter(|error| error.criticality != ngap::Criticality::IGNORE)
+        .collect();
+
+    if errors.is_empty() {
+        IeCheck::Passed
+    } else if errors
+        .iter()
+        .any(|error| error.criticality == ngap::Criticality::REJECT)
+    {
+        IeCheck::Reject(errors)
+    } else {
+        IeCheck::Notify(errors)
+    }
+}
+
+/// A rejected response ends the procedure locally, see TS 38.413 section
+/// 10.3.4.2.
+fn check_response(
+    procedure: &Procedure,
+    check: IeCheck,
+    ue_ngap_ids: (Option<u64>, Option<u32>),
+) -> Result<Vec<NGAPResponse>, Vec<NGAPResponse>> {
+    match check {
+        IeCheck::Passed => Ok(vec![]),
+        IeCheck::Notify(errors) => Ok(notify(procedure, &errors, ue_ngap_ids)),
+        IeCheck::Reject(errors) => {
+            error!(
+                "Procedure {} unsuccessfully terminated because of the IEs {:?}",
+                procedure.code, errors
+            );
+            Err(vec![])
+        }
+    }
+}
+
+fn notify(
+    procedure: &Procedure,
+    errors: &[IeError],
+    ue_ngap_ids: (Option<u64>, Option<u32>),
+) -> Vec<NGAPResponse> {
+    warn!(
+        "Ignoring the IEs {:?} in procedure {}",
+        errors, procedure.code
+    );
+    vec![error_indication(
+        ue_ngap_ids,
+        abstract_syntax_error(ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_IGNORE_AND_NOTIFY),
+        build_criticality_diagnostics(procedure, errors),
+    )]
+}
+
+/// The Cause taken for a message that left it out, which it may where the
+/// criticality of the Cause is ignore.
+pub(super) fn missing_cause() -> ngap::Cause {
+    ngap::Cause::Misc(ngap::CauseMisc(ngap::CauseMisc::UNSPECIFIED))
+}
+
+fn abstract_syntax_error(cause: u8) -> ngap::Cause {
+    ngap::Cause::Protocol(ngap::CauseProtocol(cause))
+}
+
+fn build_criticality_diagnostics(
+    procedure: &Procedure,
+    errors: &[IeError],
+) -> ngap::CriticalityDiagnostics {
+    let ies_criticality_diagnostics = (!errors.is_empty()).then(|| {
+        ngap::CriticalityDiagnostics_IE_List(
+            errors
+                .iter()
+                .map(|error| ngap::CriticalityDiagnostics_IE_Item {
+                    ie_criticality: ngap::Criticality(error.criticality),
+                    ie_id: ngap::ProtocolIE_ID(error.id),
+                    type_of_error: ngap::TypeOfError(error.type_of_error),
+                    ie_extensions: None,
+                })
+                .collect(),
+        )
+    });
+
+    ngap::CriticalityDiagnostics {
+        procedure_code: Some(ngap::ProcedureCode(procedure.code)),
+        triggering_message: Some(ngap::TriggeringMessage(procedure.triggering_message)),
+        procedure_criticality: Some(ngap::Criticality(procedure.criticality)),
+        i_es_criticality_diagnostics: ies_criticality_diagnostics,
+        ie_extensions: None,
+    }
+}
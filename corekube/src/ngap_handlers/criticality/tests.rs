@@ -0,0 +1,333 @@
+use super::*;
+
+fn reset_cause() -> ngap::NGResetProtocolIEs_Entry {
+    ngap::NGResetProtocolIEs_Entry {
+        id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+        criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+        value: ngap::NGResetProtocolIEs_EntryValue::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::HARDWARE_FAILURE,
+        ))),
+    }
+}
+
+#[test]
+fn test_check_ies() {
+    let table = ie_tables::NG_SETUP_REQUEST;
+    let complete = [
+        (ngap::ID_GLOBAL_RAN_NODE_ID, ngap::Criticality::REJECT),
+        (ngap::ID_SUPPORTED_TA_LIST, ngap::Criticality::REJECT),
+        (ngap::ID_DEFAULT_PAGING_DRX, ngap::Criticality::IGNORE),
+    ];
+    assert_eq!(check_ies(&complete, table), IeCheck::Passed);
+
+    // A missing IE with criticality ignore is not reported
+    assert_eq!(check_ies(&complete[..2], table), IeCheck::Passed);
+
+    assert_eq!(
+        check_ies(&complete[1..], table),
+        IeCheck::Reject(vec![IeError {
+            criticality: ngap::Criticality::REJECT,
+            id: ngap::ID_GLOBAL_RAN_NODE_ID,
+            type_of_error: ngap::TypeOfError::MISSING,
+        }])
+    );
+}
+
+#[test]
+fn test_check_not_understood_ies() {
+    let table = ie_tables::NG_RESET;
+    let ies = |criticality| {
+        [
+            (ngap::ID_CAUSE, ngap::Criticality::IGNORE),
+            (ngap::ID_RESET_TYPE, ngap::Criticality::REJECT),
+            (ngap::ID_RAN_NODE_NAME, criticality),
+        ]
+    };
+    let not_understood = |criticality| IeError {
+        criticality,
+        id: ngap::ID_RAN_NODE_NAME,
+        type_of_error: ngap::TypeOfError::NOT_UNDERSTOOD,
+    };
+
+    // An IE that is not comprehended is reported with the criticality it
+    // was received with
+    assert_eq!(
+        check_ies(&ies(ngap::Criticality::IGNORE), table),
+        IeCheck::Passed
+    );
+    assert_eq!(
+        check_ies(&ies(ngap::Criticality::NOTIFY), table),
+        IeCheck::Notify(vec![not_understood(ngap::Criticality::NOTIFY)])
+    );
+    assert_eq!(
+        check_ies(&ies(ngap::Criticality::REJECT), table),
+        IeCheck::Reject(vec![not_understood(ngap::Criticality::REJECT)])
+    );
+}
+
+/// An NGReset with an IE that is not comprehended, received with
+/// `criticality`.
+fn ng_reset_with_unknown_ie(criticality: u8) -> ngap::InitiatingMessage {
+    ngap::InitiatingMessage {
+        procedure_code: ngap::ProcedureCode(ngap::ID_NG_RESET),
+        criticality: ngap::Criticality(ngap::Criticality::REJECT),
+        value: ngap::InitiatingMessageValue::Id_NGReset(ngap::NGReset {
+            protocol_i_es: ngap::NGResetProtocolIEs(vec![
+                reset_cause(),
+                ngap::NGResetProtocolIEs_Entry {
+                    id: ngap::ProtocolIE_ID(ngap::ID_RESET_TYPE),
+                    criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                    value: ngap::NGResetProtocolIEs_EntryValue::Id_ResetType(
+                        ngap::ResetType::NG_Interface(ngap::ResetAll(ngap::ResetAll::RESET_ALL)),
+                    ),
+                },
+                // The checks only look at the ID and the criticality
+                ngap::NGResetProtocolIEs_Entry {
+                    id: ngap::ProtocolIE_ID(ngap::ID_RAN_NODE_NAME),
+                    criticality: ngap::Criticality(criticality),
+                    value: ngap::NGResetProtocolIEs_EntryValue::Id_Cause(ngap::Cause::Misc(
+                        ngap::CauseMisc(ngap::CauseMisc::UNSPECIFIED),
+                    )),
+                },
+            ]),
+        }),
+    }
+}
+
+/// The CriticalityDiagnostics of an ErrorIndication, along with its Cause.
+fn error_indication_diagnostics(
+    response: &NGAPResponse,
+) -> (ngap::Cause, ngap::CriticalityDiagnostics) {
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_ErrorIndication(error_indication),
+        ..
+    }) = &response.ngap_pdu
+    else {
+        panic!("Response is not an ErrorIndication");
+    };
+    let mut cause = None;
+    let mut diagnostics = None;
+    for protocol_ie in &error_indication.protocol_i_es.0 {
+        match &protocol_ie.value {
+            ngap::ErrorIndicationProtocolIEs_EntryValue::Id_Cause(value) => {
+                cause = Some(value.clone())
+            }
+            ngap::ErrorIndicationProtocolIEs_EntryValue::Id_CriticalityDiagnostics(value) => {
+                diagnostics = Some(value.clone())
+            }
+            _ => {}
+        }
+    }
+    (cause.unwrap(), diagnostics.unwrap())
+}
+
+fn assert_not_understood(diagnostics: &ngap::CriticalityDiagnostics, criticality: u8) {
+    let items = &diagnostics.i_es_criticality_diagnostics.as_ref().unwrap().0;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].ie_id.0, ngap::ID_RAN_NODE_NAME);
+    assert_eq!(items[0].ie_criticality.0, criticality);
+    assert_eq!(items[0].type_of_error.0, ngap::TypeOfError::NOT_UNDERSTOOD);
+}
+
+#[test]
+fn test_reject_not_understood_ie() {
+    let Err(rejection) =
+        check_initiating_message(&ng_reset_with_unknown_ie(ngap::Criticality::REJECT))
+    else {
+        panic!("NGReset with a rejected IE is not rejected");
+    };
+    assert_eq!(rejection.len(), 1);
+    let (cause, diagnostics) = error_indication_diagnostics(&rejection[0]);
+    assert!(matches!(
+        cause,
+        ngap::Cause::Protocol(ngap::CauseProtocol(
+            ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_REJECT
+        ))
+    ));
+    assert_not_understood(&diagnostics, ngap::Criticality::REJECT);
+}
+
+#[test]
+fn test_notify_not_understood_ie() {
+    let Ok(notifications) =
+        check_initiating_message(&ng_reset_with_unknown_ie(ngap::Criticality::NOTIFY))
+    else {
+        panic!("NGReset with an IE to ignore and notify is rejected");
+    };
+    assert_eq!(notifications.len(), 1);
+    let (cause, diagnostics) = error_indication_diagnostics(&notifications[0]);
+    assert!(matches!(
+        cause,
+        ngap::Cause::Protocol(ngap::CauseProtocol(
+            ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_IGNORE_AND_NOTIFY
+        ))
+    ));
+    assert_not_understood(&diagnostics, ngap::Criticality::NOTIFY);
+
+    // An IE to ignore is not reported
+    assert!(matches!(
+        check_initiating_message(&ng_reset_with_unknown_ie(ngap::Criticality::IGNORE)),
+        Ok(notifications) if notifications.is_empty()
+    ));
+}
+
+#[test]
+fn test_reject_with_error_indication() {
+    let init_msg = ngap::InitiatingMessage {
+        procedure_code: ngap::ProcedureCode(ngap::ID_NG_RESET),
+        criticality: ngap::Criticality(ngap::Criticality::REJECT),
+        value: ngap::InitiatingMessageValue::Id_NGReset(ngap::NGReset {
+            protocol_i_es: ngap::NGResetProtocolIEs(vec![reset_cause()]),
+        }),
+    };
+
+    let Err(rejection) = check_initiating_message(&init_msg) else {
+        panic!("NGReset without a ResetType is not rejected");
+    };
+    assert_eq!(rejection.len(), 1);
+    assert_eq!(rejection[0].sctp_stream, 0);
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_ErrorIndication(_),
+        ..
+    }) = &rejection[0].ngap_pdu
+    else {
+        panic!("Rejection is not an ErrorIndication");
+    };
+}
+
+#[test]
+fn test_reject_with_failure() {
+    let init_msg = ngap::InitiatingMessage {
+        procedure_code: ngap::ProcedureCode(ngap::ID_NG_SETUP),
+        criticality: ngap::Criticality(ngap::Criticality::REJECT),
+        value: ngap::InitiatingMessageValue::Id_NGSetup(ngap::NGSetupRequest {
+            protocol_i_es: ngap::NGSetupRequestProtocolIEs(vec![
+                ngap::NGSetupRequestProtocolIEs_Entry {
+                    id: ngap::ProtocolIE_ID(ngap::ID_DEFAULT_PAGING_DRX),
+                    criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                    value: ngap::NGSetupRequestProtocolIEs_EntryValue::Id_DefaultPagingDRX(
+                        ngap::PagingDRX(ngap::PagingDRX::V128),
+                    ),
+                },
+            ]),
+        }),
+    };
+
+    let Err(rejection) = check_initiating_message(&init_msg) else {
+        panic!("NGSetupRequest without a GlobalRANNodeID is not rejected");
+    };
+    assert_eq!(rejection.len(), 1);
+    let ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+        value: ngap::UnsuccessfulOutcomeValue::Id_NGSetup(failure),
+        ..
+    }) = &rejection[0].ngap_pdu
+    else {
+        panic!("Rejection is not an NGSetupFailure");
+    };
+    let diagnostics = failure
+        .protocol_i_es
+        .0
+        .iter()
+        .find_map(|protocol_ie| match &protocol_ie.value {
+            ngap::NGSetupFailureProtocolIEs_EntryValue::Id_CriticalityDiagnostics(diagnostics) => {
+                Some(diagnostics)
+            }
+            _ => None,
+        })
+        .unwrap();
+    let missing: Vec<_> = diagnostics
+        .i_es_criticality_diagnostics
+        .as_ref()
+        .unwrap()
+        .0
+        .iter()
+        .map(|item| item.ie_id.0)
+        .collect();
+    assert_eq!(
+        missing,
+        vec![ngap::ID_GLOBAL_RAN_NODE_ID, ngap::ID_SUPPORTED_TA_LIST]
+    );
+}
+
+#[test]
+fn test_error_indication_not_checked() {
+    let init_msg = ngap::InitiatingMessage {
+        procedure_code: ngap::ProcedureCode(ngap::ID_ERROR_INDICATION),
+        criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+        value: ngap::InitiatingMessageValue::Id_ErrorIndication(ngap::ErrorIndication {
+            protocol_i_es: ngap::ErrorIndicationProtocolIEs(vec![]),
+        }),
+    };
+    assert!(
+        matches!(check_initiating_message(&init_msg), Ok(notifications) if notifications.is_empty())
+    );
+}
+
+#[test]
+fn test_unknown_procedure() {
+    let triggering_message = ngap::TriggeringMessage::INITIATING_MESSAGE;
+    assert!(handle_unknown_procedure(
+        ngap::ID_UE_RADIO_CAPABILITY_INFO_INDICATION,
+        triggering_message,
+        ngap::Criticality::IGNORE
+    )
+    .is_empty());
+
+    let result = handle_unknown_procedure(
+        ngap::ID_UE_CONTEXT_MODIFICATION,
+        triggering_message,
+        ngap::Criticality::REJECT,
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+}
+
+#[test]
+fn test_undecodable_pdu() {
+    // An NGSetupRequest that ends after the criticality
+    let responses = handle_undecodable_pdu(&[0x00, 0x15, 0x00]);
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].sctp_stream, 0);
+    assert_eq!(responses[0].destination, Destination::Sender);
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_ErrorIndication(error_indication),
+        ..
+    }) = &responses[0].ngap_pdu
+    else {
+        panic!("Response is not an ErrorIndication");
+    };
+    let mut cause = None;
+    let mut diagnostics = None;
+    for protocol_ie in &error_indication.protocol_i_es.0 {
+        match &protocol_ie.value {
+            ngap::ErrorIndicationProtocolIEs_EntryValue::Id_Cause(value) => cause = Some(value),
+            ngap::ErrorIndicationProtocolIEs_EntryValue::Id_CriticalityDiagnostics(value) => {
+                diagnostics = Some(value)
+            }
+            _ => panic!("ErrorIndication of a PDU is about a UE"),
+        }
+    }
+    assert!(matches!(
+        cause,
+        Some(ngap::Cause::Protocol(ngap::CauseProtocol(
+            ngap::CauseProtocol::TRANSFER_SYNTAX_ERROR
+        )))
+    ));
+    let diagnostics = diagnostics.unwrap();
+    assert_eq!(
+        diagnostics.procedure_code.as_ref().unwrap().0,
+        ngap::ID_NG_SETUP
+    );
+    assert_eq!(
+        diagnostics.triggering_message.as_ref().unwrap().0,
+        ngap::TriggeringMessage::INITIATING_MESSAGE
+    );
+    assert_eq!(
+        diagnostics.procedure_criticality.as_ref().unwrap().0,
+        ngap::Criticality::REJECT
+    );
+
+    // Nothing is known of a PDU that is too short
+    let responses = handle_undecodable_pdu(&[0x20]);
+    assert_eq!(responses.len(), 1);
+}
@@ -0,0 +1,216 @@
+use log::{debug, error, info, trace};
+use nas::fgmm::{
+    DeregistrationAcceptUeOriginating, DeregistrationRequestUeOriginating,
+    DeregistrationRequestUeTerminated, MobilityManagementStatus, ACCESS_TYPE_3GPP,
+    ACCESS_TYPE_NON_3GPP,
+};
+use nas::MobilityManagementCause;
+use ngap_asn1 as ngap;
+
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::initial_ue_message::{identify_ue, reject_initial_nas_message};
+use super::nas_timers::stop_reachability_timers;
+use super::pdu_session_resource_release::release_user_plane;
+use super::ue_context_release::build_ue_context_release_command;
+use super::uplink_nas_transport::send_mobility_management_status;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{CMState, GNBAddress, RMState, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+/// Handle a De-registration Request sent by a UE in CM-IDLE, carried in an
+/// InitialUEMessage. The `plain` message is the cleartext inside the
+/// integrity protected `nas_pdu`.
+pub(super) fn handle_initial_deregistration_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    ran_ue_ngap_id: u32,
+    tai: TAI,
+    nas_pdu: &[u8],
+    plain: &[u8],
+) -> Vec<NGAPResponse> {
+    let Some(request) = DeregistrationRequestUeOriginating::decode(plain) else {
+        error!("Could not decode De-registration Request");
+        let status = MobilityManagementStatus {
+            cause: MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        };
+        return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, status.encode());
+    };
+    let identified = request.guti.and_then(|guti| {
+        identify_ue(
+            config,
+            store,
+            guti.amf_set_id,
+            guti.amf_pointer,
+            guti.tmsi,
+            request.ngksi,
+            nas_pdu,
+        )
+    });
+
+    // A UE that can not be identified is not registered here anyway, so its
+    // de-registration is accepted without a context to clean up
+    let Some((mut ue, security)) = identified else {
+        let amf_ue_ngap_id = store.allocate_amf_ue_ngap_id();
+        let mut ue = UEContext::new(amf_ue_ngap_id, ran_ue_ngap_id, *gnb);
+        return deregister_ue_originating(store, &mut ue, &request);
+    };
+
+    ue.gnb = *gnb;
+    ue.ran_ue_ngap_id = ran_ue_ngap_id;
+    ue.cm_state = CMState::Connected;
+    ue.tai = Some(tai);
+    ue.security = Some(security);
+    stop_reachability_timers(store, &ue);
+
+    let responses = deregister_ue_originating(store, &mut ue, &request);
+    store.put_ue(ue);
+    responses
+}
+
+/// Handle a De-registration Request from a UE with a NAS signalling
+/// connection, see TS 24.501 section 5.5.2.2.
+pub(super) fn handle_deregistration_request(
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    let Some(request) = DeregistrationRequestUeOriginating::decode(nas_message) else {
+        error!("Could not decode De-registration Request");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+    deregister_ue_originating(store, ue, &request)
+}
+
+fn deregister_ue_originating(
+    store: &Store,
+    ue: &mut UEContext,
+    request: &DeregistrationRequestUeOriginating,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type DeregistrationRequest");
+    debug!("De-registration Request: {:?}", request);
+
+    // Only 3GPP access is served, so there is nothing to de-register from
+    // if the UE leaves non-3GPP access only
+    let deregistered = request.access_type != ACCESS_TYPE_NON_3GPP;
+    if deregistered {
+        info!(
+            "UE {} de-registered{}",
+            ue.amf_ue_ngap_id,
+            if request.switch_off {
+                " at switch off"
+            } else {
+                ""
+            }
+        );
+        deregister(store, ue);
+    }
+
+    // A UE that is switched off does not wait for the accept
+    let mut responses = vec![];
+    if !request.switch_off {
+        let accept = DeregistrationAcceptUeOriginating.encode();
+        let nas_pdu = match ue.security.as_mut() {
+            Some(security) => {
+                security.protect(&accept, nas::SecurityHeader::IntegrityProtectedAndCiphered)
+            }
+            None => accept,
+        };
+        responses.push(NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+            destination: Destination::Sender,
+        });
+    }
+    if deregistered {
+        responses.push(NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_deregistration_release_command(ue),
+            destination: Destination::Sender,
+        });
+    }
+    responses
+}
+
+/// Handle the De-registration Accept of a UE that the network de-registered,
+/// and release its NAS signalling connection.
+pub(super) fn handle_deregistration_accept(store: &Store, ue: &mut UEContext) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type DeregistrationAccept");
+
+    info!("UE {} de-registered by the network", ue.amf_ue_ngap_id);
+    deregister(store, ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_deregistration_release_command(ue),
+        destination: Destination::Sender,
+    }]
+}
+
+/// De-register a UE from the network, see TS 24.501 section 5.5.2.3. A UE
+/// with a NAS signalling connection is sent a De-registration Request and
+/// cleaned up once it accepts, while a UE in CM-IDLE is de-registered
+/// locally without being told. Returns `None` if the UE is not known.
+pub fn deregister_ue(
+    store: &Store,
+    amf_ue_ngap_id: u64,
+    re_registration_required: bool,
+    cause: Option<nas::MobilityManagementCause>,
+) -> Option<Vec<NGAPResponse>> {
+    let mut ue = store.get_ue(amf_ue_ngap_id)?;
+
+    if ue.cm_state == CMState::Idle {
+        info!("UE {} de-registered locally", ue.amf_ue_ngap_id);
+        deregister(store, &mut ue);
+        store.remove_ue(ue.amf_ue_ngap_id);
+        return Some(vec![]);
+    }
+
+    let request = DeregistrationRequestUeTerminated {
+        re_registration_required,
+        access_type: ACCESS_TYPE_3GPP,
+        cause,
+    };
+    let Some(security) = ue.security.as_mut() else {
+        error!("No NAS security context for UE {}", ue.amf_ue_ngap_id);
+        return Some(vec![]);
+    };
+    let nas_pdu = security.protect(
+        &request.encode(),
+        nas::SecurityHeader::IntegrityProtectedAndCiphered,
+    );
+    let ngap_pdu = build_downlink_nas_transport(&ue, nas_pdu);
+    let destination = Destination::Association(ue.gnb);
+    store.put_ue(ue);
+
+    Some(vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination,
+    }])
+}
+
+/// Forget the registration of a UE and release the user plane of all its PDU
+/// sessions. The context itself is removed once the NG-RAN released it.
+pub(super) fn deregister(store: &Store, ue: &mut UEContext) {
+    ue.rm_state = RMState::Deregistered;
+    ue.tmsi = None;
+    ue.registration_area.clear();
+    ue.allowed_nssai.clear();
+    for session in ue.pdu_sessions.drain(..) {
+        release_user_plane(store, Some(session));
+    }
+}
+
+pub(super) fn build_deregistration_release_command(ue: &UEContext) -> ngap::NGAP_PDU {
+    build_ue_context_release_command(
+        ue.amf_ue_ngap_id,
+        Some(ue.ran_ue_ngap_id),
+        ngap::Cause::Nas(ngap::CauseNas(ngap::CauseNas::DEREGISTER)),
+    )
+}
@@ -0,0 +1,113 @@
+use super::*;
+
+/// A registered, connected UE with a NAS security context and a PDU session.
+fn setup() -> Store {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.tmsi = Some(1);
+    ue.security = nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]);
+    ue.set_pdu_session_active(5, true);
+    store.put_ue(ue);
+    store
+}
+
+/// A De-registration Request for 3GPP access, switched off or not.
+fn build_deregistration_request(switch_off: bool) -> Vec<u8> {
+    let deregistration_type = if switch_off { 0x09 } else { 0x01 };
+    [
+        &[0x7E, 0x00, 0x45, deregistration_type, 0x00, 0x0B][..],
+        &[
+            0xF2, 0x02, 0xf8, 0x39, 0x02, 0x00, 0x40, 0x00, 0x00, 0x00, 0x01,
+        ],
+    ]
+    .concat()
+}
+
+fn is_initiating_message(response: &NGAPResponse, procedure_code: u8) -> bool {
+    matches!(
+        &response.ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(init_msg) if init_msg.procedure_code.0 == procedure_code
+    )
+}
+
+#[test]
+fn test_deregistration_request() {
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+
+    let result =
+        handle_deregistration_request(&store, &mut ue, &build_deregistration_request(false));
+    assert_eq!(result.len(), 2);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_DOWNLINK_NAS_TRANSPORT
+    ));
+    assert!(is_initiating_message(
+        &result[1],
+        ngap::ID_UE_CONTEXT_RELEASE
+    ));
+
+    assert_eq!(ue.rm_state, RMState::Deregistered);
+    assert_eq!(ue.tmsi, None);
+    assert!(ue.pdu_sessions.is_empty());
+    // The De-registration Accept was protected
+    assert_eq!(ue.security.unwrap().dl_count, 1);
+}
+
+#[test]
+fn test_deregistration_request_switch_off() {
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+
+    let result =
+        handle_deregistration_request(&store, &mut ue, &build_deregistration_request(true));
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_UE_CONTEXT_RELEASE
+    ));
+    assert_eq!(ue.rm_state, RMState::Deregistered);
+}
+
+#[test]
+fn test_network_deregistration() {
+    let store = setup();
+
+    let result = deregister_ue(&store, 1, true, None).expect("UE should be known");
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_DOWNLINK_NAS_TRANSPORT
+    ));
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(crate::tests::test_gnb())
+    );
+
+    // The UE stays registered until it accepts
+    let mut ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.rm_state, RMState::Registered);
+
+    let result = handle_deregistration_accept(&store, &mut ue);
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_UE_CONTEXT_RELEASE
+    ));
+    assert_eq!(ue.rm_state, RMState::Deregistered);
+    assert!(ue.pdu_sessions.is_empty());
+}
+
+#[test]
+fn test_network_deregistration_idle() {
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+    ue.cm_state = CMState::Idle;
+    store.put_ue(ue);
+
+    let result = deregister_ue(&store, 1, false, None).expect("UE should be known");
+    assert!(result.is_empty());
+    assert!(store.get_ue(1).is_none());
+
+    assert!(deregister_ue(&store, 1, false, None).is_none());
+}
@@ -0,0 +1,25 @@
+use log::trace;
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use crate::store::UEContext;
+
+#[cfg(test)]
+mod tests;
+
+/// Build a DownlinkNASTransport carrying a NAS message to a UE that has a
+/// UE-associated logical NG-connection.
+pub fn build_downlink_nas_transport(ue: &UEContext, nas_pdu: Vec<u8>) -> ngap::NGAP_PDU {
+    trace!("Building DownlinkNASTransport");
+
+    use ngap::DownlinkNASTransportProtocolIEs_EntryValue as Value;
+    message::<ngap::DownlinkNASTransport>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_NAS_PDU(ngap::NAS_PDU(nas_pdu)))
+        .build()
+}
@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn test_build_downlink_nas_transport() {
+    let ue = UEContext::new(1, 10, crate::tests::test_gnb());
+
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) =
+        build_downlink_nas_transport(&ue, vec![0x7E, 0x00, 0x68])
+    else {
+        panic!("DownlinkNASTransport is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_DownlinkNASTransport(transport) = init_msg.value else {
+        panic!("InitiatingMessage is not a DownlinkNASTransport");
+    };
+
+    let mut has_nas_pdu = false;
+    for protocol_ie in transport.protocol_i_es.0 {
+        match protocol_ie.value {
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(id) => {
+                assert_eq!(id.0, 1);
+            }
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(id) => {
+                assert_eq!(id.0, 10);
+            }
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_NAS_PDU(nas_pdu) => {
+                assert_eq!(nas_pdu.0, vec![0x7E, 0x00, 0x68]);
+                has_nas_pdu = true;
+            }
+            _ => {}
+        }
+    }
+    assert!(has_nas_pdu);
+}
@@ -0,0 +1,124 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::ue_context_release::release_ng_ran_side;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct ErrorIndicationIEs from ErrorIndication(ErrorIndicationProtocolIEs_EntryValue) {
+        mandatory {}
+        optional {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            cause: Id_Cause(ngap::Cause),
+            criticality_diagnostics: Id_CriticalityDiagnostics(ngap::CriticalityDiagnostics),
+        }
+    }
+}
+
+/// Handle an ErrorIndication from a gNB, see TS 38.413 section 8.7.5. It is
+/// never answered, but if the gNB does not know the UE it was sent for, the
+/// UE-associated logical NG-connection is released on the AMF side too.
+pub fn handle_error_indication(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    error_indication: ngap::ErrorIndication,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type ErrorIndication");
+
+    let ErrorIndicationIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        cause,
+        criticality_diagnostics,
+    } = match ErrorIndicationIEs::try_from(error_indication) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    let amf_ue_ngap_id = amf_ue_ngap_id.map(|id| id.0);
+    let ran_ue_ngap_id = ran_ue_ngap_id.map(|id| id.0);
+
+    warn!(
+        "ErrorIndication from gNB {:?} for UE {:?}/{:?} with cause {:?}",
+        gnb, amf_ue_ngap_id, ran_ue_ngap_id, cause
+    );
+    if let Some(criticality_diagnostics) = criticality_diagnostics {
+        debug!("CriticalityDiagnostics: {:?}", criticality_diagnostics);
+    }
+
+    let unknown_ue = matches!(
+        cause,
+        Some(ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+            ngap::CauseRadioNetwork::UNKNOWN_LOCAL_UE_NGAP_ID
+                | ngap::CauseRadioNetwork::INCONSISTENT_REMOTE_UE_NGAP_ID
+        )))
+    );
+    if !unknown_ue {
+        return vec![];
+    }
+
+    // Only the UE the gNB knows by these IDs, preferring the AMF_UE_NGAP_ID
+    let connected = store.find_ues_connected_to(gnb);
+    let ue = match (amf_ue_ngap_id, ran_ue_ngap_id) {
+        (Some(amf_ue_ngap_id), _) => connected
+            .into_iter()
+            .find(|ue| ue.amf_ue_ngap_id == amf_ue_ngap_id),
+        (None, Some(ran_ue_ngap_id)) => connected
+            .into_iter()
+            .find(|ue| ue.ran_ue_ngap_id == ran_ue_ngap_id),
+        (None, None) => None,
+    };
+    if let Some(ue) = ue {
+        info!(
+            "Releasing UE {} unknown to gNB {:?}",
+            ue.amf_ue_ngap_id, gnb
+        );
+        release_ng_ran_side(config, store, ue);
+    }
+
+    vec![]
+}
+
+/// An ErrorIndication back to the sender, on the UE stream if the error is
+/// about a UE.
+pub(super) fn error_indication(
+    ue_ngap_ids: (Option<u64>, Option<u32>),
+    cause: ngap::Cause,
+    criticality_diagnostics: ngap::CriticalityDiagnostics,
+) -> NGAPResponse {
+    let sctp_stream = match ue_ngap_ids {
+        (None, None) => 0,
+        _ => UE_SCTP_STREAM,
+    };
+    NGAPResponse {
+        sctp_stream,
+        ngap_pdu: build_error_indication(ue_ngap_ids, cause, criticality_diagnostics),
+        destination: Destination::Sender,
+    }
+}
+
+fn build_error_indication(
+    (amf_ue_ngap_id, ran_ue_ngap_id): (Option<u64>, Option<u32>),
+    cause: ngap::Cause,
+    criticality_diagnostics: ngap::CriticalityDiagnostics,
+) -> ngap::NGAP_PDU {
+    trace!("Building ErrorIndication");
+
+    use ngap::ErrorIndicationProtocolIEs_EntryValue as Value;
+    message::<ngap::ErrorIndication>()
+        .optional_ie(amf_ue_ngap_id.map(|id| Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(id))))
+        .optional_ie(ran_ue_ngap_id.map(|id| Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(id))))
+        .ie(Value::Id_Cause(cause))
+        .ie(Value::Id_CriticalityDiagnostics(criticality_diagnostics))
+        .build()
+}
@@ -0,0 +1,92 @@
+use super::*;
+use crate::store::{CMState, UEContext};
+
+fn build_error_indication_message(
+    amf_ue_ngap_id: u64,
+    cause: ngap::CauseRadioNetwork,
+) -> ngap::ErrorIndication {
+    ngap::ErrorIndication {
+        protocol_i_es: ngap::ErrorIndicationProtocolIEs(vec![
+            ngap::ErrorIndicationProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::ErrorIndicationProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(amf_ue_ngap_id),
+                ),
+            },
+            ngap::ErrorIndicationProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::ErrorIndicationProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(cause),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_error_indication_unknown_ue() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+    store.put_ue(UEContext::new(2, 20, crate::tests::test_gnb()));
+
+    let result = handle_error_indication(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_error_indication_message(
+            1,
+            ngap::CauseRadioNetwork(ngap::CauseRadioNetwork::UNKNOWN_LOCAL_UE_NGAP_ID),
+        ),
+    );
+    assert!(result.is_empty());
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+    assert_eq!(store.get_ue(2).unwrap().cm_state, CMState::Connected);
+}
+
+#[test]
+fn test_error_indication_other_cause() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let result = handle_error_indication(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_error_indication_message(
+            1,
+            ngap::CauseRadioNetwork(ngap::CauseRadioNetwork::UNSPECIFIED),
+        ),
+    );
+    assert!(result.is_empty());
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Connected);
+}
+
+#[test]
+fn test_build_error_indication() {
+    let diagnostics = ngap::CriticalityDiagnostics {
+        procedure_code: Some(ngap::ProcedureCode(ngap::ID_NG_RESET)),
+        triggering_message: None,
+        procedure_criticality: None,
+        i_es_criticality_diagnostics: None,
+        ie_extensions: None,
+    };
+    let cause = ngap::Cause::Protocol(ngap::CauseProtocol(
+        ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_REJECT,
+    ));
+
+    let response = error_indication((Some(1), Some(10)), cause, diagnostics);
+    assert_eq!(response.sctp_stream, UE_SCTP_STREAM);
+    assert_eq!(response.destination, Destination::Sender);
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_ErrorIndication(error_indication),
+        ..
+    }) = response.ngap_pdu
+    else {
+        panic!("Response is not an ErrorIndication");
+    };
+    assert_eq!(error_indication.protocol_i_es.0.len(), 4);
+}
@@ -0,0 +1,97 @@
+use log::{debug, error, info, trace};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::ue_context_release::build_ue_context_release_command;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct HandoverCancelIEs from HandoverCancel(HandoverCancelProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+        }
+    }
+}
+
+pub fn handle_handover_cancel(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    cancel: ngap::HandoverCancel,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type HandoverCancel");
+
+    let HandoverCancelIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        cause,
+    } = match HandoverCancelIEs::try_from(cancel) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+
+    if let Some(cause) = cause {
+        info!("Handover cancelled with cause: {:?}", cause);
+    }
+
+    // The source gNB is acknowledged even if there is nothing to cancel
+    let mut responses = vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_handover_cancel_acknowledge(amf_ue_ngap_id.0, ran_ue_ngap_id.0),
+        destination: Destination::Sender,
+    }];
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in HandoverCancel");
+        return responses;
+    };
+    if let Some(handover) = ue.handover.take() {
+        // Free the resources the target may already have allocated
+        responses.push(NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_ue_context_release_command(
+                ue.amf_ue_ngap_id,
+                handover.target_ran_ue_ngap_id,
+                ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                    ngap::CauseRadioNetwork::HANDOVER_CANCELLED,
+                )),
+            ),
+            destination: Destination::Association(handover.target),
+        });
+        store.put_ue(ue);
+    }
+
+    responses
+}
+
+/// Build a HandoverCancelAcknowledge for the source gNB.
+pub fn build_handover_cancel_acknowledge(
+    amf_ue_ngap_id: u64,
+    ran_ue_ngap_id: u32,
+) -> ngap::NGAP_PDU {
+    trace!("Building HandoverCancelAcknowledge");
+
+    use ngap::HandoverCancelAcknowledgeProtocolIEs_EntryValue as Value;
+    message::<ngap::HandoverCancelAcknowledge>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ran_ue_ngap_id,
+        )))
+        .build()
+}
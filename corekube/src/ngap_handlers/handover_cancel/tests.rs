@@ -0,0 +1,85 @@
+use super::*;
+use crate::store::{Handover, UEContext};
+
+fn handover_cancel() -> ngap::HandoverCancel {
+    ngap::HandoverCancel {
+        protocol_i_es: ngap::HandoverCancelProtocolIEs(vec![
+            ngap::HandoverCancelProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverCancelProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::HandoverCancelProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverCancelProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::HandoverCancelProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverCancelProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                        ngap::CauseRadioNetwork::HANDOVER_CANCELLED,
+                    )),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_handover_cancel() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb().address;
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.handover = Some(Handover {
+        target,
+        target_ran_ue_ngap_id: Some(20),
+        dl_tunnels: vec![],
+    });
+    store.put_ue(ue);
+
+    let result = handle_handover_cancel(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        handover_cancel(),
+    );
+
+    // The source is acknowledged and the target releases what it prepared
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+            value: ngap::SuccessfulOutcomeValue::Id_HandoverCancel(_),
+            ..
+        })
+    ));
+    assert_eq!(result[1].destination, Destination::Association(target));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert!(ue.handover.is_none());
+    assert_eq!(ue.gnb, crate::tests::test_gnb());
+}
+
+#[test]
+fn test_handover_cancel_without_handover() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let result = handle_handover_cancel(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        handover_cancel(),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+}
@@ -0,0 +1,90 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::ies::protocol_ies;
+use super::ue_context_release::build_ue_context_release_command;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct HandoverNotifyIEs from HandoverNotify(HandoverNotifyProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+        }
+        optional {}
+    }
+}
+
+pub fn handle_handover_notify(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    notify: ngap::HandoverNotify,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type HandoverNotify");
+
+    let HandoverNotifyIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+    } = match HandoverNotifyIEs::try_from(notify) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in HandoverNotify");
+        return vec![];
+    };
+    let Some(handover) = ue.handover.take().filter(|h| h.target == *gnb) else {
+        error!(
+            "HandoverNotify for UE {} without a handover to this gNB",
+            amf_ue_ngap_id.0
+        );
+        return vec![];
+    };
+    if handover.target_ran_ue_ngap_id != Some(ran_ue_ngap_id.0) {
+        warn!(
+            "RAN_UE_NGAP_ID mismatch in HandoverNotify: acknowledged {:?}, received {}",
+            handover.target_ran_ue_ngap_id, ran_ue_ngap_id.0
+        );
+    }
+
+    // The UE is now served by the target gNB
+    let source = ue.gnb;
+    let source_ran_ue_ngap_id = ue.ran_ue_ngap_id;
+    ue.gnb = handover.target;
+    ue.ran_ue_ngap_id = ran_ue_ngap_id.0;
+    for (id, dl_tunnel) in handover.dl_tunnels {
+        let session = ue.pdu_session_mut(id);
+        if let (Some(smf), Some(sm_context)) = (store.smf(), &session.sm_context) {
+            if let Err(e) = smf.update_downlink(sm_context, &dl_tunnel) {
+                warn!("Could not switch downlink of PDU session {}: {:?}", id, e);
+            }
+        }
+        session.dl_tunnel = Some(dl_tunnel);
+        session.active = true;
+    }
+    info!("UE {} handed over to {:?}", ue.amf_ue_ngap_id, ue.gnb);
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_ue_context_release_command(
+            amf_ue_ngap_id.0,
+            Some(source_ran_ue_ngap_id),
+            ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                ngap::CauseRadioNetwork::SUCCESSFUL_HANDOVER,
+            )),
+        ),
+        destination: Destination::Association(source),
+    }]
+}
@@ -0,0 +1,78 @@
+use super::*;
+use crate::store::{GTPTunnel, Handover, UEContext};
+
+fn handover_notify(ran_ue_ngap_id: u32) -> ngap::HandoverNotify {
+    ngap::HandoverNotify {
+        protocol_i_es: ngap::HandoverNotifyProtocolIEs(vec![
+            ngap::HandoverNotifyProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverNotifyProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::HandoverNotifyProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverNotifyProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(ran_ue_ngap_id),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_handover_notify() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb().address;
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 2],
+        teid: 2,
+    };
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    ue.handover = Some(Handover {
+        target,
+        target_ran_ue_ngap_id: Some(20),
+        dl_tunnels: vec![(5, dl_tunnel.clone())],
+    });
+    store.put_ue(ue);
+
+    let result = handle_handover_notify(&config, &store, &target, handover_notify(20));
+
+    // The source gNB is told to release its side of the UE
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(crate::tests::test_gnb())
+    );
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_UEContextRelease(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.gnb, target);
+    assert_eq!(ue.ran_ue_ngap_id, 20);
+    assert!(ue.handover.is_none());
+    assert_eq!(ue.pdu_sessions[0].dl_tunnel, Some(dl_tunnel));
+}
+
+#[test]
+fn test_handover_notify_without_handover() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let target = crate::tests::test_target_gnb().address;
+    let result = handle_handover_notify(&config, &store, &target, handover_notify(20));
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.gnb, crate::tests::test_gnb());
+}
@@ -0,0 +1,264 @@
+use bitvec::prelude::*;
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::criticality::missing_cause;
+use super::ies::protocol_ies;
+use super::initial_context_setup::{build_allowed_nssai, build_ue_security_capabilities};
+use super::pdu_session_resource_setup::build_pdu_session_resource_setup_request_transfer;
+use super::setup_request::{build_guami, build_s_nssai};
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Handover, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct HandoverRequiredIEs from HandoverRequired(HandoverRequiredProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            handover_type: Id_HandoverType(ngap::HandoverType),
+            target_id: Id_TargetID(ngap::TargetID),
+            pdu_session_list: Id_PDUSessionResourceListHORqd(ngap::PDUSessionResourceListHORqd),
+            container: Id_SourceToTarget_TransparentContainer(
+                ngap::SourceToTarget_TransparentContainer
+            ),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+        }
+    }
+}
+
+pub fn handle_handover_required(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    handover_required: ngap::HandoverRequired,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type HandoverRequired");
+
+    let HandoverRequiredIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        handover_type,
+        target_id,
+        pdu_session_list,
+        container,
+        cause,
+    } = match HandoverRequiredIEs::try_from(handover_required) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+    debug!("Cause: {:?}", cause);
+    let cause = cause.unwrap_or_else(missing_cause);
+
+    let reject = |cause| {
+        vec![NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_handover_preparation_failure(
+                amf_ue_ngap_id.0,
+                ran_ue_ngap_id.0,
+                ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(cause)),
+            ),
+            destination: Destination::Sender,
+        }]
+    };
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in HandoverRequired");
+        return reject(ngap::CauseRadioNetwork::UNKNOWN_LOCAL_UE_NGAP_ID);
+    };
+    if handover_type.0 != ngap::HandoverType::INTRA5GS {
+        info!("Only handovers within 5GS are supported");
+        return reject(ngap::CauseRadioNetwork::HO_TARGET_NOT_ALLOWED);
+    }
+    let Some(target) = find_target_gnb(store, target_id) else {
+        info!("Unknown target of handover for UE {}", ue.amf_ue_ngap_id);
+        return reject(ngap::CauseRadioNetwork::UNKNOWN_TARGET_ID);
+    };
+
+    // Set up the sessions again at the target, with the same UPF endpoints
+    let mut sessions = vec![];
+    for item in pdu_session_list.0 {
+        let id = item.pdu_session_id.0;
+        match ue.pdu_sessions.iter().find(|s| s.id == id) {
+            Some(session) => match &session.ul_tunnel {
+                Some(ul_tunnel) => sessions.push((
+                    id,
+                    build_pdu_session_resource_setup_request_transfer(
+                        config,
+                        ul_tunnel,
+                        &session.qos_flows,
+                    ),
+                )),
+                None => warn!("No UPF endpoint known for PDU session {}", id),
+            },
+            None => warn!("Unknown PDU session {} in HandoverRequired", id),
+        }
+    }
+    if sessions.is_empty() {
+        info!("None of the PDU sessions can be handed over");
+        return reject(ngap::CauseRadioNetwork::UNSPECIFIED);
+    }
+
+    let Some(mut security) = ue.security.take() else {
+        error!("No NAS security context for UE {}", ue.amf_ue_ngap_id);
+        return reject(ngap::CauseRadioNetwork::UNSPECIFIED);
+    };
+    let (ncc, nh) = security.next_hop();
+    let ngap_pdu = build_handover_request(
+        config,
+        &ue,
+        &security.ue_security_capability,
+        (ncc, nh),
+        cause,
+        sessions,
+        container,
+    );
+    ue.security = Some(security);
+    ue.handover = Some(Handover {
+        target,
+        target_ran_ue_ngap_id: None,
+        dl_tunnels: vec![],
+    });
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Association(target),
+    }]
+}
+
+/// Look up the gNB a handover is targeted at among the connected gNBs.
+fn find_target_gnb(store: &Store, target_id: ngap::TargetID) -> Option<GNBAddress> {
+    let ngap::TargetID::TargetRANNodeID(target_ran_node_id) = target_id else {
+        info!("Handover target is not an NG-RAN node");
+        return None;
+    };
+    let ngap::GlobalRANNodeID::GlobalGNB_ID(global_gnb_id) = target_ran_node_id.global_ran_node_id
+    else {
+        info!("Handover target is not a gNB");
+        return None;
+    };
+    let ngap::GNB_ID::GNB_ID(gnb_id) = global_gnb_id.gnb_id else {
+        return None;
+    };
+
+    store
+        .find_gnb(&global_gnb_id.plmn_identity.0, &gnb_id)
+        .map(|gnb| gnb.address)
+}
+
+/// Build the SecurityContext IE that gives the target NG-RAN node the next
+/// hop parameters it derives the new KgNB from.
+pub(super) fn build_security_context((ncc, nh): (u8, [u8; 32])) -> ngap::SecurityContext {
+    ngap::SecurityContext {
+        next_hop_chaining_count: ngap::NextHopChainingCount(ncc),
+        next_hop_nh: ngap::SecurityKey(BitVec::from_vec(nh.to_vec())),
+        ie_extensions: None,
+    }
+}
+
+/// Build a HandoverRequest asking the target gNB to set up the given PDU
+/// sessions, each with its PDUSessionResourceSetupRequestTransfer.
+pub fn build_handover_request(
+    config: &crate::config::CoreKubeConfig,
+    ue: &UEContext,
+    ue_security_capability: &[u8],
+    next_hop: (u8, [u8; 32]),
+    cause: ngap::Cause,
+    sessions: Vec<(u8, Vec<u8>)>,
+    container: ngap::SourceToTarget_TransparentContainer,
+) -> ngap::NGAP_PDU {
+    trace!("Building HandoverRequest");
+
+    let setup_list = sessions
+        .into_iter()
+        .map(|(id, transfer)| ngap::PDUSessionResourceSetupItemHOReq {
+            pdu_session_id: ngap::PDUSessionID(id),
+            s_nssai: build_s_nssai(&ue.pdu_session_snssai(id)),
+            handover_request_transfer: transfer,
+            ie_extensions: None,
+        })
+        .collect();
+
+    use ngap::HandoverRequestProtocolIEs_EntryValue as Value;
+    message::<ngap::HandoverRequest>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_HandoverType(ngap::HandoverType(
+            ngap::HandoverType::INTRA5GS,
+        )))
+        .ie(Value::Id_Cause(cause))
+        .ie(Value::Id_UEAggregateMaximumBitRate(
+            ngap::UEAggregateMaximumBitRate {
+                ue_aggregate_maximum_bit_rate_dl: ngap::BitRate(config.ue_ambr_downlink),
+                ue_aggregate_maximum_bit_rate_ul: ngap::BitRate(config.ue_ambr_uplink),
+                ie_extensions: None,
+            },
+        ))
+        .ie(Value::Id_UESecurityCapabilities(
+            build_ue_security_capabilities(ue_security_capability),
+        ))
+        .ie(Value::Id_SecurityContext(build_security_context(next_hop)))
+        .ie(Value::Id_PDUSessionResourceSetupListHOReq(
+            ngap::PDUSessionResourceSetupListHOReq(setup_list),
+        ))
+        .ie(Value::Id_AllowedNSSAI(build_allowed_nssai(config, ue)))
+        .ie(Value::Id_SourceToTarget_TransparentContainer(container))
+        .ie(Value::Id_GUAMI(build_guami(config)))
+        .build()
+}
+
+/// Build a HandoverCommand telling the source gNB to move the UE to the
+/// target, with the container the target prepared for it.
+pub fn build_handover_command(
+    ue: &UEContext,
+    container: ngap::TargetToSource_TransparentContainer,
+) -> ngap::NGAP_PDU {
+    trace!("Building HandoverCommand");
+
+    use ngap::HandoverCommandProtocolIEs_EntryValue as Value;
+    message::<ngap::HandoverCommand>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_HandoverType(ngap::HandoverType(
+            ngap::HandoverType::INTRA5GS,
+        )))
+        .ie(Value::Id_TargetToSource_TransparentContainer(container))
+        .build()
+}
+
+/// Build a HandoverPreparationFailure for the source gNB.
+pub fn build_handover_preparation_failure(
+    amf_ue_ngap_id: u64,
+    ran_ue_ngap_id: u32,
+    cause: ngap::Cause,
+) -> ngap::NGAP_PDU {
+    trace!("Building HandoverPreparationFailure");
+
+    use ngap::HandoverPreparationFailureProtocolIEs_EntryValue as Value;
+    message::<ngap::HandoverPreparationFailure>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_Cause(cause))
+        .build()
+}
@@ -0,0 +1,169 @@
+use super::*;
+use crate::ngap_handlers::transfer::encode_transfer;
+use crate::store::{GTPTunnel, QosFlow};
+
+fn target_id(gnb: &crate::store::GNBContext) -> ngap::TargetID {
+    ngap::TargetID::TargetRANNodeID(ngap::TargetRANNodeID {
+        global_ran_node_id: ngap::GlobalRANNodeID::GlobalGNB_ID(ngap::GlobalGNB_ID {
+            plmn_identity: ngap::PLMNIdentity(gnb.plmn_identity.clone()),
+            gnb_id: ngap::GNB_ID::GNB_ID(gnb.gnb_id.clone()),
+            ie_extensions: None,
+        }),
+        selected_tai: ngap::TAI {
+            plmn_identity: ngap::PLMNIdentity(gnb.plmn_identity.clone()),
+            tac: ngap::TAC(vec![0x00, 0x00, 0x01]),
+            ie_extensions: None,
+        },
+        ie_extensions: None,
+    })
+}
+
+fn handover_required(target_id: ngap::TargetID) -> ngap::HandoverRequired {
+    let transfer = encode_transfer(&ngap::HandoverRequiredTransfer {
+        direct_forwarding_path_availability: None,
+        ie_extensions: None,
+    });
+
+    ngap::HandoverRequired {
+        protocol_i_es: ngap::HandoverRequiredProtocolIEs(vec![
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_HANDOVER_TYPE),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_HandoverType(
+                    ngap::HandoverType(ngap::HandoverType::INTRA5GS),
+                ),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                        ngap::CauseRadioNetwork::HANDOVER_DESIRABLE_FOR_RADIO_REASON,
+                    )),
+                ),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_TARGET_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_TargetID(target_id),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_LIST_HO_RQD),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_PDUSessionResourceListHORqd(
+                    ngap::PDUSessionResourceListHORqd(vec![ngap::PDUSessionResourceItemHORqd {
+                        pdu_session_id: ngap::PDUSessionID(5),
+                        handover_required_transfer: transfer,
+                        ie_extensions: None,
+                    }]),
+                ),
+            },
+            ngap::HandoverRequiredProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_SOURCE_TO_TARGET_TRANSPARENT_CONTAINER),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequiredProtocolIEs_EntryValue::Id_SourceToTarget_TransparentContainer(
+                    ngap::SourceToTarget_TransparentContainer(vec![0x01, 0x02, 0x03]),
+                ),
+            },
+        ]),
+    }
+}
+
+fn connected_ue() -> UEContext {
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.as_context_established = true;
+    let mut security =
+        nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+    security.initial_kgnb();
+    ue.security = Some(security);
+    let session = ue.pdu_session_mut(5);
+    session.active = true;
+    session.ul_tunnel = Some(GTPTunnel {
+        address: vec![10, 0, 0, 1],
+        teid: 1,
+    });
+    session.qos_flows = vec![QosFlow {
+        qfi: 1,
+        five_qi: 9,
+        arp_priority_level: 8,
+    }];
+    ue
+}
+
+#[test]
+fn test_handover_required() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb();
+    store.put_gnb(target.clone());
+    store.put_ue(connected_ue());
+
+    let result = handle_handover_required(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        handover_required(target_id(&target)),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(target.address)
+    );
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_HandoverResourceAllocation(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.gnb, crate::tests::test_gnb());
+    assert_eq!(
+        ue.handover.map(|h| h.target),
+        Some(target.address),
+        "the handover should be in progress"
+    );
+    assert_eq!(ue.security.map(|s| s.ncc), Some(1));
+}
+
+#[test]
+fn test_handover_required_unknown_target() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(connected_ue());
+
+    let result = handle_handover_required(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        handover_required(target_id(&crate::tests::test_target_gnb())),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+            value: ngap::UnsuccessfulOutcomeValue::Id_HandoverPreparation(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert!(ue.handover.is_none());
+    assert_eq!(ue.security.map(|s| s.ncc), Some(0));
+}
@@ -0,0 +1,160 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::criticality::missing_cause;
+use super::handover_preparation::{build_handover_command, build_handover_preparation_failure};
+use super::ies::protocol_ies;
+use super::transfer::{decode_transfer, parse_up_transport_layer_information};
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct HandoverRequestAcknowledgeIEs
+        from HandoverRequestAcknowledge(HandoverRequestAcknowledgeProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            container: Id_TargetToSource_TransparentContainer(
+                ngap::TargetToSource_TransparentContainer
+            ),
+        }
+        optional {
+            admitted_list: Id_PDUSessionResourceAdmittedList(ngap::PDUSessionResourceAdmittedList),
+            failed_list: Id_PDUSessionResourceFailedToSetupListHOAck(
+                ngap::PDUSessionResourceFailedToSetupListHOAck
+            ),
+        }
+    }
+}
+
+protocol_ies! {
+    struct HandoverFailureIEs from HandoverFailure(HandoverFailureProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+        }
+    }
+}
+
+pub fn handle_handover_request_acknowledge(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    request_ack: ngap::HandoverRequestAcknowledge,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type HandoverRequestAcknowledge");
+
+    let HandoverRequestAcknowledgeIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        container,
+        admitted_list,
+        failed_list,
+    } = match HandoverRequestAcknowledgeIEs::try_from(request_ack) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in HandoverRequestAcknowledge");
+        return vec![];
+    };
+    let Some(handover) = ue.handover.as_mut().filter(|h| h.target == *gnb) else {
+        info!(
+            "Ignoring HandoverRequestAcknowledge for UE {} without a handover in progress",
+            amf_ue_ngap_id.0
+        );
+        return vec![];
+    };
+
+    handover.target_ran_ue_ngap_id = Some(ran_ue_ngap_id.0);
+    for item in admitted_list.map(|l| l.0).unwrap_or_default() {
+        // The UPF is only told about the new endpoints once the UE arrived
+        let dl_tunnel = decode_transfer::<ngap::HandoverRequestAcknowledgeTransfer>(
+            &item.handover_request_acknowledge_transfer,
+        )
+        .and_then(|transfer| {
+            parse_up_transport_layer_information(transfer.dl_ngu_up_tnl_information)
+        });
+        match dl_tunnel {
+            Some(dl_tunnel) => handover.dl_tunnels.push((item.pdu_session_id.0, dl_tunnel)),
+            None => warn!(
+                "No downlink endpoint for admitted PDU session {}",
+                item.pdu_session_id.0
+            ),
+        }
+    }
+    for item in failed_list.map(|l| l.0).unwrap_or_default() {
+        info!(
+            "PDU session {} could not be set up at the handover target",
+            item.pdu_session_id.0
+        );
+    }
+
+    let ngap_pdu = build_handover_command(&ue, container);
+    let source = ue.gnb;
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Association(source),
+    }]
+}
+
+pub fn handle_handover_failure(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    failure: ngap::HandoverFailure,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type HandoverFailure");
+
+    let HandoverFailureIEs {
+        amf_ue_ngap_id,
+        cause,
+    } = match HandoverFailureIEs::try_from(failure) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    info!(
+        "Handover resource allocation failed for UE {} with cause: {:?}",
+        amf_ue_ngap_id.0, cause
+    );
+    let cause = cause.unwrap_or_else(missing_cause);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in HandoverFailure");
+        return vec![];
+    };
+    if !ue.handover.as_ref().is_some_and(|h| h.target == *gnb) {
+        return vec![];
+    }
+    ue.handover = None;
+    let ngap_pdu = build_handover_preparation_failure(ue.amf_ue_ngap_id, ue.ran_ue_ngap_id, cause);
+    let source = ue.gnb;
+    store.put_ue(ue);
+
+    // The UE stays with the source gNB, which is told the preparation failed
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Association(source),
+    }]
+}
@@ -0,0 +1,136 @@
+use super::*;
+use crate::ngap_handlers::transfer::{build_up_transport_layer_information, encode_transfer};
+use crate::store::{GTPTunnel, Handover, UEContext};
+
+#[test]
+fn test_handover_failure() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let failure = ngap::HandoverFailure {
+        protocol_i_es: ngap::HandoverFailureProtocolIEs(vec![
+            ngap::HandoverFailureProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverFailureProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::HandoverFailureProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverFailureProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                        ngap::CauseRadioNetwork::NO_RADIO_RESOURCES_AVAILABLE_IN_TARGET_CELL,
+                    )),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_handover_failure(&config, &store, &crate::tests::test_gnb(), failure);
+    assert!(result.is_empty());
+
+    // The UE stays with its source gNB
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.gnb, crate::tests::test_gnb());
+}
+
+#[test]
+fn test_handover_request_acknowledge() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb().address;
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.handover = Some(Handover {
+        target,
+        target_ran_ue_ngap_id: None,
+        dl_tunnels: vec![],
+    });
+    store.put_ue(ue);
+
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 2],
+        teid: 2,
+    };
+    let transfer = encode_transfer(&ngap::HandoverRequestAcknowledgeTransfer {
+        dl_ngu_up_tnl_information: build_up_transport_layer_information(&dl_tunnel),
+        dl_forwarding_up_tnl_information: None,
+        security_result: None,
+        qos_flow_setup_response_list: ngap::QosFlowListWithDataForwarding(vec![
+            ngap::QosFlowItemWithDataForwarding {
+                qos_flow_identifier: ngap::QosFlowIdentifier(1),
+                data_forwarding_accepted: None,
+                ie_extensions: None,
+            },
+        ]),
+        qos_flow_failed_to_setup_list: None,
+        data_forwarding_response_drb_list: None,
+        ie_extensions: None,
+    });
+    let request_ack = ngap::HandoverRequestAcknowledge {
+        protocol_i_es: ngap::HandoverRequestAcknowledgeProtocolIEs(vec![
+            ngap::HandoverRequestAcknowledgeProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverRequestAcknowledgeProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::HandoverRequestAcknowledgeProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverRequestAcknowledgeProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(20),
+                ),
+            },
+            ngap::HandoverRequestAcknowledgeProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_ADMITTED_LIST),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::HandoverRequestAcknowledgeProtocolIEs_EntryValue::Id_PDUSessionResourceAdmittedList(
+                    ngap::PDUSessionResourceAdmittedList(vec![
+                        ngap::PDUSessionResourceAdmittedItem {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            handover_request_acknowledge_transfer: transfer,
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+            ngap::HandoverRequestAcknowledgeProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_TARGET_TO_SOURCE_TRANSPARENT_CONTAINER),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::HandoverRequestAcknowledgeProtocolIEs_EntryValue::Id_TargetToSource_TransparentContainer(
+                    ngap::TargetToSource_TransparentContainer(vec![0x04, 0x05]),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_handover_request_acknowledge(&config, &store, &target, request_ack);
+
+    // The HandoverCommand goes to the source gNB
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(crate::tests::test_gnb())
+    );
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+            value: ngap::SuccessfulOutcomeValue::Id_HandoverPreparation(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(
+        ue.handover,
+        Some(Handover {
+            target,
+            target_ran_ue_ngap_id: Some(20),
+            dl_tunnels: vec![(5, dl_tunnel)],
+        })
+    );
+}
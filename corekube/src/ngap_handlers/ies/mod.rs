@@ -0,0 +1,119 @@
+//! Typed extraction of the ProtocolIEs of received messages.
+//!
+//! [`protocol_ies!`] declares a struct with a field per ProtocolIE of a
+//! message, and converts the message into it with `try_from`, failing with
+//! the names of all the mandatory IEs that are missing:
+//!
+//! ```ignore
+//! protocol_ies! {
+//!     struct NGResetIEs from NGReset(NGResetProtocolIEs_EntryValue) {
+//!         mandatory {
+//!             reset_type: Id_ResetType(ngap::ResetType),
+//!         }
+//!         optional {
+//!             cause: Id_Cause(ngap::Cause),
+//!         }
+//!     }
+//! }
+//!
+//! let ies = match NGResetIEs::try_from(ng_reset) {
+//!     Ok(ies) => ies,
+//!     Err(e) => {
+//!         error!("{}", e);
+//!         return vec![];
+//!     }
+//! };
+//! ```
+//!
+//! IEs that are not declared are logged and ignored, and if an IE is
+//! repeated, the last one is kept.
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// The mandatory ProtocolIEs missing from a message.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingIEs {
+    pub message: &'static str,
+    /// The names of the IEs, without the `Id_` of their variant
+    pub missing: Vec<&'static str>,
+}
+
+impl fmt::Display for MissingIEs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Missing {} in {}", self.missing.join(", "), self.message)
+    }
+}
+
+impl std::error::Error for MissingIEs {}
+
+macro_rules! protocol_ies {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident from $message:ident($entry_value:ident) {
+            mandatory {
+                $($mandatory:ident: $mandatory_id:ident($mandatory_type:ty)),* $(,)?
+            }
+            optional {
+                $($optional:ident: $optional_id:ident($optional_type:ty)),* $(,)?
+            }
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $(pub $mandatory: $mandatory_type,)*
+            $(pub $optional: Option<$optional_type>,)*
+        }
+
+        impl TryFrom<::ngap_asn1::$message> for $name {
+            type Error = $crate::ngap_handlers::MissingIEs;
+
+            fn try_from(message: ::ngap_asn1::$message) -> Result<Self, Self::Error> {
+                $(let mut $mandatory = None;)*
+                $(let mut $optional = None;)*
+
+                for protocol_ie in message.protocol_i_es.0 {
+                    match protocol_ie.value {
+                        $(::ngap_asn1::$entry_value::$mandatory_id(value) => {
+                            $mandatory = Some(value);
+                        })*
+                        $(::ngap_asn1::$entry_value::$optional_id(value) => {
+                            $optional = Some(value);
+                        })*
+                        #[allow(unreachable_patterns)]
+                        _ => {
+                            ::log::debug!(
+                                "Ignored ProtocolIE in {}: {:?}",
+                                stringify!($message),
+                                protocol_ie
+                            );
+                        }
+                    }
+                }
+
+                match ($($mandatory,)*) {
+                    ($(Some($mandatory),)*) => Ok($name {
+                        $($mandatory,)*
+                        $($optional,)*
+                    }),
+                    #[allow(unreachable_patterns)]
+                    ($($mandatory,)*) => {
+                        #[allow(unused_mut)]
+                        let mut missing = vec![];
+                        $(if $mandatory.is_none() {
+                            missing.push(&stringify!($mandatory_id)[3..]);
+                        })*
+                        Err($crate::ngap_handlers::MissingIEs {
+                            message: stringify!($message),
+                            missing,
+                        })
+                    }
+                }
+            }
+        }
+    };
+}
+
+pub(super) use protocol_ies;
@@ -0,0 +1,66 @@
+use super::*;
+use ngap_asn1 as ngap;
+
+protocol_ies! {
+    struct HandoverCancelIEs from HandoverCancel(HandoverCancelProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+        }
+    }
+}
+
+fn build_handover_cancel(
+    protocol_ies: Vec<ngap::HandoverCancelProtocolIEs_EntryValue>,
+) -> ngap::HandoverCancel {
+    ngap::HandoverCancel {
+        protocol_i_es: ngap::HandoverCancelProtocolIEs(
+            protocol_ies
+                .into_iter()
+                .map(|value| ngap::HandoverCancelProtocolIEs_Entry {
+                    // The extraction only looks at the value
+                    id: ngap::ProtocolIE_ID(0),
+                    criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                    value,
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[test]
+fn test_protocol_ies() {
+    let ies = HandoverCancelIEs::try_from(build_handover_cancel(vec![
+        ngap::HandoverCancelProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(10)),
+        ngap::HandoverCancelProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(1)),
+    ]))
+    .unwrap();
+    assert_eq!(ies.amf_ue_ngap_id.0, 1);
+    assert_eq!(ies.ran_ue_ngap_id.0, 10);
+    assert!(ies.cause.is_none());
+}
+
+#[test]
+fn test_missing_protocol_ies() {
+    let missing = HandoverCancelIEs::try_from(build_handover_cancel(vec![
+        ngap::HandoverCancelProtocolIEs_EntryValue::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::UNSPECIFIED,
+        ))),
+    ]))
+    .err()
+    .unwrap();
+    assert_eq!(
+        missing,
+        MissingIEs {
+            message: "HandoverCancel",
+            missing: vec!["AMF_UE_NGAP_ID", "RAN_UE_NGAP_ID"],
+        }
+    );
+    assert_eq!(
+        missing.to_string(),
+        "Missing AMF_UE_NGAP_ID, RAN_UE_NGAP_ID in HandoverCancel"
+    );
+}
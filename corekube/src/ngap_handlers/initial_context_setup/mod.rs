@@ -0,0 +1,329 @@
+use bitvec::prelude::*;
+use log::{debug, error, info, trace, warn};
+use nas::security::SecurityContext;
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::criticality::missing_cause;
+use super::ies::protocol_ies;
+use super::nas_timers::{abort_dl_nas_message, start_mobile_reachable_timer};
+use super::pdu_session_resource_setup::activate_downlink;
+use super::setup_request::{build_guami, build_plmn_identity, build_s_nssai};
+use super::ue_context_release::build_ue_context_release_command;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::nssf::{self, NssaiSelection};
+use crate::store::{CMState, GNBAddress, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct InitialContextSetupResponseIEs
+        from InitialContextSetupResponse(InitialContextSetupResponseProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            setup_list: Id_PDUSessionResourceSetupListCxtRes(
+                ngap::PDUSessionResourceSetupListCxtRes
+            ),
+            failed_list: Id_PDUSessionResourceFailedToSetupListCxtRes(
+                ngap::PDUSessionResourceFailedToSetupListCxtRes
+            ),
+        }
+    }
+}
+
+protocol_ies! {
+    struct InitialContextSetupFailureIEs
+        from InitialContextSetupFailure(InitialContextSetupFailureProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+            failed_list: Id_PDUSessionResourceFailedToSetupListCxtFail(
+                ngap::PDUSessionResourceFailedToSetupListCxtFail
+            ),
+        }
+    }
+}
+
+pub fn handle_initial_context_setup_response(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    ics_response: ngap::InitialContextSetupResponse,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type InitialContextSetupResponse");
+
+    let InitialContextSetupResponseIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        setup_list,
+        failed_list,
+    } = match InitialContextSetupResponseIEs::try_from(ics_response) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+
+    let updated = store.update_ue(amf_ue_ngap_id.0, |ue| {
+        if let Some(ran_ue_ngap_id) = ran_ue_ngap_id.filter(|id| id.0 != ue.ran_ue_ngap_id) {
+            warn!(
+                "RAN_UE_NGAP_ID mismatch in InitialContextSetupResponse: stored {}, received {}",
+                ue.ran_ue_ngap_id, ran_ue_ngap_id.0
+            );
+        }
+
+        // The gNB now holds the AS security context and the UE is connected
+        ue.cm_state = CMState::Connected;
+        ue.as_context_established = true;
+
+        for item in setup_list.map(|l| l.0).unwrap_or_default() {
+            let session = ue.pdu_session_mut(item.pdu_session_id.0);
+            session.active = true;
+            activate_downlink(
+                store,
+                session,
+                &item.pdu_session_resource_setup_response_transfer,
+            );
+        }
+        for item in failed_list.map(|l| l.0).unwrap_or_default() {
+            info!(
+                "PDU session {} failed to be set up during InitialContextSetup",
+                item.pdu_session_id.0
+            );
+            ue.set_pdu_session_active(item.pdu_session_id.0, false);
+        }
+    });
+    if updated.is_none() {
+        error!("Unknown AMF_UE_NGAP_ID in InitialContextSetupResponse");
+    }
+    vec![]
+}
+
+/// Handle the failure of the gNB to set up the context of a UE, see TS 38.413
+/// section 8.3.1.3. The gNB has no context of the UE, so the UE is released
+/// to CM-IDLE, along with the NAS signalling connection and any NAS message
+/// that was sent with the request.
+pub fn handle_initial_context_setup_failure(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    ics_failure: ngap::InitialContextSetupFailure,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type InitialContextSetupFailure");
+
+    let InitialContextSetupFailureIEs {
+        amf_ue_ngap_id,
+        cause,
+        failed_list,
+    } = match InitialContextSetupFailureIEs::try_from(ics_failure) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    info!("InitialContextSetup failed with cause: {:?}", cause);
+    let cause = cause.unwrap_or_else(missing_cause);
+
+    for item in failed_list.map(|l| l.0).unwrap_or_default() {
+        info!(
+            "PDU session {} failed to be set up during InitialContextSetup",
+            item.pdu_session_id.0
+        );
+    }
+
+    let released = store.update_ue(amf_ue_ngap_id.0, |ue| {
+        // The Registration Accept was not delivered, so T3550 is stopped
+        // rather than left to retransmit it. The UE keeps the 5G-GUTI it
+        // was given, as it may register with it again, see TS 24.501
+        // section 5.5.1.2.8.
+        abort_dl_nas_message(store, ue);
+        ue.cm_state = CMState::Idle;
+        ue.as_context_established = false;
+        for session in ue.pdu_sessions.iter_mut() {
+            session.active = false;
+        }
+        start_mobile_reachable_timer(config, store, ue);
+        ue.ran_ue_ngap_id
+    });
+    let Some(ran_ue_ngap_id) = released else {
+        error!("Unknown AMF_UE_NGAP_ID in InitialContextSetupFailure");
+        return vec![];
+    };
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_ue_context_release_command(amf_ue_ngap_id.0, Some(ran_ue_ngap_id), cause),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Convert the value of a NAS UE security capability IE to its NGAP form.
+///
+/// NAS has a bit for the null algorithm 0 first, which NGAP leaves out, and
+/// NGAP only defines bits for algorithms 1 to 3.
+pub(super) fn build_ue_security_capabilities(
+    ue_security_capability: &[u8],
+) -> ngap::UESecurityCapabilities {
+    let algorithms = |index: usize| {
+        let nas_bits = ue_security_capability.get(index).copied().unwrap_or(0);
+        BitVec::<u8, Msb0>::from_vec(vec![(nas_bits << 1) & 0xE0, 0])
+    };
+
+    ngap::UESecurityCapabilities {
+        n_rencryption_algorithms: ngap::NRencryptionAlgorithms(algorithms(0)),
+        n_rintegrity_protection_algorithms: ngap::NRintegrityProtectionAlgorithms(algorithms(1)),
+        eutr_aencryption_algorithms: ngap::EUTRAencryptionAlgorithms(algorithms(2)),
+        eutr_aintegrity_protection_algorithms: ngap::EUTRAintegrityProtectionAlgorithms(
+            algorithms(3),
+        ),
+        ie_extensions: None,
+    }
+}
+
+/// Build the Allowed NSSAI of a UE for the NG-RAN. A UE without any allowed
+/// S-NSSAI is given those supported by the AMF, as the IE can not be empty.
+/// The IE holds at most 8 S-NSSAIs, the first ones are kept.
+pub(super) fn build_allowed_nssai(
+    config: &crate::config::CoreKubeConfig,
+    ue: &UEContext,
+) -> ngap::AllowedNSSAI {
+    let allowed: Vec<_> = if ue.allowed_nssai.is_empty() {
+        config.nssai.supported.clone()
+    } else {
+        ue.allowed_nssai
+            .iter()
+            .map(|allowed| allowed.snssai)
+            .collect()
+    };
+    ngap::AllowedNSSAI(
+        allowed
+            .iter()
+            .take(8)
+            .map(|snssai| ngap::AllowedNSSAI_Item {
+                s_nssai: build_s_nssai(snssai),
+                ie_extensions: None,
+            })
+            .collect(),
+    )
+}
+
+/// Select the network slices of a UE registering from the tracking area it
+/// is in, using the slices its gNB supports there.
+pub(super) fn select_ue_nssai(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &UEContext,
+    requested_nssai: Option<&[nas::fgmm::MappedSnssai]>,
+) -> NssaiSelection {
+    let ta_slices = ue
+        .tai
+        .as_ref()
+        .and_then(|tai| Some(store.get_gnb(&ue.gnb)?.slices_in(tai)));
+    nssf::select_nssai(
+        config,
+        ue.supi.as_deref(),
+        requested_nssai,
+        ta_slices.as_deref(),
+    )
+}
+
+/// Build the NAS Registration Accept for a UE, giving it its registration
+/// area as the TAI list and the network slices selected for it.
+pub(super) fn build_registration_accept(
+    config: &crate::config::CoreKubeConfig,
+    tmsi: u32,
+    registration_area: &[TAI],
+    nssai: NssaiSelection,
+) -> nas::fgmm::RegistrationAccept {
+    let plmn = build_plmn_identity(config.mcc, config.mnc).0;
+    nas::fgmm::RegistrationAccept {
+        registration_result: nas::fgmm::REGISTRATION_RESULT_3GPP_ACCESS,
+        guti: Some(nas::fgmm::Guti {
+            plmn: [plmn[0], plmn[1], plmn[2]],
+            amf_region_id: config.amf_region_id.load_be(),
+            amf_set_id: config.amf_set_id.load_be(),
+            amf_pointer: config.amf_pointer.load_be(),
+            tmsi,
+        }),
+        // A TAI list holds at most 16 TAIs
+        tai_list: registration_area
+            .iter()
+            .filter_map(|tai| {
+                Some(nas::fgmm::Tai {
+                    plmn: tai.plmn_identity.as_slice().try_into().ok()?,
+                    tac: tai.tac.as_slice().try_into().ok()?,
+                })
+            })
+            .take(16)
+            .collect(),
+        allowed_nssai: nssai.allowed,
+        rejected_nssai: nssai.rejected,
+        configured_nssai: nssai.configured,
+        pdu_session_status: None,
+        pdu_session_reactivation_result: None,
+    }
+}
+
+/// Build an InitialContextSetupRequest for a UE with an established NAS
+/// security context. The KgNB is derived from the latest uplink NAS COUNT,
+/// which restarts the NH chain used for handovers.
+pub fn build_initial_context_setup_request(
+    config: &crate::config::CoreKubeConfig,
+    ue: &UEContext,
+    security: &mut SecurityContext,
+    nas_pdu: Option<Vec<u8>>,
+    pdu_session_list: Option<ngap::PDUSessionResourceSetupListCxtReq>,
+) -> ngap::NGAP_PDU {
+    trace!("Building InitialContextSetupRequest");
+
+    let kgnb = security.initial_kgnb();
+
+    use ngap::InitialContextSetupRequestProtocolIEs_EntryValue as Value;
+    let mut request = message::<ngap::InitialContextSetupRequest>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_GUAMI(build_guami(config)))
+        .ie(Value::Id_AllowedNSSAI(build_allowed_nssai(config, ue)))
+        .ie(Value::Id_UESecurityCapabilities(
+            build_ue_security_capabilities(&security.ue_security_capability),
+        ))
+        .ie(Value::Id_SecurityKey(ngap::SecurityKey(BitVec::from_vec(
+            kgnb.to_vec(),
+        ))));
+
+    // The UE-AMBR is required whenever PDU sessions are set up
+    if let Some(pdu_session_list) = pdu_session_list {
+        request = request
+            .ie(Value::Id_UEAggregateMaximumBitRate(
+                ngap::UEAggregateMaximumBitRate {
+                    ue_aggregate_maximum_bit_rate_dl: ngap::BitRate(config.ue_ambr_downlink),
+                    ue_aggregate_maximum_bit_rate_ul: ngap::BitRate(config.ue_ambr_uplink),
+                    ie_extensions: None,
+                },
+            ))
+            .ie(Value::Id_PDUSessionResourceSetupListCxtReq(
+                pdu_session_list,
+            ));
+    }
+
+    request
+        .optional_ie(nas_pdu.map(|nas_pdu| Value::Id_NAS_PDU(ngap::NAS_PDU(nas_pdu))))
+        .build()
+}
@@ -0,0 +1,197 @@
+use super::*;
+use crate::store::{CMState, NasTimer, PDUSession, UEContext};
+
+#[test]
+fn test_initial_context_setup_response() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let response = ngap::InitialContextSetupResponse {
+        protocol_i_es: ngap::InitialContextSetupResponseProtocolIEs(vec![
+            ngap::InitialContextSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::InitialContextSetupResponseProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::InitialContextSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::InitialContextSetupResponseProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::InitialContextSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_SETUP_LIST_CXT_RES),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::InitialContextSetupResponseProtocolIEs_EntryValue::Id_PDUSessionResourceSetupListCxtRes(
+                    ngap::PDUSessionResourceSetupListCxtRes(vec![
+                        ngap::PDUSessionResourceSetupItemCxtRes {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            pdu_session_resource_setup_response_transfer: vec![],
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    let result =
+        handle_initial_context_setup_response(&config, &store, &crate::tests::test_gnb(), response);
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.cm_state, CMState::Connected);
+    assert!(ue.as_context_established);
+    assert_eq!(
+        ue.pdu_sessions,
+        vec![PDUSession {
+            id: 5,
+            active: true,
+            ..Default::default()
+        }]
+    );
+}
+
+#[test]
+fn test_initial_context_setup_failure() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    // A Registration Accept sent with the request
+    ue.last_dl_nas = Some(vec![0x7E, 0x00, 0x42, 0x01]);
+    store.start_timer(1, NasTimer::T3550, u64::MAX);
+    store.put_ue(ue);
+
+    let failure = ngap::InitialContextSetupFailure {
+        protocol_i_es: ngap::InitialContextSetupFailureProtocolIEs(vec![
+            ngap::InitialContextSetupFailureProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::InitialContextSetupFailureProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::InitialContextSetupFailureProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::InitialContextSetupFailureProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                        ngap::CauseRadioNetwork::RADIO_RESOURCES_NOT_AVAILABLE,
+                    )),
+                ),
+            },
+        ]),
+    };
+
+    let result =
+        handle_initial_context_setup_failure(&config, &store, &crate::tests::test_gnb(), failure);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) = &result[0].ngap_pdu else {
+        panic!("UEContextReleaseCommand is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_UEContextRelease(command) = &init_msg.value else {
+        panic!("InitiatingMessage is not a UEContextReleaseCommand");
+    };
+    assert!(command.protocol_i_es.0.iter().any(|protocol_ie| matches!(
+        protocol_ie.value,
+        ngap::UEContextReleaseCommandProtocolIEs_EntryValue::Id_UE_NGAP_IDs(
+            ngap::UE_NGAP_IDs::UE_NGAP_ID_pair(ngap::UE_NGAP_ID_pair {
+                amf_ue_ngap_id: ngap::AMF_UE_NGAP_ID(1),
+                ran_ue_ngap_id: ngap::RAN_UE_NGAP_ID(10),
+                ..
+            })
+        )
+    )));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.cm_state, CMState::Idle);
+    assert!(!ue.as_context_established);
+    assert!(!ue.pdu_sessions[0].active);
+    assert_eq!(ue.last_dl_nas, None);
+    assert_eq!(store.timer_due(1, NasTimer::T3550), None);
+    assert!(store.timer_due(1, NasTimer::MobileReachable).is_some());
+}
+
+#[test]
+fn test_allowed_nssai() {
+    let mut config = crate::config::CoreKubeConfig::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+
+    // A UE without allowed S-NSSAIs is given the supported ones, at most 8
+    config.nssai.supported = (1..=10)
+        .map(|sst| nas::fgmm::Snssai { sst, sd: None })
+        .collect();
+    let allowed = build_allowed_nssai(&config, &ue);
+    assert_eq!(allowed.0.len(), 8);
+    assert_eq!(allowed.0[0].s_nssai.sst.0, vec![1]);
+
+    ue.allowed_nssai = vec![nas::fgmm::MappedSnssai {
+        snssai: nas::fgmm::Snssai { sst: 2, sd: None },
+        hplmn: None,
+    }];
+    let allowed = build_allowed_nssai(&config, &ue);
+    assert_eq!(allowed.0.len(), 1);
+    assert_eq!(allowed.0[0].s_nssai.sst.0, vec![2]);
+}
+
+#[test]
+fn test_ue_security_capabilities() {
+    let capabilities = build_ue_security_capabilities(&[0xE0, 0x60]);
+    assert_eq!(
+        capabilities.n_rencryption_algorithms.0.into_vec(),
+        vec![0xC0, 0x00]
+    );
+    assert_eq!(
+        capabilities.n_rintegrity_protection_algorithms.0.into_vec(),
+        vec![0xC0, 0x00]
+    );
+    assert_eq!(
+        capabilities.eutr_aencryption_algorithms.0.into_vec(),
+        vec![0x00, 0x00]
+    );
+}
+
+#[test]
+fn test_build_initial_context_setup_request() {
+    let config = crate::config::CoreKubeConfig::default();
+    let ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    let mut security = SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) =
+        build_initial_context_setup_request(&config, &ue, &mut security, Some(vec![0x7E]), None)
+    else {
+        panic!("InitialContextSetupRequest is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_InitialContextSetup(request) = init_msg.value else {
+        panic!("InitiatingMessage is not an InitialContextSetupRequest");
+    };
+
+    let mut has_security_key = false;
+    let mut has_nas_pdu = false;
+    for protocol_ie in request.protocol_i_es.0 {
+        match protocol_ie.value {
+            ngap::InitialContextSetupRequestProtocolIEs_EntryValue::Id_SecurityKey(key) => {
+                assert_eq!(key.0.len(), 256);
+                has_security_key = true;
+            }
+            ngap::InitialContextSetupRequestProtocolIEs_EntryValue::Id_NAS_PDU(nas_pdu) => {
+                assert_eq!(nas_pdu.0, vec![0x7E]);
+                has_nas_pdu = true;
+            }
+            ngap::InitialContextSetupRequestProtocolIEs_EntryValue::Id_UEAggregateMaximumBitRate(
+                _,
+            ) => {
+                panic!("UE-AMBR must only be sent with PDU sessions");
+            }
+            _ => {}
+        }
+    }
+    assert!(has_security_key);
+    assert!(has_nas_pdu);
+}
@@ -1,68 +1,219 @@
-use log::{debug, error, trace};
+use bitvec::prelude::*;
+use log::{debug, error, info, trace};
+use nas::fgmm::MobilityManagementStatus;
+use nas::security::SecurityContext;
+use nas::{MobilityManagementCause, MobilityMessageIdentifier};
 use ngap_asn1 as ngap;
 
-use super::NGAPResponse;
+use super::deregistration::handle_initial_deregistration_request;
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::ies::protocol_ies;
+use super::paging::parse_user_location_tai;
+use super::registration::handle_initial_registration_request;
+use super::service_request::handle_service_request;
+use super::setup_request::build_plmn_identity;
+use super::ue_context_release::build_ue_context_release_command;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store, UEContext, TAI};
 
 #[cfg(test)]
 mod tests;
 
+protocol_ies! {
+    struct InitialUEMessageIEs from InitialUEMessage(InitialUEMessageProtocolIEs_EntryValue) {
+        mandatory {
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            nas_pdu: Id_NAS_PDU(ngap::NAS_PDU),
+            user_location_information: Id_UserLocationInformation(ngap::UserLocationInformation),
+        }
+        optional {}
+    }
+}
+
 pub fn handle_initial_ue_message(
     config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
     initial_ue_msg: ngap::InitialUEMessage,
 ) -> Vec<NGAPResponse> {
     trace!("Handling NGAP message of type InitialUEMessage");
 
-    let mut ran_ue_ngap_id = None;
-    let mut nas_pdu = None;
-    let mut user_location_information = None;
-
-    // Fill the ProtocolIE values from the request, check if they exist
-    for protocol_ie in initial_ue_msg.protocol_i_es.0 {
-        match protocol_ie.value {
-            ngap::InitialUEMessageProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
-                ran_ue_ngap_id_value,
-            ) => {
-                ran_ue_ngap_id = Some(ran_ue_ngap_id_value);
-            }
-            ngap::InitialUEMessageProtocolIEs_EntryValue::Id_NAS_PDU(nas_pdu_value) => {
-                nas_pdu = Some(nas_pdu_value);
-            }
-            ngap::InitialUEMessageProtocolIEs_EntryValue::Id_UserLocationInformation(
-                user_location_value,
-            ) => {
-                user_location_information = Some(user_location_value);
-            }
-            _ => {
-                debug!("Ignored ProtocolIE in InitialUEMessage: {:?}", protocol_ie);
-            }
+    let InitialUEMessageIEs {
+        ran_ue_ngap_id,
+        nas_pdu,
+        user_location_information,
+    } = match InitialUEMessageIEs::try_from(initial_ue_msg) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
         }
-    }
-
-    let Some(ran_ue_ngap_id) = ran_ue_ngap_id else {
-        error!("Missing RAN_UE_NGAP_ID in InitialUEMessage");
-        return vec![];
     };
     debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+    debug!("NAS_PDU: {:?}", nas_pdu);
+    debug!("UserLocationInformation: {:?}", user_location_information);
 
-    let Some(nas_pdu) = nas_pdu else {
-        error!("Missing NAS_PDU in InitialUEMessage");
+    // Only UserLocationInformation NR is implemented
+    let Some(tai) = parse_user_location_tai(user_location_information) else {
         return vec![];
     };
-    debug!("NAS_PDU: {:?}", nas_pdu);
 
-    let Some(user_location_information) = user_location_information else {
-        error!("Missing UserLocationInformation in InitialUEMessage");
-        return vec![];
+    // An initial NAS message is at most integrity protected, so the plain
+    // message can be read before the UE and its security context are known
+    let plain = match nas_pdu.0.get(1).map(|octet| octet & 0x0F) {
+        Some(0) => Some(nas_pdu.0.clone()),
+        _ => nas::parse_sec_prot_nas(&nas_pdu.0),
+    };
+    let Some(plain) = plain else {
+        error!("Could not parse NAS_PDU in InitialUEMessage");
+        return reject_initial_nas_message(
+            store,
+            gnb,
+            ran_ue_ngap_id.0,
+            MobilityManagementStatus {
+                cause: MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+            }
+            .encode(),
+        );
     };
-    debug!("UserLocationInformation: {:?}", user_location_information);
 
-    // Only UserLocationInformation NR is implemented
-    let ngap::UserLocationInformation::UserLocationInformationNR(user_location_nr) =
-        user_location_information
-    else {
-        error!("UserLocationInformation is not UserLocationInformationNR");
-        return vec![];
+    match nas::mobility_message_type(&plain) {
+        Some(MobilityMessageIdentifier::SERVICE_REQUEST) => handle_service_request(
+            config,
+            store,
+            gnb,
+            ran_ue_ngap_id.0,
+            tai,
+            &nas_pdu.0,
+            &plain,
+        ),
+        Some(MobilityMessageIdentifier::REGISTRATION_REQUEST) => {
+            handle_initial_registration_request(
+                config,
+                store,
+                gnb,
+                ran_ue_ngap_id.0,
+                tai,
+                &nas_pdu.0,
+                &plain,
+            )
+        }
+        Some(MobilityMessageIdentifier::DEREGISTRATION_REQUEST) => {
+            handle_initial_deregistration_request(
+                config,
+                store,
+                gnb,
+                ran_ue_ngap_id.0,
+                tai,
+                &nas_pdu.0,
+                &plain,
+            )
+        }
+        other => {
+            info!("Unhandled NAS message in InitialUEMessage: {:?}", other);
+            reject_initial_nas_message(
+                store,
+                gnb,
+                ran_ue_ngap_id.0,
+                MobilityManagementStatus {
+                    cause: MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED,
+                }
+                .encode(),
+            )
+        }
+    }
+}
+
+/// Answer an initial NAS message that is not accepted with the plain
+/// `nas_message`, such as a Registration Reject, and release the signalling
+/// connection again. No UE context is kept for the UE.
+pub(super) fn reject_initial_nas_message(
+    store: &Store,
+    gnb: &GNBAddress,
+    ran_ue_ngap_id: u32,
+    nas_message: Vec<u8>,
+) -> Vec<NGAPResponse> {
+    // The gNB needs an AMF_UE_NGAP_ID to address the UE with, even though no
+    // context is kept for it
+    let amf_ue_ngap_id = store.allocate_amf_ue_ngap_id();
+    let ue = UEContext::new(amf_ue_ngap_id, ran_ue_ngap_id, *gnb);
+
+    vec![
+        NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_downlink_nas_transport(&ue, nas_message),
+            destination: Destination::Sender,
+        },
+        NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_ue_context_release_command(
+                amf_ue_ngap_id,
+                Some(ran_ue_ngap_id),
+                ngap::Cause::Nas(ngap::CauseNas(ngap::CauseNas::NORMAL_RELEASE)),
+            ),
+            destination: Destination::Sender,
+        },
+    ]
+}
+
+/// Check that a UE may register or request service in the tracking area it
+/// is in, returning the cause to reject it with otherwise.
+pub(super) fn check_tracking_area(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    tai: &TAI,
+) -> Result<(), MobilityManagementCause> {
+    if tai.plmn_identity != build_plmn_identity(config.mcc, config.mnc).0 {
+        info!("UE in tracking area of another PLMN: {:?}", tai);
+        return Err(MobilityManagementCause::PLMN_NOT_ALLOWED);
+    }
+    if store.get_gnb(gnb).is_some_and(|gnb| !gnb.serves(tai)) {
+        info!("UE in tracking area not served by its gNB: {:?}", tai);
+        return Err(MobilityManagementCause::NO_SUITABLE_CELLS_IN_TA);
+    }
+    Ok(())
+}
+
+/// Find the registered UE an initial NAS message is from by the 5G-TMSI this
+/// AMF assigned to it, and verify the message with the NAS security context
+/// the UE still shares. The security context is handed out separately so
+/// that it can be used while the UE context is being changed.
+pub(super) fn identify_ue(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    amf_set_id: u16,
+    amf_pointer: u8,
+    tmsi: u32,
+    ngksi: u8,
+    nas_pdu: &[u8],
+) -> Option<(UEContext, SecurityContext)> {
+    if amf_set_id != config.amf_set_id.load_be::<u16>()
+        || amf_pointer != config.amf_pointer.load_be::<u8>()
+    {
+        info!("Initial NAS message for a 5G-TMSI of another AMF");
+        return None;
+    }
+    let Some(mut ue) = store.find_ue_by_tmsi(tmsi) else {
+        info!("Unknown 5G-TMSI {} in initial NAS message", tmsi);
+        return None;
+    };
+
+    let Some(mut security) = ue.security.take() else {
+        info!("No NAS security context for UE {}", ue.amf_ue_ngap_id);
+        return None;
     };
+    if security.ngksi != ngksi & 0x07 {
+        info!(
+            "ngKSI mismatch in initial NAS message: stored {}, received {}",
+            security.ngksi, ngksi
+        );
+        return None;
+    }
+    if let Err(cause) = security.unprotect(nas_pdu) {
+        info!("Could not verify initial NAS message, cause {:?}", cause);
+        return None;
+    }
 
-    todo!("Implement handling of InitialUEMessage based on NAS_PDU");
+    Some((ue, security))
 }
@@ -1,5 +1,104 @@
+use super::*;
+
 #[test]
 fn it_works() {
     let result = 2 + 2;
     assert_eq!(result, 4);
 }
+
+fn build_initial_ue_message(nas_pdu: Vec<u8>) -> ngap::InitialUEMessage {
+    ngap::InitialUEMessage {
+        protocol_i_es: ngap::InitialUEMessageProtocolIEs(vec![
+            ngap::InitialUEMessageProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::InitialUEMessageProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(20),
+                ),
+            },
+            ngap::InitialUEMessageProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_NAS_PDU),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::InitialUEMessageProtocolIEs_EntryValue::Id_NAS_PDU(ngap::NAS_PDU(
+                    nas_pdu,
+                )),
+            },
+            ngap::InitialUEMessageProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_USER_LOCATION_INFORMATION),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::InitialUEMessageProtocolIEs_EntryValue::Id_UserLocationInformation(
+                    ngap::UserLocationInformation::UserLocationInformationNR(
+                        ngap::UserLocationInformationNR {
+                            nr_cgi: ngap::NR_CGI {
+                                plmn_identity: ngap::PLMNIdentity(vec![0x02, 0xf8, 0x39]),
+                                nr_cell_identity: ngap::NRCellIdentity(
+                                    bitvec::bitvec![u8, bitvec::order::Msb0; 0; 36],
+                                ),
+                                ie_extensions: None,
+                            },
+                            tai: ngap::TAI {
+                                plmn_identity: ngap::PLMNIdentity(vec![0x02, 0xf8, 0x39]),
+                                tac: ngap::TAC(vec![0x00, 0x00, 0x01]),
+                                ie_extensions: None,
+                            },
+                            time_stamp: None,
+                            ie_extensions: None,
+                        },
+                    ),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_initial_ue_message_service_request() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    // A Service Request for a 5G-TMSI that was never assigned
+    let service_request = [
+        &[0x7E, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00][..],
+        &[0x7E, 0x00, 0x4C, 0x10],
+        &[0x00, 0x07, 0xF4, 0x00, 0x40, 0x00, 0x00, 0x00, 0x01],
+    ]
+    .concat();
+    let result = handle_initial_ue_message(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_initial_ue_message(service_request),
+    );
+    assert_eq!(result.len(), 2);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_initial_ue_message_unhandled() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    // An Identity Response is not expected as an initial NAS message, and
+    // is answered with a 5GMM Status before the connection is released
+    let result = handle_initial_ue_message(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_initial_ue_message(vec![0x7E, 0x00, 0x5C, 0x00, 0x01, 0x03]),
+    );
+    assert_eq!(result.len(), 2);
+    assert!(matches!(
+        result[1].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_UEContextRelease(_),
+            ..
+        })
+    ));
+    assert!(store.get_ue(0).is_none());
+}
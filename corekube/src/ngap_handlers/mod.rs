@@ -1,10 +1,73 @@
+mod amf_configuration_update;
+mod authentication;
+mod builder;
+mod criticality;
+mod deregistration;
+mod downlink_nas_transport;
+mod error_indication;
+mod handover_cancel;
+mod handover_notification;
+mod handover_preparation;
+mod handover_resource_allocation;
+mod ies;
+mod initial_context_setup;
 mod initial_ue_message;
+mod nas_timers;
+mod ng_reset;
+mod overload;
+mod paging;
+mod path_switch_request;
+mod pdu_session_resource_modify;
+mod pdu_session_resource_notify;
+mod pdu_session_resource_release;
+mod pdu_session_resource_setup;
+mod ran_configuration_update;
+mod registration;
 mod response;
+mod service_request;
 mod setup_request;
+mod transfer;
+mod ue_context_release;
 mod uplink_nas_transport;
 
+pub use amf_configuration_update::handle_amf_configuration_update_acknowledge;
+pub use amf_configuration_update::handle_amf_configuration_update_failure;
+pub use amf_configuration_update::update_amf_configuration;
+pub use criticality::check_initiating_message;
+pub use criticality::check_successful_outcome;
+pub use criticality::check_unsuccessful_outcome;
+pub use criticality::handle_undecodable_pdu;
+pub use criticality::handle_unknown_procedure;
+pub use deregistration::deregister_ue;
+pub use error_indication::handle_error_indication;
+pub use handover_cancel::handle_handover_cancel;
+pub use handover_notification::handle_handover_notify;
+pub use handover_preparation::handle_handover_required;
+pub use handover_resource_allocation::handle_handover_failure;
+pub use handover_resource_allocation::handle_handover_request_acknowledge;
+pub use ies::MissingIEs;
+pub use initial_context_setup::handle_initial_context_setup_failure;
+pub use initial_context_setup::handle_initial_context_setup_response;
 pub use initial_ue_message::handle_initial_ue_message;
+pub use nas_timers::fire_expired_timers;
+pub use nas_timers::now;
+pub use ng_reset::handle_ng_reset;
+pub use ng_reset::handle_ng_reset_acknowledge;
+pub use ng_reset::reset_gnb;
+pub use overload::check_overload;
+pub use paging::page_ue;
+pub use path_switch_request::handle_path_switch_request;
+pub use pdu_session_resource_modify::handle_pdu_session_resource_modify_indication;
+pub use pdu_session_resource_modify::handle_pdu_session_resource_modify_response;
+pub use pdu_session_resource_notify::handle_pdu_session_resource_notify;
+pub use pdu_session_resource_release::handle_pdu_session_resource_release_response;
+pub use pdu_session_resource_setup::handle_pdu_session_resource_setup_response;
+pub use ran_configuration_update::handle_ran_configuration_update;
 pub use response::ByteResponse;
+pub use response::Destination;
 pub use response::NGAPResponse;
+pub use response::UE_SCTP_STREAM;
 pub use setup_request::handle_setup_request;
+pub use ue_context_release::handle_ue_context_release_complete;
+pub use ue_context_release::handle_ue_context_release_request;
 pub use uplink_nas_transport::handle_uplink_nas_transport;
@@ -0,0 +1,277 @@
+//! 5GMM timers of the AMF, see TS 24.501 section 10.2.
+//!
+//! A worker handles a single message and keeps no state of its own, so it
+//! can not wait for a timer. Timers are kept in the store by due time
+//! instead, and whichever worker polls the store fires the ones that
+//! expired. The NAS message a retransmission timer guards is kept in the UE
+//! context, so that any worker can send it again.
+
+use log::{debug, info, trace};
+use nas::MobilityMessageIdentifier;
+use ngap_asn1 as ngap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::deregistration::deregister;
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::ue_context_release::build_ue_context_release_command;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{CMState, NasTimer, RMState, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+/// The current time in milliseconds since the UNIX epoch, which is how due
+/// times are kept in the store.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn start_timer(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    amf_ue_ngap_id: u64,
+    timer: NasTimer,
+    now: u64,
+) {
+    let due = now + config.nas_timers.value(timer).as_millis() as u64;
+    trace!(
+        "Starting timer {:?} of UE {}, due at {}",
+        timer,
+        amf_ue_ngap_id,
+        due
+    );
+    store.start_timer(amf_ue_ngap_id, timer, due);
+}
+
+/// Keep a plain NAS message that was just sent to a UE, and start the timer
+/// that guards it until the UE answers. Nothing is kept for messages that
+/// are not answered.
+pub(super) fn guard_dl_nas_message(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    message: Vec<u8>,
+) {
+    let Some(timer) = nas::mobility_message_type(&message).and_then(NasTimer::guarding) else {
+        return;
+    };
+    start_timer(config, store, ue.amf_ue_ngap_id, timer, now());
+    ue.last_dl_nas = Some(message);
+    ue.nas_retransmissions = 0;
+}
+
+/// Stop the retransmission timer that an uplink NAS message answers, if it
+/// is running.
+pub(super) fn handle_nas_answer(
+    store: &Store,
+    ue: &mut UEContext,
+    message_type: MobilityMessageIdentifier,
+) {
+    let Some(timer) = NasTimer::answered_by(message_type) else {
+        return;
+    };
+    if store.stop_timer(ue.amf_ue_ngap_id, timer) {
+        debug!("Stopped timer {:?} of UE {}", timer, ue.amf_ue_ngap_id);
+    }
+    // The timer may have just expired, in which case its expiry finds no
+    // message to send again
+    if guarding_timer(ue) == Some(timer) {
+        ue.last_dl_nas = None;
+        ue.nas_retransmissions = 0;
+    }
+}
+
+/// Stop the timer guarding the NAS message a UE has not answered yet, which
+/// was lost along with the NAS signalling connection it was sent on.
+pub(super) fn abort_dl_nas_message(store: &Store, ue: &mut UEContext) {
+    if let Some(timer) = guarding_timer(ue) {
+        if store.stop_timer(ue.amf_ue_ngap_id, timer) {
+            debug!("Stopped timer {:?} of UE {}", timer, ue.amf_ue_ngap_id);
+        }
+    }
+    ue.last_dl_nas = None;
+    ue.nas_retransmissions = 0;
+}
+
+/// The timer guarding the NAS message a UE has not answered yet.
+fn guarding_timer(ue: &UEContext) -> Option<NasTimer> {
+    ue.last_dl_nas
+        .as_deref()
+        .and_then(nas::mobility_message_type)
+        .and_then(NasTimer::guarding)
+}
+
+/// Start the mobile reachable timer of a registered UE that entered CM-IDLE.
+pub(super) fn start_mobile_reachable_timer(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &UEContext,
+) {
+    if ue.rm_state == RMState::Registered {
+        start_timer(
+            config,
+            store,
+            ue.amf_ue_ngap_id,
+            NasTimer::MobileReachable,
+            now(),
+        );
+    }
+}
+
+/// Stop the mobile reachable and implicit de-registration timers of a UE
+/// that established a NAS signalling connection again.
+pub(super) fn stop_reachability_timers(store: &Store, ue: &UEContext) {
+    store.stop_timer(ue.amf_ue_ngap_id, NasTimer::MobileReachable);
+    store.stop_timer(ue.amf_ue_ngap_id, NasTimer::ImplicitDeregistration);
+}
+
+/// Fire the NAS timers that expired by `now`, returning the NGAP messages
+/// that this causes.
+pub fn fire_expired_timers(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    now: u64,
+) -> Vec<NGAPResponse> {
+    store
+        .take_expired_timers(now)
+        .into_iter()
+        .flat_map(|(amf_ue_ngap_id, timer)| {
+            handle_timer_expiry(config, store, amf_ue_ngap_id, timer, now)
+        })
+        .collect()
+}
+
+fn handle_timer_expiry(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    amf_ue_ngap_id: u64,
+    timer: NasTimer,
+    now: u64,
+) -> Vec<NGAPResponse> {
+    trace!(
+        "Handling expiry of timer {:?} of UE {}",
+        timer,
+        amf_ue_ngap_id
+    );
+
+    // Workers handling messages of the UE meanwhile may have answered or
+    // restarted the timer, so the UE is checked and changed in the store
+    // rather than through a copy that could overwrite their changes
+    let responses = match timer {
+        NasTimer::T3550 | NasTimer::T3560 | NasTimer::T3570 => store
+            .update_ue(amf_ue_ngap_id, |ue| {
+                retransmit(config, store, ue, timer, now)
+            }),
+        NasTimer::MobileReachable => store.update_ue(amf_ue_ngap_id, |ue| {
+            // A UE that came back in the meantime has stopped the timer, so
+            // this only guards against a race with its return
+            if ue.cm_state == CMState::Idle && ue.rm_state == RMState::Registered {
+                info!("UE {} is no longer reachable", ue.amf_ue_ngap_id);
+                start_timer(
+                    config,
+                    store,
+                    ue.amf_ue_ngap_id,
+                    NasTimer::ImplicitDeregistration,
+                    now,
+                );
+            }
+            vec![]
+        }),
+        NasTimer::ImplicitDeregistration => {
+            let idle = |ue: &UEContext| ue.cm_state == CMState::Idle;
+            if let Some(mut ue) = store.remove_ue_if(amf_ue_ngap_id, idle) {
+                info!("UE {} implicitly de-registered", ue.amf_ue_ngap_id);
+                deregister(store, &mut ue);
+            }
+            Some(vec![])
+        }
+    };
+
+    responses.unwrap_or_else(|| {
+        debug!(
+            "Timer {:?} expired for unknown UE {}",
+            timer, amf_ue_ngap_id
+        );
+        vec![]
+    })
+}
+
+/// Send the NAS message guarded by an expired retransmission timer again,
+/// or abort its procedure once it was retransmitted often enough.
+fn retransmit(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    timer: NasTimer,
+    now: u64,
+) -> Vec<NGAPResponse> {
+    // The procedure ended with the NAS signalling connection
+    if ue.cm_state != CMState::Connected {
+        ue.last_dl_nas = None;
+        ue.nas_retransmissions = 0;
+        return vec![];
+    }
+    // The UE answered while the timer expired, or a new procedure started
+    // the timer again
+    if guarding_timer(ue) != Some(timer) || store.timer_due(ue.amf_ue_ngap_id, timer).is_some() {
+        return vec![];
+    }
+    let Some(message) = ue.last_dl_nas.take() else {
+        return vec![];
+    };
+
+    if ue.nas_retransmissions >= config.nas_timers.max_retransmissions {
+        info!(
+            "Timer {:?} of UE {} expired for the last time, aborting the procedure",
+            timer, ue.amf_ue_ngap_id
+        );
+        ue.nas_retransmissions = 0;
+
+        // A UE that got a Registration Accept is registered either way, and
+        // keeps the 5G-GUTI it was given. Without authentication, security
+        // mode control or identification the UE can not be served at all.
+        if timer == NasTimer::T3550 {
+            return vec![];
+        }
+        return vec![NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_ue_context_release_command(
+                ue.amf_ue_ngap_id,
+                Some(ue.ran_ue_ngap_id),
+                ngap::Cause::Nas(ngap::CauseNas(ngap::CauseNas::UNSPECIFIED)),
+            ),
+            destination: Destination::Association(ue.gnb),
+        }];
+    }
+
+    ue.nas_retransmissions += 1;
+    info!(
+        "Timer {:?} of UE {} expired, retransmission {}",
+        timer, ue.amf_ue_ngap_id, ue.nas_retransmissions
+    );
+    let nas_pdu = match ue.security.as_mut() {
+        Some(security) => {
+            let header = if nas::mobility_message_type(&message)
+                == Some(MobilityMessageIdentifier::SECURITY_MODE_COMMAND)
+            {
+                nas::SecurityHeader::IntegrityProtectedWithNewSecurityContext
+            } else {
+                nas::SecurityHeader::IntegrityProtectedAndCiphered
+            };
+            security.protect(&message, header)
+        }
+        // Such as an Identity Request before security mode control
+        None => message.clone(),
+    };
+    ue.last_dl_nas = Some(message);
+    start_timer(config, store, ue.amf_ue_ngap_id, timer, now);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+        destination: Destination::Association(ue.gnb),
+    }]
+}
@@ -0,0 +1,199 @@
+use super::*;
+use crate::config::CoreKubeConfig;
+
+/// A Registration Accept, enough for it to be recognised as one.
+const REGISTRATION_ACCEPT: [u8; 4] = [0x7E, 0x00, 0x42, 0x01];
+const SECURITY_MODE_COMMAND: [u8; 3] = [0x7E, 0x00, 0x5D];
+
+/// A registered, connected UE with a NAS security context.
+fn setup() -> Store {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.tmsi = Some(1);
+    ue.security = nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+    store
+}
+
+fn guard(config: &CoreKubeConfig, store: &Store, message: &[u8]) {
+    let mut ue = store.get_ue(1).unwrap();
+    guard_dl_nas_message(config, store, &mut ue, message.to_vec());
+    store.put_ue(ue);
+}
+
+fn is_initiating_message(response: &NGAPResponse, procedure_code: u8) -> bool {
+    matches!(
+        &response.ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(init_msg) if init_msg.procedure_code.0 == procedure_code
+    )
+}
+
+#[test]
+fn test_registration_accept_retransmission() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &REGISTRATION_ACCEPT);
+
+    for retransmission in 1..=4 {
+        let due = store.timer_due(1, NasTimer::T3550).unwrap();
+        assert!(fire_expired_timers(&config, &store, due - 1).is_empty());
+
+        let result = fire_expired_timers(&config, &store, due);
+        assert_eq!(result.len(), 1);
+        assert!(is_initiating_message(
+            &result[0],
+            ngap::ID_DOWNLINK_NAS_TRANSPORT
+        ));
+        assert_eq!(
+            result[0].destination,
+            Destination::Association(crate::tests::test_gnb())
+        );
+        let ue = store.get_ue(1).unwrap();
+        assert_eq!(ue.nas_retransmissions, retransmission);
+        assert_eq!(ue.security.unwrap().dl_count, retransmission as u32);
+    }
+
+    // The fifth expiry ends the procedure, leaving the UE registered
+    let due = store.timer_due(1, NasTimer::T3550).unwrap();
+    assert!(fire_expired_timers(&config, &store, due).is_empty());
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.rm_state, RMState::Registered);
+    assert_eq!(ue.last_dl_nas, None);
+    assert_eq!(store.timer_due(1, NasTimer::T3550), None);
+}
+
+#[test]
+fn test_answer_stops_retransmission() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &REGISTRATION_ACCEPT);
+
+    // Messages that answer another procedure leave the timer running
+    let mut ue = store.get_ue(1).unwrap();
+    handle_nas_answer(
+        &store,
+        &mut ue,
+        MobilityMessageIdentifier::SECURITY_MODE_COMPLETE,
+    );
+    assert!(store.timer_due(1, NasTimer::T3550).is_some());
+
+    handle_nas_answer(
+        &store,
+        &mut ue,
+        MobilityMessageIdentifier::REGISTRATION_COMPLETE,
+    );
+    assert_eq!(store.timer_due(1, NasTimer::T3550), None);
+    assert_eq!(ue.last_dl_nas, None);
+}
+
+#[test]
+fn test_answer_while_timer_expires() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &REGISTRATION_ACCEPT);
+
+    // The timer is taken by a worker just before the answer comes in
+    let due = store.timer_due(1, NasTimer::T3550).unwrap();
+    assert_eq!(store.take_expired_timers(due), vec![(1, NasTimer::T3550)]);
+    let mut ue = store.get_ue(1).unwrap();
+    handle_nas_answer(
+        &store,
+        &mut ue,
+        MobilityMessageIdentifier::REGISTRATION_COMPLETE,
+    );
+    assert_eq!(ue.last_dl_nas, None);
+    store.put_ue(ue);
+
+    assert!(handle_timer_expiry(&config, &store, 1, NasTimer::T3550, due).is_empty());
+    assert_eq!(store.timer_due(1, NasTimer::T3550), None);
+    assert_eq!(store.get_ue(1).unwrap().security.unwrap().dl_count, 0);
+}
+
+#[test]
+fn test_timer_restarted_while_expiring() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &SECURITY_MODE_COMMAND);
+
+    // A new procedure starts the timer again before the expiry is handled
+    let due = store.timer_due(1, NasTimer::T3560).unwrap();
+    store.take_expired_timers(due);
+    guard(&config, &store, &SECURITY_MODE_COMMAND);
+    let restarted = store.timer_due(1, NasTimer::T3560).unwrap();
+
+    assert!(handle_timer_expiry(&config, &store, 1, NasTimer::T3560, due).is_empty());
+    assert_eq!(store.timer_due(1, NasTimer::T3560), Some(restarted));
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.nas_retransmissions, 0);
+    assert!(ue.last_dl_nas.is_some());
+}
+
+#[test]
+fn test_security_mode_command_abort() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &SECURITY_MODE_COMMAND);
+    let mut ue = store.get_ue(1).unwrap();
+    ue.nas_retransmissions = config.nas_timers.max_retransmissions;
+    store.put_ue(ue);
+
+    // The UE can not be served without a security context, so it is released
+    let due = store.timer_due(1, NasTimer::T3560).unwrap();
+    let result = fire_expired_timers(&config, &store, due);
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_UE_CONTEXT_RELEASE
+    ));
+}
+
+#[test]
+fn test_no_retransmission_when_idle() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    guard(&config, &store, &REGISTRATION_ACCEPT);
+    let mut ue = store.get_ue(1).unwrap();
+    ue.cm_state = CMState::Idle;
+    store.put_ue(ue);
+
+    let due = store.timer_due(1, NasTimer::T3550).unwrap();
+    assert!(fire_expired_timers(&config, &store, due).is_empty());
+    assert_eq!(store.get_ue(1).unwrap().last_dl_nas, None);
+}
+
+#[test]
+fn test_implicit_deregistration() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+    ue.cm_state = CMState::Idle;
+    store.put_ue(ue.clone());
+    start_mobile_reachable_timer(&config, &store, &ue);
+
+    let due = store.timer_due(1, NasTimer::MobileReachable).unwrap();
+    assert!(fire_expired_timers(&config, &store, due).is_empty());
+    let due = store
+        .timer_due(1, NasTimer::ImplicitDeregistration)
+        .unwrap();
+    assert!(store.get_ue(1).is_some());
+
+    assert!(fire_expired_timers(&config, &store, due).is_empty());
+    assert!(store.get_ue(1).is_none());
+}
+
+#[test]
+fn test_reachable_again() {
+    let config = CoreKubeConfig::default();
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+    ue.cm_state = CMState::Idle;
+    start_mobile_reachable_timer(&config, &store, &ue);
+
+    // The UE comes back with a Service Request, for instance
+    ue.cm_state = CMState::Connected;
+    stop_reachability_timers(&store, &ue);
+    store.put_ue(ue);
+    assert_eq!(store.timer_due(1, NasTimer::MobileReachable), None);
+    assert!(fire_expired_timers(&config, &store, u64::MAX).is_empty());
+    assert!(store.get_ue(1).is_some());
+}
@@ -0,0 +1,212 @@
+use log::{error, info, trace};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::ue_context_release::release_ng_ran_side;
+use super::{Destination, NGAPResponse};
+use crate::store::{GNBAddress, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct NGResetIEs from NGReset(NGResetProtocolIEs_EntryValue) {
+        mandatory {
+            reset_type: Id_ResetType(ngap::ResetType),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+        }
+    }
+}
+
+/// Handle an NGReset from a gNB, see TS 38.413 section 8.7.4.2.2. The UEs
+/// whose UE-associated logical NG-connections are reset stay registered,
+/// but are in CM-IDLE afterwards.
+pub fn handle_ng_reset(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    ng_reset: ngap::NGReset,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type NGReset");
+
+    let NGResetIEs { reset_type, cause } = match NGResetIEs::try_from(ng_reset) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    info!("gNB {:?} reset NG connections with cause {:?}", gnb, cause);
+
+    let connection_list = match reset_type {
+        ngap::ResetType::NG_Interface(_) => {
+            reset_all(config, store, gnb);
+            None
+        }
+        ngap::ResetType::PartOfNG_Interface(connection_list) => {
+            let reset = connection_list
+                .0
+                .iter()
+                .map(|item| {
+                    let ue = find_connection(store, gnb, item);
+                    // The acknowledged item has both IDs if the UE is known
+                    let ids = match &ue {
+                        Some(ue) => (Some(ue.amf_ue_ngap_id), Some(ue.ran_ue_ngap_id)),
+                        None => (
+                            item.amf_ue_ngap_id.as_ref().map(|id| id.0),
+                            item.ran_ue_ngap_id.as_ref().map(|id| id.0),
+                        ),
+                    };
+                    if let Some(ue) = ue {
+                        release_ng_ran_side(config, store, ue);
+                    }
+                    ids
+                })
+                .collect::<Vec<_>>();
+            Some(build_connection_list(&reset))
+        }
+        _ => {
+            error!("Unknown ResetType in NGReset");
+            return vec![];
+        }
+    };
+
+    vec![NGAPResponse {
+        sctp_stream: 0,
+        ngap_pdu: build_ng_reset_acknowledge(connection_list),
+        destination: Destination::Sender,
+    }]
+}
+
+pub fn handle_ng_reset_acknowledge(
+    _config: &crate::config::CoreKubeConfig,
+    _store: &Store,
+    gnb: &GNBAddress,
+    _reset_ack: ngap::NGResetAcknowledge,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type NGResetAcknowledge");
+
+    info!("gNB {:?} acknowledged the NG reset", gnb);
+    vec![]
+}
+
+/// Reset the NG interface towards a gNB on behalf of the AMF, see TS 38.413
+/// section 8.7.4.2.1. All UE-associated logical NG-connections through the
+/// gNB are reset if `amf_ue_ngap_ids` is empty, otherwise only those of the
+/// given UEs. Returns `None` if none of the given UEs is connected through
+/// the gNB.
+pub fn reset_gnb(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    amf_ue_ngap_ids: &[u64],
+) -> Option<Vec<NGAPResponse>> {
+    let reset_type = if amf_ue_ngap_ids.is_empty() {
+        info!("Resetting the NG interface towards gNB {:?}", gnb);
+        reset_all(config, store, gnb);
+        ngap::ResetType::NG_Interface(ngap::ResetAll(ngap::ResetAll::RESET_ALL))
+    } else {
+        let mut connected = store.find_ues_connected_to(gnb);
+        let reset: Vec<_> = amf_ue_ngap_ids
+            .iter()
+            .filter_map(|&amf_ue_ngap_id| {
+                let index = connected
+                    .iter()
+                    .position(|ue| ue.amf_ue_ngap_id == amf_ue_ngap_id)?;
+                let ue = connected.swap_remove(index);
+                let ids = (Some(ue.amf_ue_ngap_id), Some(ue.ran_ue_ngap_id));
+                release_ng_ran_side(config, store, ue);
+                Some(ids)
+            })
+            .collect();
+        if reset.is_empty() {
+            return None;
+        }
+        info!(
+            "Resetting {} NG connection(s) towards gNB {:?}",
+            reset.len(),
+            gnb
+        );
+        ngap::ResetType::PartOfNG_Interface(build_connection_list(&reset))
+    };
+
+    Some(vec![NGAPResponse {
+        sctp_stream: 0,
+        ngap_pdu: build_ng_reset(reset_type),
+        destination: Destination::Association(*gnb),
+    }])
+}
+
+/// Release every UE connected through a gNB, such as after the gNB restarted.
+pub(super) fn reset_all(config: &crate::config::CoreKubeConfig, store: &Store, gnb: &GNBAddress) {
+    let ues = store.find_ues_connected_to(gnb);
+    if !ues.is_empty() {
+        info!("Releasing {} UE(s) of gNB {:?}", ues.len(), gnb);
+    }
+    for ue in ues {
+        release_ng_ran_side(config, store, ue);
+    }
+}
+
+/// Find the UE with a UE-associated logical NG-connection through a gNB by
+/// the IDs the gNB knows it by, preferring the AMF_UE_NGAP_ID.
+fn find_connection(
+    store: &Store,
+    gnb: &GNBAddress,
+    item: &ngap::UE_associatedLogicalNG_connectionItem,
+) -> Option<UEContext> {
+    let connected = store.find_ues_connected_to(gnb);
+    match (&item.amf_ue_ngap_id, &item.ran_ue_ngap_id) {
+        (Some(amf_ue_ngap_id), _) => connected
+            .into_iter()
+            .find(|ue| ue.amf_ue_ngap_id == amf_ue_ngap_id.0),
+        (None, Some(ran_ue_ngap_id)) => connected
+            .into_iter()
+            .find(|ue| ue.ran_ue_ngap_id == ran_ue_ngap_id.0),
+        (None, None) => None,
+    }
+}
+
+fn build_connection_list(
+    ids: &[(Option<u64>, Option<u32>)],
+) -> ngap::UE_associatedLogicalNG_connectionList {
+    ngap::UE_associatedLogicalNG_connectionList(
+        ids.iter()
+            .map(
+                |&(amf_ue_ngap_id, ran_ue_ngap_id)| ngap::UE_associatedLogicalNG_connectionItem {
+                    amf_ue_ngap_id: amf_ue_ngap_id.map(ngap::AMF_UE_NGAP_ID),
+                    ran_ue_ngap_id: ran_ue_ngap_id.map(ngap::RAN_UE_NGAP_ID),
+                    ie_extensions: None,
+                },
+            )
+            .collect(),
+    )
+}
+
+pub fn build_ng_reset(reset_type: ngap::ResetType) -> ngap::NGAP_PDU {
+    trace!("Building NGReset");
+
+    use ngap::NGResetProtocolIEs_EntryValue as Value;
+    message::<ngap::NGReset>()
+        .ie(Value::Id_Cause(ngap::Cause::Misc(ngap::CauseMisc(
+            ngap::CauseMisc::OM_INTERVENTION,
+        ))))
+        .ie(Value::Id_ResetType(reset_type))
+        .build()
+}
+
+/// Build an NGResetAcknowledge, listing the reset connections if only part
+/// of the NG interface was reset.
+pub fn build_ng_reset_acknowledge(
+    connection_list: Option<ngap::UE_associatedLogicalNG_connectionList>,
+) -> ngap::NGAP_PDU {
+    trace!("Building NGResetAcknowledge");
+
+    use ngap::NGResetAcknowledgeProtocolIEs_EntryValue as Value;
+    message::<ngap::NGResetAcknowledge>()
+        .optional_ie(connection_list.map(Value::Id_UE_associatedLogicalNG_connectionList))
+        .build()
+}
@@ -0,0 +1,164 @@
+use super::*;
+use crate::store::{CMState, RMState};
+
+/// Two UEs connected through the test gNB, and one through another gNB.
+fn setup() -> Store {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    store.put_ue(ue);
+    store.put_ue(UEContext::new(2, 20, crate::tests::test_gnb()));
+    store.put_ue(UEContext::new(
+        3,
+        10,
+        crate::tests::test_target_gnb().address,
+    ));
+    store
+}
+
+fn build_ng_reset(reset_type: ngap::ResetType) -> ngap::NGReset {
+    ngap::NGReset {
+        protocol_i_es: ngap::NGResetProtocolIEs(vec![
+            ngap::NGResetProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::NGResetProtocolIEs_EntryValue::Id_Cause(ngap::Cause::Misc(
+                    ngap::CauseMisc(ngap::CauseMisc::HARDWARE_FAILURE),
+                )),
+            },
+            ngap::NGResetProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RESET_TYPE),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::NGResetProtocolIEs_EntryValue::Id_ResetType(reset_type),
+            },
+        ]),
+    }
+}
+
+/// The UE-associated logical NG-connections listed in an
+/// NGResetAcknowledge, if any.
+fn acknowledged_connections(response: &NGAPResponse) -> Option<Vec<(Option<u64>, Option<u32>)>> {
+    let ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+        value: ngap::SuccessfulOutcomeValue::Id_NGReset(ack),
+        ..
+    }) = &response.ngap_pdu
+    else {
+        panic!("Response is not an NGResetAcknowledge");
+    };
+    ack.protocol_i_es
+        .0
+        .iter()
+        .find_map(|protocol_ie| match &protocol_ie.value {
+            ngap::NGResetAcknowledgeProtocolIEs_EntryValue::Id_UE_associatedLogicalNG_connectionList(
+                list,
+            ) => Some(
+                list.0
+                    .iter()
+                    .map(|item| {
+                        (
+                            item.amf_ue_ngap_id.as_ref().map(|id| id.0),
+                            item.ran_ue_ngap_id.as_ref().map(|id| id.0),
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+}
+
+#[test]
+fn test_ng_reset_all() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+
+    let result = handle_ng_reset(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_ng_reset(ngap::ResetType::NG_Interface(ngap::ResetAll(
+            ngap::ResetAll::RESET_ALL,
+        ))),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert_eq!(acknowledged_connections(&result[0]), None);
+
+    // The UEs stay registered, without NG-RAN resources
+    for id in [1, 2] {
+        let ue = store.get_ue(id).unwrap();
+        assert_eq!(ue.cm_state, CMState::Idle);
+        assert_eq!(ue.rm_state, RMState::Registered);
+        assert!(ue.pdu_sessions.iter().all(|s| !s.active));
+    }
+    assert_eq!(store.get_ue(3).unwrap().cm_state, CMState::Connected);
+}
+
+#[test]
+fn test_ng_reset_part() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+
+    // UE 1 by its AMF_UE_NGAP_ID, UE 2 by its RAN_UE_NGAP_ID, and UE 3 that
+    // is connected through another gNB
+    let connection_list =
+        build_connection_list(&[(Some(1), None), (None, Some(20)), (Some(3), Some(10))]);
+    let result = handle_ng_reset(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_ng_reset(ngap::ResetType::PartOfNG_Interface(connection_list)),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        acknowledged_connections(&result[0]),
+        Some(vec![
+            (Some(1), Some(10)),
+            (Some(2), Some(20)),
+            (Some(3), Some(10))
+        ])
+    );
+
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+    assert_eq!(store.get_ue(2).unwrap().cm_state, CMState::Idle);
+    assert_eq!(store.get_ue(3).unwrap().cm_state, CMState::Connected);
+}
+
+#[test]
+fn test_ng_reset_removes_deregistered_ue() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+    let mut ue = store.get_ue(2).unwrap();
+    ue.rm_state = RMState::Deregistered;
+    store.put_ue(ue);
+
+    reset_all(&config, &store, &crate::tests::test_gnb());
+    assert!(store.get_ue(2).is_none());
+    assert!(store.get_ue(1).is_some());
+}
+
+#[test]
+fn test_reset_gnb() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+    let gnb = crate::tests::test_gnb();
+
+    // Only UEs connected through the gNB can be reset
+    assert!(reset_gnb(&config, &store, &gnb, &[3]).is_none());
+
+    let result = reset_gnb(&config, &store, &gnb, &[2, 3]).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Association(gnb));
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_NGReset(_),
+            ..
+        })
+    ));
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Connected);
+    assert_eq!(store.get_ue(2).unwrap().cm_state, CMState::Idle);
+
+    let result = reset_gnb(&config, &store, &gnb, &[]).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+}
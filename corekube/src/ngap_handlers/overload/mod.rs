@@ -0,0 +1,101 @@
+use log::{info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::amf_configuration_update::update_amf_configuration;
+use super::builder::message;
+use super::{Destination, NGAPResponse};
+use crate::config::OverloadAction;
+use crate::load::LoadChange;
+use crate::store::Store;
+
+#[cfg(test)]
+mod tests;
+
+/// Check the load of the worker, telling every gNB when an overload starts
+/// or stops, see TS 38.413 sections 8.7.6 and 8.7.7. The relative capacity
+/// the AMF advertises is lowered for the duration of the overload, which
+/// the gNBs learn through an AMF Configuration Update.
+pub fn check_overload(config: &crate::config::CoreKubeConfig, store: &Store) -> Vec<NGAPResponse> {
+    let load = store.load();
+    let Some(change) = load.check(&config.overload) else {
+        return vec![];
+    };
+
+    match change {
+        LoadChange::OverloadStarted => warn!(
+            "Overload started with {} queued message(s) and a latency of {:?}",
+            load.queue_depth(),
+            load.latency()
+        ),
+        LoadChange::OverloadStopped => info!("Overload stopped"),
+    }
+
+    let mut responses: Vec<_> = store
+        .gnbs()
+        .into_iter()
+        .map(|gnb| NGAPResponse {
+            sctp_stream: 0,
+            ngap_pdu: match change {
+                LoadChange::OverloadStarted => build_overload_start(config),
+                LoadChange::OverloadStopped => build_overload_stop(),
+            },
+            destination: Destination::Association(gnb.address),
+        })
+        .collect();
+    responses.extend(update_amf_configuration(config, store));
+    responses
+}
+
+/// The relative capacity the AMF advertises, which is lowered during an
+/// overload.
+pub(super) fn relative_amf_capacity(config: &crate::config::CoreKubeConfig, store: &Store) -> u8 {
+    if store.load().is_overloaded() {
+        config
+            .overload
+            .relative_amf_capacity
+            .min(config.relative_amf_capacity)
+    } else {
+        config.relative_amf_capacity
+    }
+}
+
+fn build_overload_action(action: OverloadAction) -> ngap::OverloadAction {
+    ngap::OverloadAction(match action {
+        OverloadAction::RejectNonEmergencyMoData => {
+            ngap::OverloadAction::REJECT_NON_EMERGENCY_MO_DT
+        }
+        OverloadAction::RejectSignalling => ngap::OverloadAction::REJECT_RRC_CR_SIGNALLING,
+        OverloadAction::PermitEmergencyAndMobileTerminatedOnly => {
+            ngap::OverloadAction::PERMIT_EMERGENCY_SESSIONS_AND_MOBILE_TERMINATED_SERVICES_ONLY
+        }
+        OverloadAction::PermitHighPriorityAndMobileTerminatedOnly => {
+            ngap::OverloadAction::PERMIT_HIGH_PRIORITY_SESSIONS_AND_MOBILE_TERMINATED_SERVICES_ONLY
+        }
+    })
+}
+
+pub(super) fn build_overload_start(config: &crate::config::CoreKubeConfig) -> ngap::NGAP_PDU {
+    trace!("Building OverloadStart");
+
+    use ngap::OverloadStartProtocolIEs_EntryValue as Value;
+    message::<ngap::OverloadStart>()
+        .ie(Value::Id_AMFOverloadResponse(
+            ngap::OverloadResponse::OverloadAction(build_overload_action(config.overload.action)),
+        ))
+        .ie(Value::Id_AMFTrafficLoadReductionIndication(
+            ngap::TrafficLoadReductionIndication(config.overload.traffic_load_reduction),
+        ))
+        .build()
+}
+
+fn build_overload_stop() -> ngap::NGAP_PDU {
+    trace!("Building OverloadStop");
+
+    ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        procedure_code: ngap::ProcedureCode(ngap::ID_OVERLOAD_STOP),
+        criticality: ngap::Criticality(ngap::Criticality::REJECT),
+        value: ngap::InitiatingMessageValue::Id_OverloadStop(ngap::OverloadStop {
+            protocol_i_es: ngap::OverloadStopProtocolIEs(vec![]),
+        }),
+    })
+}
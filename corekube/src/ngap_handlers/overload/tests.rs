@@ -0,0 +1,58 @@
+use super::*;
+
+fn procedure_code(response: &NGAPResponse) -> u8 {
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) = &response.ngap_pdu else {
+        panic!("Response is not an InitiatingMessage");
+    };
+    init_msg.procedure_code.0
+}
+
+#[test]
+fn test_check_overload() {
+    let mut config = crate::config::CoreKubeConfig::default();
+    config.overload.start_queue_depth = 1;
+    config.overload.stop_queue_depth = 0;
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb();
+    store.put_gnb(target.clone());
+    assert!(check_overload(&config, &store).is_empty());
+
+    let received = store.load().received();
+    let result = check_overload(&config, &store);
+    assert_eq!(result.len(), 2);
+    assert_eq!(procedure_code(&result[0]), ngap::ID_OVERLOAD_START);
+    assert_eq!(
+        procedure_code(&result[1]),
+        ngap::ID_AMF_CONFIGURATION_UPDATE
+    );
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(target.address)
+    );
+    assert_eq!(relative_amf_capacity(&config, &store), 32);
+    assert!(check_overload(&config, &store).is_empty());
+
+    store.load().handled(received);
+    let result = check_overload(&config, &store);
+    assert_eq!(result.len(), 2);
+    assert_eq!(procedure_code(&result[0]), ngap::ID_OVERLOAD_STOP);
+    assert_eq!(
+        procedure_code(&result[1]),
+        ngap::ID_AMF_CONFIGURATION_UPDATE
+    );
+    assert_eq!(relative_amf_capacity(&config, &store), 255);
+}
+
+#[test]
+fn test_relative_amf_capacity_not_raised() {
+    let mut config = crate::config::CoreKubeConfig::default();
+    config.relative_amf_capacity = 10;
+    config.overload.start_queue_depth = 0;
+    let store = Store::default();
+
+    assert_eq!(
+        store.load().check(&config.overload),
+        Some(LoadChange::OverloadStarted)
+    );
+    assert_eq!(relative_amf_capacity(&config, &store), 10);
+}
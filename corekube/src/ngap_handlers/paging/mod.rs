@@ -0,0 +1,133 @@
+use log::{error, info, trace};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::{Destination, NGAPResponse};
+use crate::config::PagingStrategy;
+use crate::store::{CMState, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+/// Page a CM-IDLE UE so that it comes back with a Service Request. Each call
+/// is one paging attempt, the caller stores the UE context afterwards.
+pub fn page_ue(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    paging_priority: Option<u8>,
+) -> Vec<NGAPResponse> {
+    trace!("Paging UE {}", ue.amf_ue_ngap_id);
+
+    if ue.cm_state != CMState::Idle {
+        info!("UE {} is connected, no need to page it", ue.amf_ue_ngap_id);
+        return vec![];
+    }
+    let Some(tmsi) = ue.tmsi else {
+        error!("UE {} has no 5G-TMSI to be paged with", ue.amf_ue_ngap_id);
+        return vec![];
+    };
+    if ue.registration_area.is_empty() {
+        error!("UE {} has no registration area", ue.amf_ue_ngap_id);
+        return vec![];
+    }
+
+    // The UE is most likely still near the gNB that released it
+    let last_gnb_first = config.paging_strategy == PagingStrategy::LastGNBFirst
+        && ue.paging_attempts == 0
+        && store.get_gnb(&ue.gnb).is_some();
+    let gnbs = if last_gnb_first {
+        vec![ue.gnb]
+    } else {
+        store
+            .find_gnbs_serving(&ue.registration_area)
+            .into_iter()
+            .map(|gnb| gnb.address)
+            .collect()
+    };
+    if gnbs.is_empty() {
+        info!(
+            "No gNB serves the registration area of UE {}",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    }
+    ue.paging_attempts = ue.paging_attempts.saturating_add(1);
+    info!(
+        "Paging UE {} through {} gNB(s), attempt {}",
+        ue.amf_ue_ngap_id,
+        gnbs.len(),
+        ue.paging_attempts
+    );
+
+    // Paging is non-UE-associated signalling
+    gnbs.into_iter()
+        .map(|gnb| NGAPResponse {
+            sctp_stream: 0,
+            ngap_pdu: build_paging(config, tmsi, &ue.registration_area, paging_priority),
+            destination: Destination::Association(gnb),
+        })
+        .collect()
+}
+
+/// Pick the TAI out of the location the NG-RAN reports for a UE.
+pub(super) fn parse_user_location_tai(
+    user_location_information: ngap::UserLocationInformation,
+) -> Option<TAI> {
+    match user_location_information {
+        ngap::UserLocationInformation::UserLocationInformationNR(user_location_nr) => Some(TAI {
+            plmn_identity: user_location_nr.tai.plmn_identity.0,
+            tac: user_location_nr.tai.tac.0,
+        }),
+        unsupported => {
+            error!("Unsupported UserLocationInformation: {:?}", unsupported);
+            None
+        }
+    }
+}
+
+/// Build a Paging message for the UE with the given 5G-TMSI in all the
+/// tracking areas it is registered in.
+pub fn build_paging(
+    config: &crate::config::CoreKubeConfig,
+    tmsi: u32,
+    tais: &[TAI],
+    paging_priority: Option<u8>,
+) -> ngap::NGAP_PDU {
+    trace!("Building Paging");
+
+    let tai_list = tais
+        .iter()
+        .map(|tai| ngap::TAIListForPagingItem {
+            tai: ngap::TAI {
+                plmn_identity: ngap::PLMNIdentity(tai.plmn_identity.clone()),
+                tac: ngap::TAC(tai.tac.clone()),
+                ie_extensions: None,
+            },
+            ie_extensions: None,
+        })
+        .collect();
+
+    use ngap::PagingProtocolIEs_EntryValue as Value;
+    message::<ngap::Paging>()
+        .ie(Value::Id_UEPagingIdentity(
+            ngap::UEPagingIdentity::FiveG_S_TMSI(ngap::FiveG_S_TMSI {
+                amf_set_id: ngap::AMFSetID(config.amf_set_id.clone()),
+                amf_pointer: ngap::AMFPointer(config.amf_pointer.clone()),
+                five_g_tmsi: ngap::FiveG_TMSI(tmsi.to_be_bytes().to_vec()),
+                ie_extensions: None,
+            }),
+        ))
+        .optional_ie(
+            config
+                .paging_drx
+                .map(|paging_drx| Value::Id_PagingDRX(ngap::PagingDRX(paging_drx))),
+        )
+        .ie(Value::Id_TAIListForPaging(ngap::TAIListForPaging(tai_list)))
+        .optional_ie(
+            paging_priority.map(|paging_priority| {
+                Value::Id_PagingPriority(ngap::PagingPriority(paging_priority))
+            }),
+        )
+        .build()
+}
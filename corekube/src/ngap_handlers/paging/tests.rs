@@ -0,0 +1,89 @@
+use super::*;
+use crate::store::{GNBContext, SupportedTA};
+
+fn test_tai() -> TAI {
+    TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, 0x01],
+    }
+}
+
+fn serving(mut gnb: GNBContext) -> GNBContext {
+    gnb.supported_tas = vec![SupportedTA {
+        tac: test_tai().tac,
+        plmns: vec![test_tai().plmn_identity],
+        slices: vec![],
+    }];
+    gnb
+}
+
+/// Two gNBs serving the same tracking area, and a UE that was released by
+/// the first one.
+fn setup() -> (Store, UEContext) {
+    let store = Store::default();
+    let source = GNBContext {
+        address: crate::tests::test_gnb(),
+        ..crate::tests::test_target_gnb()
+    };
+    store.put_gnb(serving(source));
+    store.put_gnb(serving(crate::tests::test_target_gnb()));
+
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.cm_state = CMState::Idle;
+    ue.tmsi = Some(0x1234);
+    ue.registration_area = vec![test_tai()];
+    (store, ue)
+}
+
+#[test]
+fn test_page_ue_last_gnb_first() {
+    let config = crate::config::CoreKubeConfig::default();
+    let (store, mut ue) = setup();
+
+    let result = page_ue(&config, &store, &mut ue, None);
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result[0].destination,
+        Destination::Association(crate::tests::test_gnb())
+    );
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_Paging(_),
+            ..
+        })
+    ));
+
+    // The UE did not answer, so the whole registration area is paged
+    let result = page_ue(&config, &store, &mut ue, None);
+    assert_eq!(result.len(), 2);
+    assert_eq!(ue.paging_attempts, 2);
+}
+
+#[test]
+fn test_page_ue_registration_area() {
+    let config = crate::config::CoreKubeConfig {
+        paging_strategy: PagingStrategy::RegistrationArea,
+        ..Default::default()
+    };
+    let (store, mut ue) = setup();
+
+    let result = page_ue(
+        &config,
+        &store,
+        &mut ue,
+        Some(ngap::PagingPriority::PRIOLEVEL1),
+    );
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|r| r.sctp_stream == 0));
+}
+
+#[test]
+fn test_page_connected_ue() {
+    let config = crate::config::CoreKubeConfig::default();
+    let (store, mut ue) = setup();
+    ue.cm_state = CMState::Connected;
+
+    assert!(page_ue(&config, &store, &mut ue, None).is_empty());
+    assert_eq!(ue.paging_attempts, 0);
+}
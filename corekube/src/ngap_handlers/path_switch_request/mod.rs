@@ -0,0 +1,224 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::handover_preparation::build_security_context;
+use super::ies::protocol_ies;
+use super::initial_context_setup::build_allowed_nssai;
+use super::transfer::{
+    build_up_transport_layer_information, decode_transfer, encode_transfer,
+    parse_up_transport_layer_information,
+};
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct PathSwitchRequestIEs from PathSwitchRequest(PathSwitchRequestProtocolIEs_EntryValue) {
+        mandatory {
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            source_amf_ue_ngap_id: Id_SourceAMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            switched_list: Id_PDUSessionResourceToBeSwitchedDLList(
+                ngap::PDUSessionResourceToBeSwitchedDLList
+            ),
+        }
+        optional {
+            failed_list: Id_PDUSessionResourceFailedToSetupListPSReq(
+                ngap::PDUSessionResourceFailedToSetupListPSReq
+            ),
+        }
+    }
+}
+
+pub fn handle_path_switch_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    path_switch_request: ngap::PathSwitchRequest,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PathSwitchRequest");
+
+    let PathSwitchRequestIEs {
+        ran_ue_ngap_id,
+        source_amf_ue_ngap_id,
+        switched_list,
+        failed_list,
+    } = match PathSwitchRequestIEs::try_from(path_switch_request) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+    debug!("SourceAMF_UE_NGAP_ID: {:?}", source_amf_ue_ngap_id);
+
+    let reject = |cause| {
+        let pdu_session_ids: Vec<u8> = switched_list
+            .0
+            .iter()
+            .map(|item| item.pdu_session_id.0)
+            .collect();
+        vec![NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_path_switch_request_failure(
+                source_amf_ue_ngap_id.0,
+                ran_ue_ngap_id.0,
+                &pdu_session_ids,
+                ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(cause)),
+            ),
+            destination: Destination::Sender,
+        }]
+    };
+
+    let Some(mut ue) = store.get_ue(source_amf_ue_ngap_id.0) else {
+        error!("Unknown SourceAMF_UE_NGAP_ID in PathSwitchRequest");
+        return reject(ngap::CauseRadioNetwork::UNKNOWN_LOCAL_UE_NGAP_ID);
+    };
+    let Some(mut security) = ue.security.take() else {
+        error!("No NAS security context for UE {}", ue.amf_ue_ngap_id);
+        return reject(ngap::CauseRadioNetwork::UNSPECIFIED);
+    };
+
+    // The UE is now served by the gNB that sent the request
+    info!("UE {} moved to {:?} over Xn", ue.amf_ue_ngap_id, gnb);
+    ue.gnb = *gnb;
+    ue.ran_ue_ngap_id = ran_ue_ngap_id.0;
+
+    let mut switched = vec![];
+    for item in switched_list.0 {
+        let id = item.pdu_session_id.0;
+        let Some(transfer) =
+            decode_transfer::<ngap::PathSwitchRequestTransfer>(&item.path_switch_request_transfer)
+        else {
+            continue;
+        };
+        let Some(dl_tunnel) =
+            parse_up_transport_layer_information(transfer.dl_ngu_up_tnl_information)
+        else {
+            continue;
+        };
+
+        let session = ue.pdu_session_mut(id);
+        if let (Some(smf), Some(sm_context)) = (store.smf(), &session.sm_context) {
+            if let Err(e) = smf.update_downlink(sm_context, &dl_tunnel) {
+                warn!("Could not switch downlink of PDU session {}: {:?}", id, e);
+            }
+        }
+        session.dl_tunnel = Some(dl_tunnel);
+        session.active = true;
+        switched.push(id);
+    }
+    for item in failed_list.map(|l| l.0).unwrap_or_default() {
+        info!(
+            "PDU session {} could not be set up at the new gNB",
+            item.pdu_session_id.0
+        );
+        ue.set_pdu_session_active(item.pdu_session_id.0, false);
+    }
+
+    if switched.is_empty() {
+        ue.security = Some(security);
+        store.put_ue(ue);
+        return reject(ngap::CauseRadioNetwork::UNSPECIFIED);
+    }
+
+    // Give the new gNB fresh key material for its next handover
+    let next_hop = security.next_hop();
+    ue.security = Some(security);
+    let ngap_pdu = build_path_switch_request_acknowledge(config, &ue, next_hop, &switched);
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Sender,
+    }]
+}
+
+/// Build a PathSwitchRequestAcknowledge for the PDU sessions that were
+/// switched to the new gNB.
+pub fn build_path_switch_request_acknowledge(
+    config: &crate::config::CoreKubeConfig,
+    ue: &UEContext,
+    next_hop: (u8, [u8; 32]),
+    switched: &[u8],
+) -> ngap::NGAP_PDU {
+    trace!("Building PathSwitchRequestAcknowledge");
+
+    let switched_list = switched
+        .iter()
+        .map(|&id| {
+            let ul_tunnel = ue
+                .pdu_sessions
+                .iter()
+                .find(|s| s.id == id)
+                .and_then(|s| s.ul_tunnel.as_ref());
+            let transfer = ngap::PathSwitchRequestAcknowledgeTransfer {
+                ul_ngu_up_tnl_information: ul_tunnel.map(build_up_transport_layer_information),
+                security_indication: None,
+                ie_extensions: None,
+            };
+            ngap::PDUSessionResourceSwitchedItem {
+                pdu_session_id: ngap::PDUSessionID(id),
+                path_switch_request_acknowledge_transfer: encode_transfer(&transfer),
+                ie_extensions: None,
+            }
+        })
+        .collect();
+
+    use ngap::PathSwitchRequestAcknowledgeProtocolIEs_EntryValue as Value;
+    message::<ngap::PathSwitchRequestAcknowledge>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_SecurityContext(build_security_context(next_hop)))
+        .ie(Value::Id_PDUSessionResourceSwitchedList(
+            ngap::PDUSessionResourceSwitchedList(switched_list),
+        ))
+        .ie(Value::Id_AllowedNSSAI(build_allowed_nssai(config, ue)))
+        .build()
+}
+
+/// Build a PathSwitchRequestFailure releasing all the given PDU sessions for
+/// the same cause.
+pub fn build_path_switch_request_failure(
+    amf_ue_ngap_id: u64,
+    ran_ue_ngap_id: u32,
+    pdu_session_ids: &[u8],
+    cause: ngap::Cause,
+) -> ngap::NGAP_PDU {
+    trace!("Building PathSwitchRequestFailure");
+
+    // The Cause is not Clone, so each transfer is encoded from the same value
+    let transfer = encode_transfer(&ngap::PathSwitchRequestUnsuccessfulTransfer {
+        cause,
+        ie_extensions: None,
+    });
+    let released_list = pdu_session_ids
+        .iter()
+        .map(|&id| ngap::PDUSessionResourceReleasedItemPSFail {
+            pdu_session_id: ngap::PDUSessionID(id),
+            path_switch_request_unsuccessful_transfer: transfer.clone(),
+            ie_extensions: None,
+        })
+        .collect();
+
+    use ngap::PathSwitchRequestFailureProtocolIEs_EntryValue as Value;
+    message::<ngap::PathSwitchRequestFailure>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_PDUSessionResourceReleasedListPSFail(
+            ngap::PDUSessionResourceReleasedListPSFail(released_list),
+        ))
+        .build()
+}
@@ -0,0 +1,115 @@
+use super::*;
+use crate::ngap_handlers::transfer::{build_up_transport_layer_information, encode_transfer};
+use crate::store::{GTPTunnel, UEContext};
+
+fn path_switch_request(dl_tunnel: &GTPTunnel) -> ngap::PathSwitchRequest {
+    let transfer = encode_transfer(&ngap::PathSwitchRequestTransfer {
+        dl_ngu_up_tnl_information: build_up_transport_layer_information(dl_tunnel),
+        dl_ngu_tnl_information_reused: None,
+        user_plane_security_information: None,
+        qos_flow_accepted_list: ngap::QosFlowAcceptedList(vec![ngap::QosFlowAcceptedItem {
+            qos_flow_identifier: ngap::QosFlowIdentifier(1),
+            ie_extensions: None,
+        }]),
+        ie_extensions: None,
+    });
+
+    ngap::PathSwitchRequest {
+        protocol_i_es: ngap::PathSwitchRequestProtocolIEs(vec![
+            ngap::PathSwitchRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PathSwitchRequestProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(20),
+                ),
+            },
+            ngap::PathSwitchRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_SOURCE_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PathSwitchRequestProtocolIEs_EntryValue::Id_SourceAMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PathSwitchRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_TO_BE_SWITCHED_DL_LIST),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PathSwitchRequestProtocolIEs_EntryValue::Id_PDUSessionResourceToBeSwitchedDLList(
+                    ngap::PDUSessionResourceToBeSwitchedDLList(vec![
+                        ngap::PDUSessionResourceToBeSwitchedDLItem {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            path_switch_request_transfer: transfer,
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_path_switch_request() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.as_context_established = true;
+    let mut security =
+        nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+    security.initial_kgnb();
+    ue.security = Some(security);
+    let session = ue.pdu_session_mut(5);
+    session.active = true;
+    session.ul_tunnel = Some(GTPTunnel {
+        address: vec![10, 0, 0, 1],
+        teid: 1,
+    });
+    store.put_ue(ue);
+
+    let target = crate::tests::test_target_gnb().address;
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 2],
+        teid: 2,
+    };
+    let result =
+        handle_path_switch_request(&config, &store, &target, path_switch_request(&dl_tunnel));
+
+    // The acknowledgement goes back to the new gNB
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+            value: ngap::SuccessfulOutcomeValue::Id_PathSwitchRequest(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.gnb, target);
+    assert_eq!(ue.ran_ue_ngap_id, 20);
+    assert_eq!(ue.pdu_sessions[0].dl_tunnel, Some(dl_tunnel));
+    assert_eq!(ue.security.map(|s| s.ncc), Some(1));
+}
+
+#[test]
+fn test_path_switch_request_unknown_ue() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    let target = crate::tests::test_target_gnb().address;
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 2],
+        teid: 2,
+    };
+    let result =
+        handle_path_switch_request(&config, &store, &target, path_switch_request(&dl_tunnel));
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+            value: ngap::UnsuccessfulOutcomeValue::Id_PathSwitchRequest(_),
+            ..
+        })
+    ));
+    assert!(store.get_ue(1).is_none());
+}
@@ -0,0 +1,346 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::transfer::{
+    build_qos_flow_level_qos_parameters, build_up_transport_layer_information, decode_transfer,
+    encode_transfer, parse_up_transport_layer_information,
+};
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{GNBAddress, QosFlow, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct PDUSessionResourceModifyResponseIEs
+        from PDUSessionResourceModifyResponse(
+            PDUSessionResourceModifyResponseProtocolIEs_EntryValue
+        )
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            modify_list: Id_PDUSessionResourceModifyListModRes(
+                ngap::PDUSessionResourceModifyListModRes
+            ),
+            failed_list: Id_PDUSessionResourceFailedToModifyListModRes(
+                ngap::PDUSessionResourceFailedToModifyListModRes
+            ),
+        }
+    }
+}
+
+protocol_ies! {
+    struct PDUSessionResourceModifyIndicationIEs
+        from PDUSessionResourceModifyIndication(
+            PDUSessionResourceModifyIndicationProtocolIEs_EntryValue
+        )
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            modify_list: Id_PDUSessionResourceModifyListModInd(
+                ngap::PDUSessionResourceModifyListModInd
+            ),
+        }
+        optional {}
+    }
+}
+
+pub fn handle_pdu_session_resource_modify_response(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    modify_response: ngap::PDUSessionResourceModifyResponse,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PDUSessionResourceModifyResponse");
+
+    let PDUSessionResourceModifyResponseIEs {
+        amf_ue_ngap_id,
+        modify_list,
+        failed_list,
+    } = match PDUSessionResourceModifyResponseIEs::try_from(modify_response) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in PDUSessionResourceModifyResponse");
+        return vec![];
+    };
+
+    for item in modify_list.map(|l| l.0).unwrap_or_default() {
+        let Some(transfer) = decode_transfer::<ngap::PDUSessionResourceModifyResponseTransfer>(
+            &item.pdu_session_resource_modify_response_transfer,
+        ) else {
+            continue;
+        };
+
+        // The NG-RAN only includes its tunnel endpoint if it has changed
+        if let Some(dl_information) = transfer.dl_ngu_up_tnl_information {
+            ue.pdu_session_mut(item.pdu_session_id.0).dl_tunnel =
+                parse_up_transport_layer_information(dl_information);
+        }
+        for failed_flow in transfer
+            .qos_flow_failed_to_add_or_modify_list
+            .map(|l| l.0)
+            .unwrap_or_default()
+        {
+            info!(
+                "QoS flow {} of PDU session {} failed to be modified: {:?}",
+                failed_flow.qos_flow_identifier.0, item.pdu_session_id.0, failed_flow.cause
+            );
+        }
+    }
+    for item in failed_list.map(|l| l.0).unwrap_or_default() {
+        match decode_transfer::<ngap::PDUSessionResourceModifyUnsuccessfulTransfer>(
+            &item.pdu_session_resource_modify_unsuccessful_transfer,
+        ) {
+            Some(transfer) => info!(
+                "PDU session {} failed to be modified: {:?}",
+                item.pdu_session_id.0, transfer.cause
+            ),
+            None => info!(
+                "PDU session {} failed to be modified",
+                item.pdu_session_id.0
+            ),
+        }
+    }
+
+    store.put_ue(ue);
+    vec![]
+}
+
+pub fn handle_pdu_session_resource_modify_indication(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    modify_indication: ngap::PDUSessionResourceModifyIndication,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PDUSessionResourceModifyIndication");
+
+    let PDUSessionResourceModifyIndicationIEs {
+        amf_ue_ngap_id,
+        modify_list,
+    } = match PDUSessionResourceModifyIndicationIEs::try_from(modify_indication) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in PDUSessionResourceModifyIndication");
+        return vec![];
+    };
+
+    let mut confirmed = vec![];
+    let mut failed = vec![];
+    for item in modify_list.0 {
+        let pdu_session_id = item.pdu_session_id.0;
+        let Some(session) = ue.pdu_sessions.iter_mut().find(|s| s.id == pdu_session_id) else {
+            warn!(
+                "Modification indicated for unknown PDU session {}",
+                pdu_session_id
+            );
+            failed.push((
+                pdu_session_id,
+                ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                    ngap::CauseRadioNetwork::UNKNOWN_PDU_SESSION_ID,
+                )),
+            ));
+            continue;
+        };
+        let Some(ul_tunnel) = session.ul_tunnel.clone() else {
+            warn!("PDU session {} has no uplink tunnel", pdu_session_id);
+            failed.push((
+                pdu_session_id,
+                ngap::Cause::Misc(ngap::CauseMisc(ngap::CauseMisc::UNSPECIFIED)),
+            ));
+            continue;
+        };
+        let Some(transfer) = decode_transfer::<ngap::PDUSessionResourceModifyIndicationTransfer>(
+            &item.pdu_session_resource_modify_indication_transfer,
+        ) else {
+            failed.push((
+                pdu_session_id,
+                ngap::Cause::Protocol(ngap::CauseProtocol(
+                    ngap::CauseProtocol::ABSTRACT_SYNTAX_ERROR_FALSELY_CONSTRUCTED_MESSAGE,
+                )),
+            ));
+            continue;
+        };
+
+        let dl_information = transfer.dl_qos_flow_per_tnl_information;
+        session.dl_tunnel =
+            parse_up_transport_layer_information(dl_information.up_transport_layer_information);
+
+        // Accept all the QoS flows the NG-RAN has mapped onto the new tunnel
+        let confirm_transfer = ngap::PDUSessionResourceModifyConfirmTransfer {
+            qos_flow_modify_confirm_list: ngap::QosFlowModifyConfirmList(
+                dl_information
+                    .associated_qos_flow_list
+                    .0
+                    .into_iter()
+                    .map(|qos_flow| ngap::QosFlowModifyConfirmItem {
+                        qos_flow_identifier: qos_flow.qos_flow_identifier,
+                        ie_extensions: None,
+                    })
+                    .collect(),
+            ),
+            ulngu_up_tnl_information: build_up_transport_layer_information(&ul_tunnel),
+            additional_ng_uuptnl_information: None,
+            qos_flow_failed_to_modify_list: None,
+            ie_extensions: None,
+        };
+        confirmed.push(ngap::PDUSessionResourceModifyItemModCfm {
+            pdu_session_id: ngap::PDUSessionID(pdu_session_id),
+            pdu_session_resource_modify_confirm_transfer: encode_transfer(&confirm_transfer),
+            ie_extensions: None,
+        });
+    }
+
+    let response = build_pdu_session_resource_modify_confirm(&ue, confirmed, failed);
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: response,
+        destination: Destination::Sender,
+    }]
+}
+
+fn build_pdu_session_resource_modify_confirm(
+    ue: &UEContext,
+    confirmed: Vec<ngap::PDUSessionResourceModifyItemModCfm>,
+    failed: Vec<(u8, ngap::Cause)>,
+) -> ngap::NGAP_PDU {
+    let failed_items: Vec<_> = failed
+        .into_iter()
+        .map(
+            |(pdu_session_id, cause)| ngap::PDUSessionResourceFailedToModifyItemModCfm {
+                pdu_session_id: ngap::PDUSessionID(pdu_session_id),
+                pdu_session_resource_modify_indication_unsuccessful_transfer: encode_transfer(
+                    &ngap::PDUSessionResourceModifyIndicationUnsuccessfulTransfer {
+                        cause,
+                        ie_extensions: None,
+                    },
+                ),
+                ie_extensions: None,
+            },
+        )
+        .collect();
+
+    use ngap::PDUSessionResourceModifyConfirmProtocolIEs_EntryValue as Value;
+    // Both lists are optional but must not be empty when present
+    message::<ngap::PDUSessionResourceModifyConfirm>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .optional_ie((!confirmed.is_empty()).then(|| {
+            Value::Id_PDUSessionResourceModifyListModCfm(ngap::PDUSessionResourceModifyListModCfm(
+                confirmed,
+            ))
+        }))
+        .optional_ie((!failed_items.is_empty()).then(|| {
+            Value::Id_PDUSessionResourceFailedToModifyListModCfm(
+                ngap::PDUSessionResourceFailedToModifyListModCfm(failed_items),
+            )
+        }))
+        .build()
+}
+
+/// Build the PDUSessionResourceModifyRequestTransfer adding or modifying the
+/// given QoS flows and releasing the flows with the given QFIs.
+pub fn build_pdu_session_resource_modify_request_transfer(
+    qos_flows_to_add_or_modify: &[QosFlow],
+    qos_flows_to_release: Vec<(u8, ngap::Cause)>,
+) -> Vec<u8> {
+    let mut protocol_ies = vec![];
+
+    if !qos_flows_to_add_or_modify.is_empty() {
+        protocol_ies.push(ngap::PDUSessionResourceModifyRequestTransferProtocolIEs_Entry {
+            id: ngap::ProtocolIE_ID(ngap::ID_QOS_FLOW_ADD_OR_MODIFY_REQUEST_LIST),
+            criticality: ngap::Criticality(ngap::Criticality::REJECT),
+            value: ngap::PDUSessionResourceModifyRequestTransferProtocolIEs_EntryValue::Id_QosFlowAddOrModifyRequestList(
+                ngap::QosFlowAddOrModifyRequestList(
+                    qos_flows_to_add_or_modify
+                        .iter()
+                        .map(|qos_flow| ngap::QosFlowAddOrModifyRequestItem {
+                            qos_flow_identifier: ngap::QosFlowIdentifier(qos_flow.qfi),
+                            qos_flow_level_qos_parameters: Some(
+                                build_qos_flow_level_qos_parameters(qos_flow),
+                            ),
+                            e_rab_id: None,
+                            ie_extensions: None,
+                        })
+                        .collect(),
+                ),
+            ),
+        });
+    }
+    if !qos_flows_to_release.is_empty() {
+        protocol_ies.push(ngap::PDUSessionResourceModifyRequestTransferProtocolIEs_Entry {
+            id: ngap::ProtocolIE_ID(ngap::ID_QOS_FLOW_TO_RELEASE_LIST),
+            criticality: ngap::Criticality(ngap::Criticality::REJECT),
+            value: ngap::PDUSessionResourceModifyRequestTransferProtocolIEs_EntryValue::Id_QosFlowToReleaseList(
+                ngap::QosFlowListWithCause(
+                    qos_flows_to_release
+                        .into_iter()
+                        .map(|(qfi, cause)| ngap::QosFlowWithCauseItem {
+                            qos_flow_identifier: ngap::QosFlowIdentifier(qfi),
+                            cause,
+                            ie_extensions: None,
+                        })
+                        .collect(),
+                ),
+            ),
+        });
+    }
+
+    encode_transfer(&ngap::PDUSessionResourceModifyRequestTransfer {
+        protocol_i_es: ngap::PDUSessionResourceModifyRequestTransferProtocolIEs(protocol_ies),
+    })
+}
+
+/// Build a PDUSessionResourceModifyRequest for a single PDU session. The
+/// `transfer` is built with [`build_pdu_session_resource_modify_request_transfer`].
+pub fn build_pdu_session_resource_modify_request(
+    ue: &UEContext,
+    pdu_session_id: u8,
+    nas_pdu: Option<Vec<u8>>,
+    transfer: Vec<u8>,
+) -> ngap::NGAP_PDU {
+    trace!("Building PDUSessionResourceModifyRequest");
+
+    use ngap::PDUSessionResourceModifyRequestProtocolIEs_EntryValue as Value;
+    message::<ngap::PDUSessionResourceModifyRequest>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_PDUSessionResourceModifyListModReq(
+            ngap::PDUSessionResourceModifyListModReq(vec![
+                ngap::PDUSessionResourceModifyItemModReq {
+                    pdu_session_id: ngap::PDUSessionID(pdu_session_id),
+                    nas_pdu: nas_pdu.map(ngap::NAS_PDU),
+                    pdu_session_resource_modify_request_transfer: transfer,
+                    ie_extensions: None,
+                },
+            ]),
+        ))
+        .build()
+}
@@ -0,0 +1,143 @@
+use super::*;
+use crate::store::{GTPTunnel, PDUSession};
+
+#[test]
+fn test_pdu_session_resource_modify_indication() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let ul_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 1],
+        teid: 1,
+    };
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.pdu_sessions.push(PDUSession {
+        id: 5,
+        active: true,
+        ul_tunnel: Some(ul_tunnel.clone()),
+        ..Default::default()
+    });
+    store.put_ue(ue);
+
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 3],
+        teid: 2,
+    };
+    let transfer = encode_transfer(&ngap::PDUSessionResourceModifyIndicationTransfer {
+        dl_qos_flow_per_tnl_information: ngap::QosFlowPerTNLInformation {
+            up_transport_layer_information: build_up_transport_layer_information(&dl_tunnel),
+            associated_qos_flow_list: ngap::AssociatedQosFlowList(vec![
+                ngap::AssociatedQosFlowItem {
+                    qos_flow_identifier: ngap::QosFlowIdentifier(1),
+                    qos_flow_mapping_indication: None,
+                    ie_extensions: None,
+                },
+            ]),
+            ie_extensions: None,
+        },
+        additional_dl_qos_flow_per_tnl_information: None,
+        ie_extensions: None,
+    });
+
+    let indication = ngap::PDUSessionResourceModifyIndication {
+        protocol_i_es: ngap::PDUSessionResourceModifyIndicationProtocolIEs(vec![
+            ngap::PDUSessionResourceModifyIndicationProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceModifyIndicationProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PDUSessionResourceModifyIndicationProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_MODIFY_LIST_MOD_IND),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceModifyIndicationProtocolIEs_EntryValue::Id_PDUSessionResourceModifyListModInd(
+                    ngap::PDUSessionResourceModifyListModInd(vec![
+                        ngap::PDUSessionResourceModifyItemModInd {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            pdu_session_resource_modify_indication_transfer: transfer,
+                            ie_extensions: None,
+                        },
+                        ngap::PDUSessionResourceModifyItemModInd {
+                            pdu_session_id: ngap::PDUSessionID(6),
+                            pdu_session_resource_modify_indication_transfer: vec![],
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_pdu_session_resource_modify_indication(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        indication,
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].sctp_stream, UE_SCTP_STREAM);
+
+    let ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+        value: ngap::SuccessfulOutcomeValue::Id_PDUSessionResourceModifyIndication(confirm),
+        ..
+    }) = &result[0].ngap_pdu
+    else {
+        panic!("Response is not a PDUSessionResourceModifyConfirm");
+    };
+    let mut confirmed = 0;
+    let mut failed = 0;
+    for protocol_ie in &confirm.protocol_i_es.0 {
+        match &protocol_ie.value {
+            ngap::PDUSessionResourceModifyConfirmProtocolIEs_EntryValue::Id_PDUSessionResourceModifyListModCfm(
+                list,
+            ) => confirmed = list.0.len(),
+            ngap::PDUSessionResourceModifyConfirmProtocolIEs_EntryValue::Id_PDUSessionResourceFailedToModifyListModCfm(
+                list,
+            ) => failed = list.0.len(),
+            _ => {}
+        }
+    }
+    assert_eq!(confirmed, 1);
+    assert_eq!(failed, 1);
+
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.pdu_sessions[0].dl_tunnel, Some(dl_tunnel));
+    assert_eq!(ue.pdu_sessions.len(), 1);
+}
+
+#[test]
+fn test_pdu_session_resource_modify_request() {
+    let ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    let transfer = build_pdu_session_resource_modify_request_transfer(
+        &[QosFlow {
+            qfi: 2,
+            five_qi: 7,
+            arp_priority_level: 8,
+        }],
+        vec![],
+    );
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) =
+        build_pdu_session_resource_modify_request(&ue, 5, None, transfer)
+    else {
+        panic!("PDUSessionResourceModifyRequest is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_PDUSessionResourceModify(request) = init_msg.value else {
+        panic!("InitiatingMessage is not a PDUSessionResourceModifyRequest");
+    };
+
+    for protocol_ie in request.protocol_i_es.0 {
+        if let ngap::PDUSessionResourceModifyRequestProtocolIEs_EntryValue::Id_PDUSessionResourceModifyListModReq(
+            list,
+        ) = protocol_ie.value
+        {
+            let transfer = decode_transfer::<ngap::PDUSessionResourceModifyRequestTransfer>(
+                &list.0[0].pdu_session_resource_modify_request_transfer,
+            )
+            .expect("Transfer should decode");
+            // Only the list of flows to add or modify is present
+            assert_eq!(transfer.protocol_i_es.0.len(), 1);
+            return;
+        }
+    }
+    panic!("Missing PDUSessionResourceModifyListModReq");
+}
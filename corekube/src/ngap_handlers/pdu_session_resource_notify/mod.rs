@@ -0,0 +1,106 @@
+use log::{debug, error, info, trace};
+use ngap_asn1 as ngap;
+
+use super::ies::protocol_ies;
+use super::pdu_session_resource_release::release_user_plane;
+use super::transfer::decode_transfer;
+use super::NGAPResponse;
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct PDUSessionResourceNotifyIEs
+        from PDUSessionResourceNotify(PDUSessionResourceNotifyProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            notify_list: Id_PDUSessionResourceNotifyList(ngap::PDUSessionResourceNotifyList),
+            released_list: Id_PDUSessionResourceReleasedListNot(
+                ngap::PDUSessionResourceReleasedListNot
+            ),
+        }
+    }
+}
+
+pub fn handle_pdu_session_resource_notify(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    notify: ngap::PDUSessionResourceNotify,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PDUSessionResourceNotify");
+
+    let PDUSessionResourceNotifyIEs {
+        amf_ue_ngap_id,
+        notify_list,
+        released_list,
+    } = match PDUSessionResourceNotifyIEs::try_from(notify) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in PDUSessionResourceNotify");
+        return vec![];
+    };
+
+    for item in notify_list.map(|l| l.0).unwrap_or_default() {
+        let pdu_session_id = item.pdu_session_id.0;
+        let Some(transfer) = decode_transfer::<ngap::PDUSessionResourceNotifyTransfer>(
+            &item.pdu_session_resource_notify_transfer,
+        ) else {
+            continue;
+        };
+
+        for notified_flow in transfer
+            .qos_flow_notify_list
+            .map(|l| l.0)
+            .unwrap_or_default()
+        {
+            info!(
+                "QoS flow {} of PDU session {}: {:?}",
+                notified_flow.qos_flow_identifier.0,
+                pdu_session_id,
+                notified_flow.notification_cause
+            );
+        }
+        for released_flow in transfer
+            .qos_flow_released_list
+            .map(|l| l.0)
+            .unwrap_or_default()
+        {
+            info!(
+                "QoS flow {} of PDU session {} released by NG-RAN: {:?}",
+                released_flow.qos_flow_identifier.0, pdu_session_id, released_flow.cause
+            );
+            if let Some(session) = ue.pdu_sessions.iter_mut().find(|s| s.id == pdu_session_id) {
+                session
+                    .qos_flows
+                    .retain(|qos_flow| qos_flow.qfi != released_flow.qos_flow_identifier.0);
+            }
+        }
+    }
+    for item in released_list.map(|l| l.0).unwrap_or_default() {
+        match decode_transfer::<ngap::PDUSessionResourceNotifyReleasedTransfer>(
+            &item.pdu_session_resource_notify_released_transfer,
+        ) {
+            Some(transfer) => info!(
+                "PDU session {} released by NG-RAN: {:?}",
+                item.pdu_session_id.0, transfer.cause
+            ),
+            None => info!("PDU session {} released by NG-RAN", item.pdu_session_id.0),
+        }
+        release_user_plane(store, ue.remove_pdu_session(item.pdu_session_id.0));
+    }
+
+    store.put_ue(ue);
+    vec![]
+}
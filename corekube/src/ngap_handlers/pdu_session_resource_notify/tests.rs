@@ -0,0 +1,51 @@
+use super::*;
+use crate::ngap_handlers::transfer::encode_transfer;
+use crate::store::UEContext;
+
+#[test]
+fn test_pdu_session_resource_notify_released() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    store.put_ue(ue);
+
+    let notify = ngap::PDUSessionResourceNotify {
+        protocol_i_es: ngap::PDUSessionResourceNotifyProtocolIEs(vec![
+            ngap::PDUSessionResourceNotifyProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceNotifyProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PDUSessionResourceNotifyProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_RELEASED_LIST_NOT),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceNotifyProtocolIEs_EntryValue::Id_PDUSessionResourceReleasedListNot(
+                    ngap::PDUSessionResourceReleasedListNot(vec![
+                        ngap::PDUSessionResourceReleasedItemNot {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            pdu_session_resource_notify_released_transfer: encode_transfer(
+                                &ngap::PDUSessionResourceNotifyReleasedTransfer {
+                                    cause: ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                                        ngap::CauseRadioNetwork::RADIO_CONNECTION_WITH_UE_LOST,
+                                    )),
+                                    ie_extensions: None,
+                                },
+                            ),
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    let result =
+        handle_pdu_session_resource_notify(&config, &store, &crate::tests::test_gnb(), notify);
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).unwrap();
+    assert!(ue.pdu_sessions.is_empty());
+}
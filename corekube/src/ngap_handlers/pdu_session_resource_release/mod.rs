@@ -0,0 +1,119 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::transfer::encode_transfer;
+use super::NGAPResponse;
+use crate::store::{GNBAddress, PDUSession, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct PDUSessionResourceReleaseResponseIEs
+        from PDUSessionResourceReleaseResponse(
+            PDUSessionResourceReleaseResponseProtocolIEs_EntryValue
+        )
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            released_list: Id_PDUSessionResourceReleasedListRelRes(
+                ngap::PDUSessionResourceReleasedListRelRes
+            ),
+        }
+        optional {}
+    }
+}
+
+pub fn handle_pdu_session_resource_release_response(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    release_response: ngap::PDUSessionResourceReleaseResponse,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PDUSessionResourceReleaseResponse");
+
+    let PDUSessionResourceReleaseResponseIEs {
+        amf_ue_ngap_id,
+        released_list,
+    } = match PDUSessionResourceReleaseResponseIEs::try_from(release_response) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in PDUSessionResourceReleaseResponse");
+        return vec![];
+    };
+
+    for item in released_list.0 {
+        info!("PDU session {} released", item.pdu_session_id.0);
+        release_user_plane(store, ue.remove_pdu_session(item.pdu_session_id.0));
+    }
+
+    store.put_ue(ue);
+    vec![]
+}
+
+/// Delete the PFCP session of a PDU session set up through the SMF-lite.
+pub(super) fn release_user_plane(store: &Store, session: Option<PDUSession>) {
+    let (Some(smf), Some(session)) = (store.smf(), session) else {
+        return;
+    };
+    let Some(sm_context) = session.sm_context else {
+        return;
+    };
+    if let Err(e) = smf.release_session(&sm_context) {
+        warn!(
+            "Could not release user plane of PDU session {}: {:?}",
+            session.id, e
+        );
+    }
+}
+
+/// Build a PDUSessionResourceReleaseCommand releasing the NG-RAN resources of
+/// the given PDU sessions, all for the same cause.
+pub fn build_pdu_session_resource_release_command(
+    ue: &UEContext,
+    pdu_session_ids: &[u8],
+    nas_pdu: Option<Vec<u8>>,
+    cause: ngap::Cause,
+) -> ngap::NGAP_PDU {
+    trace!("Building PDUSessionResourceReleaseCommand");
+
+    // Every session carries its own copy of the cause
+    let transfer = encode_transfer(&ngap::PDUSessionResourceReleaseCommandTransfer {
+        cause,
+        ie_extensions: None,
+    });
+
+    use ngap::PDUSessionResourceReleaseCommandProtocolIEs_EntryValue as Value;
+    message::<ngap::PDUSessionResourceReleaseCommand>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .optional_ie(nas_pdu.map(|nas_pdu| Value::Id_NAS_PDU(ngap::NAS_PDU(nas_pdu))))
+        .ie(Value::Id_PDUSessionResourceToReleaseListRelCmd(
+            ngap::PDUSessionResourceToReleaseListRelCmd(
+                pdu_session_ids
+                    .iter()
+                    .map(
+                        |pdu_session_id| ngap::PDUSessionResourceToReleaseItemRelCmd {
+                            pdu_session_id: ngap::PDUSessionID(*pdu_session_id),
+                            pdu_session_resource_release_command_transfer: transfer.clone(),
+                            ie_extensions: None,
+                        },
+                    )
+                    .collect(),
+            ),
+        ))
+        .build()
+}
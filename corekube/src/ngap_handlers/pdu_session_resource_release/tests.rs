@@ -0,0 +1,78 @@
+use super::*;
+use crate::store::PDUSession;
+
+#[test]
+fn test_pdu_session_resource_release_response() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    ue.set_pdu_session_active(6, true);
+    store.put_ue(ue);
+
+    let response = ngap::PDUSessionResourceReleaseResponse {
+        protocol_i_es: ngap::PDUSessionResourceReleaseResponseProtocolIEs(vec![
+            ngap::PDUSessionResourceReleaseResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceReleaseResponseProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PDUSessionResourceReleaseResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_RELEASED_LIST_REL_RES),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceReleaseResponseProtocolIEs_EntryValue::Id_PDUSessionResourceReleasedListRelRes(
+                    ngap::PDUSessionResourceReleasedListRelRes(vec![
+                        ngap::PDUSessionResourceReleasedItemRelRes {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            pdu_session_resource_release_response_transfer: vec![],
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_pdu_session_resource_release_response(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        response,
+    );
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(
+        ue.pdu_sessions,
+        vec![PDUSession {
+            id: 6,
+            active: true,
+            ..Default::default()
+        }]
+    );
+}
+
+#[test]
+fn test_pdu_session_resource_release_command() {
+    let ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) = build_pdu_session_resource_release_command(
+        &ue,
+        &[5, 6],
+        None,
+        ngap::Cause::Nas(ngap::CauseNas(ngap::CauseNas::NORMAL_RELEASE)),
+    ) else {
+        panic!("PDUSessionResourceReleaseCommand is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_PDUSessionResourceRelease(command) = init_msg.value else {
+        panic!("InitiatingMessage is not a PDUSessionResourceReleaseCommand");
+    };
+
+    assert!(command.protocol_i_es.0.iter().any(|protocol_ie| matches!(
+        &protocol_ie.value,
+        ngap::PDUSessionResourceReleaseCommandProtocolIEs_EntryValue::Id_PDUSessionResourceToReleaseListRelCmd(
+            list
+        ) if list.0.len() == 2
+    )));
+}
@@ -0,0 +1,204 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::setup_request::build_s_nssai;
+use super::transfer::{
+    build_qos_flow_level_qos_parameters, build_up_transport_layer_information, decode_transfer,
+    encode_transfer, parse_up_transport_layer_information,
+};
+use super::NGAPResponse;
+use crate::store::{GNBAddress, GTPTunnel, PDUSession, QosFlow, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct PDUSessionResourceSetupResponseIEs
+        from PDUSessionResourceSetupResponse(PDUSessionResourceSetupResponseProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            setup_list: Id_PDUSessionResourceSetupListSURes(ngap::PDUSessionResourceSetupListSURes),
+            failed_list: Id_PDUSessionResourceFailedToSetupListSURes(
+                ngap::PDUSessionResourceFailedToSetupListSURes
+            ),
+        }
+    }
+}
+
+pub fn handle_pdu_session_resource_setup_response(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    setup_response: ngap::PDUSessionResourceSetupResponse,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type PDUSessionResourceSetupResponse");
+
+    let PDUSessionResourceSetupResponseIEs {
+        amf_ue_ngap_id,
+        setup_list,
+        failed_list,
+    } = match PDUSessionResourceSetupResponseIEs::try_from(setup_response) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in PDUSessionResourceSetupResponse");
+        return vec![];
+    };
+
+    for item in setup_list.map(|l| l.0).unwrap_or_default() {
+        let session = ue.pdu_session_mut(item.pdu_session_id.0);
+        session.active = true;
+        activate_downlink(
+            store,
+            session,
+            &item.pdu_session_resource_setup_response_transfer,
+        );
+    }
+    for item in failed_list.map(|l| l.0).unwrap_or_default() {
+        match decode_transfer::<ngap::PDUSessionResourceSetupUnsuccessfulTransfer>(
+            &item.pdu_session_resource_setup_unsuccessful_transfer,
+        ) {
+            Some(transfer) => info!(
+                "PDU session {} failed to be set up: {:?}",
+                item.pdu_session_id.0, transfer.cause
+            ),
+            None => info!("PDU session {} failed to be set up", item.pdu_session_id.0),
+        }
+        ue.set_pdu_session_active(item.pdu_session_id.0, false);
+    }
+
+    store.put_ue(ue);
+    vec![]
+}
+
+/// Remember where the UPF should send downlink traffic of a PDU session to,
+/// from the PDUSessionResourceSetupResponseTransfer of the NG-RAN.
+pub(super) fn activate_downlink(store: &Store, session: &mut PDUSession, transfer: &[u8]) {
+    if let Some(transfer) =
+        decode_transfer::<ngap::PDUSessionResourceSetupResponseTransfer>(transfer)
+    {
+        session.dl_tunnel = parse_up_transport_layer_information(
+            transfer
+                .dl_qos_flow_per_tnl_information
+                .up_transport_layer_information,
+        );
+    }
+
+    // Downlink traffic is buffered in the UPF until it knows the tunnel
+    if let (Some(smf), Some(sm_context), Some(dl_tunnel)) =
+        (store.smf(), &session.sm_context, &session.dl_tunnel)
+    {
+        if let Err(e) = smf.update_downlink(sm_context, dl_tunnel) {
+            warn!(
+                "Could not update downlink of PDU session {}: {:?}",
+                session.id, e
+            );
+        }
+    }
+}
+
+/// Build the PDUSessionResourceSetupRequestTransfer for an IPv4 PDU session,
+/// telling the NG-RAN where to send uplink traffic and which QoS flows to set up.
+pub fn build_pdu_session_resource_setup_request_transfer(
+    config: &crate::config::CoreKubeConfig,
+    ul_tunnel: &GTPTunnel,
+    qos_flows: &[QosFlow],
+) -> Vec<u8> {
+    let qos_flow_setup_request_list = qos_flows
+        .iter()
+        .map(|qos_flow| ngap::QosFlowSetupRequestItem {
+            qos_flow_identifier: ngap::QosFlowIdentifier(qos_flow.qfi),
+            qos_flow_level_qos_parameters: build_qos_flow_level_qos_parameters(qos_flow),
+            e_rab_id: None,
+            ie_extensions: None,
+        })
+        .collect();
+
+    let transfer = ngap::PDUSessionResourceSetupRequestTransfer {
+        protocol_i_es: ngap::PDUSessionResourceSetupRequestTransferProtocolIEs(vec![
+            // The PDU Session AMBR is required as long as there are non-GBR flows
+            ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_AGGREGATE_MAXIMUM_BIT_RATE),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_EntryValue::Id_PDUSessionAggregateMaximumBitRate(
+                    ngap::PDUSessionAggregateMaximumBitRate {
+                        pdu_session_aggregate_maximum_bit_rate_dl: ngap::BitRate(
+                            config.session_ambr_downlink,
+                        ),
+                        pdu_session_aggregate_maximum_bit_rate_ul: ngap::BitRate(
+                            config.session_ambr_uplink,
+                        ),
+                        ie_extensions: None,
+                    },
+                ),
+            },
+            ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_UL_NGU_UP_TNL_INFORMATION),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_EntryValue::Id_UL_NGU_UP_TNLInformation(
+                    build_up_transport_layer_information(ul_tunnel),
+                ),
+            },
+            ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_TYPE),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_EntryValue::Id_PDUSessionType(
+                    ngap::PDUSessionType(ngap::PDUSessionType::IPV4),
+                ),
+            },
+            ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_QOS_FLOW_SETUP_REQUEST_LIST),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_EntryValue::Id_QosFlowSetupRequestList(
+                    ngap::QosFlowSetupRequestList(qos_flow_setup_request_list),
+                ),
+            },
+        ]),
+    };
+
+    encode_transfer(&transfer)
+}
+
+/// Build a PDUSessionResourceSetupRequest setting up a single PDU session for
+/// a UE that already has an AS context. The `transfer` is built with
+/// [`build_pdu_session_resource_setup_request_transfer`].
+pub fn build_pdu_session_resource_setup_request(
+    ue: &UEContext,
+    pdu_session_id: u8,
+    nas_pdu: Option<Vec<u8>>,
+    transfer: Vec<u8>,
+) -> ngap::NGAP_PDU {
+    trace!("Building PDUSessionResourceSetupRequest");
+
+    let snssai = ue.pdu_session_snssai(pdu_session_id);
+
+    use ngap::PDUSessionResourceSetupRequestProtocolIEs_EntryValue as Value;
+    message::<ngap::PDUSessionResourceSetupRequest>()
+        .ie(Value::Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(
+            ue.amf_ue_ngap_id,
+        )))
+        .ie(Value::Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID(
+            ue.ran_ue_ngap_id,
+        )))
+        .ie(Value::Id_PDUSessionResourceSetupListSUReq(
+            ngap::PDUSessionResourceSetupListSUReq(vec![ngap::PDUSessionResourceSetupItemSUReq {
+                pdu_session_id: ngap::PDUSessionID(pdu_session_id),
+                pdu_session_nas_pdu: nas_pdu.map(ngap::NAS_PDU),
+                s_nssai: build_s_nssai(&snssai),
+                pdu_session_resource_setup_request_transfer: transfer,
+                ie_extensions: None,
+            }]),
+        ))
+        .build()
+}
@@ -0,0 +1,167 @@
+use super::*;
+use crate::store::PDUSession;
+
+#[test]
+fn test_pdu_session_resource_setup_response() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let response = ngap::PDUSessionResourceSetupResponse {
+        protocol_i_es: ngap::PDUSessionResourceSetupResponseProtocolIEs(vec![
+            ngap::PDUSessionResourceSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceSetupResponseProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PDUSessionResourceSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_SETUP_LIST_SU_RES),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceSetupResponseProtocolIEs_EntryValue::Id_PDUSessionResourceSetupListSURes(
+                    ngap::PDUSessionResourceSetupListSURes(vec![
+                        ngap::PDUSessionResourceSetupItemSURes {
+                            pdu_session_id: ngap::PDUSessionID(1),
+                            pdu_session_resource_setup_response_transfer: vec![],
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+            ngap::PDUSessionResourceSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_FAILED_TO_SETUP_LIST_SU_RES),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceSetupResponseProtocolIEs_EntryValue::Id_PDUSessionResourceFailedToSetupListSURes(
+                    ngap::PDUSessionResourceFailedToSetupListSURes(vec![
+                        ngap::PDUSessionResourceFailedToSetupItemSURes {
+                            pdu_session_id: ngap::PDUSessionID(2),
+                            pdu_session_resource_setup_unsuccessful_transfer: vec![],
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_pdu_session_resource_setup_response(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        response,
+    );
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(
+        ue.pdu_sessions,
+        vec![
+            PDUSession {
+                id: 1,
+                active: true,
+                ..Default::default()
+            },
+            PDUSession {
+                id: 2,
+                active: false,
+                ..Default::default()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_pdu_session_resource_setup_response_stores_dl_tunnel() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 2],
+        teid: 0x1234,
+    };
+    let transfer = encode_transfer(&ngap::PDUSessionResourceSetupResponseTransfer {
+        dl_qos_flow_per_tnl_information: ngap::QosFlowPerTNLInformation {
+            up_transport_layer_information: build_up_transport_layer_information(&dl_tunnel),
+            associated_qos_flow_list: ngap::AssociatedQosFlowList(vec![
+                ngap::AssociatedQosFlowItem {
+                    qos_flow_identifier: ngap::QosFlowIdentifier(1),
+                    qos_flow_mapping_indication: None,
+                    ie_extensions: None,
+                },
+            ]),
+            ie_extensions: None,
+        },
+        additional_dl_qos_flow_per_tnl_information: None,
+        security_result: None,
+        qos_flow_failed_to_setup_list: None,
+        ie_extensions: None,
+    });
+
+    let response = ngap::PDUSessionResourceSetupResponse {
+        protocol_i_es: ngap::PDUSessionResourceSetupResponseProtocolIEs(vec![
+            ngap::PDUSessionResourceSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceSetupResponseProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::PDUSessionResourceSetupResponseProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_SETUP_LIST_SU_RES),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::PDUSessionResourceSetupResponseProtocolIEs_EntryValue::Id_PDUSessionResourceSetupListSURes(
+                    ngap::PDUSessionResourceSetupListSURes(vec![
+                        ngap::PDUSessionResourceSetupItemSURes {
+                            pdu_session_id: ngap::PDUSessionID(1),
+                            pdu_session_resource_setup_response_transfer: transfer,
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+        ]),
+    };
+
+    handle_pdu_session_resource_setup_response(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        response,
+    );
+
+    let ue = store.get_ue(1).unwrap();
+    assert!(ue.pdu_sessions[0].active);
+    assert_eq!(ue.pdu_sessions[0].dl_tunnel, Some(dl_tunnel));
+}
+
+#[test]
+fn test_pdu_session_resource_setup_request_transfer() {
+    let config = crate::config::CoreKubeConfig::default();
+    let ul_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 1],
+        teid: 0xabcd,
+    };
+    let qos_flow = QosFlow {
+        qfi: 1,
+        five_qi: 9,
+        arp_priority_level: 8,
+    };
+
+    let transfer =
+        build_pdu_session_resource_setup_request_transfer(&config, &ul_tunnel, &[qos_flow]);
+    let decoded = decode_transfer::<ngap::PDUSessionResourceSetupRequestTransfer>(&transfer)
+        .expect("Transfer should decode");
+
+    let mut decoded_tunnel = None;
+    for protocol_ie in decoded.protocol_i_es.0 {
+        if let ngap::PDUSessionResourceSetupRequestTransferProtocolIEs_EntryValue::Id_UL_NGU_UP_TNLInformation(
+            information,
+        ) = protocol_ie.value
+        {
+            decoded_tunnel = parse_up_transport_layer_information(information);
+        }
+    }
+    assert_eq!(decoded_tunnel, Some(ul_tunnel));
+}
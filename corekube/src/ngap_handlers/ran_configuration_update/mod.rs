@@ -0,0 +1,135 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::setup_request::{build_plmn_identity, build_supported_tas};
+use super::{Destination, NGAPResponse};
+use crate::store::{GNBAddress, Store};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct RANConfigurationUpdateIEs
+        from RANConfigurationUpdate(RANConfigurationUpdateProtocolIEs_EntryValue)
+    {
+        mandatory {}
+        optional {
+            ran_node_name: Id_RANNodeName(ngap::RANNodeName),
+            supported_ta_list: Id_SupportedTAList(ngap::SupportedTAList),
+            paging_drx: Id_DefaultPagingDRX(ngap::PagingDRX),
+            global_ran_node_id: Id_GlobalRANNodeID(ngap::GlobalRANNodeID),
+        }
+    }
+}
+
+/// Handle a RANConfigurationUpdate, see TS 38.413 section 8.7.2. The gNB
+/// context is updated with the values the gNB sent, the others are kept.
+pub fn handle_ran_configuration_update(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    update: ngap::RANConfigurationUpdate,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type RANConfigurationUpdate");
+
+    let RANConfigurationUpdateIEs {
+        ran_node_name,
+        supported_ta_list,
+        paging_drx,
+        global_ran_node_id,
+    } = match RANConfigurationUpdateIEs::try_from(update) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+
+    // Only a gNB that completed NG Setup has a configuration to update
+    let Some(mut gnb_context) = store.get_gnb(gnb) else {
+        warn!("RANConfigurationUpdate from gNB {:?} before NG Setup", gnb);
+        return vec![failure(ngap::Cause::Protocol(ngap::CauseProtocol(
+            ngap::CauseProtocol::MESSAGE_NOT_COMPATIBLE_WITH_RECEIVER_STATE,
+        )))];
+    };
+
+    if let Some(supported_ta_list) = supported_ta_list {
+        let supported_tas = build_supported_tas(supported_ta_list);
+        // The gNB can not serve any UE of the AMF without its PLMN
+        let plmn_identity = build_plmn_identity(config.mcc, config.mnc).0;
+        if !supported_tas
+            .iter()
+            .any(|ta| ta.plmns.contains(&plmn_identity))
+        {
+            warn!("gNB {:?} no longer supports the PLMN of the AMF", gnb);
+            return vec![failure(ngap::Cause::Misc(ngap::CauseMisc(
+                ngap::CauseMisc::UNKNOWN_PLMN,
+            )))];
+        }
+        debug!("SupportedTAs: {:?}", supported_tas);
+        gnb_context.supported_tas = supported_tas;
+    }
+
+    if let Some(ran_node_name) = ran_node_name {
+        gnb_context.name = Some(ran_node_name.0);
+    }
+
+    if let Some(paging_drx) = paging_drx {
+        gnb_context.paging_drx = paging_drx.0;
+    }
+
+    match global_ran_node_id {
+        Some(ngap::GlobalRANNodeID::GlobalGNB_ID(ngap::GlobalGNB_ID {
+            plmn_identity,
+            gnb_id: ngap::GNB_ID::GNB_ID(gnb_id),
+            ..
+        })) => {
+            gnb_context.plmn_identity = plmn_identity.0;
+            gnb_context.gnb_id = gnb_id;
+        }
+        Some(global_ran_node_id) => {
+            debug!("Ignored GlobalRANNodeID {:?}", global_ran_node_id);
+        }
+        None => {}
+    }
+
+    info!("gNB {:?} updated its configuration", gnb);
+    store.put_gnb(gnb_context);
+
+    vec![NGAPResponse {
+        sctp_stream: 0,
+        ngap_pdu: build_ran_configuration_update_acknowledge(),
+        destination: Destination::Sender,
+    }]
+}
+
+fn failure(cause: ngap::Cause) -> NGAPResponse {
+    NGAPResponse {
+        sctp_stream: 0,
+        ngap_pdu: build_ran_configuration_update_failure(cause, None),
+        destination: Destination::Sender,
+    }
+}
+
+fn build_ran_configuration_update_acknowledge() -> ngap::NGAP_PDU {
+    trace!("Building RANConfigurationUpdateAcknowledge");
+
+    message::<ngap::RANConfigurationUpdateAcknowledge>().build()
+}
+
+/// Build a RANConfigurationUpdateFailure, with the CriticalityDiagnostics if
+/// the update was rejected because of its IEs.
+pub(super) fn build_ran_configuration_update_failure(
+    cause: ngap::Cause,
+    criticality_diagnostics: Option<ngap::CriticalityDiagnostics>,
+) -> ngap::NGAP_PDU {
+    trace!("Building RANConfigurationUpdateFailure");
+
+    use ngap::RANConfigurationUpdateFailureProtocolIEs_EntryValue as Value;
+    message::<ngap::RANConfigurationUpdateFailure>()
+        .ie(Value::Id_Cause(cause))
+        .optional_ie(criticality_diagnostics.map(Value::Id_CriticalityDiagnostics))
+        .build()
+}
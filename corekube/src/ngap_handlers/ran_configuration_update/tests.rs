@@ -0,0 +1,129 @@
+use super::*;
+use crate::store::{GNBContext, SupportedTA};
+
+fn setup() -> Store {
+    let store = Store::default();
+    let mut gnb = crate::tests::test_target_gnb();
+    gnb.address = crate::tests::test_gnb();
+    gnb.supported_tas = vec![SupportedTA {
+        tac: vec![0x00, 0x00, 0x01],
+        plmns: vec![vec![0x02, 0xf8, 0x39]],
+        slices: vec![],
+    }];
+    store.put_gnb(gnb);
+    store
+}
+
+fn build_supported_ta_list(plmn_identity: Vec<u8>) -> ngap::SupportedTAList {
+    ngap::SupportedTAList(vec![ngap::SupportedTAItem {
+        tac: ngap::TAC(vec![0x00, 0x00, 0x02]),
+        broadcast_plmn_list: ngap::BroadcastPLMNList(vec![ngap::BroadcastPLMNItem {
+            plmn_identity: ngap::PLMNIdentity(plmn_identity),
+            tai_slice_support_list: ngap::SliceSupportList(vec![]),
+            ie_extensions: None,
+        }]),
+        ie_extensions: None,
+    }])
+}
+
+fn build_update(
+    ran_node_name: &str,
+    supported_ta_list: ngap::SupportedTAList,
+) -> ngap::RANConfigurationUpdate {
+    ngap::RANConfigurationUpdate {
+        protocol_i_es: ngap::RANConfigurationUpdateProtocolIEs(vec![
+            ngap::RANConfigurationUpdateProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_NODE_NAME),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::RANConfigurationUpdateProtocolIEs_EntryValue::Id_RANNodeName(
+                    ngap::RANNodeName(ran_node_name.to_string()),
+                ),
+            },
+            ngap::RANConfigurationUpdateProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_SUPPORTED_TA_LIST),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::RANConfigurationUpdateProtocolIEs_EntryValue::Id_SupportedTAList(
+                    supported_ta_list,
+                ),
+            },
+        ]),
+    }
+}
+
+fn gnb_context(store: &Store) -> GNBContext {
+    store.get_gnb(&crate::tests::test_gnb()).unwrap()
+}
+
+#[test]
+fn test_ran_configuration_update() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+
+    let result = handle_ran_configuration_update(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_update("updated", build_supported_ta_list(vec![0x02, 0xf8, 0x39])),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+            value: ngap::SuccessfulOutcomeValue::Id_RANConfigurationUpdate(_),
+            ..
+        })
+    ));
+
+    let gnb = gnb_context(&store);
+    assert_eq!(gnb.name.as_deref(), Some("updated"));
+    assert_eq!(gnb.supported_tas.len(), 1);
+    assert_eq!(gnb.supported_tas[0].tac, vec![0x00, 0x00, 0x02]);
+    // Values the gNB did not send are kept
+    assert_eq!(gnb.gnb_id, crate::tests::test_target_gnb().gnb_id);
+}
+
+#[test]
+fn test_ran_configuration_update_unknown_plmn() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+
+    let result = handle_ran_configuration_update(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_update("updated", build_supported_ta_list(vec![0x00, 0xf1, 0x10])),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+            value: ngap::UnsuccessfulOutcomeValue::Id_RANConfigurationUpdate(_),
+            ..
+        })
+    ));
+
+    // The gNB context is left as it was
+    let gnb = gnb_context(&store);
+    assert_eq!(gnb.name.as_deref(), Some("target"));
+    assert_eq!(gnb.supported_tas[0].tac, vec![0x00, 0x00, 0x01]);
+}
+
+#[test]
+fn test_ran_configuration_update_before_ng_setup() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    let result = handle_ran_configuration_update(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_update("updated", build_supported_ta_list(vec![0x02, 0xf8, 0x39])),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::UnsuccessfulOutcome(_)
+    ));
+    assert!(store.get_gnb(&crate::tests::test_gnb()).is_none());
+}
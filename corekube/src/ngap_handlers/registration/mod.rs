@@ -0,0 +1,288 @@
+use log::{debug, error, info, trace};
+use nas::fgmm::{
+    MobilityManagementStatus, RegistrationReject, RegistrationRequest, REGISTRATION_TYPE_EMERGENCY,
+    REGISTRATION_TYPE_MOBILITY_UPDATING, REGISTRATION_TYPE_PERIODIC_UPDATING,
+};
+use nas::MobilityManagementCause;
+
+use super::authentication::authenticate_new_ue;
+use super::deregistration::{build_deregistration_release_command, deregister};
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::initial_context_setup::{
+    build_initial_context_setup_request, build_registration_accept, select_ue_nssai,
+};
+use super::initial_ue_message::{check_tracking_area, identify_ue, reject_initial_nas_message};
+use super::nas_timers::{guard_dl_nas_message, stop_reachability_timers};
+use super::pdu_session_resource_release::release_user_plane;
+use super::service_request::{
+    pdu_session_bitmap, reactivate_pdu_sessions, release_unknown_pdu_sessions,
+};
+use super::uplink_nas_transport::send_mobility_management_status;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{CMState, GNBAddress, RMState, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+/// Handle a Registration Request carried in an InitialUEMessage, see TS
+/// 24.501 section 5.5.1. The `plain` message is the cleartext inside the
+/// integrity protected `nas_pdu`.
+///
+/// A UE that identifies itself with a 5G-GUTI of this AMF and protects the
+/// request with its current NAS security context keeps using that context.
+/// Any other UE that registers for the first time is authenticated first,
+/// see [`super::authentication`].
+pub(super) fn handle_initial_registration_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    ran_ue_ngap_id: u32,
+    tai: TAI,
+    nas_pdu: &[u8],
+    plain: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type RegistrationRequest");
+
+    let Some(request) = RegistrationRequest::decode(plain) else {
+        error!("Could not decode Registration Request");
+        let status = MobilityManagementStatus {
+            cause: MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        };
+        return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, status.encode());
+    };
+    debug!("Registration Request: {:?}", request);
+
+    let reject = |cause, t3346| {
+        let reject = RegistrationReject {
+            cause,
+            t3346,
+            rejected_nssai: vec![],
+        };
+        reject_initial_nas_message(store, gnb, ran_ue_ngap_id, reject.encode())
+    };
+
+    // Emergency registration is not subject to congestion control
+    if let Some(backoff) = config.nas_congestion_backoff {
+        if request.registration_type != REGISTRATION_TYPE_EMERGENCY {
+            info!("Registration Request rejected because of congestion");
+            return reject(MobilityManagementCause::CONGESTION, Some(backoff));
+        }
+    }
+    if let Err(cause) = check_tracking_area(config, store, gnb, &tai) {
+        return reject(cause, None);
+    }
+
+    let identified = request.guti.and_then(|guti| {
+        identify_ue(
+            config,
+            store,
+            guti.amf_set_id,
+            guti.amf_pointer,
+            guti.tmsi,
+            request.ngksi,
+            nas_pdu,
+        )
+    });
+    let Some((mut ue, security)) = identified else {
+        // A UE updating its registration with a context that is gone has to
+        // register again. Any other UE, including one that registers for
+        // emergency services without a 5G-GUTI or a security context, starts
+        // over as a new UE.
+        return match request.registration_type {
+            REGISTRATION_TYPE_MOBILITY_UPDATING | REGISTRATION_TYPE_PERIODIC_UPDATING => {
+                reject(MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED, None)
+            }
+            _ => authenticate_new_ue(config, store, gnb, ran_ue_ngap_id, tai, request),
+        };
+    };
+
+    ue.gnb = *gnb;
+    ue.ran_ue_ngap_id = ran_ue_ngap_id;
+    ue.cm_state = CMState::Connected;
+    ue.as_context_established = false;
+    ue.paging_attempts = 0;
+    ue.tai = Some(tai);
+    stop_reachability_timers(store, &ue);
+    ue.security = Some(security);
+
+    match accept_initial_registration(config, store, &mut ue, &request) {
+        Ok(responses) => {
+            store.put_ue(ue);
+            responses
+        }
+        Err(reject) => reject_initial_nas_message(store, gnb, ran_ue_ngap_id, reject.encode()),
+    }
+}
+
+/// Accept the Registration Request that a UE sent in its initial NAS
+/// message once its NAS security context is in place, and set up its
+/// context in the gNB. If no network slice is available the Registration
+/// Reject to send is returned instead, and the UE is left as it was.
+pub(super) fn accept_initial_registration(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    request: &RegistrationRequest,
+) -> Result<Vec<NGAPResponse>, RegistrationReject> {
+    let nssai = select_ue_nssai(config, store, ue, request.requested_nssai.as_deref());
+    if nssai.allowed.is_empty() {
+        info!("No network slice available for UE {}", ue.amf_ue_ngap_id);
+        return Err(RegistrationReject {
+            cause: MobilityManagementCause::NO_NETWORK_SLICES_AVAILABLE,
+            t3346: None,
+            rejected_nssai: nssai.rejected,
+        });
+    }
+    let Some(mut security) = ue.security.take() else {
+        return Ok(vec![]);
+    };
+    ue.allowed_nssai = nssai.allowed.clone();
+    let tmsi = update_registration(store, ue, request);
+
+    // Pending uplink data is handled as in a Service Request
+    let (pdu_session_list, reactivation_failed) =
+        reactivate_pdu_sessions(config, ue, request.uplink_data_status.unwrap_or(0));
+
+    let mut accept = build_registration_accept(config, tmsi, &ue.registration_area, nssai);
+    accept.pdu_session_status = request.pdu_session_status.map(|_| pdu_session_bitmap(ue));
+    accept.pdu_session_reactivation_result =
+        request.uplink_data_status.map(|_| reactivation_failed);
+    let accept = accept.encode();
+    let nas_pdu = security.protect(&accept, nas::SecurityHeader::IntegrityProtectedAndCiphered);
+    let ngap_pdu = build_initial_context_setup_request(
+        config,
+        ue,
+        &mut security,
+        Some(nas_pdu),
+        pdu_session_list,
+    );
+    ue.security = Some(security);
+    guard_dl_nas_message(config, store, ue, accept);
+
+    Ok(vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Sender,
+    }])
+}
+
+/// Handle a Registration Request from a UE with a NAS signalling connection,
+/// such as a mobility registration update after moving to a new tracking area.
+pub(super) fn handle_registration_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type RegistrationRequest");
+
+    let Some(request) = RegistrationRequest::decode(nas_message) else {
+        error!("Could not decode Registration Request");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+    debug!("Registration Request: {:?}", request);
+
+    let nssai = select_ue_nssai(config, store, ue, request.requested_nssai.as_deref());
+    if nssai.allowed.is_empty() {
+        info!("No network slice available for UE {}", ue.amf_ue_ngap_id);
+        let reject = RegistrationReject {
+            cause: MobilityManagementCause::NO_NETWORK_SLICES_AVAILABLE,
+            t3346: None,
+            rejected_nssai: nssai.rejected,
+        };
+        return reject_registration(store, ue, reject);
+    }
+    ue.allowed_nssai = nssai.allowed.clone();
+    let tmsi = update_registration(store, ue, &request);
+
+    let mut accept = build_registration_accept(config, tmsi, &ue.registration_area, nssai);
+    accept.pdu_session_status = request.pdu_session_status.map(|_| pdu_session_bitmap(ue));
+    let accept = accept.encode();
+    let Some(security) = ue.security.as_mut() else {
+        return vec![];
+    };
+    let nas_pdu = security.protect(&accept, nas::SecurityHeader::IntegrityProtectedAndCiphered);
+    guard_dl_nas_message(config, store, ue, accept);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Reject the Registration Request of a UE with a NAS signalling connection,
+/// which leaves it de-registered, and release the connection.
+pub(super) fn reject_registration(
+    store: &Store,
+    ue: &mut UEContext,
+    reject: RegistrationReject,
+) -> Vec<NGAPResponse> {
+    deregister(store, ue);
+    let Some(security) = ue.security.as_mut() else {
+        return vec![];
+    };
+    let nas_pdu = security.protect(
+        &reject.encode(),
+        nas::SecurityHeader::IntegrityProtectedAndCiphered,
+    );
+
+    vec![
+        NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+            destination: Destination::Sender,
+        },
+        NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_deregistration_release_command(ue),
+            destination: Destination::Sender,
+        },
+    ]
+}
+
+/// Update the registration of a UE according to the type of registration it
+/// requested, returning the 5G-TMSI to accept it with.
+fn update_registration(store: &Store, ue: &mut UEContext, request: &RegistrationRequest) -> u32 {
+    match request.registration_type {
+        REGISTRATION_TYPE_MOBILITY_UPDATING => {
+            info!("Mobility registration update of UE {}", ue.amf_ue_ngap_id);
+        }
+        REGISTRATION_TYPE_PERIODIC_UPDATING => {
+            debug!("Periodic registration update of UE {}", ue.amf_ue_ngap_id);
+        }
+        // Reserved registration types are handled as initial registration
+        registration_type => {
+            if registration_type == REGISTRATION_TYPE_EMERGENCY {
+                info!("Emergency registration of UE {}", ue.amf_ue_ngap_id);
+            } else {
+                info!("Initial registration of UE {}", ue.amf_ue_ngap_id);
+            }
+
+            // Nothing of an earlier registration carries over
+            for session in ue.pdu_sessions.drain(..) {
+                release_user_plane(store, Some(session));
+            }
+            ue.registration_area.clear();
+        }
+    }
+    ue.rm_state = RMState::Registered;
+
+    if let Some(pdu_session_status) = request.pdu_session_status {
+        release_unknown_pdu_sessions(store, ue, pdu_session_status);
+    }
+
+    // A UE that left its registration area is given a new one around the
+    // tracking area it is in now
+    if let Some(tai) = &ue.tai {
+        if !ue.registration_area.contains(tai) {
+            debug!("New registration area for UE {}", ue.amf_ue_ngap_id);
+            ue.registration_area = vec![tai.clone()];
+        }
+    }
+
+    *ue.tmsi.get_or_insert_with(|| store.allocate_tmsi())
+}
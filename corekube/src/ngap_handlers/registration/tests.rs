@@ -0,0 +1,305 @@
+use super::*;
+use crate::store::PDUSession;
+use ngap_asn1 as ngap;
+
+const KAMF: [u8; 32] = [0x42; 32];
+
+fn test_tai(tac: u8) -> TAI {
+    TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, tac],
+    }
+}
+
+/// A registered UE in CM-IDLE with 5G-TMSI 1 and a PDU session, registered
+/// in tracking area 1.
+fn setup() -> Store {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.cm_state = CMState::Idle;
+    ue.tmsi = Some(1);
+    ue.security = nas::security::SecurityContext::new(1, KAMF, vec![0xE0, 0xE0]);
+    ue.tai = Some(test_tai(1));
+    ue.registration_area = vec![test_tai(1)];
+    ue.pdu_sessions.push(PDUSession {
+        id: 5,
+        ..Default::default()
+    });
+    store.put_ue(ue);
+    store
+}
+
+/// A Registration Request of the given type from 5G-GUTI with 5G-TMSI 1,
+/// using ngKSI 1.
+fn build_registration_request(registration_type: u8, tmsi: u8) -> Vec<u8> {
+    [
+        &[0x7E, 0x00, 0x41, 0x10 | registration_type, 0x00, 0x0B][..],
+        &[
+            0xF2, 0x02, 0xf8, 0x39, 0x02, 0x00, 0x40, 0x00, 0x00, 0x00, tmsi,
+        ],
+    ]
+    .concat()
+}
+
+/// Integrity protect a message with NAS COUNT 0, the way the UE would.
+fn integrity_protect(plain: &[u8]) -> Vec<u8> {
+    let mut payload = vec![0x00];
+    payload.extend_from_slice(plain);
+    let knas_int = nas::security::derive_nas_key(&KAMF, 0x02, 2);
+    let mac = nas::security::nia2(&knas_int, 0, 0, 0, &payload);
+    [&[0x7E, 0x01][..], &mac, &payload].concat()
+}
+
+fn initial_registration_request(
+    store: &Store,
+    registration_type: u8,
+    tmsi: u8,
+    tai: TAI,
+) -> Vec<NGAPResponse> {
+    initial_registration_request_with_config(
+        &crate::config::CoreKubeConfig::default(),
+        store,
+        registration_type,
+        tmsi,
+        tai,
+    )
+}
+
+fn initial_registration_request_with_config(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    registration_type: u8,
+    tmsi: u8,
+    tai: TAI,
+) -> Vec<NGAPResponse> {
+    let plain = build_registration_request(registration_type, tmsi);
+    handle_initial_registration_request(
+        config,
+        store,
+        &crate::tests::test_gnb(),
+        20,
+        tai,
+        &integrity_protect(&plain),
+        &plain,
+    )
+}
+
+fn is_initiating_message(response: &NGAPResponse, procedure_code: u8) -> bool {
+    matches!(
+        &response.ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(init_msg) if init_msg.procedure_code.0 == procedure_code
+    )
+}
+
+/// The plain NAS message sent to a UE that was rejected, along with the
+/// release of its signalling connection.
+fn rejection(result: &[NGAPResponse]) -> Vec<u8> {
+    assert_eq!(result.len(), 2);
+    assert!(is_initiating_message(
+        &result[1],
+        ngap::ID_UE_CONTEXT_RELEASE
+    ));
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(transport),
+        ..
+    }) = &result[0].ngap_pdu
+    else {
+        panic!("Rejection is not sent in a DownlinkNASTransport");
+    };
+    transport
+        .protocol_i_es
+        .0
+        .iter()
+        .find_map(|protocol_ie| match &protocol_ie.value {
+            ngap::DownlinkNASTransportProtocolIEs_EntryValue::Id_NAS_PDU(nas_pdu) => {
+                Some(nas_pdu.0.clone())
+            }
+            _ => None,
+        })
+        .expect("DownlinkNASTransport has no NAS_PDU")
+}
+
+#[test]
+fn test_periodic_registration_update() {
+    let store = setup();
+
+    let result =
+        initial_registration_request(&store, REGISTRATION_TYPE_PERIODIC_UPDATING, 1, test_tai(1));
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_INITIAL_CONTEXT_SETUP
+    ));
+
+    // The security context was used again, and nothing else changed
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.cm_state, CMState::Connected);
+    assert_eq!(ue.ran_ue_ngap_id, 20);
+    assert_eq!(ue.tmsi, Some(1));
+    assert_eq!(ue.registration_area, vec![test_tai(1)]);
+    assert_eq!(ue.pdu_sessions.len(), 1);
+    assert_eq!(ue.security.unwrap().dl_count, 1);
+}
+
+#[test]
+fn test_mobility_registration_update() {
+    let store = setup();
+
+    let result =
+        initial_registration_request(&store, REGISTRATION_TYPE_MOBILITY_UPDATING, 1, test_tai(2));
+    assert_eq!(result.len(), 1);
+
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.tai, Some(test_tai(2)));
+    assert_eq!(ue.registration_area, vec![test_tai(2)]);
+    assert_eq!(ue.pdu_sessions.len(), 1);
+}
+
+#[test]
+fn test_initial_registration_with_security_context() {
+    let store = setup();
+
+    let result =
+        initial_registration_request(&store, nas::fgmm::REGISTRATION_TYPE_INITIAL, 1, test_tai(1));
+    assert_eq!(result.len(), 1);
+
+    // The PDU sessions of the earlier registration are gone
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.rm_state, RMState::Registered);
+    assert!(ue.pdu_sessions.is_empty());
+}
+
+#[test]
+fn test_registration_needs_authentication() {
+    let store = setup();
+
+    // A new UE is asked for its SUCI, leaving the UE of 5G-TMSI 1 alone
+    let result =
+        initial_registration_request(&store, nas::fgmm::REGISTRATION_TYPE_INITIAL, 2, test_tai(1));
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_DOWNLINK_NAS_TRANSPORT
+    ));
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+
+    // A registration update is answered with UE identity can not be derived
+    let result =
+        initial_registration_request(&store, REGISTRATION_TYPE_PERIODIC_UPDATING, 2, test_tai(1));
+    assert_eq!(rejection(&result), vec![0x7E, 0x00, 0x44, 0x09]);
+}
+
+#[test]
+fn test_registration_in_other_plmn() {
+    let store = setup();
+    let tai = TAI {
+        plmn_identity: vec![0x00, 0xf1, 0x10],
+        tac: vec![0x00, 0x00, 0x01],
+    };
+
+    let result = initial_registration_request(&store, REGISTRATION_TYPE_PERIODIC_UPDATING, 1, tai);
+    assert_eq!(rejection(&result), vec![0x7E, 0x00, 0x44, 0x0B]);
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+}
+
+#[test]
+fn test_registration_in_unserved_tracking_area() {
+    let store = setup();
+    let mut gnb = crate::tests::test_target_gnb();
+    gnb.address = crate::tests::test_gnb();
+    store.put_gnb(gnb);
+
+    let result =
+        initial_registration_request(&store, REGISTRATION_TYPE_PERIODIC_UPDATING, 1, test_tai(9));
+    assert_eq!(rejection(&result), vec![0x7E, 0x00, 0x44, 0x0F]);
+}
+
+#[test]
+fn test_registration_congestion() {
+    let store = setup();
+    let config = crate::config::CoreKubeConfig {
+        nas_congestion_backoff: Some(600),
+        ..Default::default()
+    };
+
+    let result = initial_registration_request_with_config(
+        &config,
+        &store,
+        REGISTRATION_TYPE_PERIODIC_UPDATING,
+        1,
+        test_tai(1),
+    );
+    assert_eq!(
+        rejection(&result),
+        vec![0x7E, 0x00, 0x44, 0x16, 0x5F, 0x01, 0x2A]
+    );
+
+    // Emergency registration is still possible
+    let result = initial_registration_request_with_config(
+        &config,
+        &store,
+        REGISTRATION_TYPE_EMERGENCY,
+        1,
+        test_tai(1),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_INITIAL_CONTEXT_SETUP
+    ));
+}
+
+#[test]
+fn test_registration_without_network_slices() {
+    let store = setup();
+    let mut config = crate::config::CoreKubeConfig::default();
+    config.nssai.supported = vec![nas::fgmm::Snssai { sst: 2, sd: None }];
+
+    let result = initial_registration_request_with_config(
+        &config,
+        &store,
+        REGISTRATION_TYPE_PERIODIC_UPDATING,
+        1,
+        test_tai(1),
+    );
+    assert_eq!(rejection(&result), vec![0x7E, 0x00, 0x44, 0x3E]);
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+}
+
+#[test]
+fn test_registration_allowed_nssai() {
+    let store = setup();
+
+    let result =
+        initial_registration_request(&store, REGISTRATION_TYPE_PERIODIC_UPDATING, 1, test_tai(1));
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        store.get_ue(1).unwrap().allowed_nssai,
+        vec![nas::fgmm::MappedSnssai {
+            snssai: nas::fgmm::Snssai::default(),
+            hplmn: None,
+        }]
+    );
+}
+
+#[test]
+fn test_registration_request_connected() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = setup();
+    let mut ue = store.get_ue(1).unwrap();
+    ue.cm_state = CMState::Connected;
+    ue.tai = Some(test_tai(2));
+
+    let result = handle_registration_request(
+        &config,
+        &store,
+        &mut ue,
+        &build_registration_request(REGISTRATION_TYPE_MOBILITY_UPDATING, 1),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(is_initiating_message(
+        &result[0],
+        ngap::ID_DOWNLINK_NAS_TRANSPORT
+    ));
+    assert_eq!(ue.registration_area, vec![test_tai(2)]);
+}
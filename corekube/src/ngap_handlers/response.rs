@@ -1,13 +1,37 @@
+use bitvec::prelude::*;
 use ngap_asn1 as ngap;
 
+use crate::store::GNBAddress;
+
+/// SCTP stream for UE-associated signalling. Stream 0 is reserved for
+/// non-UE-associated signalling such as NG Setup.
+pub const UE_SCTP_STREAM: u8 = 1;
+
+/// The gNB a response is sent to. The worker resolves it to the frontend
+/// serving the gNB through the gNB context store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Destination {
+    /// The gNB that sent the message being handled
+    Sender,
+    /// A gNB by the NGAP association it is connected through
+    Association(GNBAddress),
+    /// A gNB that completed NG Setup, by its Global gNB ID
+    GlobalGNBID {
+        plmn_identity: Vec<u8>,
+        gnb_id: BitVec<u8, Msb0>,
+    },
+}
+
 /// A core response type for NGAP messages.
 pub struct NGAPResponse {
     pub sctp_stream: u8,
     pub ngap_pdu: ngap::NGAP_PDU,
+    pub destination: Destination,
 }
 
 /// A core response type for byte-encoded NGAP messages.
 pub struct ByteResponse {
     pub sctp_stream: u8,
     pub buf: Vec<u8>,
+    pub destination: Destination,
 }
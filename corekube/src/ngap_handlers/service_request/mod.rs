@@ -0,0 +1,196 @@
+use log::{debug, error, info, trace};
+use nas::fgmm::{
+    MobilityManagementStatus, ServiceAccept, ServiceReject, ServiceRequest,
+    SERVICE_TYPE_MOBILE_TERMINATED_SERVICES,
+};
+use nas::MobilityManagementCause;
+use ngap_asn1 as ngap;
+
+use super::initial_context_setup::build_initial_context_setup_request;
+use super::initial_ue_message::{check_tracking_area, identify_ue, reject_initial_nas_message};
+use super::nas_timers::stop_reachability_timers;
+use super::pdu_session_resource_release::release_user_plane;
+use super::pdu_session_resource_setup::build_pdu_session_resource_setup_request_transfer;
+use super::setup_request::build_s_nssai;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{CMState, GNBAddress, Store, UEContext, TAI};
+
+#[cfg(test)]
+mod tests;
+
+/// Handle a Service Request sent by a UE in CM-IDLE, carried in an
+/// InitialUEMessage, see TS 24.501 section 5.6.1. The `plain` message is the
+/// cleartext inside the integrity protected `nas_pdu`.
+pub fn handle_service_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    ran_ue_ngap_id: u32,
+    tai: TAI,
+    nas_pdu: &[u8],
+    plain: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type ServiceRequest");
+
+    let Some(request) = ServiceRequest::decode(plain) else {
+        error!("Could not decode Service Request");
+        let status = MobilityManagementStatus {
+            cause: MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        };
+        return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, status.encode());
+    };
+    debug!("Service Request: {:?}", request);
+
+    // A UE that was paged is let through, as its service was asked for by
+    // the network
+    if let Some(backoff) = config.nas_congestion_backoff {
+        if request.service_type != SERVICE_TYPE_MOBILE_TERMINATED_SERVICES {
+            info!("Service Request rejected because of congestion");
+            let reject = ServiceReject {
+                cause: MobilityManagementCause::CONGESTION,
+                pdu_session_status: None,
+                t3346: Some(backoff),
+            };
+            return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, reject.encode());
+        }
+    }
+    if let Err(cause) = check_tracking_area(config, store, gnb, &tai) {
+        let reject = ServiceReject {
+            cause,
+            pdu_session_status: None,
+            t3346: None,
+        };
+        return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, reject.encode());
+    }
+
+    let Some((mut ue, mut security)) = identify_ue(
+        config,
+        store,
+        request.amf_set_id,
+        request.amf_pointer,
+        request.tmsi,
+        request.ngksi,
+        nas_pdu,
+    ) else {
+        // The UE has to register again to get a new context
+        let reject = ServiceReject {
+            cause: MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED,
+            pdu_session_status: None,
+            t3346: None,
+        };
+        return reject_initial_nas_message(store, gnb, ran_ue_ngap_id, reject.encode());
+    };
+
+    // The UE is reachable again through the gNB it sent the request from
+    ue.gnb = *gnb;
+    ue.ran_ue_ngap_id = ran_ue_ngap_id;
+    ue.cm_state = CMState::Connected;
+    ue.as_context_established = false;
+    ue.paging_attempts = 0;
+    ue.tai = Some(tai);
+    stop_reachability_timers(store, &ue);
+
+    if let Some(pdu_session_status) = request.pdu_session_status {
+        release_unknown_pdu_sessions(store, &mut ue, pdu_session_status);
+    }
+
+    // Re-activate the user plane of the sessions the UE has uplink data for,
+    // or of all sessions if it was paged for downlink data
+    let requested = match request.service_type {
+        SERVICE_TYPE_MOBILE_TERMINATED_SERVICES => u16::MAX,
+        _ => request.uplink_data_status.unwrap_or(0),
+    };
+    let (pdu_session_list, reactivation_failed) =
+        reactivate_pdu_sessions(config, &mut ue, requested);
+
+    let accept = ServiceAccept {
+        pdu_session_status: request.pdu_session_status.map(|_| pdu_session_bitmap(&ue)),
+        pdu_session_reactivation_result: request.uplink_data_status.map(|_| reactivation_failed),
+    };
+    let nas_pdu = security.protect(
+        &accept.encode(),
+        nas::SecurityHeader::IntegrityProtectedAndCiphered,
+    );
+    let ngap_pdu = build_initial_context_setup_request(
+        config,
+        &ue,
+        &mut security,
+        Some(nas_pdu),
+        pdu_session_list,
+    );
+    ue.security = Some(security);
+    store.put_ue(ue);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu,
+        destination: Destination::Sender,
+    }]
+}
+
+/// Release locally the PDU sessions that the UE no longer has, according to
+/// the PDU session status it sent.
+pub(super) fn release_unknown_pdu_sessions(
+    store: &Store,
+    ue: &mut UEContext,
+    pdu_session_status: u16,
+) {
+    let (kept, released): (Vec<_>, Vec<_>) = ue
+        .pdu_sessions
+        .drain(..)
+        .partition(|s| pdu_session_status & (1 << s.id) != 0);
+    ue.pdu_sessions = kept;
+    for session in released {
+        info!("PDU session {} released by the UE", session.id);
+        release_user_plane(store, Some(session));
+    }
+}
+
+/// Build the list of PDU sessions to set up again with the UE context, for
+/// the `requested` PDU session IDs. Also returns the requested sessions
+/// whose user plane can not be re-established.
+pub(super) fn reactivate_pdu_sessions(
+    config: &crate::config::CoreKubeConfig,
+    ue: &mut UEContext,
+    requested: u16,
+) -> (Option<ngap::PDUSessionResourceSetupListCxtReq>, u16) {
+    let mut setup_list = vec![];
+    let mut reactivation_failed = 0u16;
+    for session in ue.pdu_sessions.iter_mut() {
+        if requested & (1 << session.id) == 0 {
+            continue;
+        }
+        session.active = false;
+        session.dl_tunnel = None;
+        let Some(ul_tunnel) = &session.ul_tunnel else {
+            reactivation_failed |= 1 << session.id;
+            continue;
+        };
+        setup_list.push(ngap::PDUSessionResourceSetupItemCxtReq {
+            pdu_session_id: ngap::PDUSessionID(session.id),
+            nas_pdu: None,
+            s_nssai: build_s_nssai(&session.snssai),
+            pdu_session_resource_setup_request_transfer:
+                build_pdu_session_resource_setup_request_transfer(
+                    config,
+                    ul_tunnel,
+                    &session.qos_flows,
+                ),
+            ie_extensions: None,
+        });
+    }
+
+    let pdu_session_list = if setup_list.is_empty() {
+        None
+    } else {
+        Some(ngap::PDUSessionResourceSetupListCxtReq(setup_list))
+    };
+    (pdu_session_list, reactivation_failed)
+}
+
+/// The PDU session status of a UE, one bit per PDU session ID.
+pub(super) fn pdu_session_bitmap(ue: &UEContext) -> u16 {
+    ue.pdu_sessions
+        .iter()
+        .fold(0, |bitmap, session| bitmap | (1 << session.id))
+}
@@ -0,0 +1,166 @@
+use super::*;
+use crate::store::{GTPTunnel, PDUSession, QosFlow};
+
+const KAMF: [u8; 32] = [0x42; 32];
+
+fn test_tai() -> TAI {
+    TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, 0x01],
+    }
+}
+
+/// A registered UE in CM-IDLE with 5G-TMSI 1 and two PDU sessions.
+fn setup() -> Store {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_target_gnb().address);
+    ue.cm_state = CMState::Idle;
+    ue.tmsi = Some(1);
+    ue.security = nas::security::SecurityContext::new(1, KAMF, vec![0xE0, 0xE0]);
+    for id in [5, 6] {
+        ue.pdu_sessions.push(PDUSession {
+            id,
+            ul_tunnel: Some(GTPTunnel {
+                address: vec![127, 0, 0, 1],
+                teid: id as u32,
+            }),
+            qos_flows: vec![QosFlow {
+                qfi: 1,
+                five_qi: 9,
+                arp_priority_level: 8,
+            }],
+            ..Default::default()
+        });
+    }
+    store.put_ue(ue);
+    store
+}
+
+/// A Service Request for data from 5G-TMSI 1, with uplink data pending for PDU
+/// session 5 and PDU session 6 no longer known to the UE.
+fn build_service_request(tmsi: u8) -> Vec<u8> {
+    [
+        &[0x7E, 0x00, 0x4C, 0x11][..],
+        &[0x00, 0x07, 0xF4, 0x00, 0x40, 0x00, 0x00, 0x00, tmsi],
+        &[0x40, 0x02, 0x20, 0x00],
+        &[0x50, 0x02, 0x20, 0x00],
+    ]
+    .concat()
+}
+
+/// Integrity protect the Service Request with NAS COUNT 0, the way the UE would.
+fn integrity_protect(kamf: &[u8; 32], plain: &[u8]) -> Vec<u8> {
+    let mut payload = vec![0x00];
+    payload.extend_from_slice(plain);
+    let knas_int = nas::security::derive_nas_key(kamf, 0x02, 2);
+    let mac = nas::security::nia2(&knas_int, 0, 0, 0, &payload);
+    [&[0x7E, 0x01][..], &mac, &payload].concat()
+}
+
+fn service_request(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    kamf: &[u8; 32],
+    tmsi: u8,
+) -> Vec<NGAPResponse> {
+    let plain = build_service_request(tmsi);
+    handle_service_request(
+        config,
+        store,
+        &crate::tests::test_gnb(),
+        20,
+        test_tai(),
+        &integrity_protect(kamf, &plain),
+        &plain,
+    )
+}
+
+fn assert_rejected(result: &[NGAPResponse]) {
+    assert_eq!(result.len(), 2);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+    assert!(matches!(
+        result[1].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_UEContextRelease(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_service_request() {
+    let store = setup();
+
+    let result = service_request(&Default::default(), &store, &KAMF, 1);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    let ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+        value: ngap::InitiatingMessageValue::Id_InitialContextSetup(request),
+        ..
+    }) = &result[0].ngap_pdu
+    else {
+        panic!("Service Request is not answered with an InitialContextSetupRequest");
+    };
+    let setup_list = request.protocol_i_es.0.iter().find_map(|protocol_ie| {
+        match &protocol_ie.value {
+            ngap::InitialContextSetupRequestProtocolIEs_EntryValue::Id_PDUSessionResourceSetupListCxtReq(
+                setup_list,
+            ) => Some(setup_list),
+            _ => None,
+        }
+    });
+    let setup_list = setup_list.expect("PDU session 5 should be re-activated");
+    assert_eq!(setup_list.0.len(), 1);
+    assert_eq!(setup_list.0[0].pdu_session_id.0, 5);
+
+    // The UE is connected through the new gNB, and PDU session 6 is gone
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.cm_state, CMState::Connected);
+    assert_eq!(ue.gnb, crate::tests::test_gnb());
+    assert_eq!(ue.ran_ue_ngap_id, 20);
+    assert_eq!(ue.tai, Some(test_tai()));
+    assert_eq!(ue.pdu_sessions.len(), 1);
+    assert_eq!(ue.pdu_sessions[0].id, 5);
+    assert_eq!(ue.security.unwrap().dl_count, 1);
+}
+
+#[test]
+fn test_service_request_unknown_tmsi() {
+    let store = setup();
+
+    let result = service_request(&Default::default(), &store, &KAMF, 2);
+    assert_rejected(&result);
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+}
+
+#[test]
+fn test_service_request_bad_mac() {
+    let store = setup();
+
+    let result = service_request(&Default::default(), &store, &[0x24; 32], 1);
+    assert_rejected(&result);
+
+    // The stored UE context is left as it was
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.cm_state, CMState::Idle);
+    assert_eq!(ue.pdu_sessions.len(), 2);
+}
+
+#[test]
+fn test_service_request_congestion() {
+    let store = setup();
+    let config = crate::config::CoreKubeConfig {
+        nas_congestion_backoff: Some(600),
+        ..Default::default()
+    };
+
+    let result = service_request(&config, &store, &KAMF, 1);
+    assert_rejected(&result);
+    assert_eq!(store.get_ue(1).unwrap().cm_state, CMState::Idle);
+}
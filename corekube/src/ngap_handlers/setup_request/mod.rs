@@ -1,79 +1,157 @@
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
+use nas::fgmm::Snssai;
 use ngap_asn1 as ngap;
 
-use super::NGAPResponse;
+use super::builder::message;
+use super::ies::protocol_ies;
+use super::ng_reset::reset_all;
+use super::overload::{build_overload_start, relative_amf_capacity};
+use super::{Destination, NGAPResponse};
+use crate::store::{GNBAddress, GNBContext, Store, SupportedTA};
 
 #[cfg(test)]
 mod tests;
 
+protocol_ies! {
+    struct NGSetupRequestIEs from NGSetupRequest(NGSetupRequestProtocolIEs_EntryValue) {
+        mandatory {
+            global_ran_node_id: Id_GlobalRANNodeID(ngap::GlobalRANNodeID),
+            supported_ta_list: Id_SupportedTAList(ngap::SupportedTAList),
+        }
+        optional {
+            paging_drx: Id_DefaultPagingDRX(ngap::PagingDRX),
+            ran_node_name: Id_RANNodeName(ngap::RANNodeName),
+        }
+    }
+}
+
 pub fn handle_setup_request(
     config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
     ng_setup: ngap::NGSetupRequest,
 ) -> Vec<NGAPResponse> {
     trace!("Handling NGAP message of type NGSetupRequest");
 
-    let mut global_ran_node_id = None;
-    let mut supported_ta_list = None;
-    let mut paging_drx = None;
-
-    // Fill the ProtocolIE values from the request, check if they exist
-    for protocol_ie in ng_setup.protocol_i_es.0 {
-        match protocol_ie.value {
-            ngap::NGSetupRequestProtocolIEs_EntryValue::Id_GlobalRANNodeID(
-                global_ran_node_id_value,
-            ) => {
-                global_ran_node_id = Some(global_ran_node_id_value);
-            }
-            ngap::NGSetupRequestProtocolIEs_EntryValue::Id_SupportedTAList(
-                supported_ta_list_value,
-            ) => {
-                supported_ta_list = Some(supported_ta_list_value);
-            }
-            ngap::NGSetupRequestProtocolIEs_EntryValue::Id_DefaultPagingDRX(
-                default_paging_drx_value,
-            ) => {
-                paging_drx = Some(default_paging_drx_value);
-            }
-            _ => {
-                debug!("Ignored ProtocolIE in NGSetupRequest: {:?}", protocol_ie);
-            }
+    let NGSetupRequestIEs {
+        global_ran_node_id,
+        supported_ta_list,
+        paging_drx,
+        ran_node_name,
+    } = match NGSetupRequestIEs::try_from(ng_setup) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
         }
-    }
-
-    let Some(global_ran_node_id) = global_ran_node_id else {
-        error!("Missing GlobalRANNodeID in NGSetupRequest");
-        return vec![];
     };
     debug!("GlobalRANNodeID: {:?}", global_ran_node_id);
+    debug!("SupportedTAList: {:?}", supported_ta_list);
+    debug!("DefaultPagingDRX: {:?}", paging_drx);
 
+    // Only gNBs are supported, see TS 38.413 section 8.7.1.4
+    let failure = || {
+        vec![NGAPResponse {
+            sctp_stream: 0,
+            ngap_pdu: build_setup_failure(
+                ngap::Cause::Misc(ngap::CauseMisc(ngap::CauseMisc::UNSPECIFIED)),
+                None,
+            ),
+            destination: Destination::Sender,
+        }]
+    };
     let ngap::GlobalRANNodeID::GlobalGNB_ID(global_gnb_id) = global_ran_node_id else {
-        error!("GlobalRANNodeID is not a GlobalGNB_ID");
-        return vec![];
+        warn!("GlobalRANNodeID is not a GlobalGNB_ID");
+        return failure();
     };
     debug!("GlobalGNB_ID: {:?}", global_gnb_id);
 
-    let Some(supported_ta_list) = supported_ta_list else {
-        error!("Missing SupportedTAList in NGSetupRequest");
-        return vec![];
+    let ngap::GNB_ID::GNB_ID(gnb_id) = global_gnb_id.gnb_id else {
+        warn!("GNB_ID is not a gNB-ID bit string");
+        return failure();
     };
-    debug!("SupportedTAList: {:?}", supported_ta_list);
 
-    let Some(paging_drx) = paging_drx else {
-        error!("Missing DefaultPagingDRX in NGSetupRequest");
-        return vec![];
-    };
-    debug!("DefaultPagingDRX: {:?}", paging_drx);
+    // NG Setup re-initialises the UE-associated logical NG-connections like
+    // an NG reset, see TS 38.413 section 8.7.1.1
+    reset_all(config, store, gnb);
+
+    // Remember the gNB so that later procedures can find it again
+    store.put_gnb(GNBContext {
+        address: *gnb,
+        plmn_identity: global_gnb_id.plmn_identity.0,
+        gnb_id,
+        name: ran_node_name.map(|name| name.0),
+        supported_tas: build_supported_tas(supported_ta_list),
+        // The DefaultPagingDRX may be left out as its criticality is ignore
+        paging_drx: paging_drx.map_or(ngap::PagingDRX::V128, |paging_drx| paging_drx.0),
+    });
 
     // Create the NGSetupResponse
     let response = NGAPResponse {
         sctp_stream: 0,
-        ngap_pdu: build_setup_response(config),
+        ngap_pdu: build_setup_response(config, relative_amf_capacity(config, store)),
+        destination: Destination::Sender,
     };
 
+    // A gNB that joins during an overload is told about it straight away
+    if store.load().is_overloaded() {
+        let overload_start = NGAPResponse {
+            sctp_stream: 0,
+            ngap_pdu: build_overload_start(config),
+            destination: Destination::Sender,
+        };
+        return vec![response, overload_start];
+    }
+
     vec![response]
 }
 
-fn build_plmn_identity(mcc: u8, mnc: u8) -> ngap::PLMNIdentity {
+/// Flatten a SupportedTAList into the form kept in the gNB context.
+pub(super) fn build_supported_tas(supported_ta_list: ngap::SupportedTAList) -> Vec<SupportedTA> {
+    supported_ta_list
+        .0
+        .into_iter()
+        .map(|item| {
+            let mut plmns = vec![];
+            let mut slices = vec![];
+            for plmn in item.broadcast_plmn_list.0 {
+                for slice in plmn.tai_slice_support_list.0 {
+                    match parse_s_nssai(&slice.s_nssai) {
+                        Some(snssai) => slices.push((plmn.plmn_identity.0.clone(), snssai)),
+                        None => debug!("Ignored invalid S-NSSAI {:?}", slice.s_nssai),
+                    }
+                }
+                plmns.push(plmn.plmn_identity.0);
+            }
+            SupportedTA {
+                tac: item.tac.0,
+                plmns,
+                slices,
+            }
+        })
+        .collect()
+}
+
+pub(super) fn build_s_nssai(snssai: &Snssai) -> ngap::S_NSSAI {
+    ngap::S_NSSAI {
+        sst: ngap::SST(vec![snssai.sst]),
+        sd: snssai.sd.map(|sd| ngap::SD(sd.to_vec())),
+        ie_extensions: None,
+    }
+}
+
+pub(super) fn parse_s_nssai(s_nssai: &ngap::S_NSSAI) -> Option<Snssai> {
+    let sd = match &s_nssai.sd {
+        Some(sd) => Some(sd.0.as_slice().try_into().ok()?),
+        None => None,
+    };
+    Some(Snssai {
+        sst: *s_nssai.sst.0.first()?,
+        sd,
+    })
+}
+
+pub(super) fn build_plmn_identity(mcc: u8, mnc: u8) -> ngap::PLMNIdentity {
     let mut mnc1 = mnc / 100;
     if mnc1 == 0 {
         mnc1 = 0x0f;
@@ -88,66 +166,76 @@ fn build_plmn_identity(mcc: u8, mnc: u8) -> ngap::PLMNIdentity {
     ngap::PLMNIdentity(vec![mcc2 << 4 | mcc1, mnc1 << 4 | mcc3, mnc3 << 4 | mnc2])
 }
 
-fn build_setup_response(config: &crate::config::CoreKubeConfig) -> ngap::NGAP_PDU {
+pub(super) fn build_guami(config: &crate::config::CoreKubeConfig) -> ngap::GUAMI {
+    ngap::GUAMI {
+        plmn_identity: build_plmn_identity(config.mcc, config.mnc),
+        amf_region_id: ngap::AMFRegionID(config.amf_region_id.clone()),
+        amf_set_id: ngap::AMFSetID(config.amf_set_id.clone()),
+        amf_pointer: ngap::AMFPointer(config.amf_pointer.clone()),
+        ie_extensions: None,
+    }
+}
+
+/// The GUAMIs served by the AMF, advertised to the gNBs.
+pub(super) fn build_served_guami_list(
+    config: &crate::config::CoreKubeConfig,
+) -> ngap::ServedGUAMIList {
+    ngap::ServedGUAMIList(vec![ngap::ServedGUAMIItem {
+        guami: build_guami(config),
+        backup_amf_name: None,
+        ie_extensions: None,
+    }])
+}
+
+/// The PLMN of the AMF and the slices it supports, advertised to the gNBs.
+pub(super) fn build_plmn_support_list(
+    config: &crate::config::CoreKubeConfig,
+) -> ngap::PLMNSupportList {
+    ngap::PLMNSupportList(vec![ngap::PLMNSupportItem {
+        plmn_identity: build_plmn_identity(config.mcc, config.mnc),
+        slice_support_list: ngap::SliceSupportList(
+            config
+                .nssai
+                .supported
+                .iter()
+                .map(|snssai| ngap::SliceSupportItem {
+                    s_nssai: build_s_nssai(snssai),
+                    ie_extensions: None,
+                })
+                .collect(),
+        ),
+        ie_extensions: None,
+    }])
+}
+
+fn build_setup_response(
+    config: &crate::config::CoreKubeConfig,
+    relative_amf_capacity: u8,
+) -> ngap::NGAP_PDU {
     trace!("Building NGSetupResponse");
 
-    ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
-        procedure_code: ngap::ProcedureCode(ngap::ID_NG_SETUP),
-        criticality: ngap::Criticality(ngap::Criticality::REJECT),
-        value: ngap::SuccessfulOutcomeValue::Id_NGSetup(ngap::NGSetupResponse {
-            protocol_i_es: ngap::NGSetupResponseProtocolIEs(vec![
-                ngap::NGSetupResponseProtocolIEs_Entry {
-                    id: ngap::ProtocolIE_ID(ngap::ID_AMF_NAME),
-                    criticality: ngap::Criticality(ngap::Criticality::REJECT),
-                    value: ngap::NGSetupResponseProtocolIEs_EntryValue::Id_AMFName(ngap::AMFName(
-                        config.amf_name.to_owned(),
-                    )),
-                },
-                ngap::NGSetupResponseProtocolIEs_Entry {
-                    id: ngap::ProtocolIE_ID(ngap::ID_SERVED_GUAMI_LIST),
-                    criticality: ngap::Criticality(ngap::Criticality::REJECT),
-                    value: ngap::NGSetupResponseProtocolIEs_EntryValue::Id_ServedGUAMIList(
-                        ngap::ServedGUAMIList(vec![ngap::ServedGUAMIItem {
-                            guami: ngap::GUAMI {
-                                plmn_identity: build_plmn_identity(config.mcc, config.mnc),
-                                amf_region_id: ngap::AMFRegionID(config.amf_region_id.clone()),
-                                amf_set_id: ngap::AMFSetID(config.amf_set_id.clone()),
-                                amf_pointer: ngap::AMFPointer(config.amf_pointer.clone()),
-                                ie_extensions: None,
-                            },
-                            backup_amf_name: None,
-                            ie_extensions: None,
-                        }]),
-                    ),
-                },
-                ngap::NGSetupResponseProtocolIEs_Entry {
-                    id: ngap::ProtocolIE_ID(ngap::ID_RELATIVE_AMF_CAPACITY),
-                    criticality: ngap::Criticality(ngap::Criticality::IGNORE),
-                    value: ngap::NGSetupResponseProtocolIEs_EntryValue::Id_RelativeAMFCapacity(
-                        ngap::RelativeAMFCapacity(config.relative_amf_capacity),
-                    ),
-                },
-                ngap::NGSetupResponseProtocolIEs_Entry {
-                    id: ngap::ProtocolIE_ID(ngap::ID_PLMN_SUPPORT_LIST),
-                    criticality: ngap::Criticality(ngap::Criticality::REJECT),
-                    value: ngap::NGSetupResponseProtocolIEs_EntryValue::Id_PLMNSupportList(
-                        ngap::PLMNSupportList(vec![ngap::PLMNSupportItem {
-                            plmn_identity: build_plmn_identity(config.mcc, config.mnc),
-                            slice_support_list: ngap::SliceSupportList(vec![
-                                ngap::SliceSupportItem {
-                                    s_nssai: ngap::S_NSSAI {
-                                        sst: ngap::SST(config.sst.clone()),
-                                        sd: None,
-                                        ie_extensions: None,
-                                    },
-                                    ie_extensions: None,
-                                },
-                            ]),
-                            ie_extensions: None,
-                        }]),
-                    ),
-                },
-            ]),
-        }),
-    })
+    use ngap::NGSetupResponseProtocolIEs_EntryValue as Value;
+    message::<ngap::NGSetupResponse>()
+        .ie(Value::Id_AMFName(ngap::AMFName(config.amf_name.to_owned())))
+        .ie(Value::Id_ServedGUAMIList(build_served_guami_list(config)))
+        .ie(Value::Id_RelativeAMFCapacity(ngap::RelativeAMFCapacity(
+            relative_amf_capacity,
+        )))
+        .ie(Value::Id_PLMNSupportList(build_plmn_support_list(config)))
+        .build()
+}
+
+/// Build an NGSetupFailure, with the CriticalityDiagnostics if the request
+/// was rejected because of its IEs.
+pub(super) fn build_setup_failure(
+    cause: ngap::Cause,
+    criticality_diagnostics: Option<ngap::CriticalityDiagnostics>,
+) -> ngap::NGAP_PDU {
+    trace!("Building NGSetupFailure");
+
+    use ngap::NGSetupFailureProtocolIEs_EntryValue as Value;
+    message::<ngap::NGSetupFailure>()
+        .ie(Value::Id_Cause(cause))
+        .optional_ie(criticality_diagnostics.map(Value::Id_CriticalityDiagnostics))
+        .build()
 }
@@ -1,5 +1,51 @@
 use super::*;
 
+/// An NGSetupRequest from the gNB `global_ran_node_id`, which leaves out the
+/// DefaultPagingDRX.
+fn build_setup_request(global_ran_node_id: ngap::GlobalRANNodeID) -> ngap::NGSetupRequest {
+    ngap::NGSetupRequest {
+        protocol_i_es: ngap::NGSetupRequestProtocolIEs(vec![
+            ngap::NGSetupRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_GLOBAL_RAN_NODE_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::NGSetupRequestProtocolIEs_EntryValue::Id_GlobalRANNodeID(
+                    global_ran_node_id,
+                ),
+            },
+            ngap::NGSetupRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_SUPPORTED_TA_LIST),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::NGSetupRequestProtocolIEs_EntryValue::Id_SupportedTAList(
+                    ngap::SupportedTAList(vec![ngap::SupportedTAItem {
+                        tac: ngap::TAC(vec![0x00, 0x00, 0x01]),
+                        broadcast_plmn_list: ngap::BroadcastPLMNList(vec![
+                            ngap::BroadcastPLMNItem {
+                                plmn_identity: build_plmn_identity(208, 93),
+                                tai_slice_support_list: ngap::SliceSupportList(vec![
+                                    ngap::SliceSupportItem {
+                                        s_nssai: build_s_nssai(&Snssai::default()),
+                                        ie_extensions: None,
+                                    },
+                                ]),
+                                ie_extensions: None,
+                            },
+                        ]),
+                        ie_extensions: None,
+                    }]),
+                ),
+            },
+        ]),
+    }
+}
+
+fn global_gnb_id() -> ngap::GlobalRANNodeID {
+    ngap::GlobalRANNodeID::GlobalGNB_ID(ngap::GlobalGNB_ID {
+        plmn_identity: build_plmn_identity(208, 93),
+        gnb_id: ngap::GNB_ID::GNB_ID(bitvec::bitvec![u8, bitvec::order::Msb0; 1; 22]),
+        ie_extensions: None,
+    })
+}
+
 #[test]
 fn it_works() {
     let result = 2 + 2;
@@ -12,3 +58,67 @@ fn test_plmn_identity() {
     let plmn_identity_expected_bytes: [u8; 3] = [0x02, 0xf8, 0x39];
     assert_eq!(plmn_identity, plmn_identity_expected_bytes.to_vec());
 }
+
+#[test]
+fn test_s_nssai() {
+    let snssai = Snssai {
+        sst: 2,
+        sd: Some([0x00, 0x00, 0x01]),
+    };
+    assert_eq!(parse_s_nssai(&build_s_nssai(&snssai)), Some(snssai));
+
+    let embb = Snssai::default();
+    assert_eq!(parse_s_nssai(&build_s_nssai(&embb)), Some(embb));
+}
+
+#[test]
+fn test_setup_request_without_paging_drx() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    let result = handle_setup_request(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_setup_request(global_gnb_id()),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::SuccessfulOutcome(ngap::SuccessfulOutcome {
+            value: ngap::SuccessfulOutcomeValue::Id_NGSetup(_),
+            ..
+        })
+    ));
+
+    let gnb = store.get_gnb(&crate::tests::test_gnb()).unwrap();
+    assert_eq!(gnb.paging_drx, ngap::PagingDRX::V128);
+}
+
+#[test]
+fn test_setup_request_from_ng_enb() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+
+    let ng_enb_id = ngap::GlobalRANNodeID::GlobalNgENB_ID(ngap::GlobalNgENB_ID {
+        plmn_identity: build_plmn_identity(208, 93),
+        ng_enb_id: ngap::NgENB_ID::MacroNgENB_ID(bitvec::bitvec![u8, bitvec::order::Msb0; 1; 20]),
+        ie_extensions: None,
+    });
+    let result = handle_setup_request(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_setup_request(ng_enb_id),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].destination, Destination::Sender);
+    assert!(matches!(
+        &result[0].ngap_pdu,
+        ngap::NGAP_PDU::UnsuccessfulOutcome(ngap::UnsuccessfulOutcome {
+            value: ngap::UnsuccessfulOutcomeValue::Id_NGSetup(_),
+            ..
+        })
+    ));
+    assert!(store.get_gnb(&crate::tests::test_gnb()).is_none());
+}
@@ -0,0 +1,91 @@
+use asn1_codecs::{aper::AperCodec, PerCodecData};
+use bitvec::prelude::*;
+use log::error;
+use ngap_asn1 as ngap;
+
+use crate::store::{GTPTunnel, QosFlow};
+
+/// Encode a transfer IE such as PDUSessionResourceSetupRequestTransfer. These
+/// are carried APER-encoded inside an OCTET STRING of the outer message, and
+/// are opaque to the NG-RAN node's NGAP layer.
+pub fn encode_transfer<T: AperCodec>(transfer: &T) -> Vec<u8> {
+    let mut codec_data = PerCodecData::default();
+    transfer
+        .aper_encode(&mut codec_data)
+        .expect("Error encoding NGAP transfer IE");
+    codec_data.get_inner().expect("Error getting inner buffer")
+}
+
+/// Decode a transfer IE from the OCTET STRING it was carried in.
+pub fn decode_transfer<T: AperCodec<Output = T>>(buf: &[u8]) -> Option<T> {
+    let mut codec_data = PerCodecData::from_slice_aper(buf);
+    match T::aper_decode(&mut codec_data) {
+        Ok(transfer) => Some(transfer),
+        Err(e) => {
+            error!("Error decoding NGAP transfer IE: {:?}", e);
+            None
+        }
+    }
+}
+
+pub fn build_up_transport_layer_information(
+    tunnel: &GTPTunnel,
+) -> ngap::UPTransportLayerInformation {
+    ngap::UPTransportLayerInformation::GTPTunnel(ngap::GTPTunnel {
+        transport_layer_address: ngap::TransportLayerAddress(BitVec::from_vec(
+            tunnel.address.clone(),
+        )),
+        gtp_teid: ngap::GTP_TEID(tunnel.teid.to_be_bytes().to_vec()),
+        ie_extensions: None,
+    })
+}
+
+pub fn parse_up_transport_layer_information(
+    information: ngap::UPTransportLayerInformation,
+) -> Option<GTPTunnel> {
+    let tunnel = match information {
+        ngap::UPTransportLayerInformation::GTPTunnel(tunnel) => tunnel,
+        unsupported => {
+            error!("Unsupported UPTransportLayerInformation: {:?}", unsupported);
+            return None;
+        }
+    };
+    let Ok(teid) = <[u8; 4]>::try_from(tunnel.gtp_teid.0) else {
+        error!("GTP-TEID is not 4 octets long");
+        return None;
+    };
+
+    Some(GTPTunnel {
+        address: tunnel.transport_layer_address.0.into_vec(),
+        teid: u32::from_be_bytes(teid),
+    })
+}
+
+/// Build the QoS parameters of a non-GBR flow with a standardised 5QI.
+pub fn build_qos_flow_level_qos_parameters(qos_flow: &QosFlow) -> ngap::QosFlowLevelQosParameters {
+    ngap::QosFlowLevelQosParameters {
+        qos_characteristics: ngap::QosCharacteristics::NonDynamic5QI(
+            ngap::NonDynamic5QIDescriptor {
+                five_qi: ngap::FiveQI(qos_flow.five_qi),
+                priority_level_qos: None,
+                averaging_window: None,
+                maximum_data_burst_volume: None,
+                ie_extensions: None,
+            },
+        ),
+        allocation_and_retention_priority: ngap::AllocationAndRetentionPriority {
+            priority_level_arp: ngap::PriorityLevelARP(qos_flow.arp_priority_level),
+            pre_emption_capability: ngap::Pre_emptionCapability(
+                ngap::Pre_emptionCapability::SHALL_NOT_TRIGGER_PRE_EMPTION,
+            ),
+            pre_emption_vulnerability: ngap::Pre_emptionVulnerability(
+                ngap::Pre_emptionVulnerability::NOT_PRE_EMPTABLE,
+            ),
+            ie_extensions: None,
+        },
+        gbr_qos_information: None,
+        reflective_qos_attribute: None,
+        additional_qos_flow_information: None,
+        ie_extensions: None,
+    }
+}
@@ -0,0 +1,219 @@
+use log::{debug, error, info, trace, warn};
+use ngap_asn1 as ngap;
+
+use super::builder::message;
+use super::criticality::missing_cause;
+use super::ies::protocol_ies;
+use super::nas_timers::start_mobile_reachable_timer;
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::store::{CMState, GNBAddress, RMState, Store, UEContext, NRCGI};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct UEContextReleaseRequestIEs
+        from UEContextReleaseRequest(UEContextReleaseRequestProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+        }
+        optional {
+            cause: Id_Cause(ngap::Cause),
+            pdu_session_list: Id_PDUSessionResourceListCxtRelReq(
+                ngap::PDUSessionResourceListCxtRelReq
+            ),
+        }
+    }
+}
+
+protocol_ies! {
+    struct UEContextReleaseCompleteIEs
+        from UEContextReleaseComplete(UEContextReleaseCompleteProtocolIEs_EntryValue)
+    {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+        }
+        optional {
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            recommended_cells: Id_InfoOnRecommendedCellsAndRANNodesForPaging(
+                ngap::InfoOnRecommendedCellsAndRANNodesForPaging
+            ),
+            pdu_session_list: Id_PDUSessionResourceListCxtRelCpl(
+                ngap::PDUSessionResourceListCxtRelCpl
+            ),
+        }
+    }
+}
+
+pub fn handle_ue_context_release_request(
+    _config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    release_request: ngap::UEContextReleaseRequest,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type UEContextReleaseRequest");
+
+    let UEContextReleaseRequestIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        cause,
+        pdu_session_list,
+    } = match UEContextReleaseRequestIEs::try_from(release_request) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+    info!(
+        "NG-RAN requested UE context release with cause: {:?}",
+        cause
+    );
+    let cause = cause.unwrap_or_else(missing_cause);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in UEContextReleaseRequest");
+        return vec![];
+    };
+
+    // The user plane of these PDU sessions is deactivated along with the
+    // UE context
+    for item in pdu_session_list.map(|l| l.0).unwrap_or_default() {
+        ue.set_pdu_session_active(item.pdu_session_id.0, false);
+    }
+    store.put_ue(ue);
+
+    // Release the UE context with the cause given by the NG-RAN
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_ue_context_release_command(amf_ue_ngap_id.0, Some(ran_ue_ngap_id.0), cause),
+        destination: Destination::Sender,
+    }]
+}
+
+pub fn handle_ue_context_release_complete(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    gnb: &GNBAddress,
+    release_complete: ngap::UEContextReleaseComplete,
+) -> Vec<NGAPResponse> {
+    trace!("Handling NGAP message of type UEContextReleaseComplete");
+
+    let UEContextReleaseCompleteIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        recommended_cells,
+        pdu_session_list,
+    } = match UEContextReleaseCompleteIEs::try_from(release_complete) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+
+    let Some(mut ue) = store.get_ue(amf_ue_ngap_id.0) else {
+        error!("Unknown AMF_UE_NGAP_ID in UEContextReleaseComplete");
+        return vec![];
+    };
+    // After a handover, the source gNB releases its side while the UE stays
+    // connected through the target
+    if ue.gnb != *gnb {
+        info!(
+            "UE {} released by a gNB that no longer serves it",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    }
+    if let Some(ran_ue_ngap_id) = ran_ue_ngap_id.filter(|id| id.0 != ue.ran_ue_ngap_id) {
+        warn!(
+            "RAN_UE_NGAP_ID mismatch in UEContextReleaseComplete: stored {}, received {}",
+            ue.ran_ue_ngap_id, ran_ue_ngap_id.0
+        );
+    }
+
+    // The PDU sessions that still had NG-RAN resources until now
+    for item in pdu_session_list.map(|l| l.0).unwrap_or_default() {
+        debug!(
+            "PDU session {} resources released with the UE context",
+            item.pdu_session_id.0
+        );
+    }
+
+    ue.recommended_cells_for_paging = recommended_cells
+        .map(|info| build_recommended_cells(info.recommended_cells_for_paging))
+        .unwrap_or_default();
+    release_ng_ran_side(config, store, ue);
+    vec![]
+}
+
+/// Store a UE whose NG-RAN side is gone, which leaves it idle with no user
+/// plane resources. The NAS security context is kept so that the UE can
+/// come back with a Service Request. A de-registered UE is forgotten along
+/// with its NG-RAN side.
+pub(super) fn release_ng_ran_side(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    mut ue: UEContext,
+) {
+    if ue.rm_state == RMState::Deregistered {
+        info!("UE {} removed after de-registration", ue.amf_ue_ngap_id);
+        store.remove_ue(ue.amf_ue_ngap_id);
+        return;
+    }
+
+    ue.cm_state = CMState::Idle;
+    ue.as_context_established = false;
+    for session in ue.pdu_sessions.iter_mut() {
+        session.active = false;
+    }
+    start_mobile_reachable_timer(config, store, &ue);
+    store.put_ue(ue);
+}
+
+/// Keep the NR cells out of the recommended cells for paging.
+fn build_recommended_cells(recommended_cells: ngap::RecommendedCellsForPaging) -> Vec<NRCGI> {
+    recommended_cells
+        .recommended_cell_list
+        .0
+        .into_iter()
+        .filter_map(|item| match item.ngran_cgi {
+            ngap::NGRAN_CGI::NR_CGI(nr_cgi) => Some(NRCGI {
+                plmn_identity: nr_cgi.plmn_identity.0,
+                nr_cell_identity: nr_cgi.nr_cell_identity.0,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build a UEContextReleaseCommand, identifying the UE by its UE NGAP ID pair
+/// if the RAN_UE_NGAP_ID is known, or by the AMF_UE_NGAP_ID only otherwise.
+pub fn build_ue_context_release_command(
+    amf_ue_ngap_id: u64,
+    ran_ue_ngap_id: Option<u32>,
+    cause: ngap::Cause,
+) -> ngap::NGAP_PDU {
+    trace!("Building UEContextReleaseCommand");
+
+    let ue_ngap_ids = match ran_ue_ngap_id {
+        Some(ran_ue_ngap_id) => ngap::UE_NGAP_IDs::UE_NGAP_ID_pair(ngap::UE_NGAP_ID_pair {
+            amf_ue_ngap_id: ngap::AMF_UE_NGAP_ID(amf_ue_ngap_id),
+            ran_ue_ngap_id: ngap::RAN_UE_NGAP_ID(ran_ue_ngap_id),
+            ie_extensions: None,
+        }),
+        None => ngap::UE_NGAP_IDs::AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(amf_ue_ngap_id)),
+    };
+
+    use ngap::UEContextReleaseCommandProtocolIEs_EntryValue as Value;
+    message::<ngap::UEContextReleaseCommand>()
+        .ie(Value::Id_UE_NGAP_IDs(ue_ngap_ids))
+        .ie(Value::Id_Cause(cause))
+        .build()
+}
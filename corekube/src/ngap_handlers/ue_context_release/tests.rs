@@ -0,0 +1,235 @@
+use super::*;
+use crate::store::UEContext;
+
+fn release_complete() -> ngap::UEContextReleaseComplete {
+    ngap::UEContextReleaseComplete {
+        protocol_i_es: ngap::UEContextReleaseCompleteProtocolIEs(vec![
+            ngap::UEContextReleaseCompleteProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseCompleteProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::UEContextReleaseCompleteProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseCompleteProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_ue_context_release_complete() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.as_context_established = true;
+    ue.set_pdu_session_active(5, true);
+    ue.security = nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+
+    let release_complete = ngap::UEContextReleaseComplete {
+        protocol_i_es: ngap::UEContextReleaseCompleteProtocolIEs(vec![
+            ngap::UEContextReleaseCompleteProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseCompleteProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::UEContextReleaseCompleteProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseCompleteProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::UEContextReleaseCompleteProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_INFO_ON_RECOMMENDED_CELLS_AND_RAN_NODES_FOR_PAGING),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseCompleteProtocolIEs_EntryValue::Id_InfoOnRecommendedCellsAndRANNodesForPaging(
+                    ngap::InfoOnRecommendedCellsAndRANNodesForPaging {
+                        recommended_cells_for_paging: ngap::RecommendedCellsForPaging {
+                            recommended_cell_list: ngap::RecommendedCellList(vec![
+                                ngap::RecommendedCellItem {
+                                    ngran_cgi: ngap::NGRAN_CGI::NR_CGI(ngap::NR_CGI {
+                                        plmn_identity: ngap::PLMNIdentity(vec![0x02, 0xf8, 0x39]),
+                                        nr_cell_identity: ngap::NRCellIdentity(
+                                            bitvec::bitvec![u8, bitvec::order::Msb0; 1; 36],
+                                        ),
+                                        ie_extensions: None,
+                                    }),
+                                    time_stayed_in_cell: None,
+                                    ie_extensions: None,
+                                },
+                            ]),
+                            ie_extensions: None,
+                        },
+                        recommend_ran_nodes_for_paging: ngap::RecommendedRANNodesForPaging {
+                            recommended_ran_node_list: ngap::RecommendedRANNodeList(vec![]),
+                            ie_extensions: None,
+                        },
+                        ie_extensions: None,
+                    },
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_ue_context_release_complete(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        release_complete,
+    );
+    assert!(result.is_empty());
+
+    let ue = store
+        .get_ue(1)
+        .expect("UE context should be kept while idle");
+    assert_eq!(ue.cm_state, CMState::Idle);
+    assert!(!ue.as_context_established);
+    assert!(ue.pdu_sessions.iter().all(|s| !s.active));
+    assert!(ue.security.is_some());
+    assert_eq!(ue.recommended_cells_for_paging.len(), 1);
+    assert!(store
+        .timer_due(1, crate::store::NasTimer::MobileReachable)
+        .is_some());
+}
+
+#[test]
+fn test_ue_context_release_request() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    store.put_ue(ue);
+
+    let release_request = ngap::UEContextReleaseRequest {
+        protocol_i_es: ngap::UEContextReleaseRequestProtocolIEs(vec![
+            ngap::UEContextReleaseRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UEContextReleaseRequestProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::UEContextReleaseRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UEContextReleaseRequestProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::UEContextReleaseRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_PDU_SESSION_RESOURCE_LIST_CXT_REL_REQ),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UEContextReleaseRequestProtocolIEs_EntryValue::Id_PDUSessionResourceListCxtRelReq(
+                    ngap::PDUSessionResourceListCxtRelReq(vec![
+                        ngap::PDUSessionResourceItemCxtRelReq {
+                            pdu_session_id: ngap::PDUSessionID(5),
+                            ie_extensions: None,
+                        },
+                    ]),
+                ),
+            },
+            ngap::UEContextReleaseRequestProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_CAUSE),
+                criticality: ngap::Criticality(ngap::Criticality::IGNORE),
+                value: ngap::UEContextReleaseRequestProtocolIEs_EntryValue::Id_Cause(
+                    ngap::Cause::RadioNetwork(ngap::CauseRadioNetwork(
+                        ngap::CauseRadioNetwork::USER_INACTIVITY,
+                    )),
+                ),
+            },
+        ]),
+    };
+
+    let result = handle_ue_context_release_request(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        release_request,
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].sctp_stream, UE_SCTP_STREAM);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_UEContextRelease(_),
+            ..
+        })
+    ));
+
+    // The UE stays connected until the release is complete
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.cm_state, CMState::Connected);
+    assert!(!ue.pdu_sessions[0].active);
+}
+
+#[test]
+fn test_ue_context_release_command_by_amf_id_only() {
+    let ngap::NGAP_PDU::InitiatingMessage(init_msg) = build_ue_context_release_command(
+        1,
+        None,
+        ngap::Cause::Nas(ngap::CauseNas(ngap::CauseNas::DEREGISTER)),
+    ) else {
+        panic!("UEContextReleaseCommand is not an InitiatingMessage");
+    };
+    let ngap::InitiatingMessageValue::Id_UEContextRelease(command) = init_msg.value else {
+        panic!("InitiatingMessage is not a UEContextReleaseCommand");
+    };
+
+    assert!(command.protocol_i_es.0.iter().any(|protocol_ie| matches!(
+        protocol_ie.value,
+        ngap::UEContextReleaseCommandProtocolIEs_EntryValue::Id_UE_NGAP_IDs(
+            ngap::UE_NGAP_IDs::AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID(1))
+        )
+    )));
+}
+
+#[test]
+fn test_ue_context_release_complete_after_handover() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb().address;
+    let mut ue = UEContext::new(1, 20, target);
+    ue.as_context_established = true;
+    store.put_ue(ue);
+
+    // The source gNB completes the release it was asked for after the handover
+    let result = handle_ue_context_release_complete(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        release_complete(),
+    );
+    assert!(result.is_empty());
+
+    let ue = store.get_ue(1).expect("UE context should still exist");
+    assert_eq!(ue.cm_state, CMState::Connected);
+    assert!(ue.as_context_established);
+    assert_eq!(ue.gnb, target);
+}
+
+#[test]
+fn test_ue_context_release_complete_after_deregistration() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.rm_state = RMState::Deregistered;
+    store.put_ue(ue);
+
+    let result = handle_ue_context_release_complete(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        release_complete(),
+    );
+    assert!(result.is_empty());
+    assert!(store.get_ue(1).is_none());
+}
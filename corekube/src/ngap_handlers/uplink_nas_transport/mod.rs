@@ -1,12 +1,405 @@
-use log::trace;
+use log::{debug, error, info, trace, warn};
+use nas::fgmm::{
+    DlNasTransport, MobilityManagementStatus, RegistrationRequest, SecurityModeComplete,
+    UlNasTransport, PAYLOAD_CONTAINER_N1_SM_INFORMATION,
+};
+use nas::fgsm::{
+    PduSessionEstablishmentAccept, PduSessionEstablishmentReject, PduSessionEstablishmentRequest,
+};
+use nas::{MobilityManagementCause, MobilityMessageIdentifier};
 use ngap_asn1 as ngap;
 
-use super::NGAPResponse;
+use super::authentication::{
+    handle_authentication_failure, handle_authentication_response, handle_identity_response,
+    handle_security_mode_reject,
+};
+use super::deregistration::{handle_deregistration_accept, handle_deregistration_request};
+use super::downlink_nas_transport::build_downlink_nas_transport;
+use super::ies::protocol_ies;
+use super::nas_timers::handle_nas_answer;
+use super::paging::parse_user_location_tai;
+use super::pdu_session_resource_setup::{
+    build_pdu_session_resource_setup_request, build_pdu_session_resource_setup_request_transfer,
+};
+use super::registration::{
+    accept_initial_registration, handle_registration_request, reject_registration,
+};
+use super::{Destination, NGAPResponse, UE_SCTP_STREAM};
+use crate::nssf;
+use crate::smf::SmfError;
+use crate::store::{GNBAddress, Store, UEContext};
+
+#[cfg(test)]
+mod tests;
+
+protocol_ies! {
+    struct UplinkNASTransportIEs from UplinkNASTransport(UplinkNASTransportProtocolIEs_EntryValue) {
+        mandatory {
+            amf_ue_ngap_id: Id_AMF_UE_NGAP_ID(ngap::AMF_UE_NGAP_ID),
+            ran_ue_ngap_id: Id_RAN_UE_NGAP_ID(ngap::RAN_UE_NGAP_ID),
+            nas_pdu: Id_NAS_PDU(ngap::NAS_PDU),
+        }
+        optional {
+            user_location_information: Id_UserLocationInformation(ngap::UserLocationInformation),
+        }
+    }
+}
 
 pub fn handle_uplink_nas_transport(
-    _config: &crate::config::CoreKubeConfig,
-    _uplink_nas: ngap::UplinkNASTransport,
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    _gnb: &GNBAddress,
+    uplink_nas: ngap::UplinkNASTransport,
 ) -> Vec<NGAPResponse> {
     trace!("Handling NGAP message of type UplinkNASTransport");
-    vec![]
+
+    let UplinkNASTransportIEs {
+        amf_ue_ngap_id,
+        ran_ue_ngap_id,
+        nas_pdu,
+        user_location_information,
+    } = match UplinkNASTransportIEs::try_from(uplink_nas) {
+        Ok(ies) => ies,
+        Err(e) => {
+            error!("{}", e);
+            return vec![];
+        }
+    };
+    debug!("AMF_UE_NGAP_ID: {:?}", amf_ue_ngap_id);
+    debug!("RAN_UE_NGAP_ID: {:?}", ran_ue_ngap_id);
+    debug!("NAS_PDU: {:?}", nas_pdu);
+
+    // The UE context stays locked while its NAS message is handled, so that
+    // the advanced NAS COUNTs and anything the NAS handler changes are not
+    // lost to a concurrent update of the same UE
+    let responses = store.update_ue(amf_ue_ngap_id.0, |ue| {
+        if let Some(tai) = user_location_information.and_then(parse_user_location_tai) {
+            ue.tai = Some(tai);
+        }
+        if ue.ran_ue_ngap_id != ran_ue_ngap_id.0 {
+            warn!(
+                "RAN_UE_NGAP_ID mismatch in UplinkNASTransport: stored {}, received {}",
+                ue.ran_ue_ngap_id, ran_ue_ngap_id.0
+            );
+        }
+        handle_nas_message(config, store, ue, nas_pdu.0)
+    });
+    responses.unwrap_or_else(|| {
+        error!("Unknown AMF_UE_NGAP_ID in UplinkNASTransport");
+        vec![]
+    })
+}
+
+/// Unprotect a NAS message from a UE and hand it to its handler.
+fn handle_nas_message(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_pdu: Vec<u8>,
+) -> Vec<NGAPResponse> {
+    // A message that can not be integrity checked is discarded without an
+    // answer, see TS 24.501 section 4.4.4.3, as it may not come from the UE
+    // at all and must not advance the uplink NAS COUNT
+    let nas_message = if is_unprotected_answer(&nas_pdu) {
+        nas_pdu
+    } else {
+        let Some(security) = ue.security.as_mut() else {
+            error!("No NAS security context for UE {}", ue.amf_ue_ngap_id);
+            return vec![];
+        };
+        match security.unprotect(&nas_pdu) {
+            Ok(nas_message) => nas_message,
+            Err(cause) => {
+                error!("Could not unprotect NAS message, cause {:?}", cause);
+                return vec![];
+            }
+        }
+    };
+
+    let message_type = nas::mobility_message_type(&nas_message);
+    if let Some(message_type) = message_type {
+        handle_nas_answer(store, ue, message_type);
+    }
+
+    match message_type {
+        Some(MobilityMessageIdentifier::IDENTITY_RESPONSE) => {
+            handle_identity_response(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::AUTHENTICATION_RESPONSE) => {
+            handle_authentication_response(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::AUTHENTICATION_FAILURE) => {
+            handle_authentication_failure(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::SECURITY_MODE_COMPLETE) => {
+            handle_security_mode_complete(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::SECURITY_MODE_REJECT) => handle_security_mode_reject(ue),
+        Some(MobilityMessageIdentifier::UPLINK_NAS_TRANSPORT) => {
+            handle_ul_nas_transport(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::REGISTRATION_REQUEST) => {
+            handle_registration_request(config, store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::REGISTRATION_COMPLETE) => {
+            debug!("UE {} completed its registration", ue.amf_ue_ngap_id);
+            vec![]
+        }
+        Some(MobilityMessageIdentifier::DEREGISTRATION_REQUEST) => {
+            handle_deregistration_request(store, ue, &nas_message)
+        }
+        Some(MobilityMessageIdentifier::DEREGISTRATION_ACCEPT_UE_TERMINATED) => {
+            handle_deregistration_accept(store, ue)
+        }
+        Some(MobilityMessageIdentifier::STATUS) => {
+            // Never answered, so that two peers can not keep reporting errors
+            info!(
+                "5GMM Status from UE {} with cause {:?}",
+                ue.amf_ue_ngap_id,
+                nas_message.get(3)
+            );
+            vec![]
+        }
+        other => {
+            info!("Unhandled NAS message in UplinkNASTransport: {:?}", other);
+            send_mobility_management_status(
+                ue,
+                MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED,
+            )
+        }
+    }
+}
+
+/// Whether a NAS message is one of those that a UE without a NAS security
+/// context sends plain while it is identified and authenticated, see TS
+/// 24.501 section 4.4.4.3.
+fn is_unprotected_answer(nas_pdu: &[u8]) -> bool {
+    if nas_pdu.get(1).map(|header| header & 0x0F) != Some(nas::SecurityHeader::NotProtected as u8) {
+        return false;
+    }
+    matches!(
+        nas::mobility_message_type(nas_pdu),
+        Some(
+            MobilityMessageIdentifier::IDENTITY_RESPONSE
+                | MobilityMessageIdentifier::AUTHENTICATION_RESPONSE
+                | MobilityMessageIdentifier::AUTHENTICATION_FAILURE
+                | MobilityMessageIdentifier::SECURITY_MODE_REJECT
+        )
+    )
+}
+
+/// Report an error in a NAS message from a UE with a 5GMM Status, see TS
+/// 24.501 section 7. A UE without a NAS security context is not answered,
+/// as a 5GMM Status is not among the messages that the AMF may send without
+/// protection, see TS 24.501 section 4.4.4.2.
+pub(super) fn send_mobility_management_status(
+    ue: &mut UEContext,
+    cause: MobilityManagementCause,
+) -> Vec<NGAPResponse> {
+    let Some(security) = ue.security.as_mut() else {
+        return vec![];
+    };
+    let nas_pdu = security.protect(
+        &MobilityManagementStatus { cause }.encode(),
+        nas::SecurityHeader::IntegrityProtectedAndCiphered,
+    );
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Once NAS security is in place, accept the registration that the UE
+/// sent again in the Security Mode Complete, and set up the UE context in
+/// the gNB.
+fn handle_security_mode_complete(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type SecurityModeComplete");
+
+    let Some(pending) = ue.pending_registration.take() else {
+        info!(
+            "Unexpected Security Mode Complete from UE {}",
+            ue.amf_ue_ngap_id
+        );
+        return vec![];
+    };
+    let request = SecurityModeComplete::decode(nas_message)
+        .and_then(|complete| complete.nas_message_container)
+        .and_then(|container| RegistrationRequest::decode(&container))
+        .unwrap_or(pending);
+
+    match accept_initial_registration(config, store, ue, &request) {
+        Ok(responses) => responses,
+        Err(reject) => reject_registration(store, ue, reject),
+    }
+}
+
+/// Forward the 5GSM message carried in an UL NAS Transport to the SMF-lite.
+fn handle_ul_nas_transport(
+    config: &crate::config::CoreKubeConfig,
+    store: &Store,
+    ue: &mut UEContext,
+    nas_message: &[u8],
+) -> Vec<NGAPResponse> {
+    trace!("Handling NAS message of type ULNASTransport");
+
+    let Some(transport) = UlNasTransport::decode(nas_message) else {
+        error!("Could not decode UL NAS Transport");
+        return send_mobility_management_status(
+            ue,
+            MobilityManagementCause::INVALID_MANDATORY_INFORMATION,
+        );
+    };
+    if transport.payload_container_type != PAYLOAD_CONTAINER_N1_SM_INFORMATION {
+        info!(
+            "Unhandled payload container type {} in UL NAS Transport",
+            transport.payload_container_type
+        );
+        return vec![];
+    }
+    let Some(request) = PduSessionEstablishmentRequest::decode(&transport.payload_container) else {
+        info!("Unhandled 5GSM message in UL NAS Transport");
+        return vec![];
+    };
+    let pdu_session_id = transport.pdu_session_id.unwrap_or(request.pdu_session_id);
+    let dnn = transport.dnn.as_deref().and_then(nas::fgsm::decode_dnn);
+
+    // A 5GSM message for a slice the UE may not use goes back to the UE
+    // undelivered, see TS 24.501 section 5.4.5.2.5
+    let Some(snssai) = nssf::select_session_snssai(&ue.allowed_nssai, transport.snssai.as_ref())
+    else {
+        let Some(nas_pdu) = protect_dl_nas_transport(
+            ue,
+            pdu_session_id,
+            transport.payload_container,
+            Some(MobilityManagementCause::PAYLOAD_WAS_NOT_FORWARDED),
+        ) else {
+            return vec![];
+        };
+        return vec![NGAPResponse {
+            sctp_stream: UE_SCTP_STREAM,
+            ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+            destination: Destination::Sender,
+        }];
+    };
+
+    let Some(smf) = store.smf() else {
+        warn!(
+            "No SMF-lite configured, rejecting PDU session {}",
+            pdu_session_id
+        );
+        return reject_pdu_session(ue, &request, nas::fgsm::CAUSE_INSUFFICIENT_RESOURCES);
+    };
+    let established = match smf.establish_session(
+        dnn.as_deref(),
+        config.session_ambr_uplink,
+        config.session_ambr_downlink,
+    ) {
+        Ok(established) => established,
+        Err(SmfError::UnknownDnn) => {
+            info!(
+                "Unknown DNN {:?} requested for PDU session {}",
+                dnn, pdu_session_id
+            );
+            return reject_pdu_session(ue, &request, nas::fgsm::CAUSE_MISSING_OR_UNKNOWN_DNN);
+        }
+        Err(e) => {
+            error!(
+                "Could not establish PDU session {}: {:?}",
+                pdu_session_id, e
+            );
+            return reject_pdu_session(ue, &request, nas::fgsm::CAUSE_INSUFFICIENT_RESOURCES);
+        }
+    };
+
+    let accept = PduSessionEstablishmentAccept {
+        pdu_session_id,
+        pti: request.pti,
+        qfi: established.qos_flow.qfi,
+        session_ambr_downlink: bit_rate_to_mbps(config.session_ambr_downlink),
+        session_ambr_uplink: bit_rate_to_mbps(config.session_ambr_uplink),
+        ue_ip_address: established.sm_context.ue_ip_address.octets(),
+        snssai: Some(snssai),
+        dnn: Some(established.sm_context.dnn.clone()),
+    };
+    let Some(nas_pdu) = protect_dl_nas_transport(ue, pdu_session_id, accept.encode(), None) else {
+        return vec![];
+    };
+    let transfer = build_pdu_session_resource_setup_request_transfer(
+        config,
+        &established.ul_tunnel,
+        &[established.qos_flow],
+    );
+
+    let session = ue.pdu_session_mut(pdu_session_id);
+    session.snssai = snssai;
+    session.ul_tunnel = Some(established.ul_tunnel);
+    session.qos_flows = vec![established.qos_flow];
+    session.sm_context = Some(established.sm_context);
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_pdu_session_resource_setup_request(
+            ue,
+            pdu_session_id,
+            Some(nas_pdu),
+            transfer,
+        ),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Send a PDU Session Establishment Reject back in a DownlinkNASTransport.
+fn reject_pdu_session(
+    ue: &mut UEContext,
+    request: &PduSessionEstablishmentRequest,
+    cause: u8,
+) -> Vec<NGAPResponse> {
+    let reject = PduSessionEstablishmentReject {
+        pdu_session_id: request.pdu_session_id,
+        pti: request.pti,
+        cause,
+    };
+    let Some(nas_pdu) = protect_dl_nas_transport(ue, request.pdu_session_id, reject.encode(), None)
+    else {
+        return vec![];
+    };
+
+    vec![NGAPResponse {
+        sctp_stream: UE_SCTP_STREAM,
+        ngap_pdu: build_downlink_nas_transport(ue, nas_pdu),
+        destination: Destination::Sender,
+    }]
+}
+
+/// Wrap a 5GSM message in a security protected DL NAS Transport, with a
+/// 5GMM cause if the message is returned to the UE undelivered.
+fn protect_dl_nas_transport(
+    ue: &mut UEContext,
+    pdu_session_id: u8,
+    payload_container: Vec<u8>,
+    cause: Option<MobilityManagementCause>,
+) -> Option<Vec<u8>> {
+    let transport = DlNasTransport {
+        payload_container_type: PAYLOAD_CONTAINER_N1_SM_INFORMATION,
+        payload_container,
+        pdu_session_id: Some(pdu_session_id),
+        cause,
+    };
+    let security = ue.security.as_mut()?;
+    Some(security.protect(
+        &transport.encode(),
+        nas::SecurityHeader::IntegrityProtectedAndCiphered,
+    ))
+}
+
+/// Convert a bit rate in bit/s to the whole Mbps used in the Session-AMBR IE.
+fn bit_rate_to_mbps(bit_rate: u64) -> u16 {
+    (bit_rate / 1_000_000).min(u16::MAX as u64) as u16
 }
@@ -1,5 +1,296 @@
+use super::*;
+
 #[test]
 fn it_works() {
     let result = 2 + 2;
     assert_eq!(result, 4);
 }
+
+fn build_uplink_nas_transport(nas_pdu: Vec<u8>) -> ngap::UplinkNASTransport {
+    ngap::UplinkNASTransport {
+        protocol_i_es: ngap::UplinkNASTransportProtocolIEs(vec![
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_AMF_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_AMF_UE_NGAP_ID(
+                    ngap::AMF_UE_NGAP_ID(1),
+                ),
+            },
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_RAN_UE_NGAP_ID),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_RAN_UE_NGAP_ID(
+                    ngap::RAN_UE_NGAP_ID(10),
+                ),
+            },
+            ngap::UplinkNASTransportProtocolIEs_Entry {
+                id: ngap::ProtocolIE_ID(ngap::ID_NAS_PDU),
+                criticality: ngap::Criticality(ngap::Criticality::REJECT),
+                value: ngap::UplinkNASTransportProtocolIEs_EntryValue::Id_NAS_PDU(ngap::NAS_PDU(
+                    nas_pdu,
+                )),
+            },
+        ]),
+    }
+}
+
+#[test]
+fn test_security_mode_complete() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    ue.pending_registration =
+        RegistrationRequest::decode(&[0x7E, 0x00, 0x41, 0x79, 0x00, 0x01, 0x00]);
+    store.put_ue(ue);
+
+    // Integrity protect a Security Mode Complete the way the UE would
+    let mut payload = vec![0x00, 0x7E, 0x00, 0x5E];
+    let knas_int = nas::security::derive_nas_key(&kamf, 0x02, 2);
+    let mac = nas::security::nia2(&knas_int, 0, 0, 0, &payload);
+    let mut nas_pdu = vec![0x7E, 0x03];
+    nas_pdu.extend_from_slice(&mac);
+    nas_pdu.append(&mut payload);
+
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(nas_pdu),
+    );
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].sctp_stream, UE_SCTP_STREAM);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_InitialContextSetup(_),
+            ..
+        })
+    ));
+
+    // The Registration Accept used up a downlink NAS COUNT and a 5G-TMSI,
+    // and is sent again until the UE completes its registration
+    let ue = store.get_ue(1).unwrap();
+    assert!(ue.tmsi.is_some());
+    assert!(ue.last_dl_nas.is_some());
+    assert!(store.timer_due(1, crate::store::NasTimer::T3550).is_some());
+    assert_eq!(ue.security.unwrap().dl_count, 1);
+}
+
+#[test]
+fn test_bad_mac_is_dropped() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+
+    let nas_pdu = vec![0x7E, 0x03, 0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x7E, 0x00, 0x5E];
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(nas_pdu),
+    );
+    assert!(result.is_empty());
+    assert!(store.get_ue(1).unwrap().tmsi.is_none());
+}
+
+/// Integrity protect an uplink NAS message with the given NAS COUNT, the way
+/// the UE would.
+fn integrity_protect(kamf: &[u8; 32], count: u32, plain: &[u8]) -> Vec<u8> {
+    let mut payload = vec![count as u8];
+    payload.extend_from_slice(plain);
+    let knas_int = nas::security::derive_nas_key(kamf, 0x02, 2);
+    let mac = nas::security::nia2(&knas_int, count, 0, 0, &payload);
+    let mut nas_pdu = vec![0x7E, 0x01];
+    nas_pdu.extend_from_slice(&mac);
+    nas_pdu.append(&mut payload);
+    nas_pdu
+}
+
+/// An UL NAS Transport carrying a PDU Session Establishment Request for PDU
+/// session 5.
+fn build_pdu_session_establishment_request() -> Vec<u8> {
+    [
+        &[0x7E, 0x00, 0x67, 0x01, 0x00, 0x06][..],
+        // PDU Session Establishment Request, with integrity protection maximum data rate
+        &[0x2E, 0x05, 0x01, 0xC1, 0xFF, 0xFF],
+        // PDU session ID
+        &[0x12, 0x05],
+    ]
+    .concat()
+}
+
+#[test]
+fn test_pdu_session_establishment() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::with_smf(crate::tests::test_smf(Default::default()));
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    ue.allowed_nssai = vec![nas::fgmm::MappedSnssai {
+        snssai: nas::fgmm::Snssai::default(),
+        hplmn: None,
+    }];
+    store.put_ue(ue);
+
+    let nas_pdu = integrity_protect(&kamf, 0, &build_pdu_session_establishment_request());
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(nas_pdu),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_PDUSessionResourceSetup(_),
+            ..
+        })
+    ));
+
+    let ue = store.get_ue(1).unwrap();
+    assert_eq!(ue.pdu_sessions.len(), 1);
+    let session = &ue.pdu_sessions[0];
+    assert_eq!(session.id, 5);
+    assert!(!session.active);
+    assert!(session.ul_tunnel.is_some());
+    assert_eq!(session.qos_flows.len(), 1);
+    assert_eq!(
+        session.sm_context.as_ref().unwrap().ue_ip_address,
+        std::net::Ipv4Addr::new(10, 45, 0, 2)
+    );
+    assert_eq!(session.snssai, nas::fgmm::Snssai::default());
+    // The Establishment Accept was sent in a protected DL NAS Transport
+    assert_eq!(ue.security.unwrap().dl_count, 1);
+}
+
+#[test]
+fn test_pdu_session_establishment_in_other_slice() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::with_smf(crate::tests::test_smf(Default::default()));
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    ue.allowed_nssai = vec![nas::fgmm::MappedSnssai {
+        snssai: nas::fgmm::Snssai::default(),
+        hplmn: None,
+    }];
+    store.put_ue(ue);
+
+    // The request is for the URLLC slice, which the UE is not allowed to use
+    let plain = [
+        build_pdu_session_establishment_request(),
+        vec![0x22, 0x01, 0x02],
+    ]
+    .concat();
+    let nas_pdu = integrity_protect(&kamf, 0, &plain);
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(nas_pdu),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+    assert!(store.get_ue(1).unwrap().pdu_sessions.is_empty());
+}
+
+#[test]
+fn test_pdu_session_establishment_without_smf() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+
+    let nas_pdu = integrity_protect(&kamf, 0, &build_pdu_session_establishment_request());
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(nas_pdu),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+    assert!(store.get_ue(1).unwrap().pdu_sessions.is_empty());
+}
+
+#[test]
+fn test_unhandled_message_is_answered_with_status() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+
+    // A message type that does not exist
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(integrity_protect(&kamf, 0, &[0x7E, 0x00, 0x99])),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+    assert_eq!(store.get_ue(1).unwrap().security.unwrap().dl_count, 1);
+
+    // A 5GMM Status from the UE is not answered
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(integrity_protect(&kamf, 1, &[0x7E, 0x00, 0x64, 0x61])),
+    );
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_undecodable_message_is_answered_with_status() {
+    let config = crate::config::CoreKubeConfig::default();
+    let store = Store::default();
+    let kamf = [0x42; 32];
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.security = nas::security::SecurityContext::new(1, kamf, vec![0xE0, 0xE0]);
+    store.put_ue(ue);
+
+    // An UL NAS Transport without its payload container
+    let result = handle_uplink_nas_transport(
+        &config,
+        &store,
+        &crate::tests::test_gnb(),
+        build_uplink_nas_transport(integrity_protect(&kamf, 0, &[0x7E, 0x00, 0x67])),
+    );
+    assert_eq!(result.len(), 1);
+    assert!(matches!(
+        result[0].ngap_pdu,
+        ngap::NGAP_PDU::InitiatingMessage(ngap::InitiatingMessage {
+            value: ngap::InitiatingMessageValue::Id_DownlinkNASTransport(_),
+            ..
+        })
+    ));
+    assert_eq!(store.get_ue(1).unwrap().security.unwrap().dl_count, 1);
+}
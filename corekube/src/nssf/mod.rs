@@ -0,0 +1,152 @@
+//! NSSF-lite: selection of the network slices a UE may use, see TS 23.501
+//! section 5.15.5.2.1.
+//!
+//! The S-NSSAIs a UE is subscribed to are taken from the configuration by
+//! SUPI, and are values of its HPLMN. For a roaming UE these are mapped to
+//! S-NSSAIs of this PLMN. Of those, the ones supported by the AMF make up
+//! the Configured NSSAI, and the ones also supported by the gNB in the
+//! tracking area of the UE can be allowed.
+
+use log::{debug, info};
+use nas::fgmm::{
+    MappedSnssai, RejectedSnssai, Snssai, REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_PLMN,
+    REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_REGISTRATION_AREA,
+};
+
+use crate::config::{CoreKubeConfig, SubscribedSnssai};
+
+#[cfg(test)]
+mod tests;
+
+/// The network slices of a UE, as given to it in the Registration Accept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NssaiSelection {
+    pub allowed: Vec<MappedSnssai>,
+    pub rejected: Vec<RejectedSnssai>,
+    /// Left empty when the UE already knows which S-NSSAIs it can request,
+    /// that is when all of the S-NSSAIs it requested are valid in the PLMN
+    pub configured: Vec<MappedSnssai>,
+}
+
+/// Select the network slices for a UE with the given SUPI that registers
+/// with the `requested` NSSAI. `ta_slices` are the S-NSSAIs supported in
+/// the tracking area of the UE, or `None` if they are not known.
+pub fn select_nssai(
+    config: &CoreKubeConfig,
+    supi: Option<&str>,
+    requested: Option<&[MappedSnssai]>,
+    ta_slices: Option<&[Snssai]>,
+) -> NssaiSelection {
+    let subscribed = subscribed_nssai(config, supi);
+    let roaming = supi.is_some_and(|supi| is_roaming(config, supi));
+
+    // The subscribed S-NSSAIs that can be used in this PLMN, along with
+    // whether they are default S-NSSAIs
+    let available: Vec<(MappedSnssai, bool)> = subscribed
+        .iter()
+        .filter_map(|subscribed| {
+            let mapped = if roaming {
+                let mapping = config
+                    .nssai
+                    .roaming_mapping
+                    .iter()
+                    .find(|mapping| mapping.hplmn == subscribed.snssai)?;
+                MappedSnssai {
+                    snssai: mapping.serving,
+                    hplmn: Some(subscribed.snssai),
+                }
+            } else {
+                MappedSnssai {
+                    snssai: subscribed.snssai,
+                    hplmn: None,
+                }
+            };
+            if !config.nssai.supported.contains(&mapped.snssai) {
+                return None;
+            }
+            Some((mapped, subscribed.default))
+        })
+        .collect();
+    let in_ta = |snssai: &Snssai| ta_slices.is_none_or(|slices| slices.contains(snssai));
+
+    let mut selection = NssaiSelection::default();
+    for requested in requested.unwrap_or_default() {
+        match available
+            .iter()
+            .find(|(mapped, _)| mapped.snssai == requested.snssai)
+        {
+            Some((mapped, _)) if in_ta(&mapped.snssai) => selection.allowed.push(*mapped),
+            Some(_) => selection.rejected.push(RejectedSnssai {
+                snssai: requested.snssai,
+                cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_REGISTRATION_AREA,
+            }),
+            None => selection.rejected.push(RejectedSnssai {
+                snssai: requested.snssai,
+                cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_PLMN,
+            }),
+        }
+    }
+
+    // Without any requested S-NSSAI that can be allowed, the UE gets the
+    // default S-NSSAIs of its subscription
+    if selection.allowed.is_empty() {
+        selection.allowed = available
+            .iter()
+            .filter(|(mapped, default)| *default && in_ta(&mapped.snssai))
+            .map(|(mapped, _)| *mapped)
+            .collect();
+    }
+
+    let requested_valid = requested.is_some_and(|requested| {
+        requested.iter().all(|requested| {
+            available
+                .iter()
+                .any(|(mapped, _)| mapped.snssai == requested.snssai)
+        })
+    });
+    if !requested_valid {
+        selection.configured = available.iter().map(|(mapped, _)| *mapped).collect();
+    }
+
+    debug!("Selected NSSAI {:?}", selection);
+    selection
+}
+
+/// Select the S-NSSAI of a new PDU session of a UE, which is the one the
+/// UE asked for if it is allowed, or else the first allowed S-NSSAI.
+/// Returns `None` if the UE asked for an S-NSSAI that it may not use.
+pub fn select_session_snssai(
+    allowed: &[MappedSnssai],
+    requested: Option<&MappedSnssai>,
+) -> Option<Snssai> {
+    match requested {
+        Some(requested) => {
+            let found = allowed
+                .iter()
+                .find(|allowed| allowed.snssai == requested.snssai);
+            if found.is_none() {
+                info!("S-NSSAI {:?} is not allowed", requested.snssai);
+            }
+            found.map(|allowed| allowed.snssai)
+        }
+        None => allowed.first().map(|allowed| allowed.snssai),
+    }
+}
+
+fn subscribed_nssai<'a>(config: &'a CoreKubeConfig, supi: Option<&str>) -> &'a [SubscribedSnssai] {
+    supi.and_then(|supi| config.nssai.subscriptions.get(supi))
+        .unwrap_or(&config.nssai.default_subscription)
+}
+
+/// Whether the HPLMN of an IMSI based SUPI is another PLMN than this one.
+fn is_roaming(config: &CoreKubeConfig, supi: &str) -> bool {
+    let Some(imsi) = supi.strip_prefix("imsi-") else {
+        return false;
+    };
+    let plmn = if config.mnc < 100 {
+        format!("{:03}{:02}", config.mcc, config.mnc)
+    } else {
+        format!("{:03}{:03}", config.mcc, config.mnc)
+    };
+    !imsi.starts_with(&plmn)
+}
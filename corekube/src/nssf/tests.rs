@@ -0,0 +1,161 @@
+use super::*;
+use crate::config::SnssaiMapping;
+
+const EMBB: Snssai = Snssai { sst: 1, sd: None };
+const URLLC: Snssai = Snssai { sst: 2, sd: None };
+const MIOT: Snssai = Snssai {
+    sst: 3,
+    sd: Some([0x00, 0x00, 0x01]),
+};
+
+/// An AMF serving eMBB and URLLC, with a subscriber to all three slices of
+/// which eMBB is the default.
+fn test_config() -> CoreKubeConfig {
+    let mut config = CoreKubeConfig::default();
+    config.nssai.supported = vec![EMBB, URLLC];
+    config.nssai.subscriptions.insert(
+        "imsi-208930000000001".to_string(),
+        vec![
+            SubscribedSnssai {
+                snssai: EMBB,
+                default: true,
+            },
+            SubscribedSnssai {
+                snssai: URLLC,
+                default: false,
+            },
+            SubscribedSnssai {
+                snssai: MIOT,
+                default: false,
+            },
+        ],
+    );
+    config
+}
+
+fn plain(snssai: Snssai) -> MappedSnssai {
+    MappedSnssai {
+        snssai,
+        hplmn: None,
+    }
+}
+
+#[test]
+fn test_requested_nssai() {
+    let config = test_config();
+
+    let selection = select_nssai(
+        &config,
+        Some("imsi-208930000000001"),
+        Some(&[plain(URLLC), plain(MIOT)]),
+        None,
+    );
+    assert_eq!(selection.allowed, vec![plain(URLLC)]);
+    assert_eq!(
+        selection.rejected,
+        vec![RejectedSnssai {
+            snssai: MIOT,
+            cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_PLMN,
+        }]
+    );
+    assert_eq!(selection.configured, vec![plain(EMBB), plain(URLLC)]);
+
+    // Nothing needs correcting if all requested S-NSSAIs can be allowed
+    let selection = select_nssai(
+        &config,
+        Some("imsi-208930000000001"),
+        Some(&[plain(URLLC)]),
+        None,
+    );
+    assert_eq!(selection.allowed, vec![plain(URLLC)]);
+    assert!(selection.rejected.is_empty());
+    assert!(selection.configured.is_empty());
+}
+
+#[test]
+fn test_default_nssai() {
+    let config = test_config();
+
+    let selection = select_nssai(&config, Some("imsi-208930000000001"), None, None);
+    assert_eq!(selection.allowed, vec![plain(EMBB)]);
+    assert!(selection.rejected.is_empty());
+    assert_eq!(selection.configured, vec![plain(EMBB), plain(URLLC)]);
+
+    // A UE without a subscription of its own gets the default subscription
+    let selection = select_nssai(&config, None, Some(&[plain(URLLC)]), None);
+    assert_eq!(selection.allowed, vec![plain(EMBB)]);
+    assert_eq!(selection.rejected.len(), 1);
+}
+
+#[test]
+fn test_tracking_area_slices() {
+    let config = test_config();
+
+    let selection = select_nssai(
+        &config,
+        Some("imsi-208930000000001"),
+        Some(&[plain(EMBB), plain(URLLC)]),
+        Some(&[EMBB]),
+    );
+    assert_eq!(selection.allowed, vec![plain(EMBB)]);
+    assert_eq!(
+        selection.rejected,
+        vec![RejectedSnssai {
+            snssai: URLLC,
+            cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_REGISTRATION_AREA,
+        }]
+    );
+    assert!(selection.configured.is_empty());
+
+    // No slice at all if the default one is not supported either
+    let selection = select_nssai(&config, Some("imsi-208930000000001"), None, Some(&[]));
+    assert!(selection.allowed.is_empty());
+}
+
+#[test]
+fn test_roaming() {
+    let mut config = test_config();
+    let hplmn_embb = Snssai {
+        sst: 1,
+        sd: Some([0x00, 0x00, 0x07]),
+    };
+    config.nssai.subscriptions.insert(
+        "imsi-001010000000001".to_string(),
+        vec![
+            SubscribedSnssai {
+                snssai: hplmn_embb,
+                default: true,
+            },
+            SubscribedSnssai {
+                snssai: URLLC,
+                default: false,
+            },
+        ],
+    );
+    config.nssai.roaming_mapping = vec![SnssaiMapping {
+        hplmn: hplmn_embb,
+        serving: EMBB,
+    }];
+
+    // URLLC of the HPLMN has no counterpart in this PLMN
+    let selection = select_nssai(&config, Some("imsi-001010000000001"), None, None);
+    let mapped = MappedSnssai {
+        snssai: EMBB,
+        hplmn: Some(hplmn_embb),
+    };
+    assert_eq!(selection.allowed, vec![mapped]);
+    assert_eq!(selection.configured, vec![mapped]);
+}
+
+#[test]
+fn test_select_session_snssai() {
+    let allowed = [plain(EMBB), plain(URLLC)];
+
+    assert_eq!(select_session_snssai(&allowed, None), Some(EMBB));
+    assert_eq!(
+        select_session_snssai(&allowed, Some(&plain(URLLC))),
+        Some(URLLC)
+    );
+    assert_eq!(select_session_snssai(&allowed, Some(&plain(MIOT))), None);
+    assert_eq!(select_session_snssai(&[], None), None);
+}
@@ -0,0 +1,92 @@
+//! Reloading the configuration while the worker runs.
+//!
+//! The configuration is kept in a [`ConfigHandle`], which every message
+//! loads once before it is handled. Swapping in a new configuration does
+//! not wait for the messages in flight, they finish with the configuration
+//! they started with. A reload is triggered by SIGHUP, or by a change to the
+//! modification time of the configuration file.
+
+use arc_swap::ArcSwap;
+use log::{debug, info, warn};
+use signal_hook::consts::SIGHUP;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::config::CoreKubeConfig;
+use crate::{ngap_handlers, store};
+
+#[cfg(test)]
+mod tests;
+
+/// How often the configuration file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The configuration shared by all threads, which can be swapped for a new
+/// one at any time.
+pub type ConfigHandle = ArcSwap<CoreKubeConfig>;
+
+/// Read the configuration file again and swap it in if it is valid. Returns
+/// the AMFConfigurationUpdates that tell the gNBs about the change, if they
+/// need to know. On error, the current configuration is kept.
+pub fn reload(
+    handle: &ConfigHandle,
+    store: &store::Store,
+    path: &Path,
+) -> Result<Vec<ngap_handlers::NGAPResponse>, String> {
+    let new = CoreKubeConfig::from_file(path)?;
+    let current = handle.load();
+    current.check_reloadable(&new)?;
+
+    let responses = if current.ng_configuration_differs(&new) {
+        ngap_handlers::update_amf_configuration(&new, store)
+    } else {
+        vec![]
+    };
+    handle.store(Arc::new(new));
+    Ok(responses)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reload the configuration from `path` whenever SIGHUP is received or the
+/// file changes, sending the resulting NGAP messages to the gNBs through
+/// `ngap_socket`.
+pub fn run(handle: &ConfigHandle, store: &store::Store, path: &Path, ngap_socket: &UdpSocket) {
+    let hangup = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGHUP, Arc::clone(&hangup))
+        .expect("couldn't register the SIGHUP handler");
+
+    let mut last_modified = modified(path);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let now_modified = modified(path);
+        if !hangup.swap(false, Ordering::Relaxed) && now_modified == last_modified {
+            continue;
+        }
+        last_modified = now_modified;
+
+        debug!("Reloading the configuration from {}", path.display());
+        match reload(handle, store, path) {
+            Ok(responses) => {
+                info!(
+                    "Reloaded the configuration from {}, updating {} gNB(s)",
+                    path.display(),
+                    responses.len()
+                );
+                let responses = crate::encode_responses(responses);
+                crate::send_responses(store, ngap_socket, None, responses);
+            }
+            Err(reason) => {
+                warn!("Keeping the current configuration: {}", reason);
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use super::*;
+use crate::config::{NasTimerConfig, OverloadAction, OverloadConfig, PagingStrategy, SmfConfig};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+/// Write a configuration file that is unique to the calling test.
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("corekube-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn setup() -> (ConfigHandle, store::Store) {
+    let store = store::Store::default();
+    store.put_gnb(crate::tests::test_target_gnb());
+    (ArcSwap::from_pointee(CoreKubeConfig::default()), store)
+}
+
+#[test]
+fn test_from_toml() {
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        amf_name = "amf1"
+        amf_set_id = 0x3FF
+        supported_nssai = [{ sst = 1 }, { sst = 2, sd = 0x000001 }]
+        paging_drx = 2
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.amf_name, "amf1");
+    assert!(config.amf_set_id.all());
+    assert_eq!(config.nssai.supported.len(), 2);
+    assert_eq!(config.nssai.supported[1].sd, Some([0x00, 0x00, 0x01]));
+    assert_eq!(config.paging_drx, Some(2));
+    // Settings that are left out keep their default
+    assert_eq!(config.relative_amf_capacity, 255);
+
+    assert!(CoreKubeConfig::from_toml("amf_set_id = 1024").is_err());
+    assert!(CoreKubeConfig::from_toml("amf_name = \"\"").is_err());
+    assert!(CoreKubeConfig::from_toml("supported_nssai = []").is_err());
+    let too_many = vec!["{ sst = 1 }"; 1025].join(", ");
+    assert!(CoreKubeConfig::from_toml(&format!("supported_nssai = [{}]", too_many)).is_err());
+    assert!(CoreKubeConfig::from_toml("unknown = 1").is_err());
+}
+
+#[test]
+fn test_subscribers_from_toml() {
+    // Test set 1 of TS 35.207, given once with OPc and once with OP
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        [[subscribers]]
+        supi = "imsi-208930000000001"
+        k = "465b5ce8b199b49faa5f0a2ee238a6bc"
+        opc = "cd63cb71954a9f4e48a5994e37a02baf"
+
+        [[subscribers]]
+        supi = "imsi-208930000000002"
+        k = "465b5ce8b199b49faa5f0a2ee238a6bc"
+        op = "cdc202d5123e20f62b6d676ac72cb318"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.subscribers.len(), 2);
+    assert_eq!(
+        config.subscribers["imsi-208930000000001"].opc,
+        config.subscribers["imsi-208930000000002"].opc
+    );
+
+    let subscriber = |supi: &str, keys: &str| {
+        CoreKubeConfig::from_toml(&format!(
+            "[[subscribers]]\nsupi = \"{}\"\nk = \"465b5ce8b199b49faa5f0a2ee238a6bc\"\n{}",
+            supi, keys
+        ))
+    };
+    let opc = "opc = \"cd63cb71954a9f4e48a5994e37a02baf\"";
+    assert!(subscriber("imsi-208930000000001", opc).is_ok());
+    assert!(subscriber("nai-user@example.com", opc).is_err());
+    assert!(subscriber("imsi-208930000000001", "").is_err());
+    assert!(subscriber("imsi-208930000000001", "opc = \"00\"").is_err());
+    assert!(subscriber(
+        "imsi-208930000000001",
+        "opc = \"cd63cb71954a9f4e48a5994e37a02baf\"\nop = \"cdc202d5123e20f62b6d676ac72cb318\""
+    )
+    .is_err());
+}
+
+#[test]
+fn test_nssai_from_toml() {
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        default_subscription = [{ sst = 1, default = true }, { sst = 2 }]
+
+        [[nssai_subscriptions]]
+        supi = "imsi-208930000000001"
+        snssais = [{ sst = 1, sd = 0x000001, default = true }]
+
+        [[roaming_mapping]]
+        hplmn = { sst = 1, sd = 0x000002 }
+        serving = { sst = 1 }
+        "#,
+    )
+    .unwrap();
+    let nssai = &config.nssai;
+    assert_eq!(nssai.default_subscription.len(), 2);
+    assert!(nssai.default_subscription[0].default);
+    assert!(!nssai.default_subscription[1].default);
+    let subscription = &nssai.subscriptions["imsi-208930000000001"];
+    assert_eq!(subscription[0].snssai.sd, Some([0x00, 0x00, 0x01]));
+    assert!(subscription[0].default);
+    assert_eq!(nssai.roaming_mapping[0].hplmn.sd, Some([0x00, 0x00, 0x02]));
+    assert_eq!(nssai.roaming_mapping[0].serving.sd, None);
+
+    assert!(
+        CoreKubeConfig::from_toml("default_subscription = [{ sst = 1, sd = 0x1000000 }]").is_err()
+    );
+    assert!(CoreKubeConfig::from_toml("[[roaming_mapping]]\nhplmn = { sst = 1 }").is_err());
+}
+
+#[test]
+fn test_smf_from_toml() {
+    assert!(CoreKubeConfig::from_toml("").unwrap().smf.is_none());
+
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        [smf]
+        pfcp_bind_addr = "10.0.0.1"
+        upf_addr = "10.0.0.2:8805"
+
+        [[smf.dnns]]
+        dnn = "internet"
+        ue_pool_start = "10.45.0.2"
+        ue_pool_size = 254
+        five_qi = 7
+        "#,
+    )
+    .unwrap();
+    let smf = config.smf.unwrap();
+    assert_eq!(smf.pfcp_bind_addr, Ipv4Addr::new(10, 0, 0, 1));
+    assert_eq!(smf.pfcp_bind_port, SmfConfig::default().pfcp_bind_port);
+    assert_eq!(smf.upf_addr, "10.0.0.2:8805".parse().unwrap());
+    assert_eq!(smf.dnns.len(), 1);
+    assert_eq!(smf.dnns[0].ue_pool_start, Ipv4Addr::new(10, 45, 0, 2));
+    assert_eq!(smf.dnns[0].five_qi, 7);
+    assert_eq!(
+        smf.dnns[0].arp_priority_level,
+        SmfConfig::default().dnns[0].arp_priority_level
+    );
+
+    // An empty section enables the SMF-lite with its defaults
+    assert!(CoreKubeConfig::from_toml("[smf]").unwrap().smf.is_some());
+    assert!(CoreKubeConfig::from_toml("[smf]\ndnns = []").is_err());
+    assert!(CoreKubeConfig::from_toml(
+        "[[smf.dnns]]\ndnn = \"internet\"\nue_pool_start = \"10.45.0.2\"\nue_pool_size = 254\narp_priority_level = 16"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_paging_strategy_from_toml() {
+    let config = CoreKubeConfig::from_toml("paging_strategy = \"last_gnb_first\"").unwrap();
+    assert_eq!(config.paging_strategy, PagingStrategy::LastGNBFirst);
+    let config = CoreKubeConfig::from_toml("paging_strategy = \"registration_area\"").unwrap();
+    assert_eq!(config.paging_strategy, PagingStrategy::RegistrationArea);
+    assert!(CoreKubeConfig::from_toml("paging_strategy = \"everywhere\"").is_err());
+}
+
+#[test]
+fn test_nas_timers_from_toml() {
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        [nas_timers]
+        t3550 = 3
+        implicit_deregistration = 3600
+        max_retransmissions = 2
+        poll_interval_ms = 50
+        "#,
+    )
+    .unwrap();
+    let timers = &config.nas_timers;
+    let defaults = NasTimerConfig::default();
+    assert_eq!(timers.t3550, Duration::from_secs(3));
+    assert_eq!(timers.t3560, defaults.t3560);
+    assert_eq!(timers.implicit_deregistration, Duration::from_secs(3600));
+    assert_eq!(timers.max_retransmissions, 2);
+    assert_eq!(timers.poll_interval, Duration::from_millis(50));
+
+    assert!(CoreKubeConfig::from_toml("[nas_timers]\npoll_interval_ms = 0").is_err());
+    assert!(CoreKubeConfig::from_toml("[nas_timers]\nt3550_secs = 3").is_err());
+}
+
+#[test]
+fn test_overload_from_toml() {
+    let config = CoreKubeConfig::from_toml(
+        r#"
+        [overload]
+        start_queue_depth = 2000
+        stop_queue_depth = 1000
+        start_latency_ms = 300
+        action = "reject_signalling"
+        traffic_load_reduction = 30
+        "#,
+    )
+    .unwrap();
+    let overload = &config.overload;
+    assert_eq!(overload.start_queue_depth, 2000);
+    assert_eq!(overload.stop_queue_depth, 1000);
+    assert_eq!(overload.start_latency, Duration::from_millis(300));
+    assert_eq!(
+        overload.stop_latency,
+        OverloadConfig::default().stop_latency
+    );
+    assert_eq!(overload.action, OverloadAction::RejectSignalling);
+    assert_eq!(overload.traffic_load_reduction, 30);
+
+    assert!(CoreKubeConfig::from_toml("[overload]\naction = \"drop_everything\"").is_err());
+    assert!(CoreKubeConfig::from_toml("[overload]\ntraffic_load_reduction = 100").is_err());
+    assert!(CoreKubeConfig::from_toml("[overload]\ncheck_interval_ms = 0").is_err());
+}
+
+#[test]
+fn test_reload_updates_gnbs() {
+    let (handle, store) = setup();
+    let path = write_config("reload-updates-gnbs", "relative_amf_capacity = 10");
+
+    let responses = reload(&handle, &store, &path).unwrap();
+    assert_eq!(handle.load().relative_amf_capacity, 10);
+    assert_eq!(responses.len(), 1);
+    assert_eq!(
+        responses[0].destination,
+        ngap_handlers::Destination::Association(crate::tests::test_target_gnb().address)
+    );
+
+    // Nothing the gNBs know about changed
+    std::fs::write(&path, "relative_amf_capacity = 10\npaging_drx = 1").unwrap();
+    assert!(reload(&handle, &store, &path).unwrap().is_empty());
+    assert_eq!(handle.load().paging_drx, Some(1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_reload_keeps_config_on_error() {
+    let (handle, store) = setup();
+    let path = write_config("reload-keeps-config", "relative_amf_capacity = 256");
+    assert!(reload(&handle, &store, &path).is_err());
+
+    // Loaded before the reload, like a message in flight
+    let in_flight = handle.load_full();
+    std::fs::write(&path, "bind_port = 9000\nrelative_amf_capacity = 10").unwrap();
+    assert!(reload(&handle, &store, &path).is_err());
+    assert_eq!(handle.load().relative_amf_capacity, 255);
+    assert!(Arc::ptr_eq(&in_flight, &handle.load_full()));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,376 @@
+//! SMF-lite: the parts of an SMF needed to set up default PDU sessions.
+//!
+//! UE IP addresses are allocated from per-DNN pools, every session gets a
+//! single default QoS flow, and the user plane is set up over PFCP with one
+//! uplink and one downlink PDR in the UPF.
+
+use log::{debug, info, trace, warn};
+use pfcp::ie::{self, FTeid, Ie};
+use pfcp::message::*;
+use pfcp::Message;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::config::{DnnConfig, SmfConfig};
+use crate::store::{GTPTunnel, QosFlow, SMContext};
+
+#[cfg(test)]
+mod tests;
+
+/// QFI of the default QoS flow of every session.
+const DEFAULT_QFI: u8 = 1;
+
+const UPLINK_PDR_ID: u16 = 1;
+const DOWNLINK_PDR_ID: u16 = 2;
+const UPLINK_FAR_ID: u32 = 1;
+const DOWNLINK_FAR_ID: u32 = 2;
+const SESSION_QER_ID: u32 = 1;
+
+/// Precedence of the PDRs, there is only one per direction.
+const PDR_PRECEDENCE: u32 = 255;
+
+#[derive(Debug)]
+pub enum SmfError {
+    UnknownDnn,
+    PoolExhausted,
+    Pfcp(io::Error),
+    /// The UPF answered with a PFCP cause other than Request accepted
+    Rejected(u8),
+}
+
+impl From<io::Error> for SmfError {
+    fn from(e: io::Error) -> Self {
+        SmfError::Pfcp(e)
+    }
+}
+
+/// A PDU session whose user plane has been set up in the UPF.
+#[derive(Debug)]
+pub struct EstablishedSession {
+    pub sm_context: SMContext,
+    pub ul_tunnel: GTPTunnel,
+    pub qos_flow: QosFlow,
+}
+
+/// Addresses of a DNN pool, stored as offsets from its first address.
+struct IpPool {
+    start: u32,
+    size: u32,
+    allocated: BTreeSet<u32>,
+}
+
+impl IpPool {
+    fn allocate(&mut self) -> Option<Ipv4Addr> {
+        let offset = (0..self.size).find(|o| !self.allocated.contains(o))?;
+        self.allocated.insert(offset);
+        Some(Ipv4Addr::from(self.start + offset))
+    }
+
+    fn release(&mut self, address: Ipv4Addr) {
+        self.allocated
+            .remove(&u32::from(address).wrapping_sub(self.start));
+    }
+}
+
+pub struct Smf {
+    pfcp: pfcp::client::Client,
+    node_id: Ipv4Addr,
+    dnns: Vec<DnnConfig>,
+    pools: Mutex<HashMap<String, IpPool>>,
+    next_cp_seid: AtomicU64,
+}
+
+impl Smf {
+    /// Bind the N4 socket and set up the PFCP association with the UPF.
+    pub fn start(config: &SmfConfig) -> io::Result<Smf> {
+        let pfcp = pfcp::client::Client::connect(
+            (config.pfcp_bind_addr, config.pfcp_bind_port),
+            config.upf_addr,
+        )?;
+        let smf = Smf {
+            pfcp,
+            node_id: config.pfcp_bind_addr,
+            dnns: config.dnns.clone(),
+            pools: Mutex::new(
+                config
+                    .dnns
+                    .iter()
+                    .map(|dnn| {
+                        let pool = IpPool {
+                            start: u32::from(dnn.ue_pool_start),
+                            size: dnn.ue_pool_size,
+                            allocated: BTreeSet::new(),
+                        };
+                        (dnn.dnn.clone(), pool)
+                    })
+                    .collect(),
+            ),
+            next_cp_seid: AtomicU64::new(1),
+        };
+
+        let response = smf.pfcp.request(Message::new(
+            ASSOCIATION_SETUP_REQUEST,
+            None,
+            vec![ie::node_id(smf.node_id), ie::recovery_time_stamp(0)],
+        ))?;
+        if let Err(SmfError::Rejected(cause)) = check_cause(&response) {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("PFCP association rejected with cause {}", cause),
+            ));
+        }
+        info!("PFCP association set up with {}", config.upf_addr);
+
+        Ok(smf)
+    }
+
+    /// Allocate a UE IP address and set up the user plane of a new session
+    /// on the given DNN, or the default DNN if none is given.
+    pub fn establish_session(
+        &self,
+        dnn: Option<&str>,
+        session_ambr_uplink: u64,
+        session_ambr_downlink: u64,
+    ) -> Result<EstablishedSession, SmfError> {
+        trace!("Establishing PFCP session for DNN {:?}", dnn);
+
+        let dnn_config = match dnn {
+            Some(dnn) => self.dnns.iter().find(|d| d.dnn == dnn),
+            None => self.dnns.first(),
+        }
+        .ok_or(SmfError::UnknownDnn)?;
+        let ue_ip_address = self
+            .pools
+            .lock()
+            .expect("IP pool lock poisoned")
+            .get_mut(&dnn_config.dnn)
+            .ok_or(SmfError::UnknownDnn)?
+            .allocate()
+            .ok_or(SmfError::PoolExhausted)?;
+        let qos_flow = QosFlow {
+            qfi: DEFAULT_QFI,
+            five_qi: dnn_config.five_qi,
+            arp_priority_level: dnn_config.arp_priority_level,
+        };
+
+        let request = self.build_session_establishment_request(
+            ue_ip_address,
+            &qos_flow,
+            session_ambr_uplink,
+            session_ambr_downlink,
+        );
+        let result = self
+            .pfcp
+            .request(request)
+            .map_err(SmfError::from)
+            .and_then(|response| parse_session_establishment_response(&response));
+        let (up_seid, ul_tunnel) = match result {
+            Ok(established) => established,
+            Err(e) => {
+                self.release_address(&dnn_config.dnn, ue_ip_address);
+                return Err(e);
+            }
+        };
+        debug!(
+            "PFCP session {} established for UE IP address {}",
+            up_seid, ue_ip_address
+        );
+
+        Ok(EstablishedSession {
+            sm_context: SMContext {
+                dnn: dnn_config.dnn.clone(),
+                ue_ip_address,
+                up_seid,
+            },
+            ul_tunnel,
+            qos_flow,
+        })
+    }
+
+    /// Start forwarding downlink traffic to the NG-RAN tunnel endpoint.
+    pub fn update_downlink(
+        &self,
+        sm_context: &SMContext,
+        dl_tunnel: &GTPTunnel,
+    ) -> Result<(), SmfError> {
+        trace!("Updating downlink of PFCP session {}", sm_context.up_seid);
+
+        let Ok(address) = <[u8; 4]>::try_from(dl_tunnel.address.as_slice()) else {
+            warn!("Only IPv4 tunnel endpoints are supported");
+            return Err(SmfError::Rejected(ie::CAUSE_REQUEST_REJECTED));
+        };
+        let update_far = Ie::grouped(
+            ie::UPDATE_FAR,
+            vec![
+                ie::far_id(DOWNLINK_FAR_ID),
+                ie::apply_action(ie::APPLY_ACTION_FORW),
+                Ie::grouped(
+                    ie::UPDATE_FORWARDING_PARAMETERS,
+                    vec![
+                        ie::destination_interface(ie::INTERFACE_ACCESS),
+                        ie::outer_header_creation(dl_tunnel.teid, address.into()),
+                    ],
+                ),
+            ],
+        );
+
+        let response = self.pfcp.request(Message::new(
+            SESSION_MODIFICATION_REQUEST,
+            Some(sm_context.up_seid),
+            vec![update_far],
+        ))?;
+        check_cause(&response)
+    }
+
+    /// Delete the user plane of a session and return its UE IP address to
+    /// the pool.
+    pub fn release_session(&self, sm_context: &SMContext) -> Result<(), SmfError> {
+        trace!("Releasing PFCP session {}", sm_context.up_seid);
+
+        // The address is freed even if the UPF has already lost the session
+        self.release_address(&sm_context.dnn, sm_context.ue_ip_address);
+        let response = self.pfcp.request(Message::new(
+            SESSION_DELETION_REQUEST,
+            Some(sm_context.up_seid),
+            vec![],
+        ))?;
+        check_cause(&response)
+    }
+
+    fn release_address(&self, dnn: &str, address: Ipv4Addr) {
+        let mut pools = self.pools.lock().expect("IP pool lock poisoned");
+        if let Some(pool) = pools.get_mut(dnn) {
+            pool.release(address);
+        }
+    }
+
+    fn build_session_establishment_request(
+        &self,
+        ue_ip_address: Ipv4Addr,
+        qos_flow: &QosFlow,
+        session_ambr_uplink: u64,
+        session_ambr_downlink: u64,
+    ) -> Message {
+        let cp_seid = self.next_cp_seid.fetch_add(1, Ordering::Relaxed);
+
+        // Uplink traffic arrives over N3 in a tunnel the UPF allocates
+        let uplink_pdr = Ie::grouped(
+            ie::CREATE_PDR,
+            vec![
+                ie::pdr_id(UPLINK_PDR_ID),
+                ie::precedence(PDR_PRECEDENCE),
+                Ie::grouped(
+                    ie::PDI,
+                    vec![
+                        ie::source_interface(ie::INTERFACE_ACCESS),
+                        ie::f_teid(FTeid::Choose),
+                        ie::ue_ip_address(ue_ip_address, false),
+                        ie::qfi(qos_flow.qfi),
+                    ],
+                ),
+                ie::outer_header_removal(),
+                ie::far_id(UPLINK_FAR_ID),
+                ie::qer_id(SESSION_QER_ID),
+            ],
+        );
+        let downlink_pdr = Ie::grouped(
+            ie::CREATE_PDR,
+            vec![
+                ie::pdr_id(DOWNLINK_PDR_ID),
+                ie::precedence(PDR_PRECEDENCE),
+                Ie::grouped(
+                    ie::PDI,
+                    vec![
+                        ie::source_interface(ie::INTERFACE_CORE),
+                        ie::ue_ip_address(ue_ip_address, true),
+                    ],
+                ),
+                ie::far_id(DOWNLINK_FAR_ID),
+                ie::qer_id(SESSION_QER_ID),
+            ],
+        );
+        let uplink_far = Ie::grouped(
+            ie::CREATE_FAR,
+            vec![
+                ie::far_id(UPLINK_FAR_ID),
+                ie::apply_action(ie::APPLY_ACTION_FORW),
+                Ie::grouped(
+                    ie::FORWARDING_PARAMETERS,
+                    vec![ie::destination_interface(ie::INTERFACE_CORE)],
+                ),
+            ],
+        );
+        // Downlink traffic is buffered until the NG-RAN tunnel is known
+        let downlink_far = Ie::grouped(
+            ie::CREATE_FAR,
+            vec![
+                ie::far_id(DOWNLINK_FAR_ID),
+                ie::apply_action(ie::APPLY_ACTION_BUFF),
+            ],
+        );
+        let qer = Ie::grouped(
+            ie::CREATE_QER,
+            vec![
+                ie::qer_id(SESSION_QER_ID),
+                ie::gate_status_open(),
+                ie::mbr(session_ambr_uplink / 1000, session_ambr_downlink / 1000),
+                ie::qfi(qos_flow.qfi),
+            ],
+        );
+
+        Message::new(
+            SESSION_ESTABLISHMENT_REQUEST,
+            Some(0),
+            vec![
+                ie::node_id(self.node_id),
+                ie::f_seid(cp_seid, self.node_id),
+                uplink_pdr,
+                downlink_pdr,
+                uplink_far,
+                downlink_far,
+                qer,
+            ],
+        )
+    }
+}
+
+fn check_cause(response: &Message) -> Result<(), SmfError> {
+    match response.find(ie::CAUSE).and_then(ie::parse_cause) {
+        Some(ie::CAUSE_REQUEST_ACCEPTED) => Ok(()),
+        Some(cause) => Err(SmfError::Rejected(cause)),
+        None => Err(SmfError::Rejected(ie::CAUSE_MANDATORY_IE_MISSING)),
+    }
+}
+
+/// Get the UP SEID and the uplink tunnel the UPF allocated.
+fn parse_session_establishment_response(response: &Message) -> Result<(u64, GTPTunnel), SmfError> {
+    check_cause(response)?;
+    let missing = SmfError::Rejected(ie::CAUSE_MANDATORY_IE_MISSING);
+
+    let Some((up_seid, _)) = response.find(ie::F_SEID).and_then(ie::parse_f_seid) else {
+        return Err(missing);
+    };
+    let ul_f_teid = response
+        .ies
+        .iter()
+        .filter(|i| i.ie_type == ie::CREATED_PDR)
+        .filter_map(Ie::children)
+        .find(|created_pdr| {
+            ie::find(created_pdr, ie::PDR_ID).and_then(ie::parse_pdr_id) == Some(UPLINK_PDR_ID)
+        })
+        .and_then(|created_pdr| ie::find(&created_pdr, ie::F_TEID).and_then(ie::parse_f_teid));
+    let Some(FTeid::Assigned { teid, address }) = ul_f_teid else {
+        return Err(missing);
+    };
+
+    Ok((
+        up_seid,
+        GTPTunnel {
+            address: address.octets().to_vec(),
+            teid,
+        },
+    ))
+}
@@ -0,0 +1,125 @@
+use super::*;
+use crate::config::SmfConfig;
+
+fn single_address_config() -> SmfConfig {
+    let mut config = SmfConfig::default();
+    config.dnns[0].ue_pool_size = 1;
+    config
+}
+
+#[test]
+fn test_establish_session() {
+    let smf = crate::tests::test_smf(SmfConfig::default());
+
+    let session = smf
+        .establish_session(None, 1_000_000_000, 2_000_000_000)
+        .unwrap();
+    assert_eq!(session.sm_context.dnn, "internet");
+    assert_eq!(
+        session.sm_context.ue_ip_address,
+        Ipv4Addr::new(10, 45, 0, 2)
+    );
+    assert_eq!(session.ul_tunnel.address, vec![127, 0, 0, 1]);
+    assert_eq!(
+        session.qos_flow,
+        QosFlow {
+            qfi: DEFAULT_QFI,
+            five_qi: 9,
+            arp_priority_level: 8,
+        }
+    );
+
+    // The next session gets the next address and its own tunnel
+    let other = smf
+        .establish_session(Some("internet"), 1_000_000_000, 2_000_000_000)
+        .unwrap();
+    assert_eq!(other.sm_context.ue_ip_address, Ipv4Addr::new(10, 45, 0, 3));
+    assert_ne!(other.ul_tunnel.teid, session.ul_tunnel.teid);
+    assert_ne!(other.sm_context.up_seid, session.sm_context.up_seid);
+}
+
+#[test]
+fn test_unknown_dnn() {
+    let smf = crate::tests::test_smf(SmfConfig::default());
+
+    assert!(matches!(
+        smf.establish_session(Some("ims"), 1_000_000_000, 1_000_000_000),
+        Err(SmfError::UnknownDnn)
+    ));
+}
+
+#[test]
+fn test_update_downlink() {
+    let smf = crate::tests::test_smf(SmfConfig::default());
+    let session = smf
+        .establish_session(None, 1_000_000_000, 1_000_000_000)
+        .unwrap();
+
+    let dl_tunnel = GTPTunnel {
+        address: vec![10, 0, 0, 1],
+        teid: 0x1234,
+    };
+    smf.update_downlink(&session.sm_context, &dl_tunnel)
+        .unwrap();
+
+    let unknown = SMContext {
+        up_seid: 0xDEAD,
+        ..session.sm_context
+    };
+    assert!(matches!(
+        smf.update_downlink(&unknown, &dl_tunnel),
+        Err(SmfError::Rejected(ie::CAUSE_SESSION_CONTEXT_NOT_FOUND))
+    ));
+}
+
+#[test]
+fn test_release_session_frees_address() {
+    let smf = crate::tests::test_smf(single_address_config());
+    let session = smf
+        .establish_session(None, 1_000_000_000, 1_000_000_000)
+        .unwrap();
+
+    assert!(matches!(
+        smf.establish_session(None, 1_000_000_000, 1_000_000_000),
+        Err(SmfError::PoolExhausted)
+    ));
+
+    smf.release_session(&session.sm_context).unwrap();
+    let session = smf
+        .establish_session(None, 1_000_000_000, 1_000_000_000)
+        .unwrap();
+    assert_eq!(
+        session.sm_context.ue_ip_address,
+        Ipv4Addr::new(10, 45, 0, 2)
+    );
+}
+
+#[test]
+fn test_ip_pool() {
+    let mut pool = IpPool {
+        start: u32::from(Ipv4Addr::new(10, 0, 0, 1)),
+        size: 2,
+        allocated: BTreeSet::new(),
+    };
+    assert_eq!(pool.allocate(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+    assert_eq!(pool.allocate(), Some(Ipv4Addr::new(10, 0, 0, 2)));
+    assert_eq!(pool.allocate(), None);
+
+    pool.release(Ipv4Addr::new(10, 0, 0, 1));
+    // Addresses outside the pool are ignored
+    pool.release(Ipv4Addr::new(192, 168, 0, 1));
+    assert_eq!(pool.allocate(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+}
+
+#[test]
+fn test_ip_pool_must_fit() {
+    let mut config = crate::config::CoreKubeConfig::default();
+    let mut smf_config = SmfConfig::default();
+    smf_config.dnns[0].ue_pool_start = Ipv4Addr::new(255, 255, 255, 0);
+    smf_config.dnns[0].ue_pool_size = 256;
+    config.smf = Some(smf_config);
+    assert!(config.validate().is_ok());
+
+    config.smf.as_mut().unwrap().dnns[0].ue_pool_size = 257;
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,518 @@
+use bitvec::prelude::*;
+use nas::fgmm::{MappedSnssai, Snssai};
+use std::collections::{BTreeSet, HashMap};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::load::LoadMonitor;
+use crate::smf::Smf;
+
+#[cfg(test)]
+mod tests;
+
+/// Identifies a gNB by the frontend it is connected through and the
+/// frontend ID prepended to every message from that association.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GNBAddress {
+    pub frontend: SocketAddr,
+    pub frontend_id: [u8; 4],
+}
+
+/// Registration management state of a UE, see TS 23.501 section 5.3.2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RMState {
+    Registered,
+    Deregistered,
+}
+
+/// Connection management state of a UE, see TS 23.501 section 5.3.3.2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CMState {
+    Idle,
+    Connected,
+}
+
+/// A 5GMM timer the AMF runs for a UE, see TS 24.501 section 10.2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NasTimer {
+    /// Waits for the Registration Complete after a Registration Accept
+    T3550,
+    /// Waits for the answer to an Authentication Request or a Security Mode
+    /// Command
+    T3560,
+    /// Waits for the Identity Response
+    T3570,
+    /// Runs while the UE is in CM-IDLE, until it has to be heard from again
+    MobileReachable,
+    /// Runs after the mobile reachable timer expired, until the UE is
+    /// implicitly de-registered
+    ImplicitDeregistration,
+}
+
+impl NasTimer {
+    /// The timer that guards a downlink NAS message of the given type, if the
+    /// message is retransmitted until the UE answers it.
+    pub fn guarding(message_type: nas::MobilityMessageIdentifier) -> Option<NasTimer> {
+        use nas::MobilityMessageIdentifier::*;
+        match message_type {
+            REGISTRATION_ACCEPT => Some(NasTimer::T3550),
+            AUTHENTICATION_REQUEST | SECURITY_MODE_COMMAND => Some(NasTimer::T3560),
+            IDENTITY_REQUEST => Some(NasTimer::T3570),
+            _ => None,
+        }
+    }
+
+    /// The timer that an uplink NAS message of the given type stops, if it
+    /// answers a retransmitted message.
+    pub fn answered_by(message_type: nas::MobilityMessageIdentifier) -> Option<NasTimer> {
+        use nas::MobilityMessageIdentifier::*;
+        match message_type {
+            REGISTRATION_COMPLETE => Some(NasTimer::T3550),
+            AUTHENTICATION_RESPONSE
+            | AUTHENTICATION_FAILURE
+            | SECURITY_MODE_COMPLETE
+            | SECURITY_MODE_REJECT => Some(NasTimer::T3560),
+            IDENTITY_RESPONSE => Some(NasTimer::T3570),
+            _ => None,
+        }
+    }
+}
+
+/// A GTP-U tunnel endpoint on the N3 interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GTPTunnel {
+    /// IPv4 or IPv6 address of the endpoint
+    pub address: Vec<u8>,
+    pub teid: u32,
+}
+
+/// A QoS flow of a PDU session, using a standardised 5QI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QosFlow {
+    pub qfi: u8,
+    pub five_qi: u8,
+    pub arp_priority_level: u8,
+}
+
+/// Session management state of a PDU session set up through the SMF-lite.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SMContext {
+    pub dnn: String,
+    pub ue_ip_address: Ipv4Addr,
+    /// SEID of the PFCP session in the UPF
+    pub up_seid: u64,
+}
+
+/// A PDU session known for a UE, and whether its NG-RAN resources are set up.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PDUSession {
+    pub id: u8,
+    pub active: bool,
+    /// The network slice the session was established in
+    pub snssai: Snssai,
+    /// UPF endpoint that the NG-RAN sends uplink traffic to
+    pub ul_tunnel: Option<GTPTunnel>,
+    /// NG-RAN endpoint that the UPF sends downlink traffic to
+    pub dl_tunnel: Option<GTPTunnel>,
+    pub qos_flows: Vec<QosFlow>,
+    pub sm_context: Option<SMContext>,
+}
+
+/// An N2 handover of a UE that is being prepared or executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Handover {
+    pub target: GNBAddress,
+    /// RAN_UE_NGAP_ID allocated by the target, once it acknowledged
+    pub target_ran_ue_ngap_id: Option<u32>,
+    /// NG-RAN endpoints at the target for each admitted PDU session
+    pub dl_tunnels: Vec<(u8, GTPTunnel)>,
+}
+
+/// A tracking area identity.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TAI {
+    pub plmn_identity: Vec<u8>,
+    pub tac: Vec<u8>,
+}
+
+/// An NR cell global identity.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NRCGI {
+    pub plmn_identity: Vec<u8>,
+    pub nr_cell_identity: BitVec<u8, Msb0>,
+}
+
+/// State kept for each UE across messages.
+#[derive(Clone, Debug)]
+pub struct UEContext {
+    pub amf_ue_ngap_id: u64,
+    pub ran_ue_ngap_id: u32,
+    pub gnb: GNBAddress,
+    pub rm_state: RMState,
+    pub cm_state: CMState,
+    pub as_context_established: bool,
+    /// SUPI of the UE, once it is known
+    pub supi: Option<String>,
+    /// S-NSSAIs the UE may use in its registration area
+    pub allowed_nssai: Vec<MappedSnssai>,
+    pub pdu_sessions: Vec<PDUSession>,
+    pub tmsi: Option<u32>,
+    pub security: Option<nas::security::SecurityContext>,
+    /// Cells the NG-RAN recommended for paging when the UE was released
+    pub recommended_cells_for_paging: Vec<NRCGI>,
+    pub handover: Option<Handover>,
+    /// Tracking area the NG-RAN last reported the UE in
+    pub tai: Option<TAI>,
+    /// Tracking areas the UE is registered in, where it is paged
+    pub registration_area: Vec<TAI>,
+    /// Paging messages sent since the UE was last heard from
+    pub paging_attempts: u8,
+    /// Plain NAS message that is retransmitted while the UE does not answer
+    /// it, along with the retransmissions so far
+    pub last_dl_nas: Option<Vec<u8>>,
+    pub nas_retransmissions: u8,
+    /// The Registration Request of a UE that is being identified or
+    /// authenticated, which is answered once NAS security is in place
+    pub pending_registration: Option<nas::fgmm::RegistrationRequest>,
+    /// The 5G AKA challenge sent to the UE, until it answers it
+    pub authentication: Option<nas::aka::AuthenticationVector>,
+}
+
+impl UEContext {
+    /// Create a context for a UE that has just connected through `gnb`.
+    pub fn new(amf_ue_ngap_id: u64, ran_ue_ngap_id: u32, gnb: GNBAddress) -> Self {
+        UEContext {
+            amf_ue_ngap_id,
+            ran_ue_ngap_id,
+            gnb,
+            rm_state: RMState::Registered,
+            cm_state: CMState::Connected,
+            as_context_established: false,
+            supi: None,
+            allowed_nssai: vec![],
+            pdu_sessions: vec![],
+            tmsi: None,
+            security: None,
+            recommended_cells_for_paging: vec![],
+            handover: None,
+            tai: None,
+            registration_area: vec![],
+            paging_attempts: 0,
+            last_dl_nas: None,
+            nas_retransmissions: 0,
+            pending_registration: None,
+            authentication: None,
+        }
+    }
+
+    /// Get a PDU session of the UE, adding it if it was not known yet.
+    pub fn pdu_session_mut(&mut self, id: u8) -> &mut PDUSession {
+        let index = match self.pdu_sessions.iter().position(|s| s.id == id) {
+            Some(index) => index,
+            None => {
+                self.pdu_sessions.push(PDUSession {
+                    id,
+                    ..Default::default()
+                });
+                self.pdu_sessions.len() - 1
+            }
+        };
+        &mut self.pdu_sessions[index]
+    }
+
+    /// Mark the NG-RAN resources of a PDU session as (de)activated, adding
+    /// the session if it was not known yet.
+    pub fn set_pdu_session_active(&mut self, id: u8, active: bool) {
+        self.pdu_session_mut(id).active = active;
+    }
+
+    /// The S-NSSAI of a PDU session of the UE.
+    pub fn pdu_session_snssai(&self, id: u8) -> Snssai {
+        self.pdu_sessions
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| s.snssai)
+            .unwrap_or_default()
+    }
+
+    /// Forget a PDU session entirely, e.g. once its resources are released.
+    pub fn remove_pdu_session(&mut self, id: u8) -> Option<PDUSession> {
+        let index = self.pdu_sessions.iter().position(|s| s.id == id)?;
+        Some(self.pdu_sessions.remove(index))
+    }
+}
+
+/// A tracking area supported by a gNB, with its broadcast PLMNs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SupportedTA {
+    pub tac: Vec<u8>,
+    pub plmns: Vec<Vec<u8>>,
+    /// S-NSSAIs supported in the tracking area, each with the broadcast PLMN
+    /// it is supported for
+    pub slices: Vec<(Vec<u8>, Snssai)>,
+}
+
+/// State kept for each gNB that has completed NG Setup.
+#[derive(Clone, Debug)]
+pub struct GNBContext {
+    pub address: GNBAddress,
+    pub plmn_identity: Vec<u8>,
+    pub gnb_id: BitVec<u8, Msb0>,
+    pub name: Option<String>,
+    pub supported_tas: Vec<SupportedTA>,
+    pub paging_drx: u8,
+}
+
+impl GNBContext {
+    /// Whether the gNB broadcasts the given tracking area.
+    pub fn serves(&self, tai: &TAI) -> bool {
+        self.supported_tas
+            .iter()
+            .any(|ta| ta.tac == tai.tac && ta.plmns.contains(&tai.plmn_identity))
+    }
+
+    /// The S-NSSAIs the gNB supports in the given tracking area.
+    pub fn slices_in(&self, tai: &TAI) -> Vec<Snssai> {
+        self.supported_tas
+            .iter()
+            .filter(|ta| ta.tac == tai.tac)
+            .flat_map(|ta| &ta.slices)
+            .filter(|(plmn, _)| *plmn == tai.plmn_identity)
+            .map(|(_, snssai)| *snssai)
+            .collect()
+    }
+}
+
+/// Running NAS timers, ordered by due time like a sorted set keyed by UE and
+/// timer.
+#[derive(Default)]
+struct TimerSet {
+    by_due: BTreeSet<(u64, u64, NasTimer)>,
+    due: HashMap<(u64, NasTimer), u64>,
+}
+
+/// In-memory store of UE and gNB contexts, shared between worker threads.
+///
+/// Contexts are handed out as copies, so a handler works on a snapshot and
+/// writes it back when done, in the same way it would with an external
+/// database.
+///
+/// NAS timers are kept here too rather than in any worker. Due times are in
+/// milliseconds since the UNIX epoch, so that they mean the same to every
+/// worker polling for expired timers.
+#[derive(Default)]
+pub struct Store {
+    next_amf_ue_ngap_id: AtomicU64,
+    next_tmsi: AtomicU32,
+    ues: Mutex<HashMap<u64, UEContext>>,
+    gnbs: Mutex<HashMap<GNBAddress, GNBContext>>,
+    timers: Mutex<TimerSet>,
+    /// SQN of the latest authentication vector of each subscriber, see
+    /// [`crate::ausf`]
+    sqns: Mutex<HashMap<String, u64>>,
+    load: LoadMonitor,
+    smf: Option<Smf>,
+}
+
+impl Store {
+    /// Create a store whose PDU sessions are backed by the SMF-lite.
+    pub fn with_smf(smf: Smf) -> Self {
+        Store {
+            smf: Some(smf),
+            ..Default::default()
+        }
+    }
+
+    /// The SMF-lite, if it is enabled.
+    pub fn smf(&self) -> Option<&Smf> {
+        self.smf.as_ref()
+    }
+
+    /// The load of the worker, see [`crate::load`].
+    pub fn load(&self) -> &LoadMonitor {
+        &self.load
+    }
+
+    /// Allocate a new, unused AMF_UE_NGAP_ID.
+    pub fn allocate_amf_ue_ngap_id(&self) -> u64 {
+        self.next_amf_ue_ngap_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Allocate a new 5G-TMSI for a UE.
+    pub fn allocate_tmsi(&self) -> u32 {
+        self.next_tmsi.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn get_ue(&self, amf_ue_ngap_id: u64) -> Option<UEContext> {
+        let ues = self.ues.lock().expect("UE store lock poisoned");
+        ues.get(&amf_ue_ngap_id).cloned()
+    }
+
+    pub fn put_ue(&self, ue: UEContext) {
+        let mut ues = self.ues.lock().expect("UE store lock poisoned");
+        ues.insert(ue.amf_ue_ngap_id, ue);
+    }
+
+    /// Change the context of a UE in place, with the UE contexts locked so
+    /// that no other worker writes it in the meantime. `change` must not use
+    /// the UE contexts of the store itself. Returns `None` if there is no
+    /// such UE.
+    pub fn update_ue<T>(
+        &self,
+        amf_ue_ngap_id: u64,
+        change: impl FnOnce(&mut UEContext) -> T,
+    ) -> Option<T> {
+        let mut ues = self.ues.lock().expect("UE store lock poisoned");
+        ues.get_mut(&amf_ue_ngap_id).map(change)
+    }
+
+    /// Find the context of a registered UE by the 5G-TMSI assigned to it.
+    pub fn find_ue_by_tmsi(&self, tmsi: u32) -> Option<UEContext> {
+        let ues = self.ues.lock().expect("UE store lock poisoned");
+        ues.values().find(|ue| ue.tmsi == Some(tmsi)).cloned()
+    }
+
+    /// Find the UEs with a UE-associated logical NG-connection through a gNB.
+    pub fn find_ues_connected_to(&self, gnb: &GNBAddress) -> Vec<UEContext> {
+        let ues = self.ues.lock().expect("UE store lock poisoned");
+        ues.values()
+            .filter(|ue| ue.gnb == *gnb && ue.cm_state == CMState::Connected)
+            .cloned()
+            .collect()
+    }
+
+    /// Remove the context of a UE, stopping any of its timers.
+    pub fn remove_ue(&self, amf_ue_ngap_id: u64) -> Option<UEContext> {
+        self.remove_ue_if(amf_ue_ngap_id, |_| true)
+    }
+
+    /// Remove the context of a UE if it meets `condition` at the time,
+    /// stopping any of its timers.
+    pub fn remove_ue_if(
+        &self,
+        amf_ue_ngap_id: u64,
+        condition: impl FnOnce(&UEContext) -> bool,
+    ) -> Option<UEContext> {
+        let mut ues = self.ues.lock().expect("UE store lock poisoned");
+        if ues.get(&amf_ue_ngap_id).is_some_and(|ue| !condition(ue)) {
+            return None;
+        }
+        let ue = ues.remove(&amf_ue_ngap_id);
+        drop(ues);
+
+        let mut timers = self.timers.lock().expect("timer store lock poisoned");
+        let TimerSet { by_due, due } = &mut *timers;
+        due.retain(|&(id, timer), at| {
+            if id == amf_ue_ngap_id {
+                by_due.remove(&(*at, id, timer));
+            }
+            id != amf_ue_ngap_id
+        });
+        ue
+    }
+
+    /// Start a NAS timer of a UE that expires at `due`, restarting it if it
+    /// was running already.
+    pub fn start_timer(&self, amf_ue_ngap_id: u64, timer: NasTimer, due: u64) {
+        let mut timers = self.timers.lock().expect("timer store lock poisoned");
+        if let Some(previous) = timers.due.insert((amf_ue_ngap_id, timer), due) {
+            timers.by_due.remove(&(previous, amf_ue_ngap_id, timer));
+        }
+        timers.by_due.insert((due, amf_ue_ngap_id, timer));
+    }
+
+    /// Stop a NAS timer of a UE, returning whether it was running.
+    pub fn stop_timer(&self, amf_ue_ngap_id: u64, timer: NasTimer) -> bool {
+        let mut timers = self.timers.lock().expect("timer store lock poisoned");
+        let Some(due) = timers.due.remove(&(amf_ue_ngap_id, timer)) else {
+            return false;
+        };
+        timers.by_due.remove(&(due, amf_ue_ngap_id, timer))
+    }
+
+    /// When a NAS timer of a UE expires, if it is running.
+    pub fn timer_due(&self, amf_ue_ngap_id: u64, timer: NasTimer) -> Option<u64> {
+        let timers = self.timers.lock().expect("timer store lock poisoned");
+        timers.due.get(&(amf_ue_ngap_id, timer)).copied()
+    }
+
+    /// Take the NAS timers that expired by `now`, earliest first. A timer is
+    /// handed to a single caller only, so any number of workers can poll.
+    pub fn take_expired_timers(&self, now: u64) -> Vec<(u64, NasTimer)> {
+        let mut timers = self.timers.lock().expect("timer store lock poisoned");
+        let mut expired = vec![];
+        while let Some(&(due, amf_ue_ngap_id, timer)) = timers.by_due.first() {
+            if due > now {
+                break;
+            }
+            timers.by_due.pop_first();
+            timers.due.remove(&(amf_ue_ngap_id, timer));
+            expired.push((amf_ue_ngap_id, timer));
+        }
+        expired
+    }
+
+    /// Advance the SQN of a subscriber by `step`, wrapping around at `max`,
+    /// and return the new value. The SQN of a subscriber starts at 0.
+    pub fn advance_sqn(&self, supi: &str, step: u64, max: u64) -> u64 {
+        let mut sqns = self.sqns.lock().expect("SQN store lock poisoned");
+        let sqn = sqns.entry(supi.to_string()).or_default();
+        *sqn = sqn.wrapping_add(step) & max;
+        *sqn
+    }
+
+    /// Set the SQN of a subscriber, after re-synchronising with the UE.
+    pub fn set_sqn(&self, supi: &str, sqn: u64) {
+        let mut sqns = self.sqns.lock().expect("SQN store lock poisoned");
+        sqns.insert(supi.to_string(), sqn);
+    }
+
+    pub fn get_gnb(&self, address: &GNBAddress) -> Option<GNBContext> {
+        let gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.get(address).cloned()
+    }
+
+    /// Find a gNB that completed NG Setup by its Global gNB ID.
+    pub fn find_gnb(
+        &self,
+        plmn_identity: &[u8],
+        gnb_id: &BitSlice<u8, Msb0>,
+    ) -> Option<GNBContext> {
+        let gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.values()
+            .find(|gnb| gnb.plmn_identity == plmn_identity && gnb.gnb_id == gnb_id)
+            .cloned()
+    }
+
+    /// Find a gNB that completed NG Setup by the value of its gNB ID, in any
+    /// PLMN.
+    pub fn find_gnb_by_id(&self, gnb_id: u32) -> Option<GNBContext> {
+        let gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.values()
+            .find(|gnb| gnb.gnb_id.load_be::<u32>() == gnb_id)
+            .cloned()
+    }
+
+    /// All gNBs that completed NG Setup.
+    pub fn gnbs(&self) -> Vec<GNBContext> {
+        let gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.values().cloned().collect()
+    }
+
+    /// Find the gNBs that serve any of the given tracking areas.
+    pub fn find_gnbs_serving(&self, tais: &[TAI]) -> Vec<GNBContext> {
+        let gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.values()
+            .filter(|gnb| tais.iter().any(|tai| gnb.serves(tai)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn put_gnb(&self, gnb: GNBContext) {
+        let mut gnbs = self.gnbs.lock().expect("gNB store lock poisoned");
+        gnbs.insert(gnb.address, gnb);
+    }
+}
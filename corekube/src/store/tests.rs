@@ -0,0 +1,245 @@
+use super::*;
+
+#[test]
+fn test_allocate_amf_ue_ngap_id() {
+    let store = Store::default();
+    let first = store.allocate_amf_ue_ngap_id();
+    let second = store.allocate_amf_ue_ngap_id();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_put_get_remove_ue() {
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    let ue = store.get_ue(1).expect("UE context should exist");
+    assert_eq!(ue.ran_ue_ngap_id, 10);
+    assert_eq!(ue.cm_state, CMState::Connected);
+
+    assert!(store.remove_ue(1).is_some());
+    assert!(store.get_ue(1).is_none());
+}
+
+#[test]
+fn test_update_and_remove_ue_if() {
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+
+    assert_eq!(store.update_ue(1, |ue| ue.tmsi.replace(7)), Some(None));
+    assert_eq!(store.get_ue(1).unwrap().tmsi, Some(7));
+    assert_eq!(store.update_ue(2, |ue| ue.tmsi), None);
+
+    store.start_timer(1, NasTimer::T3550, 1_000);
+    assert!(store
+        .remove_ue_if(1, |ue| ue.cm_state == CMState::Idle)
+        .is_none());
+    assert_eq!(store.timer_due(1, NasTimer::T3550), Some(1_000));
+    assert!(store
+        .remove_ue_if(1, |ue| ue.cm_state == CMState::Connected)
+        .is_some());
+    assert!(store.get_ue(1).is_none());
+    assert_eq!(store.timer_due(1, NasTimer::T3550), None);
+}
+
+#[test]
+fn test_find_ue_by_tmsi() {
+    let store = Store::default();
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.tmsi = Some(0x1234);
+    store.put_ue(ue);
+    store.put_ue(UEContext::new(2, 20, crate::tests::test_gnb()));
+
+    assert_eq!(store.find_ue_by_tmsi(0x1234).unwrap().amf_ue_ngap_id, 1);
+    assert!(store.find_ue_by_tmsi(0x4321).is_none());
+}
+
+#[test]
+fn test_set_pdu_session_active() {
+    let mut ue = UEContext::new(1, 10, crate::tests::test_gnb());
+    ue.set_pdu_session_active(5, true);
+    ue.set_pdu_session_active(5, false);
+    assert_eq!(
+        ue.pdu_sessions,
+        vec![PDUSession {
+            id: 5,
+            active: false,
+            ..Default::default()
+        }]
+    );
+}
+
+#[test]
+fn test_find_gnb() {
+    let store = Store::default();
+    let target = crate::tests::test_target_gnb();
+    store.put_gnb(target.clone());
+
+    let found = store
+        .find_gnb(&target.plmn_identity, &target.gnb_id)
+        .expect("gNB should be found by its Global gNB ID");
+    assert_eq!(found.address, target.address);
+
+    assert!(store
+        .find_gnb(&[0x00, 0xf1, 0x10], &target.gnb_id)
+        .is_none());
+    assert!(store
+        .find_gnb(&target.plmn_identity, bits![u8, Msb0; 0; 22])
+        .is_none());
+}
+
+#[test]
+fn test_find_gnbs_serving() {
+    let store = Store::default();
+    let tai = TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, 0x01],
+    };
+    let mut target = crate::tests::test_target_gnb();
+    target.supported_tas = vec![SupportedTA {
+        tac: tai.tac.clone(),
+        plmns: vec![tai.plmn_identity.clone()],
+        slices: vec![],
+    }];
+    store.put_gnb(target.clone());
+
+    let found = store.find_gnbs_serving(std::slice::from_ref(&tai));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].address, target.address);
+
+    let other_tac = TAI {
+        tac: vec![0x00, 0x00, 0x02],
+        ..tai
+    };
+    assert!(store.find_gnbs_serving(&[other_tac]).is_empty());
+}
+
+#[test]
+fn test_slices_in() {
+    let tai = TAI {
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        tac: vec![0x00, 0x00, 0x01],
+    };
+    let embb = nas::fgmm::Snssai::default();
+    let mut gnb = crate::tests::test_target_gnb();
+    gnb.supported_tas = vec![
+        SupportedTA {
+            tac: tai.tac.clone(),
+            plmns: vec![tai.plmn_identity.clone(), vec![0x00, 0xf1, 0x10]],
+            slices: vec![
+                (tai.plmn_identity.clone(), embb),
+                (
+                    vec![0x00, 0xf1, 0x10],
+                    nas::fgmm::Snssai { sst: 2, sd: None },
+                ),
+            ],
+        },
+        SupportedTA {
+            tac: vec![0x00, 0x00, 0x02],
+            plmns: vec![tai.plmn_identity.clone()],
+            slices: vec![(
+                tai.plmn_identity.clone(),
+                nas::fgmm::Snssai { sst: 3, sd: None },
+            )],
+        },
+    ];
+
+    assert_eq!(gnb.slices_in(&tai), vec![embb]);
+}
+
+#[test]
+fn test_timers() {
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+    store.put_ue(UEContext::new(2, 20, crate::tests::test_gnb()));
+
+    store.start_timer(1, NasTimer::T3550, 6_000);
+    store.start_timer(2, NasTimer::MobileReachable, 3_000);
+    store.start_timer(1, NasTimer::MobileReachable, 9_000);
+    assert!(store.take_expired_timers(2_999).is_empty());
+
+    // A restarted timer only expires at its new due time
+    store.start_timer(1, NasTimer::T3550, 12_000);
+    assert_eq!(store.timer_due(1, NasTimer::T3550), Some(12_000));
+    assert_eq!(
+        store.take_expired_timers(10_000),
+        vec![
+            (2, NasTimer::MobileReachable),
+            (1, NasTimer::MobileReachable)
+        ]
+    );
+    assert!(store.take_expired_timers(10_000).is_empty());
+
+    assert!(store.stop_timer(1, NasTimer::T3550));
+    assert!(!store.stop_timer(1, NasTimer::T3550));
+    assert!(store.take_expired_timers(20_000).is_empty());
+
+    // The timers of a UE go away with its context
+    store.start_timer(2, NasTimer::T3570, 30_000);
+    store.remove_ue(2);
+    assert_eq!(store.timer_due(2, NasTimer::T3570), None);
+    assert!(store.take_expired_timers(40_000).is_empty());
+}
+
+#[test]
+fn test_timer_for_message() {
+    use nas::MobilityMessageIdentifier::*;
+
+    assert_eq!(
+        NasTimer::guarding(REGISTRATION_ACCEPT),
+        Some(NasTimer::T3550)
+    );
+    assert_eq!(
+        NasTimer::guarding(SECURITY_MODE_COMMAND),
+        Some(NasTimer::T3560)
+    );
+    assert_eq!(NasTimer::guarding(DEREGISTRATION_ACCEPT), None);
+    assert_eq!(
+        NasTimer::answered_by(SECURITY_MODE_REJECT),
+        Some(NasTimer::T3560)
+    );
+    assert_eq!(
+        NasTimer::answered_by(IDENTITY_RESPONSE),
+        Some(NasTimer::T3570)
+    );
+    assert_eq!(NasTimer::answered_by(REGISTRATION_REQUEST), None);
+}
+
+#[test]
+fn test_find_ues_connected_to() {
+    let store = Store::default();
+    store.put_ue(UEContext::new(1, 10, crate::tests::test_gnb()));
+    let mut idle = UEContext::new(2, 20, crate::tests::test_gnb());
+    idle.cm_state = CMState::Idle;
+    store.put_ue(idle);
+    store.put_ue(UEContext::new(
+        3,
+        30,
+        crate::tests::test_target_gnb().address,
+    ));
+
+    let connected = store.find_ues_connected_to(&crate::tests::test_gnb());
+    assert_eq!(connected.len(), 1);
+    assert_eq!(connected[0].amf_ue_ngap_id, 1);
+}
+
+#[test]
+fn test_find_gnb_by_id() {
+    let store = Store::default();
+    store.put_gnb(crate::tests::test_target_gnb());
+
+    let gnb = store.find_gnb_by_id(0x3F_FFFF).unwrap();
+    assert_eq!(gnb.address, crate::tests::test_target_gnb().address);
+    assert!(store.find_gnb_by_id(1).is_none());
+}
+
+#[test]
+fn test_sqn() {
+    let store = Store::default();
+    assert_eq!(store.advance_sqn("imsi-1", 32, 0xFFFF), 32);
+    assert_eq!(store.advance_sqn("imsi-1", 32, 0xFFFF), 64);
+    assert_eq!(store.advance_sqn("imsi-2", 32, 0xFFFF), 32);
+
+    store.set_sqn("imsi-1", 0xFFF0);
+    assert_eq!(store.advance_sqn("imsi-1", 32, 0xFFFF), 0x10);
+}
@@ -1,5 +1,43 @@
 use super::*;
 
+/// The gNB address used by tests that need to identify a sender.
+pub fn test_gnb() -> store::GNBAddress {
+    store::GNBAddress {
+        frontend: "127.0.0.1:9977".parse().unwrap(),
+        frontend_id: [0, 0, 0, 1],
+    }
+}
+
+/// A second gNB that completed NG Setup, used as the target of handovers.
+pub fn test_target_gnb() -> store::GNBContext {
+    store::GNBContext {
+        address: store::GNBAddress {
+            frontend: "127.0.0.1:9978".parse().unwrap(),
+            frontend_id: [0, 0, 0, 2],
+        },
+        plmn_identity: vec![0x02, 0xf8, 0x39],
+        gnb_id: bitvec::bitvec![u8, bitvec::order::Msb0; 1; 22],
+        name: Some("target".to_string()),
+        supported_tas: vec![],
+        paging_drx: 0,
+    }
+}
+
+/// Start an SMF-lite with the given configuration, associated with a PFCP
+/// responder running on a background thread.
+pub fn test_smf(mut smf_config: config::SmfConfig) -> smf::Smf {
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    smf_config.upf_addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut responder = pfcp::responder::Responder::new(
+            std::net::Ipv4Addr::LOCALHOST,
+            std::net::Ipv4Addr::LOCALHOST,
+        );
+        responder.run(&socket)
+    });
+    smf::Smf::start(&smf_config).unwrap()
+}
+
 #[test]
 fn test_setup_request() {
     let mut config = config::CoreKubeConfig::default();
@@ -12,10 +50,13 @@ fn test_setup_request() {
         0x00, 0x00, 0x10, 0x08, 0x00, 0x00, 0x01, 0x00, 0x15, 0x40, 0x01, 0x40,
     ];
 
-    let result = ngap_handler_entrypoint(&config, &ngap_input_bytes);
+    let store = store::Store::default();
+
+    let result = ngap_handler_entrypoint(&config, &store, &test_gnb(), &ngap_input_bytes);
     assert_eq!(result.len(), 1);
 
     assert_eq!(result[0].sctp_stream, 0x00);
+    assert!(store.get_gnb(&test_gnb()).is_some());
 
     let ngap_expected_bytes: [u8; 54] = [
         0x20, 0x15, 0x00, 0x32, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x0e, 0x05, 0x80, 0x6f, 0x70,
@@ -27,6 +68,57 @@ fn test_setup_request() {
     assert_eq!(result[0].buf, ngap_expected_bytes.to_vec());
 }
 
+#[test]
+fn test_resolve_destination() {
+    let store = store::Store::default();
+    let target = test_target_gnb();
+    store.put_gnb(target.clone());
+
+    assert_eq!(
+        resolve_destination(
+            &store,
+            Some(&test_gnb()),
+            &ngap_handlers::Destination::Sender
+        ),
+        Some(test_gnb())
+    );
+    assert_eq!(
+        resolve_destination(
+            &store,
+            Some(&test_gnb()),
+            &ngap_handlers::Destination::Association(target.address)
+        ),
+        Some(target.address)
+    );
+    assert_eq!(
+        resolve_destination(
+            &store,
+            Some(&test_gnb()),
+            &ngap_handlers::Destination::GlobalGNBID {
+                plmn_identity: target.plmn_identity.clone(),
+                gnb_id: target.gnb_id.clone(),
+            }
+        ),
+        Some(target.address)
+    );
+
+    // Without a sender, only gNBs known to the store can be reached
+    assert_eq!(
+        resolve_destination(&store, None, &ngap_handlers::Destination::Sender),
+        None
+    );
+
+    // A gNB that has not completed NG Setup can only be answered directly
+    assert_eq!(
+        resolve_destination(
+            &store,
+            Some(&target.address),
+            &ngap_handlers::Destination::Association(test_gnb())
+        ),
+        None
+    );
+}
+
 // #[test]
 // fn test_initial_ue_message() {
 //     let mut config = config::CoreKubeConfig::default();
@@ -41,6 +133,7 @@ fn test_setup_request() {
 //         0x01, 0x00, 0x5a, 0x40, 0x01, 0x10, 0x00, 0x70, 0x40, 0x01, 0x00,
 //     ];
 
-//     let result = ngap_handler_entrypoint(&config, &ngap_input_bytes);
+//     let store = store::Store::default();
+//     let result = ngap_handler_entrypoint(&config, &store, &test_gnb(), &ngap_input_bytes);
 //     assert_eq!(result.len(), 1);
 // }
@@ -0,0 +1,184 @@
+//! 5G AKA, see TS 33.501 section 6.1.3.2, with the MILENAGE algorithm set of
+//! TS 35.206 computing the authentication functions of the home network.
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+
+use crate::security::kdf;
+
+#[cfg(test)]
+mod tests;
+
+/// Authentication management field sent in AUTN, with the separation bit
+/// set as required for 5G AKA, see TS 33.501 Annex M.
+pub const AMF_SEPARATION_BIT: [u8; 2] = [0x80, 0x00];
+
+/// The ABBA parameter of the current key hierarchy, see TS 33.501 Annex A.7.1.
+pub const ABBA: [u8; 2] = [0x00, 0x00];
+
+/// Highest SQN, which is 48 bits long.
+pub const MAX_SQN: u64 = 0xFFFF_FFFF_FFFF;
+
+/// Rotation amounts and constants of MILENAGE, see TS 35.206 section 4.1.
+const R: [u32; 5] = [64, 0, 32, 64, 96];
+const C: [u8; 5] = [0x00, 0x01, 0x02, 0x04, 0x08];
+
+/// The MILENAGE functions for a subscriber key K and its OPc.
+#[derive(Clone, Debug)]
+pub struct Milenage {
+    k: [u8; 16],
+    opc: [u8; 16],
+}
+
+impl Milenage {
+    pub fn new(k: [u8; 16], opc: [u8; 16]) -> Self {
+        Milenage { k, opc }
+    }
+
+    /// Derive OPc from the operator variant algorithm configuration field
+    /// OP, for subscribers that are provisioned with OP.
+    pub fn opc(k: &[u8; 16], op: &[u8; 16]) -> [u8; 16] {
+        xor(&encrypt(k, op), op)
+    }
+
+    /// OUT1 to OUT5, of which OUT1 is the only one that depends on SQN and
+    /// AMF.
+    fn out(&self, n: usize, rand: &[u8; 16], sqn: &[u8; 6], amf: &[u8; 2]) -> [u8; 16] {
+        let temp = encrypt(&self.k, &xor(rand, &self.opc));
+        let input = if n == 0 {
+            let mut in1 = [0u8; 16];
+            for half in in1.chunks_mut(8) {
+                half[..6].copy_from_slice(sqn);
+                half[6..].copy_from_slice(amf);
+            }
+            xor(&temp, &rotate(&xor(&in1, &self.opc), R[n]))
+        } else {
+            let mut input = rotate(&xor(&temp, &self.opc), R[n]);
+            input[15] ^= C[n];
+            input
+        };
+        xor(&encrypt(&self.k, &input), &self.opc)
+    }
+
+    /// f1, the network authentication function, giving MAC-A.
+    pub fn f1(&self, rand: &[u8; 16], sqn: &[u8; 6], amf: &[u8; 2]) -> [u8; 8] {
+        self.out(0, rand, sqn, amf)[..8].try_into().unwrap()
+    }
+
+    /// f1*, the re-synchronisation message authentication function, giving
+    /// MAC-S.
+    pub fn f1_star(&self, rand: &[u8; 16], sqn: &[u8; 6], amf: &[u8; 2]) -> [u8; 8] {
+        self.out(0, rand, sqn, amf)[8..].try_into().unwrap()
+    }
+
+    /// f2 to f5, giving RES, CK, IK and AK.
+    pub fn f2345(&self, rand: &[u8; 16]) -> ([u8; 8], [u8; 16], [u8; 16], [u8; 6]) {
+        let out2 = self.out(1, rand, &[0; 6], &[0; 2]);
+        (
+            out2[8..].try_into().unwrap(),
+            self.out(2, rand, &[0; 6], &[0; 2]),
+            self.out(3, rand, &[0; 6], &[0; 2]),
+            out2[..6].try_into().unwrap(),
+        )
+    }
+
+    /// f5*, the anonymity key function for re-synchronisation, giving AK.
+    pub fn f5_star(&self, rand: &[u8; 16]) -> [u8; 6] {
+        self.out(4, rand, &[0; 6], &[0; 2])[..6].try_into().unwrap()
+    }
+}
+
+fn encrypt(key: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let cipher = Aes128::new(key.into());
+    let mut block = (*block).into();
+    cipher.encrypt_block(&mut block);
+    block.into()
+}
+
+fn xor<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
+
+/// Rotate a 128 bit value left by a whole number of octets.
+fn rotate(value: &[u8; 16], bits: u32) -> [u8; 16] {
+    let mut value = *value;
+    value.rotate_left(bits as usize / 8);
+    value
+}
+
+fn sqn_octets(sqn: u64) -> [u8; 6] {
+    sqn.to_be_bytes()[2..].try_into().unwrap()
+}
+
+/// The serving network name that keys are bound to, see TS 24.501 section
+/// 9.12.1.
+pub fn serving_network_name(mcc: u16, mnc: u16) -> String {
+    format!("5G:mnc{:03}.mcc{:03}.3gppnetwork.org", mnc, mcc)
+}
+
+/// A 5G HE AV, see TS 33.501 section 6.1.3.2.0, with the KSEAF that the
+/// AUSF derives once the UE answered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthenticationVector {
+    pub rand: [u8; 16],
+    pub autn: [u8; 16],
+    pub xres_star: [u8; 16],
+    pub kseaf: [u8; 32],
+}
+
+impl AuthenticationVector {
+    /// Generate the vector for the challenge `rand` with sequence number
+    /// `sqn`.
+    pub fn generate(
+        milenage: &Milenage,
+        sqn: u64,
+        rand: [u8; 16],
+        serving_network_name: &str,
+    ) -> Self {
+        let sqn = sqn_octets(sqn);
+        let mac_a = milenage.f1(&rand, &sqn, &AMF_SEPARATION_BIT);
+        let (res, ck, ik, ak) = milenage.f2345(&rand);
+        let sqn_xor_ak = xor(&sqn, &ak);
+
+        let mut autn = [0u8; 16];
+        autn[..6].copy_from_slice(&sqn_xor_ak);
+        autn[6..8].copy_from_slice(&AMF_SEPARATION_BIT);
+        autn[8..].copy_from_slice(&mac_a);
+
+        let ck_ik = [ck, ik].concat();
+        let sn_name = serving_network_name.as_bytes();
+        // RES*, KAUSF and KSEAF, see TS 33.501 Annex A.4, A.2 and A.6
+        let xres_star = kdf(&ck_ik, 0x6B, &[sn_name, &rand, &res]);
+        let kausf = kdf(&ck_ik, 0x6A, &[sn_name, &sqn_xor_ak]);
+        let kseaf = kdf(&kausf, 0x6C, &[sn_name]);
+
+        AuthenticationVector {
+            rand,
+            autn,
+            xres_star: xres_star[16..].try_into().unwrap(),
+            kseaf,
+        }
+    }
+
+    /// Derive KAMF for the UE with the given IMSI, given as its digits, see
+    /// TS 33.501 Annex A.7.
+    pub fn kamf(&self, imsi: &str) -> [u8; 32] {
+        kdf(&self.kseaf, 0x6D, &[imsi.as_bytes(), &ABBA])
+    }
+}
+
+/// Recover the sequence number of the UE from the AUTS it sent in an
+/// Authentication Failure with cause synch failure, for the challenge
+/// `rand`. Returns `None` if the MAC-S in AUTS is not valid, see TS 33.102
+/// section 6.3.5.
+pub fn resynchronise(milenage: &Milenage, rand: &[u8; 16], auts: &[u8; 14]) -> Option<u64> {
+    let ak = milenage.f5_star(rand);
+    let sqn_ms = xor(auts[..6].try_into().unwrap(), &ak);
+    // The AMF of MAC-S is a dummy value of all zeroes
+    if milenage.f1_star(rand, &sqn_ms, &[0; 2]) != auts[6..] {
+        return None;
+    }
+    let mut sqn = [0u8; 8];
+    sqn[2..].copy_from_slice(&sqn_ms);
+    Some(u64::from_be_bytes(sqn))
+}
@@ -0,0 +1,113 @@
+use super::*;
+
+// Test set 1 of TS 35.207
+
+fn test_set_1() -> Milenage {
+    let k: [u8; 16] = hex::decode("465b5ce8b199b49faa5f0a2ee238a6bc")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let op: [u8; 16] = hex::decode("cdc202d5123e20f62b6d676ac72cb318")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let opc = Milenage::opc(&k, &op);
+    assert_eq!(
+        opc.to_vec(),
+        hex::decode("cd63cb71954a9f4e48a5994e37a02baf").unwrap()
+    );
+    Milenage::new(k, opc)
+}
+
+fn test_rand() -> [u8; 16] {
+    hex::decode("23553cbe9637a89d218ae64dae47bf35")
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+#[test]
+fn test_milenage() {
+    let milenage = test_set_1();
+    let rand = test_rand();
+    let sqn = [0xff, 0x9b, 0xb4, 0xd0, 0xb6, 0x07];
+    let amf = [0xb9, 0xb9];
+
+    assert_eq!(
+        milenage.f1(&rand, &sqn, &amf).to_vec(),
+        hex::decode("4a9ffac354dfafb3").unwrap()
+    );
+    assert_eq!(
+        milenage.f1_star(&rand, &sqn, &amf).to_vec(),
+        hex::decode("01cfaf9ec4e871e9").unwrap()
+    );
+
+    let (res, ck, ik, ak) = milenage.f2345(&rand);
+    assert_eq!(res.to_vec(), hex::decode("a54211d5e3ba50bf").unwrap());
+    assert_eq!(
+        ck.to_vec(),
+        hex::decode("b40ba9a3c58b2a05bbf0d987b21bf8cb").unwrap()
+    );
+    assert_eq!(
+        ik.to_vec(),
+        hex::decode("f769bcd751044604127672711c6d3441").unwrap()
+    );
+    assert_eq!(ak.to_vec(), hex::decode("aa689c648370").unwrap());
+    assert_eq!(
+        milenage.f5_star(&rand).to_vec(),
+        hex::decode("451e8beca43b").unwrap()
+    );
+}
+
+#[test]
+fn test_serving_network_name() {
+    assert_eq!(
+        serving_network_name(208, 93),
+        "5G:mnc093.mcc208.3gppnetwork.org"
+    );
+}
+
+#[test]
+fn test_authentication_vector() {
+    let milenage = test_set_1();
+    let rand = test_rand();
+    let vector =
+        AuthenticationVector::generate(&milenage, 0x20, rand, "5G:mnc093.mcc208.3gppnetwork.org");
+    assert_eq!(vector.rand, rand);
+
+    // The UE gets SQN back from AUTN with AK, and checks MAC-A
+    let (_, _, _, ak) = milenage.f2345(&rand);
+    let sqn: [u8; 6] = std::array::from_fn(|i| vector.autn[i] ^ ak[i]);
+    assert_eq!(sqn, [0, 0, 0, 0, 0, 0x20]);
+    assert_eq!(vector.autn[6..8], AMF_SEPARATION_BIT);
+    assert_eq!(
+        vector.autn[8..],
+        milenage.f1(&rand, &sqn, &AMF_SEPARATION_BIT)
+    );
+
+    // Keys are bound to the serving network
+    let other =
+        AuthenticationVector::generate(&milenage, 0x20, rand, "5G:mnc001.mcc001.3gppnetwork.org");
+    assert_ne!(vector.xres_star, other.xres_star);
+    assert_ne!(
+        vector.kamf("208930000000001"),
+        other.kamf("208930000000001")
+    );
+}
+
+#[test]
+fn test_resynchronise() {
+    let milenage = test_set_1();
+    let rand = test_rand();
+
+    // AUTS as built by a UE whose SQN is 0x1234
+    let sqn_ms = [0, 0, 0, 0, 0x12, 0x34];
+    let ak = milenage.f5_star(&rand);
+    let concealed_sqn: [u8; 6] = std::array::from_fn(|i| sqn_ms[i] ^ ak[i]);
+    let mac_s = milenage.f1_star(&rand, &sqn_ms, &[0; 2]);
+    let mut auts: [u8; 14] = [&concealed_sqn[..], &mac_s].concat().try_into().unwrap();
+    assert_eq!(resynchronise(&milenage, &rand, &auts), Some(0x1234));
+
+    auts[13] ^= 0x01;
+    assert_eq!(resynchronise(&milenage, &rand, &auts), None);
+}
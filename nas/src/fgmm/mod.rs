@@ -0,0 +1,1024 @@
+//! Encoding of 5GS mobility management messages, see TS 24.501 section 8.2.
+
+use crate::{
+    MobilityManagementCause, MobilityMessageIdentifier, ProtocolDiscriminator, SecurityHeader,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// 5GS registration result value for 3GPP access, TS 24.501 section 9.11.3.6.
+pub const REGISTRATION_RESULT_3GPP_ACCESS: u8 = 0x01;
+
+/// Payload container type for N1 SM information, TS 24.501 section 9.11.3.40.
+pub const PAYLOAD_CONTAINER_N1_SM_INFORMATION: u8 = 0x01;
+
+/// 5GS registration type values, TS 24.501 section 9.11.3.7.
+pub const REGISTRATION_TYPE_INITIAL: u8 = 0x01;
+pub const REGISTRATION_TYPE_MOBILITY_UPDATING: u8 = 0x02;
+pub const REGISTRATION_TYPE_PERIODIC_UPDATING: u8 = 0x03;
+pub const REGISTRATION_TYPE_EMERGENCY: u8 = 0x04;
+
+/// Service type values, TS 24.501 section 9.11.3.50.
+pub const SERVICE_TYPE_SIGNALLING: u8 = 0x00;
+pub const SERVICE_TYPE_DATA: u8 = 0x01;
+pub const SERVICE_TYPE_MOBILE_TERMINATED_SERVICES: u8 = 0x02;
+
+/// Causes of rejecting an S-NSSAI, TS 24.501 section 9.11.3.46.
+pub const REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_PLMN: u8 = 0x00;
+pub const REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_REGISTRATION_AREA: u8 = 0x01;
+
+/// Access types of a de-registration, TS 24.501 section 9.11.3.20.
+pub const ACCESS_TYPE_3GPP: u8 = 0x01;
+pub const ACCESS_TYPE_NON_3GPP: u8 = 0x02;
+pub const ACCESS_TYPE_3GPP_AND_NON_3GPP: u8 = 0x03;
+
+/// Protection scheme of a SUCI that leaves the SUPI in the clear, TS 33.501
+/// Annex C.1.
+pub const PROTECTION_SCHEME_NULL: u8 = 0x00;
+
+/// Types of identity of a 5GS mobile identity, TS 24.501 section 9.11.3.4.
+const IDENTITY_TYPE_SUCI: u8 = 0x01;
+const IDENTITY_TYPE_5G_GUTI: u8 = 0x02;
+const IDENTITY_TYPE_5G_S_TMSI: u8 = 0x04;
+
+const IEI_5G_GUTI: u8 = 0x77;
+const IEI_ALLOWED_NSSAI: u8 = 0x15;
+const IEI_PDU_SESSION_ID: u8 = 0x12;
+const IEI_OLD_PDU_SESSION_ID: u8 = 0x59;
+const IEI_DNN: u8 = 0x25;
+const IEI_UPLINK_DATA_STATUS: u8 = 0x40;
+const IEI_PDU_SESSION_STATUS: u8 = 0x50;
+const IEI_PDU_SESSION_REACTIVATION_RESULT: u8 = 0x26;
+const IEI_NAS_MESSAGE_CONTAINER: u8 = 0x71;
+const IEI_5GMM_CAUSE: u8 = 0x58;
+const IEI_UE_SECURITY_CAPABILITY: u8 = 0x2E;
+const IEI_LAST_VISITED_REGISTERED_TAI: u8 = 0x52;
+const IEI_TAI_LIST: u8 = 0x54;
+const IEI_T3346_VALUE: u8 = 0x5F;
+const IEI_S_NSSAI: u8 = 0x22;
+const IEI_REQUESTED_NSSAI: u8 = 0x2F;
+const IEI_CONFIGURED_NSSAI: u8 = 0x31;
+const IEI_REJECTED_NSSAI: u8 = 0x11;
+const IEI_REJECTED_NSSAI_REGISTRATION_REJECT: u8 = 0x69;
+const IEI_RAND: u8 = 0x21;
+const IEI_AUTN: u8 = 0x20;
+const IEI_AUTHENTICATION_RESPONSE_PARAMETER: u8 = 0x2D;
+const IEI_AUTHENTICATION_FAILURE_PARAMETER: u8 = 0x30;
+const IEI_ADDITIONAL_5G_SECURITY_INFORMATION: u8 = 0x36;
+
+/// Bit of the additional 5G security information IE asking the UE to send
+/// its initial NAS message again, TS 24.501 section 9.11.3.12.
+const RETRANSMISSION_OF_INITIAL_NAS_MESSAGE_REQUEST: u8 = 0x02;
+
+/// SD value of an S-NSSAI without an SD, TS 23.003 section 28.4.2.
+const NO_SD: [u8; 3] = [0xFF, 0xFF, 0xFF];
+
+/// Type of list of a TAI list holding TAIs of different PLMNs, TS 24.501
+/// section 9.11.3.9.
+const TAI_LIST_TYPE_DIFFERENT_PLMNS: u8 = 0x02;
+
+/// A 5G-GUTI, see TS 23.003 section 2.10.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Guti {
+    pub plmn: [u8; 3],
+    pub amf_region_id: u8,
+    pub amf_set_id: u16,
+    pub amf_pointer: u8,
+    pub tmsi: u32,
+}
+
+impl Guti {
+    /// Encode as the value of a 5GS mobile identity IE.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![0xF2];
+        buf.extend_from_slice(&self.plmn);
+        buf.push(self.amf_region_id);
+        buf.push((self.amf_set_id >> 2) as u8);
+        buf.push(((self.amf_set_id & 0x03) as u8) << 6 | (self.amf_pointer & 0x3F));
+        buf.extend_from_slice(&self.tmsi.to_be_bytes());
+        buf
+    }
+
+    /// Decode the value of a 5GS mobile identity IE holding a 5G-GUTI.
+    pub fn decode(buf: &[u8]) -> Option<Guti> {
+        if buf.len() != 11 || buf[0] & 0x07 != IDENTITY_TYPE_5G_GUTI {
+            return None;
+        }
+        Some(Guti {
+            plmn: [buf[1], buf[2], buf[3]],
+            amf_region_id: buf[4],
+            amf_set_id: (buf[5] as u16) << 2 | (buf[6] >> 6) as u16,
+            amf_pointer: buf[6] & 0x3F,
+            tmsi: u32::from_be_bytes(buf[7..11].try_into().unwrap()),
+        })
+    }
+}
+
+/// A SUCI of a SUPI that is an IMSI, see TS 23.003 section 2.2B.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suci {
+    /// The PLMN of the IMSI, encoded as in a PLMN identity
+    pub plmn: [u8; 3],
+    pub routing_indicator: [u8; 2],
+    pub protection_scheme: u8,
+    pub home_network_public_key_id: u8,
+    /// The MSIN in BCD for the null scheme, or the MSIN concealed by the
+    /// protection scheme
+    pub scheme_output: Vec<u8>,
+}
+
+impl Suci {
+    /// Decode the value of a 5GS mobile identity IE holding a SUCI of an
+    /// IMSI.
+    pub fn decode(buf: &[u8]) -> Option<Suci> {
+        // SUPI format IMSI in bits 7 to 5
+        if buf.len() < 8 || buf[0] & 0x77 != IDENTITY_TYPE_SUCI {
+            return None;
+        }
+        Some(Suci {
+            plmn: [buf[1], buf[2], buf[3]],
+            routing_indicator: [buf[4], buf[5]],
+            protection_scheme: buf[6] & 0x0F,
+            home_network_public_key_id: buf[7],
+            scheme_output: buf[8..].to_vec(),
+        })
+    }
+
+    /// The SUPI as `imsi-` followed by the digits of the IMSI, if the SUCI
+    /// uses the null scheme.
+    pub fn supi(&self) -> Option<String> {
+        if self.protection_scheme != PROTECTION_SCHEME_NULL {
+            return None;
+        }
+        let [plmn0, plmn1, plmn2] = self.plmn;
+        // MCC digits 1 to 3, then MNC digits 1 to 3 of which the last one
+        // may be a filler, as may be the last digit of the MSIN
+        let plmn_digits = [
+            plmn0 & 0x0F,
+            plmn0 >> 4,
+            plmn1 & 0x0F,
+            plmn2 & 0x0F,
+            plmn2 >> 4,
+            plmn1 >> 4,
+        ];
+        let msin_digits = self
+            .scheme_output
+            .iter()
+            .flat_map(|octet| [octet & 0x0F, octet >> 4]);
+
+        let mut supi = "imsi-".to_string();
+        for digit in plmn_digits.into_iter().chain(msin_digits) {
+            match digit {
+                0..=9 => supi.push((b'0' + digit) as char),
+                0x0F => {}
+                _ => return None,
+            }
+        }
+        Some(supi)
+    }
+}
+
+/// A tracking area identity, see TS 23.003 section 19.4.2.3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tai {
+    pub plmn: [u8; 3],
+    pub tac: [u8; 3],
+}
+
+/// Encode a TAI list IE value, see TS 24.501 section 9.11.3.9. The list must
+/// hold between 1 and 16 TAIs.
+fn encode_tai_list(tais: &[Tai]) -> Vec<u8> {
+    let mut buf = vec![(TAI_LIST_TYPE_DIFFERENT_PLMNS << 5) | ((tais.len() as u8 - 1) & 0x1F)];
+    for tai in tais {
+        buf.extend_from_slice(&tai.plmn);
+        buf.extend_from_slice(&tai.tac);
+    }
+    buf
+}
+
+/// An S-NSSAI as used in NAS, see TS 24.501 section 9.11.2.8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Snssai {
+    pub sst: u8,
+    pub sd: Option<[u8; 3]>,
+}
+
+impl Default for Snssai {
+    /// eMBB without an SD, the standardised slice that every UE supports.
+    fn default() -> Self {
+        Snssai { sst: 1, sd: None }
+    }
+}
+
+impl Snssai {
+    /// Encode as a length-prefixed S-NSSAI IE value.
+    pub fn encode(&self) -> Vec<u8> {
+        MappedSnssai {
+            snssai: *self,
+            hplmn: None,
+        }
+        .encode()
+    }
+}
+
+/// An S-NSSAI of the serving PLMN along with the HPLMN S-NSSAI it is mapped
+/// to, for a UE that is roaming. Encoded as an S-NSSAI IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MappedSnssai {
+    pub snssai: Snssai,
+    pub hplmn: Option<Snssai>,
+}
+
+impl MappedSnssai {
+    /// Encode as a length-prefixed S-NSSAI IE value.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![0, self.snssai.sst];
+        // An SD that is left out while the mapped SD is not takes the value
+        // meaning no SD
+        let sd = match self.hplmn {
+            Some(Snssai { sd: Some(_), .. }) => Some(self.snssai.sd.unwrap_or(NO_SD)),
+            _ => self.snssai.sd,
+        };
+        if let Some(sd) = sd {
+            buf.extend_from_slice(&sd);
+        }
+        if let Some(hplmn) = self.hplmn {
+            buf.push(hplmn.sst);
+            if let Some(sd) = hplmn.sd {
+                buf.extend_from_slice(&sd);
+            }
+        }
+        buf[0] = (buf.len() - 1) as u8;
+        buf
+    }
+
+    /// Decode the value of an S-NSSAI IE, without its length.
+    pub fn decode(value: &[u8]) -> Option<MappedSnssai> {
+        let (sd, mapped) = match value.len() {
+            1 | 2 => (None, &value[1..]),
+            4 | 5 | 8 => (decode_sd(&value[1..4]), &value[4..]),
+            _ => return None,
+        };
+        let hplmn = mapped.first().map(|&sst| Snssai {
+            sst,
+            sd: mapped.get(1..4).and_then(decode_sd),
+        });
+        Some(MappedSnssai {
+            snssai: Snssai { sst: value[0], sd },
+            hplmn,
+        })
+    }
+}
+
+fn decode_sd(octets: &[u8]) -> Option<[u8; 3]> {
+    let sd: [u8; 3] = octets.try_into().ok()?;
+    Some(sd).filter(|sd| *sd != NO_SD)
+}
+
+/// An S-NSSAI the UE requested and is not allowed to use, with the
+/// REJECTED_NSSAI_CAUSE_* value why, see TS 24.501 section 9.11.3.46.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RejectedSnssai {
+    pub snssai: Snssai,
+    pub cause: u8,
+}
+
+impl RejectedSnssai {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = self.snssai.encode();
+        buf[0] = (buf[0] << 4) | (self.cause & 0x0F);
+        buf
+    }
+}
+
+/// Encode a GPRS timer 2 IE, TS 24.008 section 10.5.7.4, for a timer of at
+/// least `seconds`. Longer timers lose precision, up to 31 decihours.
+fn encode_gprs_timer_2(iei: u8, seconds: u32) -> Vec<u8> {
+    let value = if seconds <= 2 * 31 {
+        seconds.div_ceil(2)
+    } else if seconds <= 60 * 31 {
+        (0b001 << 5) | seconds.div_ceil(60)
+    } else {
+        (0b010 << 5) | seconds.div_ceil(360).min(31)
+    };
+    vec![iei, 1, value as u8]
+}
+
+/// Encode a PDU session bitmap such as the PDU session status IE value, where
+/// bit N of `sessions` stands for PDU session ID N, see TS 24.501 section
+/// 9.11.3.44.
+fn encode_pdu_session_bitmap(iei: u8, sessions: u16) -> Vec<u8> {
+    let bitmap = sessions.to_le_bytes();
+    vec![iei, 2, bitmap[0], bitmap[1]]
+}
+
+fn decode_pdu_session_bitmap(value: &[u8]) -> Option<u16> {
+    Some(u16::from_le_bytes([*value.first()?, *value.get(1)?]))
+}
+
+/// Encode an NSSAI IE value, see TS 24.501 section 9.11.3.37.
+fn encode_nssai(nssai: &[MappedSnssai]) -> Vec<u8> {
+    nssai.iter().flat_map(MappedSnssai::encode).collect()
+}
+
+/// Decode an NSSAI IE value, see TS 24.501 section 9.11.3.37.
+fn decode_nssai(mut value: &[u8]) -> Option<Vec<MappedSnssai>> {
+    let mut nssai = vec![];
+    while let Some(&length) = value.first() {
+        let length = length as usize;
+        nssai.push(MappedSnssai::decode(value.get(1..1 + length)?)?);
+        value = &value[1 + length..];
+    }
+    Some(nssai)
+}
+
+/// Encode an IE holding a list of S-NSSAIs, left out if the list is empty.
+fn encode_nssai_ie(iei: u8, nssai: Vec<u8>) -> Vec<u8> {
+    if nssai.is_empty() {
+        return vec![];
+    }
+    let mut buf = vec![iei, nssai.len() as u8];
+    buf.extend_from_slice(&nssai);
+    buf
+}
+
+/// Start a plain 5GMM message of the given type.
+fn header(message_type: MobilityMessageIdentifier) -> Vec<u8> {
+    vec![
+        ProtocolDiscriminator::MobilityManagement as u8,
+        SecurityHeader::NotProtected as u8,
+        message_type as u8,
+    ]
+}
+
+/// Registration Accept, see TS 24.501 section 8.2.7.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationAccept {
+    pub registration_result: u8,
+    pub guti: Option<Guti>,
+    /// The registration area, left out if empty
+    pub tai_list: Vec<Tai>,
+    pub allowed_nssai: Vec<MappedSnssai>,
+    /// Requested S-NSSAIs that are not allowed, left out if empty
+    pub rejected_nssai: Vec<RejectedSnssai>,
+    /// S-NSSAIs the UE may request in the PLMN, left out if empty
+    pub configured_nssai: Vec<MappedSnssai>,
+    /// PDU sessions the network still has, one bit per PDU session ID
+    pub pdu_session_status: Option<u16>,
+    /// PDU sessions whose user plane could not be re-established
+    pub pdu_session_reactivation_result: Option<u16>,
+}
+
+impl RegistrationAccept {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::REGISTRATION_ACCEPT);
+        buf.extend_from_slice(&[1, self.registration_result]);
+
+        if let Some(guti) = self.guti {
+            let guti = guti.encode();
+            buf.push(IEI_5G_GUTI);
+            buf.extend_from_slice(&(guti.len() as u16).to_be_bytes());
+            buf.extend_from_slice(&guti);
+        }
+
+        if !self.tai_list.is_empty() {
+            let tai_list = encode_tai_list(&self.tai_list);
+            buf.push(IEI_TAI_LIST);
+            buf.push(tai_list.len() as u8);
+            buf.extend_from_slice(&tai_list);
+        }
+
+        buf.extend_from_slice(&encode_nssai_ie(
+            IEI_ALLOWED_NSSAI,
+            encode_nssai(&self.allowed_nssai),
+        ));
+        buf.extend_from_slice(&encode_nssai_ie(
+            IEI_REJECTED_NSSAI,
+            self.rejected_nssai
+                .iter()
+                .flat_map(RejectedSnssai::encode)
+                .collect(),
+        ));
+        buf.extend_from_slice(&encode_nssai_ie(
+            IEI_CONFIGURED_NSSAI,
+            encode_nssai(&self.configured_nssai),
+        ));
+
+        if let Some(sessions) = self.pdu_session_status {
+            buf.extend_from_slice(&encode_pdu_session_bitmap(IEI_PDU_SESSION_STATUS, sessions));
+        }
+        if let Some(sessions) = self.pdu_session_reactivation_result {
+            buf.extend_from_slice(&encode_pdu_session_bitmap(
+                IEI_PDU_SESSION_REACTIVATION_RESULT,
+                sessions,
+            ));
+        }
+
+        buf
+    }
+}
+
+/// Registration Request, see TS 24.501 section 8.2.6.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationRequest {
+    pub registration_type: u8,
+    /// Whether the UE has pending signalling or data, and wants the NAS
+    /// signalling connection kept after the registration
+    pub follow_on_request: bool,
+    pub ngksi: u8,
+    /// The 5G-GUTI of the UE, if it identified itself with one
+    pub guti: Option<Guti>,
+    /// The SUCI of the UE, if it identified itself with one
+    pub suci: Option<Suci>,
+    /// The value of the UE security capability IE
+    pub ue_security_capability: Option<Vec<u8>>,
+    pub last_visited_tai: Option<Tai>,
+    /// The S-NSSAIs the UE wants to use, as values of the serving PLMN
+    pub requested_nssai: Option<Vec<MappedSnssai>>,
+    /// PDU sessions with pending uplink data, one bit per PDU session ID
+    pub uplink_data_status: Option<u16>,
+    /// PDU sessions the UE still has, one bit per PDU session ID
+    pub pdu_session_status: Option<u16>,
+}
+
+impl RegistrationRequest {
+    /// Decode a plain Registration Request message.
+    pub fn decode(buf: &[u8]) -> Option<RegistrationRequest> {
+        if *buf.get(2)? != MobilityMessageIdentifier::REGISTRATION_REQUEST as u8 {
+            return None;
+        }
+        let length = u16::from_be_bytes([*buf.get(4)?, *buf.get(5)?]) as usize;
+        let identity = buf.get(6..6 + length)?;
+
+        let mut request = RegistrationRequest {
+            registration_type: buf[3] & 0x07,
+            follow_on_request: buf[3] & 0x08 != 0,
+            ngksi: buf[3] >> 4,
+            guti: Guti::decode(identity),
+            suci: Suci::decode(identity),
+            ue_security_capability: None,
+            last_visited_tai: None,
+            requested_nssai: None,
+            uplink_data_status: None,
+            pdu_session_status: None,
+        };
+
+        let mut rest = &buf[6 + length..];
+        while let Some(&iei) = rest.first() {
+            // Type 1 IEs carry their value in the lower half of the IEI octet
+            if iei & 0x80 != 0 {
+                rest = &rest[1..];
+                continue;
+            }
+            if iei == IEI_LAST_VISITED_REGISTERED_TAI {
+                let value = rest.get(1..7)?;
+                request.last_visited_tai = Some(Tai {
+                    plmn: [value[0], value[1], value[2]],
+                    tac: [value[3], value[4], value[5]],
+                });
+                rest = &rest[7..];
+                continue;
+            }
+            // IEIs 0x7X are the type 6 IEs with a two octet length
+            if iei & 0xF0 == 0x70 {
+                let length = u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?]) as usize;
+                rest = rest.get(3 + length..)?;
+                continue;
+            }
+
+            let length = *rest.get(1)? as usize;
+            let value = rest.get(2..2 + length)?;
+            match iei {
+                IEI_UE_SECURITY_CAPABILITY => {
+                    request.ue_security_capability = Some(value.to_vec());
+                }
+                IEI_REQUESTED_NSSAI => {
+                    request.requested_nssai = decode_nssai(value);
+                }
+                IEI_UPLINK_DATA_STATUS => {
+                    request.uplink_data_status = decode_pdu_session_bitmap(value);
+                }
+                IEI_PDU_SESSION_STATUS => {
+                    request.pdu_session_status = decode_pdu_session_bitmap(value);
+                }
+                _ => {}
+            }
+            rest = &rest[2 + length..];
+        }
+
+        Some(request)
+    }
+}
+
+/// UL NAS Transport, see TS 24.501 section 8.2.10.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UlNasTransport {
+    pub payload_container_type: u8,
+    pub payload_container: Vec<u8>,
+    pub pdu_session_id: Option<u8>,
+    /// The S-NSSAI the UE wants the PDU session in
+    pub snssai: Option<MappedSnssai>,
+    /// Encoded as in the DNN IE, see [`crate::fgsm::decode_dnn`]
+    pub dnn: Option<Vec<u8>>,
+}
+
+impl UlNasTransport {
+    /// Decode a plain UL NAS Transport message.
+    pub fn decode(buf: &[u8]) -> Option<UlNasTransport> {
+        if *buf.get(2)? != MobilityMessageIdentifier::UPLINK_NAS_TRANSPORT as u8 {
+            return None;
+        }
+        let payload_container_type = buf.get(3)? & 0x0F;
+        let length = u16::from_be_bytes([*buf.get(4)?, *buf.get(5)?]) as usize;
+        let payload_container = buf.get(6..6 + length)?.to_vec();
+
+        let mut transport = UlNasTransport {
+            payload_container_type,
+            payload_container,
+            pdu_session_id: None,
+            snssai: None,
+            dnn: None,
+        };
+
+        let mut rest = &buf[6 + length..];
+        while let Some(&iei) = rest.first() {
+            // Type 1 IEs carry their value in the lower half of the IEI octet
+            if iei & 0x80 != 0 {
+                rest = &rest[1..];
+                continue;
+            }
+            if iei == IEI_PDU_SESSION_ID || iei == IEI_OLD_PDU_SESSION_ID {
+                let value = *rest.get(1)?;
+                if iei == IEI_PDU_SESSION_ID {
+                    transport.pdu_session_id = Some(value);
+                }
+                rest = &rest[2..];
+                continue;
+            }
+
+            let length = *rest.get(1)? as usize;
+            let value = rest.get(2..2 + length)?;
+            match iei {
+                IEI_S_NSSAI => transport.snssai = MappedSnssai::decode(value),
+                IEI_DNN => transport.dnn = Some(value.to_vec()),
+                _ => {}
+            }
+            rest = &rest[2 + length..];
+        }
+
+        Some(transport)
+    }
+}
+
+/// DL NAS Transport, see TS 24.501 section 8.2.11.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DlNasTransport {
+    pub payload_container_type: u8,
+    pub payload_container: Vec<u8>,
+    pub pdu_session_id: Option<u8>,
+    /// Why the payload is sent back to the UE instead of being forwarded
+    pub cause: Option<MobilityManagementCause>,
+}
+
+impl DlNasTransport {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::DOWNLINK_NAS_TRANSPORT);
+        buf.push(self.payload_container_type & 0x0F);
+        buf.extend_from_slice(&(self.payload_container.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&self.payload_container);
+
+        if let Some(pdu_session_id) = self.pdu_session_id {
+            buf.extend_from_slice(&[IEI_PDU_SESSION_ID, pdu_session_id]);
+        }
+        if let Some(cause) = self.cause {
+            buf.extend_from_slice(&[IEI_5GMM_CAUSE, cause as u8]);
+        }
+
+        buf
+    }
+}
+
+/// Service Request, see TS 24.501 section 8.2.16.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceRequest {
+    pub ngksi: u8,
+    pub service_type: u8,
+    pub amf_set_id: u16,
+    pub amf_pointer: u8,
+    pub tmsi: u32,
+    /// PDU sessions with pending uplink data, one bit per PDU session ID
+    pub uplink_data_status: Option<u16>,
+    /// PDU sessions the UE still has, one bit per PDU session ID
+    pub pdu_session_status: Option<u16>,
+    /// The complete, ciphered Service Request if the UE had to send one
+    pub nas_message_container: Option<Vec<u8>>,
+}
+
+impl ServiceRequest {
+    /// Decode a plain Service Request message.
+    pub fn decode(buf: &[u8]) -> Option<ServiceRequest> {
+        if *buf.get(2)? != MobilityMessageIdentifier::SERVICE_REQUEST as u8 {
+            return None;
+        }
+        let ngksi = buf.get(3)? & 0x0F;
+        let service_type = buf.get(3)? >> 4;
+
+        // The 5G-S-TMSI is a 5GS mobile identity IE of fixed length
+        let length = u16::from_be_bytes([*buf.get(4)?, *buf.get(5)?]) as usize;
+        let identity = buf.get(6..6 + length)?;
+        if length != 7 || identity[0] & 0x07 != IDENTITY_TYPE_5G_S_TMSI {
+            return None;
+        }
+
+        let mut request = ServiceRequest {
+            ngksi,
+            service_type,
+            amf_set_id: (identity[1] as u16) << 2 | (identity[2] >> 6) as u16,
+            amf_pointer: identity[2] & 0x3F,
+            tmsi: u32::from_be_bytes(identity[3..7].try_into().unwrap()),
+            uplink_data_status: None,
+            pdu_session_status: None,
+            nas_message_container: None,
+        };
+
+        let mut rest = &buf[6 + length..];
+        while let Some(&iei) = rest.first() {
+            if iei == IEI_NAS_MESSAGE_CONTAINER {
+                let length = u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?]) as usize;
+                request.nas_message_container = Some(rest.get(3..3 + length)?.to_vec());
+                rest = &rest[3 + length..];
+                continue;
+            }
+
+            let length = *rest.get(1)? as usize;
+            let value = rest.get(2..2 + length)?;
+            match iei {
+                IEI_UPLINK_DATA_STATUS => {
+                    request.uplink_data_status = decode_pdu_session_bitmap(value);
+                }
+                IEI_PDU_SESSION_STATUS => {
+                    request.pdu_session_status = decode_pdu_session_bitmap(value);
+                }
+                _ => {}
+            }
+            rest = &rest[2 + length..];
+        }
+
+        Some(request)
+    }
+}
+
+/// Service Accept, see TS 24.501 section 8.2.17.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServiceAccept {
+    /// PDU sessions the network still has, one bit per PDU session ID
+    pub pdu_session_status: Option<u16>,
+    /// PDU sessions whose user plane could not be re-established
+    pub pdu_session_reactivation_result: Option<u16>,
+}
+
+impl ServiceAccept {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::SERVICE_ACCEPT);
+        if let Some(sessions) = self.pdu_session_status {
+            buf.extend_from_slice(&encode_pdu_session_bitmap(IEI_PDU_SESSION_STATUS, sessions));
+        }
+        if let Some(sessions) = self.pdu_session_reactivation_result {
+            buf.extend_from_slice(&encode_pdu_session_bitmap(
+                IEI_PDU_SESSION_REACTIVATION_RESULT,
+                sessions,
+            ));
+        }
+        buf
+    }
+}
+
+/// Service Reject, see TS 24.501 section 8.2.18.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServiceReject {
+    pub cause: MobilityManagementCause,
+    pub pdu_session_status: Option<u16>,
+    /// Back-off timer in seconds, for a rejection because of congestion
+    pub t3346: Option<u32>,
+}
+
+impl ServiceReject {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::SERVICE_REJECT);
+        buf.push(self.cause as u8);
+        if let Some(sessions) = self.pdu_session_status {
+            buf.extend_from_slice(&encode_pdu_session_bitmap(IEI_PDU_SESSION_STATUS, sessions));
+        }
+        if let Some(seconds) = self.t3346 {
+            buf.extend_from_slice(&encode_gprs_timer_2(IEI_T3346_VALUE, seconds));
+        }
+        buf
+    }
+}
+
+/// Registration Reject message, see TS 24.501 section 8.2.9.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationReject {
+    pub cause: MobilityManagementCause,
+    /// Back-off timer in seconds, for a rejection because of congestion
+    pub t3346: Option<u32>,
+    /// Requested S-NSSAIs that are not allowed, left out if empty
+    pub rejected_nssai: Vec<RejectedSnssai>,
+}
+
+impl RegistrationReject {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::REGISTRATION_REJECT);
+        buf.push(self.cause as u8);
+        if let Some(seconds) = self.t3346 {
+            buf.extend_from_slice(&encode_gprs_timer_2(IEI_T3346_VALUE, seconds));
+        }
+        buf.extend_from_slice(&encode_nssai_ie(
+            IEI_REJECTED_NSSAI_REGISTRATION_REJECT,
+            self.rejected_nssai
+                .iter()
+                .flat_map(RejectedSnssai::encode)
+                .collect(),
+        ));
+        buf
+    }
+}
+
+/// 5GMM Status message, reporting an error in a message from the UE, see
+/// TS 24.501 section 8.2.29.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MobilityManagementStatus {
+    pub cause: MobilityManagementCause,
+}
+
+impl MobilityManagementStatus {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::STATUS);
+        buf.push(self.cause as u8);
+        buf
+    }
+}
+
+/// De-registration Request sent by the UE, see TS 24.501 section 8.2.12.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeregistrationRequestUeOriginating {
+    pub switch_off: bool,
+    pub access_type: u8,
+    pub ngksi: u8,
+    /// The 5G-GUTI of the UE, if it identified itself with one
+    pub guti: Option<Guti>,
+}
+
+impl DeregistrationRequestUeOriginating {
+    /// Decode a plain De-registration Request message.
+    pub fn decode(buf: &[u8]) -> Option<DeregistrationRequestUeOriginating> {
+        if *buf.get(2)? != MobilityMessageIdentifier::DEREGISTRATION_REQUEST as u8 {
+            return None;
+        }
+        let length = u16::from_be_bytes([*buf.get(4)?, *buf.get(5)?]) as usize;
+        let identity = buf.get(6..6 + length)?;
+
+        Some(DeregistrationRequestUeOriginating {
+            switch_off: buf[3] & 0x08 != 0,
+            access_type: buf[3] & 0x03,
+            ngksi: buf[3] >> 4,
+            guti: Guti::decode(identity),
+        })
+    }
+}
+
+/// De-registration Accept sent to a UE that de-registered, see TS 24.501
+/// section 8.2.13.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeregistrationAcceptUeOriginating;
+
+impl DeregistrationAcceptUeOriginating {
+    pub fn encode(&self) -> Vec<u8> {
+        header(MobilityMessageIdentifier::DEREGISTRATION_ACCEPT)
+    }
+}
+
+/// De-registration Request sent by the network, see TS 24.501 section 8.2.14.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeregistrationRequestUeTerminated {
+    pub re_registration_required: bool,
+    pub access_type: u8,
+    pub cause: Option<MobilityManagementCause>,
+}
+
+impl DeregistrationRequestUeTerminated {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::DEREGISTRATION_REQUEST_UE_TERMINATED);
+        let re_registration_required = if self.re_registration_required {
+            0x04
+        } else {
+            0x00
+        };
+        buf.push(re_registration_required | (self.access_type & 0x03));
+        if let Some(cause) = self.cause {
+            buf.extend_from_slice(&[IEI_5GMM_CAUSE, cause as u8]);
+        }
+        buf
+    }
+}
+
+/// Identity Request asking the UE for its SUCI, see TS 24.501 section 8.2.21.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdentityRequest;
+
+impl IdentityRequest {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::IDENTITY_REQUEST);
+        buf.push(IDENTITY_TYPE_SUCI);
+        buf
+    }
+}
+
+/// Identity Response, see TS 24.501 section 8.2.22.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdentityResponse {
+    /// The SUCI of the UE, if that is the identity it sent
+    pub suci: Option<Suci>,
+}
+
+impl IdentityResponse {
+    /// Decode a plain Identity Response message.
+    pub fn decode(buf: &[u8]) -> Option<IdentityResponse> {
+        if *buf.get(2)? != MobilityMessageIdentifier::IDENTITY_RESPONSE as u8 {
+            return None;
+        }
+        let length = u16::from_be_bytes([*buf.get(3)?, *buf.get(4)?]) as usize;
+        let identity = buf.get(5..5 + length)?;
+        Some(IdentityResponse {
+            suci: Suci::decode(identity),
+        })
+    }
+}
+
+/// Authentication Request of 5G AKA, see TS 24.501 section 8.2.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthenticationRequest {
+    /// The ngKSI that the new NAS security context is to get
+    pub ngksi: u8,
+    pub rand: [u8; 16],
+    pub autn: [u8; 16],
+}
+
+impl AuthenticationRequest {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::AUTHENTICATION_REQUEST);
+        buf.push(self.ngksi & 0x0F);
+        buf.push(crate::aka::ABBA.len() as u8);
+        buf.extend_from_slice(&crate::aka::ABBA);
+        buf.push(IEI_RAND);
+        buf.extend_from_slice(&self.rand);
+        buf.extend_from_slice(&[IEI_AUTN, self.autn.len() as u8]);
+        buf.extend_from_slice(&self.autn);
+        buf
+    }
+}
+
+/// Authentication Response, see TS 24.501 section 8.2.2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthenticationResponse {
+    /// RES* computed by the UE, which is left out for EAP-AKA'
+    pub res_star: Option<[u8; 16]>,
+}
+
+impl AuthenticationResponse {
+    /// Decode a plain Authentication Response message.
+    pub fn decode(buf: &[u8]) -> Option<AuthenticationResponse> {
+        if *buf.get(2)? != MobilityMessageIdentifier::AUTHENTICATION_RESPONSE as u8 {
+            return None;
+        }
+
+        let mut response = AuthenticationResponse { res_star: None };
+        let mut rest = &buf[3..];
+        while let Some(&iei) = rest.first() {
+            // The EAP message IE is a type 6 IE with a two octet length
+            if iei & 0xF0 == 0x70 {
+                let length = u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?]) as usize;
+                rest = rest.get(3 + length..)?;
+                continue;
+            }
+
+            let length = *rest.get(1)? as usize;
+            let value = rest.get(2..2 + length)?;
+            if iei == IEI_AUTHENTICATION_RESPONSE_PARAMETER {
+                response.res_star = value.try_into().ok();
+            }
+            rest = &rest[2 + length..];
+        }
+
+        Some(response)
+    }
+}
+
+/// Authentication Failure, see TS 24.501 section 8.2.4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthenticationFailure {
+    pub cause: MobilityManagementCause,
+    /// AUTS, sent along with cause synch failure
+    pub auts: Option<[u8; 14]>,
+}
+
+impl AuthenticationFailure {
+    /// Decode a plain Authentication Failure message.
+    pub fn decode(buf: &[u8]) -> Option<AuthenticationFailure> {
+        if *buf.get(2)? != MobilityMessageIdentifier::AUTHENTICATION_FAILURE as u8 {
+            return None;
+        }
+        let cause = MobilityManagementCause::from_u8(*buf.get(3)?)?;
+
+        let mut auts = None;
+        let mut rest = &buf[4..];
+        while let Some(&iei) = rest.first() {
+            let length = *rest.get(1)? as usize;
+            let value = rest.get(2..2 + length)?;
+            if iei == IEI_AUTHENTICATION_FAILURE_PARAMETER {
+                auts = value.try_into().ok();
+            }
+            rest = &rest[2 + length..];
+        }
+
+        Some(AuthenticationFailure { cause, auts })
+    }
+}
+
+/// Authentication Reject, see TS 24.501 section 8.2.5.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthenticationReject;
+
+impl AuthenticationReject {
+    pub fn encode(&self) -> Vec<u8> {
+        header(MobilityMessageIdentifier::AUTHENTICATION_REJECT)
+    }
+}
+
+/// Security Mode Command, see TS 24.501 section 8.2.25.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityModeCommand {
+    /// Type of the selected ciphering and integrity algorithms, see TS
+    /// 24.501 section 9.11.3.34
+    pub ciphering_algorithm: u8,
+    pub integrity_algorithm: u8,
+    pub ngksi: u8,
+    /// The UE security capability the UE sent, for it to check that it was
+    /// not tampered with
+    pub replayed_ue_security_capability: Vec<u8>,
+    /// Whether the UE is to send its initial NAS message again in the
+    /// Security Mode Complete, in full and ciphered
+    pub request_initial_nas_message: bool,
+}
+
+impl SecurityModeCommand {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(MobilityMessageIdentifier::SECURITY_MODE_COMMAND);
+        buf.push((self.ciphering_algorithm << 4) | (self.integrity_algorithm & 0x0F));
+        buf.push(self.ngksi & 0x0F);
+        buf.push(self.replayed_ue_security_capability.len() as u8);
+        buf.extend_from_slice(&self.replayed_ue_security_capability);
+        if self.request_initial_nas_message {
+            buf.extend_from_slice(&[
+                IEI_ADDITIONAL_5G_SECURITY_INFORMATION,
+                1,
+                RETRANSMISSION_OF_INITIAL_NAS_MESSAGE_REQUEST,
+            ]);
+        }
+        buf
+    }
+}
+
+/// Security Mode Complete, see TS 24.501 section 8.2.26.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityModeComplete {
+    /// The initial NAS message of the UE, if it was asked to send it again
+    /// or could not send it in full before
+    pub nas_message_container: Option<Vec<u8>>,
+}
+
+impl SecurityModeComplete {
+    /// Decode a plain Security Mode Complete message.
+    pub fn decode(buf: &[u8]) -> Option<SecurityModeComplete> {
+        if *buf.get(2)? != MobilityMessageIdentifier::SECURITY_MODE_COMPLETE as u8 {
+            return None;
+        }
+
+        // All of the optional IEs are type 6 IEs with a two octet length
+        let mut nas_message_container = None;
+        let mut rest = &buf[3..];
+        while let Some(&iei) = rest.first() {
+            let length = u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?]) as usize;
+            let value = rest.get(3..3 + length)?;
+            if iei == IEI_NAS_MESSAGE_CONTAINER {
+                nas_message_container = Some(value.to_vec());
+            }
+            rest = &rest[3 + length..];
+        }
+
+        Some(SecurityModeComplete {
+            nas_message_container,
+        })
+    }
+}
@@ -0,0 +1,539 @@
+use super::*;
+
+#[test]
+fn test_guti_encode() {
+    let guti = Guti {
+        plmn: [0x02, 0xf8, 0x39],
+        amf_region_id: 0x02,
+        amf_set_id: 0x001,
+        amf_pointer: 0x00,
+        tmsi: 0x0000_0001,
+    };
+    assert_eq!(
+        guti.encode(),
+        vec![0xF2, 0x02, 0xf8, 0x39, 0x02, 0x00, 0x40, 0x00, 0x00, 0x00, 0x01]
+    );
+}
+
+#[test]
+fn test_registration_accept_encode() {
+    let accept = RegistrationAccept {
+        registration_result: REGISTRATION_RESULT_3GPP_ACCESS,
+        guti: None,
+        tai_list: vec![],
+        allowed_nssai: vec![
+            MappedSnssai {
+                snssai: Snssai { sst: 1, sd: None },
+                hplmn: None,
+            },
+            MappedSnssai {
+                snssai: Snssai {
+                    sst: 2,
+                    sd: Some([0x00, 0x00, 0x01]),
+                },
+                hplmn: None,
+            },
+        ],
+        rejected_nssai: vec![],
+        configured_nssai: vec![],
+        pdu_session_status: None,
+        pdu_session_reactivation_result: None,
+    };
+    assert_eq!(
+        accept.encode(),
+        vec![0x7E, 0x00, 0x42, 0x01, 0x01, 0x15, 0x07, 0x01, 0x01, 0x04, 0x02, 0x00, 0x00, 0x01]
+    );
+}
+
+#[test]
+fn test_registration_accept_nssai_encode() {
+    let accept = RegistrationAccept {
+        registration_result: REGISTRATION_RESULT_3GPP_ACCESS,
+        guti: None,
+        tai_list: vec![],
+        allowed_nssai: vec![MappedSnssai {
+            snssai: Snssai { sst: 1, sd: None },
+            hplmn: Some(Snssai { sst: 2, sd: None }),
+        }],
+        rejected_nssai: vec![
+            RejectedSnssai {
+                snssai: Snssai { sst: 3, sd: None },
+                cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_PLMN,
+            },
+            RejectedSnssai {
+                snssai: Snssai {
+                    sst: 4,
+                    sd: Some([0x00, 0x00, 0x01]),
+                },
+                cause: REJECTED_NSSAI_CAUSE_NOT_AVAILABLE_IN_REGISTRATION_AREA,
+            },
+        ],
+        configured_nssai: vec![MappedSnssai {
+            snssai: Snssai { sst: 1, sd: None },
+            hplmn: Some(Snssai { sst: 2, sd: None }),
+        }],
+        pdu_session_status: None,
+        pdu_session_reactivation_result: None,
+    };
+    assert_eq!(
+        accept.encode(),
+        [
+            &[0x7E, 0x00, 0x42, 0x01, 0x01][..],
+            // Allowed NSSAI
+            &[0x15, 0x03, 0x02, 0x01, 0x02],
+            // Rejected NSSAI
+            &[0x11, 0x07, 0x10, 0x03, 0x41, 0x04, 0x00, 0x00, 0x01],
+            // Configured NSSAI
+            &[0x31, 0x03, 0x02, 0x01, 0x02],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_mapped_snssai() {
+    let sd = Some([0x00, 0x00, 0x01]);
+    for (mapped, value) in [
+        (
+            MappedSnssai {
+                snssai: Snssai { sst: 1, sd: None },
+                hplmn: None,
+            },
+            vec![0x01],
+        ),
+        (
+            MappedSnssai {
+                snssai: Snssai { sst: 1, sd },
+                hplmn: Some(Snssai { sst: 2, sd: None }),
+            },
+            vec![0x01, 0x00, 0x00, 0x01, 0x02],
+        ),
+        (
+            MappedSnssai {
+                snssai: Snssai { sst: 1, sd: None },
+                hplmn: Some(Snssai { sst: 2, sd }),
+            },
+            vec![0x01, 0xFF, 0xFF, 0xFF, 0x02, 0x00, 0x00, 0x01],
+        ),
+    ] {
+        assert_eq!(mapped.encode(), [&[value.len() as u8][..], &value].concat());
+        assert_eq!(MappedSnssai::decode(&value), Some(mapped));
+    }
+    assert_eq!(MappedSnssai::decode(&[0x01, 0x00, 0x00]), None);
+}
+
+#[test]
+fn test_ul_nas_transport_decode() {
+    let buf = [
+        &[0x7E, 0x00, 0x67, 0x01, 0x00, 0x04, 0x2E, 0x01, 0x05, 0xC1][..],
+        // PDU session ID
+        &[0x12, 0x01],
+        // Request type
+        &[0x81],
+        // S-NSSAI
+        &[0x22, 0x01, 0x01],
+        // DNN
+        &[0x25, 0x02, 0x01, b'a'],
+    ]
+    .concat();
+    assert_eq!(
+        UlNasTransport::decode(&buf),
+        Some(UlNasTransport {
+            payload_container_type: PAYLOAD_CONTAINER_N1_SM_INFORMATION,
+            payload_container: vec![0x2E, 0x01, 0x05, 0xC1],
+            pdu_session_id: Some(1),
+            snssai: Some(MappedSnssai {
+                snssai: Snssai { sst: 1, sd: None },
+                hplmn: None,
+            }),
+            dnn: Some(vec![0x01, b'a']),
+        })
+    );
+
+    // Truncated payload container
+    assert_eq!(UlNasTransport::decode(&buf[..8]), None);
+}
+
+#[test]
+fn test_dl_nas_transport_encode() {
+    let transport = DlNasTransport {
+        payload_container_type: PAYLOAD_CONTAINER_N1_SM_INFORMATION,
+        payload_container: vec![0x2E, 0x01, 0x05, 0xC3, 27],
+        pdu_session_id: Some(1),
+        cause: None,
+    };
+    assert_eq!(
+        transport.encode(),
+        vec![0x7E, 0x00, 0x68, 0x01, 0x00, 0x05, 0x2E, 0x01, 0x05, 0xC3, 27, 0x12, 0x01]
+    );
+}
+
+#[test]
+fn test_service_request_decode() {
+    let buf = [
+        &[0x7E, 0x00, 0x4C, 0x10][..],
+        // 5G-S-TMSI
+        &[0x00, 0x07, 0xF4, 0x00, 0x40, 0x00, 0x00, 0x00, 0x01],
+        // Uplink data status, PDU session 5
+        &[0x40, 0x02, 0x20, 0x00],
+        // PDU session status, PDU sessions 5 and 9
+        &[0x50, 0x02, 0x20, 0x02],
+    ]
+    .concat();
+    assert_eq!(
+        ServiceRequest::decode(&buf),
+        Some(ServiceRequest {
+            ngksi: 0,
+            service_type: SERVICE_TYPE_DATA,
+            amf_set_id: 0x001,
+            amf_pointer: 0x00,
+            tmsi: 0x0000_0001,
+            uplink_data_status: Some(1 << 5),
+            pdu_session_status: Some(1 << 5 | 1 << 9),
+            nas_message_container: None,
+        })
+    );
+
+    // Not a 5G-S-TMSI
+    let mut other_identity = buf.clone();
+    other_identity[6] = 0xF2;
+    assert_eq!(ServiceRequest::decode(&other_identity), None);
+}
+
+#[test]
+fn test_service_accept_encode() {
+    let accept = ServiceAccept {
+        pdu_session_status: Some(1 << 5),
+        pdu_session_reactivation_result: Some(1 << 9),
+    };
+    assert_eq!(
+        accept.encode(),
+        vec![0x7E, 0x00, 0x4E, 0x50, 0x02, 0x20, 0x00, 0x26, 0x02, 0x00, 0x02]
+    );
+}
+
+#[test]
+fn test_service_reject_encode() {
+    let reject = ServiceReject {
+        cause: MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED,
+        pdu_session_status: None,
+        t3346: None,
+    };
+    assert_eq!(reject.encode(), vec![0x7E, 0x00, 0x4D, 0x09]);
+
+    let reject = ServiceReject {
+        cause: MobilityManagementCause::CONGESTION,
+        pdu_session_status: Some(1 << 1),
+        t3346: Some(600),
+    };
+    assert_eq!(
+        reject.encode(),
+        vec![0x7E, 0x00, 0x4D, 0x16, 0x50, 0x02, 0x02, 0x00, 0x5F, 0x01, 0x2A]
+    );
+}
+
+#[test]
+fn test_registration_reject_encode() {
+    let reject = RegistrationReject {
+        cause: MobilityManagementCause::PLMN_NOT_ALLOWED,
+        t3346: None,
+        rejected_nssai: vec![],
+    };
+    assert_eq!(reject.encode(), vec![0x7E, 0x00, 0x44, 0x0B]);
+
+    // Timers are rounded up to the precision of their unit
+    for (seconds, value) in [(61, 0x1F), (63, 0x22), (3600, 0x4A), (100_000, 0x5F)] {
+        let reject = RegistrationReject {
+            cause: MobilityManagementCause::CONGESTION,
+            t3346: Some(seconds),
+            rejected_nssai: vec![],
+        };
+        assert_eq!(
+            reject.encode(),
+            vec![0x7E, 0x00, 0x44, 0x16, 0x5F, 0x01, value]
+        );
+    }
+}
+
+#[test]
+fn test_mobility_management_status_encode() {
+    let status = MobilityManagementStatus {
+        cause: MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED,
+    };
+    assert_eq!(status.encode(), vec![0x7E, 0x00, 0x64, 0x61]);
+}
+
+#[test]
+fn test_deregistration_request_ue_originating_decode() {
+    let guti = Guti {
+        plmn: [0x02, 0xf8, 0x39],
+        amf_region_id: 0x02,
+        amf_set_id: 0x001,
+        amf_pointer: 0x00,
+        tmsi: 0x0000_0001,
+    };
+    let buf = [&[0x7E, 0x00, 0x45, 0x09, 0x00, 0x0B][..], &guti.encode()].concat();
+    assert_eq!(
+        DeregistrationRequestUeOriginating::decode(&buf),
+        Some(DeregistrationRequestUeOriginating {
+            switch_off: true,
+            access_type: ACCESS_TYPE_3GPP,
+            ngksi: 0,
+            guti: Some(guti),
+        })
+    );
+}
+
+#[test]
+fn test_deregistration_request_ue_terminated_encode() {
+    let request = DeregistrationRequestUeTerminated {
+        re_registration_required: true,
+        access_type: ACCESS_TYPE_3GPP,
+        cause: None,
+    };
+    assert_eq!(request.encode(), vec![0x7E, 0x00, 0x47, 0x05]);
+
+    let request = DeregistrationRequestUeTerminated {
+        re_registration_required: false,
+        access_type: ACCESS_TYPE_3GPP_AND_NON_3GPP,
+        cause: Some(MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED),
+    };
+    assert_eq!(request.encode(), vec![0x7E, 0x00, 0x47, 0x03, 0x58, 0x09]);
+}
+
+#[test]
+fn test_registration_accept_tai_list_encode() {
+    let accept = RegistrationAccept {
+        registration_result: REGISTRATION_RESULT_3GPP_ACCESS,
+        guti: None,
+        tai_list: vec![Tai {
+            plmn: [0x02, 0xf8, 0x39],
+            tac: [0x00, 0x00, 0x01],
+        }],
+        allowed_nssai: vec![],
+        rejected_nssai: vec![],
+        configured_nssai: vec![],
+        pdu_session_status: Some(1 << 5),
+        pdu_session_reactivation_result: None,
+    };
+    assert_eq!(
+        accept.encode(),
+        [
+            &[0x7E, 0x00, 0x42, 0x01, 0x01][..],
+            // TAI list of a single TAI
+            &[0x54, 0x07, 0x40, 0x02, 0xf8, 0x39, 0x00, 0x00, 0x01],
+            // PDU session status
+            &[0x50, 0x02, 0x20, 0x00],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_registration_request_decode() {
+    let guti = Guti {
+        plmn: [0x02, 0xf8, 0x39],
+        amf_region_id: 0x02,
+        amf_set_id: 0x001,
+        amf_pointer: 0x00,
+        tmsi: 0x0000_0001,
+    };
+    let buf = [
+        // Mobility registration updating with follow-on request, ngKSI 1
+        &[0x7E, 0x00, 0x41, 0x1A, 0x00, 0x0B][..],
+        &guti.encode(),
+        // UE security capability
+        &[0x2E, 0x02, 0xE0, 0xE0],
+        // Last visited registered TAI
+        &[0x52, 0x02, 0xf8, 0x39, 0x00, 0x00, 0x02],
+        // MICO indication
+        &[0xB1],
+        // Requested NSSAI
+        &[0x2F, 0x05, 0x01, 0x01, 0x02, 0x02, 0x01],
+        // NAS message container
+        &[0x71, 0x00, 0x02, 0x7E, 0x00],
+        // PDU session status
+        &[0x50, 0x02, 0x20, 0x00],
+    ]
+    .concat();
+    assert_eq!(
+        RegistrationRequest::decode(&buf),
+        Some(RegistrationRequest {
+            registration_type: REGISTRATION_TYPE_MOBILITY_UPDATING,
+            follow_on_request: true,
+            ngksi: 1,
+            guti: Some(guti),
+            suci: None,
+            ue_security_capability: Some(vec![0xE0, 0xE0]),
+            last_visited_tai: Some(Tai {
+                plmn: [0x02, 0xf8, 0x39],
+                tac: [0x00, 0x00, 0x02],
+            }),
+            requested_nssai: Some(vec![
+                MappedSnssai {
+                    snssai: Snssai { sst: 1, sd: None },
+                    hplmn: None,
+                },
+                MappedSnssai {
+                    snssai: Snssai { sst: 2, sd: None },
+                    hplmn: Some(Snssai { sst: 1, sd: None }),
+                },
+            ]),
+            uplink_data_status: None,
+            pdu_session_status: Some(1 << 5),
+        })
+    );
+}
+
+/// The value of a 5GS mobile identity IE holding the SUCI of IMSI
+/// 208930000000001 with the null scheme.
+const SUCI: [u8; 13] = [
+    0x01, 0x02, 0xf8, 0x39, 0xf0, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+#[test]
+fn test_suci_decode() {
+    let suci = Suci::decode(&SUCI).unwrap();
+    assert_eq!(suci.protection_scheme, PROTECTION_SCHEME_NULL);
+    assert_eq!(suci.supi(), Some("imsi-208930000000001".to_string()));
+
+    // A concealed SUPI can not be read
+    let mut concealed = SUCI;
+    concealed[6] = 0x01;
+    assert_eq!(Suci::decode(&concealed).unwrap().supi(), None);
+
+    // Not a SUCI
+    assert_eq!(
+        Suci::decode(&[0xF2, 0x02, 0xf8, 0x39, 0x02, 0x00, 0x40, 0x00]),
+        None
+    );
+}
+
+#[test]
+fn test_registration_request_with_suci_decode() {
+    let buf = [
+        &[0x7E, 0x00, 0x41, 0x79, 0x00, 0x0D][..],
+        &SUCI,
+        &[0x2E, 0x02, 0xE0, 0xE0],
+    ]
+    .concat();
+    let request = RegistrationRequest::decode(&buf).unwrap();
+    assert_eq!(request.registration_type, REGISTRATION_TYPE_INITIAL);
+    assert_eq!(request.ngksi, 7);
+    assert_eq!(request.guti, None);
+    assert_eq!(request.suci, Suci::decode(&SUCI));
+    assert_eq!(request.ue_security_capability, Some(vec![0xE0, 0xE0]));
+}
+
+#[test]
+fn test_identity_request_encode() {
+    assert_eq!(IdentityRequest.encode(), vec![0x7E, 0x00, 0x5B, 0x01]);
+}
+
+#[test]
+fn test_identity_response_decode() {
+    let buf = [&[0x7E, 0x00, 0x5C, 0x00, 0x0D][..], &SUCI].concat();
+    assert_eq!(
+        IdentityResponse::decode(&buf),
+        Some(IdentityResponse {
+            suci: Suci::decode(&SUCI),
+        })
+    );
+    assert_eq!(IdentityResponse::decode(&buf[..10]), None);
+}
+
+#[test]
+fn test_authentication_request_encode() {
+    let request = AuthenticationRequest {
+        ngksi: 1,
+        rand: [0x11; 16],
+        autn: [0x22; 16],
+    };
+    let expected = [
+        &[0x7E, 0x00, 0x56, 0x01, 0x02, 0x00, 0x00, 0x21][..],
+        &[0x11; 16],
+        &[0x20, 0x10],
+        &[0x22; 16],
+    ]
+    .concat();
+    assert_eq!(request.encode(), expected);
+}
+
+#[test]
+fn test_authentication_response_decode() {
+    let buf = [&[0x7E, 0x00, 0x57, 0x2D, 0x10][..], &[0x33; 16]].concat();
+    assert_eq!(
+        AuthenticationResponse::decode(&buf),
+        Some(AuthenticationResponse {
+            res_star: Some([0x33; 16]),
+        })
+    );
+
+    // EAP-AKA' carries the response in an EAP message instead
+    let buf = [0x7E, 0x00, 0x57, 0x78, 0x00, 0x02, 0x01, 0x02];
+    assert_eq!(
+        AuthenticationResponse::decode(&buf),
+        Some(AuthenticationResponse { res_star: None })
+    );
+}
+
+#[test]
+fn test_authentication_failure_decode() {
+    let buf = [&[0x7E, 0x00, 0x59, 0x15, 0x30, 0x0E][..], &[0x44; 14]].concat();
+    assert_eq!(
+        AuthenticationFailure::decode(&buf),
+        Some(AuthenticationFailure {
+            cause: MobilityManagementCause::SYNCH_FAILURE,
+            auts: Some([0x44; 14]),
+        })
+    );
+
+    let buf = [0x7E, 0x00, 0x59, 0x14];
+    assert_eq!(
+        AuthenticationFailure::decode(&buf),
+        Some(AuthenticationFailure {
+            cause: MobilityManagementCause::MAC_FAILURE,
+            auts: None,
+        })
+    );
+}
+
+#[test]
+fn test_authentication_reject_encode() {
+    assert_eq!(AuthenticationReject.encode(), vec![0x7E, 0x00, 0x58]);
+}
+
+#[test]
+fn test_security_mode_command_encode() {
+    let command = SecurityModeCommand {
+        ciphering_algorithm: 2,
+        integrity_algorithm: 2,
+        ngksi: 1,
+        replayed_ue_security_capability: vec![0xE0, 0xE0],
+        request_initial_nas_message: true,
+    };
+    assert_eq!(
+        command.encode(),
+        vec![0x7E, 0x00, 0x5D, 0x22, 0x01, 0x02, 0xE0, 0xE0, 0x36, 0x01, 0x02]
+    );
+}
+
+#[test]
+fn test_security_mode_complete_decode() {
+    let buf = [
+        &[0x7E, 0x00, 0x5E][..],
+        // IMEISV
+        &[
+            0x77, 0x00, 0x09, 0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0,
+        ],
+        // NAS message container
+        &[0x71, 0x00, 0x03, 0x7E, 0x00, 0x41],
+    ]
+    .concat();
+    assert_eq!(
+        SecurityModeComplete::decode(&buf),
+        Some(SecurityModeComplete {
+            nas_message_container: Some(vec![0x7E, 0x00, 0x41]),
+        })
+    );
+}
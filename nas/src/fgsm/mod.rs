@@ -0,0 +1,159 @@
+//! Encoding of 5GS session management messages, see TS 24.501 section 8.3.
+
+use crate::fgmm::Snssai;
+use crate::{ProtocolDiscriminator, SessionMessageIdentifier};
+
+#[cfg(test)]
+mod tests;
+
+/// PDU session type values, TS 24.501 section 9.11.4.11.
+pub const PDU_SESSION_TYPE_IPV4: u8 = 0x01;
+
+/// SSC mode values, TS 24.501 section 9.11.4.16.
+pub const SSC_MODE_1: u8 = 0x01;
+
+/// 5GSM cause values, TS 24.501 section 9.11.4.2.
+pub const CAUSE_INSUFFICIENT_RESOURCES: u8 = 26;
+pub const CAUSE_MISSING_OR_UNKNOWN_DNN: u8 = 27;
+
+const IEI_PDU_ADDRESS: u8 = 0x29;
+const IEI_S_NSSAI: u8 = 0x22;
+const IEI_DNN: u8 = 0x25;
+
+/// Unit of the Session-AMBR values, 1 Mbps, TS 24.501 section 9.11.4.14.
+const AMBR_UNIT_1_MBPS: u8 = 0x06;
+
+/// Start a 5GSM message of the given type.
+fn header(pdu_session_id: u8, pti: u8, message_type: SessionMessageIdentifier) -> Vec<u8> {
+    vec![
+        ProtocolDiscriminator::SessionManagement as u8,
+        pdu_session_id,
+        pti,
+        message_type as u8,
+    ]
+}
+
+/// Decode a DNN from its APN-style encoding, a sequence of length-prefixed
+/// labels, see TS 23.003 section 9.1.
+pub fn decode_dnn(buf: &[u8]) -> Option<String> {
+    let mut labels = vec![];
+    let mut rest = buf;
+    while let Some(&length) = rest.first() {
+        let label = rest.get(1..1 + length as usize)?;
+        labels.push(std::str::from_utf8(label).ok()?);
+        rest = &rest[1 + length as usize..];
+    }
+    Some(labels.join("."))
+}
+
+pub fn encode_dnn(dnn: &str) -> Vec<u8> {
+    dnn.split('.')
+        .flat_map(|label| std::iter::once(label.len() as u8).chain(label.bytes()))
+        .collect()
+}
+
+/// The header of a PDU Session Establishment Request, see TS 24.501 section
+/// 8.3.1. None of its optional IEs are needed to set up a default session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PduSessionEstablishmentRequest {
+    pub pdu_session_id: u8,
+    pub pti: u8,
+}
+
+impl PduSessionEstablishmentRequest {
+    pub fn decode(buf: &[u8]) -> Option<PduSessionEstablishmentRequest> {
+        if ProtocolDiscriminator::from_u8(*buf.first()?)?
+            != ProtocolDiscriminator::SessionManagement
+            || *buf.get(3)? != SessionMessageIdentifier::PDU_SESSION_ESTABLISHMENT_REQUEST as u8
+        {
+            return None;
+        }
+        Some(PduSessionEstablishmentRequest {
+            pdu_session_id: buf[1],
+            pti: buf[2],
+        })
+    }
+}
+
+/// PDU Session Establishment Accept for an IPv4 session with a single QoS
+/// flow matching all traffic, see TS 24.501 section 8.3.2.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PduSessionEstablishmentAccept {
+    pub pdu_session_id: u8,
+    pub pti: u8,
+    pub qfi: u8,
+    /// Session-AMBR in Mbps
+    pub session_ambr_downlink: u16,
+    pub session_ambr_uplink: u16,
+    pub ue_ip_address: [u8; 4],
+    pub snssai: Option<Snssai>,
+    pub dnn: Option<String>,
+}
+
+impl PduSessionEstablishmentAccept {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(
+            self.pdu_session_id,
+            self.pti,
+            SessionMessageIdentifier::PDU_SESSION_ESTABLISHMENT_ACCEPT,
+        );
+        buf.push(SSC_MODE_1 << 4 | PDU_SESSION_TYPE_IPV4);
+
+        // A single default QoS rule with a match-all packet filter, see TS
+        // 24.501 section 9.11.4.13
+        let qos_rule = [
+            // Create new QoS rule, default rule, one packet filter
+            0x31, // Bidirectional packet filter 1, with only a match-all component
+            0x31, 0x01, 0x01, // Lowest precedence, then the QFI
+            0xFF, self.qfi,
+        ];
+        let qos_rules_len = qos_rule.len() + 3;
+        buf.extend_from_slice(&(qos_rules_len as u16).to_be_bytes());
+        buf.push(1);
+        buf.extend_from_slice(&(qos_rule.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&qos_rule);
+
+        buf.push(6);
+        buf.push(AMBR_UNIT_1_MBPS);
+        buf.extend_from_slice(&self.session_ambr_downlink.to_be_bytes());
+        buf.push(AMBR_UNIT_1_MBPS);
+        buf.extend_from_slice(&self.session_ambr_uplink.to_be_bytes());
+
+        buf.extend_from_slice(&[IEI_PDU_ADDRESS, 5, PDU_SESSION_TYPE_IPV4]);
+        buf.extend_from_slice(&self.ue_ip_address);
+
+        if let Some(snssai) = self.snssai {
+            buf.push(IEI_S_NSSAI);
+            buf.extend_from_slice(&snssai.encode());
+        }
+
+        if let Some(dnn) = &self.dnn {
+            let dnn = encode_dnn(dnn);
+            buf.push(IEI_DNN);
+            buf.push(dnn.len() as u8);
+            buf.extend_from_slice(&dnn);
+        }
+
+        buf
+    }
+}
+
+/// PDU Session Establishment Reject, see TS 24.501 section 8.3.3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PduSessionEstablishmentReject {
+    pub pdu_session_id: u8,
+    pub pti: u8,
+    pub cause: u8,
+}
+
+impl PduSessionEstablishmentReject {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(
+            self.pdu_session_id,
+            self.pti,
+            SessionMessageIdentifier::PDU_SESSION_ESTABLISHMENT_REJECT,
+        );
+        buf.push(self.cause);
+        buf
+    }
+}
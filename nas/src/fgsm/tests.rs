@@ -0,0 +1,70 @@
+use super::*;
+
+#[test]
+fn test_dnn_roundtrip() {
+    let encoded = encode_dnn("internet.mnc093.mcc208.gprs");
+    assert_eq!(&encoded[..9], b"\x08internet");
+    assert_eq!(
+        decode_dnn(&encoded).as_deref(),
+        Some("internet.mnc093.mcc208.gprs")
+    );
+    assert_eq!(decode_dnn(&[4, b'a']), None);
+}
+
+#[test]
+fn test_pdu_session_establishment_request_decode() {
+    let request = PduSessionEstablishmentRequest::decode(&[0x2E, 0x01, 0x05, 0xC1, 0xFF, 0xFF]);
+    assert_eq!(
+        request,
+        Some(PduSessionEstablishmentRequest {
+            pdu_session_id: 1,
+            pti: 5
+        })
+    );
+
+    // A PDU Session Release Request is not an Establishment Request
+    assert_eq!(
+        PduSessionEstablishmentRequest::decode(&[0x2E, 0x01, 0x05, 0xD1]),
+        None
+    );
+}
+
+#[test]
+fn test_pdu_session_establishment_accept_encode() {
+    let accept = PduSessionEstablishmentAccept {
+        pdu_session_id: 1,
+        pti: 5,
+        qfi: 1,
+        session_ambr_downlink: 1000,
+        session_ambr_uplink: 500,
+        ue_ip_address: [10, 45, 0, 2],
+        snssai: None,
+        dnn: Some("internet".to_string()),
+    };
+    let expected = [
+        &[0x2E, 0x01, 0x05, 0xC2, 0x11][..],
+        // QoS rules
+        &[
+            0x00, 0x09, 0x01, 0x00, 0x06, 0x31, 0x31, 0x01, 0x01, 0xFF, 0x01,
+        ],
+        // Session-AMBR
+        &[0x06, 0x06, 0x03, 0xE8, 0x06, 0x01, 0xF4],
+        // PDU address
+        &[0x29, 0x05, 0x01, 10, 45, 0, 2],
+        // DNN
+        &[0x25, 0x09, 0x08],
+        b"internet",
+    ]
+    .concat();
+    assert_eq!(accept.encode(), expected);
+}
+
+#[test]
+fn test_pdu_session_establishment_reject_encode() {
+    let reject = PduSessionEstablishmentReject {
+        pdu_session_id: 1,
+        pti: 5,
+        cause: CAUSE_MISSING_OR_UNKNOWN_DNN,
+    };
+    assert_eq!(reject.encode(), vec![0x2E, 0x01, 0x05, 0xC3, 27]);
+}
@@ -1,92 +1,187 @@
-use bitvec::{prelude::*, ptr::null};
 use log::trace;
 
-pub static NAS_UNSPECIFIED_PROTOCOL_ERROR: u8 = 111;
-pub static NAS_MESSAGE_TYPE_NONEXISTENT: u8 = 97;
-pub static NAS_INVALID_MANDATORY_INFO: u8 = 96;
+pub mod aka;
+pub mod fgmm;
+pub mod fgsm;
+pub mod security;
 
-pub fn parse(buf: Vec<u8>, inner: bool, sec_hdr: bool, null_cipher: bool) -> Result<Vec<u8>, u8> {
+/// 5GMM cause values, TS 24.501 9.11.3.2.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MobilityManagementCause {
+    ILLEGAL_UE = 3,
+    PEI_NOT_ACCEPTED = 5,
+    ILLEGAL_ME = 6,
+    FIVEGS_SERVICES_NOT_ALLOWED = 7,
+    UE_IDENTITY_CANNOT_BE_DERIVED = 9,
+    IMPLICITLY_DEREGISTERED = 10,
+    PLMN_NOT_ALLOWED = 11,
+    TRACKING_AREA_NOT_ALLOWED = 12,
+    ROAMING_NOT_ALLOWED_IN_TA = 13,
+    NO_SUITABLE_CELLS_IN_TA = 15,
+
+    MAC_FAILURE = 20,
+    SYNCH_FAILURE = 21,
+    CONGESTION = 22,
+    UE_SECURITY_CAPABILITIES_MISMATCH = 23,
+    SECURITY_MODE_REJECTED_UNSPECIFIED = 24,
+    NON_5G_AUTHENTICATION_UNACCEPTABLE = 26,
+    N1_MODE_NOT_ALLOWED = 27,
+    RESTRICTED_SERVICE_AREA = 28,
+    NO_NETWORK_SLICES_AVAILABLE = 62,
+    NGKSI_ALREADY_IN_USE = 71,
+    PAYLOAD_WAS_NOT_FORWARDED = 90,
+
+    SEMANTICALLY_INCORRECT_MESSAGE = 95,
+    INVALID_MANDATORY_INFORMATION = 96,
+    MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED = 97,
+    MESSAGE_TYPE_NOT_COMPATIBLE_WITH_THE_PROTOCOL_STATE = 98,
+    INFORMATION_ELEMENT_NON_EXISTENT_OR_NOT_IMPLEMENTED = 99,
+    CONDITIONAL_IE_ERROR = 100,
+    MESSAGE_NOT_COMPATIBLE_WITH_THE_PROTOCOL_STATE = 101,
+    PROTOCOL_ERROR_UNSPECIFIED = 111,
+}
+
+impl MobilityManagementCause {
+    pub fn from_u8(value: u8) -> Option<MobilityManagementCause> {
+        match value {
+            3 => Some(MobilityManagementCause::ILLEGAL_UE),
+            5 => Some(MobilityManagementCause::PEI_NOT_ACCEPTED),
+            6 => Some(MobilityManagementCause::ILLEGAL_ME),
+            7 => Some(MobilityManagementCause::FIVEGS_SERVICES_NOT_ALLOWED),
+            9 => Some(MobilityManagementCause::UE_IDENTITY_CANNOT_BE_DERIVED),
+            10 => Some(MobilityManagementCause::IMPLICITLY_DEREGISTERED),
+            11 => Some(MobilityManagementCause::PLMN_NOT_ALLOWED),
+            12 => Some(MobilityManagementCause::TRACKING_AREA_NOT_ALLOWED),
+            13 => Some(MobilityManagementCause::ROAMING_NOT_ALLOWED_IN_TA),
+            15 => Some(MobilityManagementCause::NO_SUITABLE_CELLS_IN_TA),
+            20 => Some(MobilityManagementCause::MAC_FAILURE),
+            21 => Some(MobilityManagementCause::SYNCH_FAILURE),
+            22 => Some(MobilityManagementCause::CONGESTION),
+            23 => Some(MobilityManagementCause::UE_SECURITY_CAPABILITIES_MISMATCH),
+            24 => Some(MobilityManagementCause::SECURITY_MODE_REJECTED_UNSPECIFIED),
+            26 => Some(MobilityManagementCause::NON_5G_AUTHENTICATION_UNACCEPTABLE),
+            27 => Some(MobilityManagementCause::N1_MODE_NOT_ALLOWED),
+            28 => Some(MobilityManagementCause::RESTRICTED_SERVICE_AREA),
+            62 => Some(MobilityManagementCause::NO_NETWORK_SLICES_AVAILABLE),
+            71 => Some(MobilityManagementCause::NGKSI_ALREADY_IN_USE),
+            90 => Some(MobilityManagementCause::PAYLOAD_WAS_NOT_FORWARDED),
+            95 => Some(MobilityManagementCause::SEMANTICALLY_INCORRECT_MESSAGE),
+            96 => Some(MobilityManagementCause::INVALID_MANDATORY_INFORMATION),
+            97 => Some(MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED),
+            98 => {
+                Some(MobilityManagementCause::MESSAGE_TYPE_NOT_COMPATIBLE_WITH_THE_PROTOCOL_STATE)
+            }
+            99 => {
+                Some(MobilityManagementCause::INFORMATION_ELEMENT_NON_EXISTENT_OR_NOT_IMPLEMENTED)
+            }
+            100 => Some(MobilityManagementCause::CONDITIONAL_IE_ERROR),
+            101 => Some(MobilityManagementCause::MESSAGE_NOT_COMPATIBLE_WITH_THE_PROTOCOL_STATE),
+            111 => Some(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a NAS message, returning the plain NAS message it carries.
+///
+/// Security protected 5GMM messages are unwrapped when `inner` is set and the
+/// payload is readable, i.e. not ciphered or ciphered with the null algorithm.
+/// The MAC is not checked here, see [`security::SecurityContext::unprotect`].
+pub fn parse(
+    buf: Vec<u8>,
+    inner: bool,
+    sec_hdr: bool,
+    null_cipher: bool,
+) -> Result<Vec<u8>, MobilityManagementCause> {
     if buf.len() < 3 {
-        return Err(NAS_UNSPECIFIED_PROTOCOL_ERROR);
+        return Err(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED);
     }
 
     let protocol_discriminator = ProtocolDiscriminator::from_u8(buf[0]);
-    let sec_hdr_type = SecurityHeader::from_u8(buf[1]);
-    let message_id = MessageIdentifier::from_u8(buf[2]);
-
-    let protocol_discriminator = protocol_discriminator.ok_or(NAS_UNSPECIFIED_PROTOCOL_ERROR)?;
-
-    // Parse, recurse and exit if the message is security protected
-    if protocol_discriminator == ProtocolDiscriminator::MobilityManagement
-        && let Some(sec_hdr_type) = sec_hdr_type
-        && sec_hdr_type != SecurityHeader::NotProtected
-    {
-        trace!("Security protected NAS message");
-
-        // Parse the security protected NAS message
-        let msg = parse_sec_prot_nas(buf, inner, null_cipher);
-        let Some(msg) = msg else {
-            return Err(NAS_INVALID_MANDATORY_INFO);
-        };
+    let sec_hdr_type = SecurityHeader::from_u8(buf[1] & 0x0F);
+
+    let protocol_discriminator =
+        protocol_discriminator.ok_or(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED)?;
+
+    // Parse, recurse and exit if the message is security protected. Messages
+    // that were already unwrapped (sec_hdr) can not be protected again.
+    if protocol_discriminator == ProtocolDiscriminator::MobilityManagement && !sec_hdr {
+        if let Some(sec_hdr_type) = sec_hdr_type.filter(|t| *t != SecurityHeader::NotProtected) {
+            trace!("Security protected NAS message");
 
-        // If we're told to decode the inner message as well, and we can, then
-        // do so by recursing.
-        if inner
-            && (sec_hdr_type == SecurityHeader::IntegrityProtected
-                || sec_hdr_type == SecurityHeader::IntegrityProtectedWithNewSecurityContext
-                || null_cipher)
-        {
-            trace!("Parse clear-text NAS message payload");
-            let result = parse(msg[3], inner, true, false);
-            return Ok(msg);
-            //                 if cont is not None:
-            //     Msg.replace(Msg[3], cont)
-            // return Msg, err
-        } else {
-            // Otherwise leave the inner payload as is and exit
-            return Ok(msg);
+            // Parse the security protected NAS message
+            let msg = parse_sec_prot_nas(&buf);
+            let Some(msg) = msg else {
+                return Err(MobilityManagementCause::INVALID_MANDATORY_INFORMATION);
+            };
+
+            // If we're told to decode the inner message as well, and we can, then
+            // do so by recursing.
+            if inner
+                && (sec_hdr_type == SecurityHeader::IntegrityProtected
+                    || sec_hdr_type == SecurityHeader::IntegrityProtectedWithNewSecurityContext
+                    || null_cipher)
+            {
+                trace!("Parse clear-text NAS message payload");
+                return parse(msg, inner, true, false);
+            } else {
+                // Otherwise leave the inner payload as is and exit
+                return Ok(msg);
+            }
         }
     }
 
-    let message = if protocol_discriminator == ProtocolDiscriminator::MobilityManagement {
+    if protocol_discriminator == ProtocolDiscriminator::MobilityManagement {
         trace!("5GMM unprotected NAS message");
-        let msg = fgmm_type_classes(message_id);
-        let Some(msg) = msg else {
-            return Err(NAS_MESSAGE_TYPE_NONEXISTENT);
+        let Some(MessageIdentifier::MobilityManagement(_)) = MessageIdentifier::from_u8(buf[2])
+        else {
+            return Err(MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED);
         };
-        Ok(msg)
-    } else if protocol_discriminator == ProtocolDiscriminator::SessionManagement {
+        Ok(buf)
+    } else {
         trace!("5GSM");
         let sm_typ = buf.get(3);
         let Some(sm_typ) = sm_typ else {
-            return Err(NAS_INVALID_MANDATORY_INFO);
+            return Err(MobilityManagementCause::INVALID_MANDATORY_INFORMATION);
         };
 
-        let msg = fgsm_type_classes(message_id);
-        let Some(msg) = msg else {
-            return Err(NAS_MESSAGE_TYPE_NONEXISTENT);
+        let Some(MessageIdentifier::SessionManagement(_)) = MessageIdentifier::from_u8(*sm_typ)
+        else {
+            return Err(MobilityManagementCause::MESSAGE_TYPE_NON_EXISTENT_OR_NOT_IMPLEMENTED);
         };
-        Ok(msg)
-    };
+        Ok(buf)
+    }
 }
 
-pub fn parse_sec_prot_nas(buf: Vec<u8>, inner: bool, null_cipher: bool) -> Optional<Vec<u8>> {
-    None
+/// Get the plain NAS message out of a security protected 5GMM message, which
+/// is prefixed by the extended protocol discriminator, security header type,
+/// 4 byte MAC and sequence number.
+pub fn parse_sec_prot_nas(buf: &[u8]) -> Option<Vec<u8>> {
+    if buf.len() < 10 {
+        return None;
+    }
+    Some(buf[7..].to_vec())
 }
 
-pub fn fgmm_type_classes(typ: u8) -> Option<Vec<u8>> {
-    None
+/// Get the 5GMM message type of a plain NAS message.
+pub fn mobility_message_type(buf: &[u8]) -> Option<MobilityMessageIdentifier> {
+    if ProtocolDiscriminator::from_u8(*buf.first()?)? != ProtocolDiscriminator::MobilityManagement {
+        return None;
+    }
+    MobilityMessageIdentifier::from_u8(*buf.get(2)?)
 }
 
 #[allow(non_camel_case_types)]
-enum SecurityHeader {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityHeader {
     NotProtected = 0,
     IntegrityProtected = 1,
-    IntegrityProtectedAndCiphered = 3,
+    IntegrityProtectedAndCiphered = 2,
     // Can only be used with Security Mode Command
-    IntegrityProtectedWithNewSecurityContext = 4,
+    IntegrityProtectedWithNewSecurityContext = 3,
     // Can only be used with Security Mode Complete
-    IntegrityProtectedAndCipheredWithNewSecurityContext = 5,
+    IntegrityProtectedAndCipheredWithNewSecurityContext = 4,
 }
 
 impl SecurityHeader {
@@ -94,16 +189,17 @@ impl SecurityHeader {
         match value {
             0 => Some(SecurityHeader::NotProtected),
             1 => Some(SecurityHeader::IntegrityProtected),
-            3 => Some(SecurityHeader::IntegrityProtectedAndCiphered),
-            4 => Some(SecurityHeader::IntegrityProtectedWithNewSecurityContext),
-            5 => Some(SecurityHeader::IntegrityProtectedAndCipheredWithNewSecurityContext),
+            2 => Some(SecurityHeader::IntegrityProtectedAndCiphered),
+            3 => Some(SecurityHeader::IntegrityProtectedWithNewSecurityContext),
+            4 => Some(SecurityHeader::IntegrityProtectedAndCipheredWithNewSecurityContext),
             _ => None,
         }
     }
 }
 
 #[allow(non_camel_case_types)]
-enum MessageIdentifier {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageIdentifier {
     MobilityManagement(MobilityMessageIdentifier),
     SessionManagement(SessionMessageIdentifier),
 }
@@ -124,7 +220,8 @@ impl MessageIdentifier {
     }
 }
 
-enum ProtocolDiscriminator {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolDiscriminator {
     MobilityManagement = 0x7E,
     SessionManagement = 0x2E,
 }
@@ -140,15 +237,16 @@ impl ProtocolDiscriminator {
 }
 
 #[allow(non_camel_case_types)]
-enum MobilityMessageIdentifier {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MobilityMessageIdentifier {
     REGISTRATION_REQUEST = 0x41,
     REGISTRATION_ACCEPT = 0x42,
     REGISTRATION_COMPLETE = 0x43,
     REGISTRATION_REJECT = 0x44,
     DEREGISTRATION_REQUEST = 0x45,
     DEREGISTRATION_ACCEPT = 0x46,
-    DEREGISTRATION_REQUEST_UE_ORIGINATING = 0x47,
-    DEREGISTRATION_ACCEPT_UE_ORIGINATING = 0x48,
+    DEREGISTRATION_REQUEST_UE_TERMINATED = 0x47,
+    DEREGISTRATION_ACCEPT_UE_TERMINATED = 0x48,
 
     SERVICE_REQUEST = 0x4C,
     SERVICE_REJECT = 0x4D,
@@ -187,8 +285,8 @@ impl MobilityMessageIdentifier {
             0x44 => Some(MobilityMessageIdentifier::REGISTRATION_REJECT),
             0x45 => Some(MobilityMessageIdentifier::DEREGISTRATION_REQUEST),
             0x46 => Some(MobilityMessageIdentifier::DEREGISTRATION_ACCEPT),
-            0x47 => Some(MobilityMessageIdentifier::DEREGISTRATION_REQUEST_UE_ORIGINATING),
-            0x48 => Some(MobilityMessageIdentifier::DEREGISTRATION_ACCEPT_UE_ORIGINATING),
+            0x47 => Some(MobilityMessageIdentifier::DEREGISTRATION_REQUEST_UE_TERMINATED),
+            0x48 => Some(MobilityMessageIdentifier::DEREGISTRATION_ACCEPT_UE_TERMINATED),
             0x4C => Some(MobilityMessageIdentifier::SERVICE_REQUEST),
             0x4D => Some(MobilityMessageIdentifier::SERVICE_REJECT),
             0x4E => Some(MobilityMessageIdentifier::SERVICE_ACCEPT),
@@ -219,7 +317,8 @@ impl MobilityMessageIdentifier {
 }
 
 #[allow(non_camel_case_types)]
-enum SessionMessageIdentifier {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionMessageIdentifier {
     PDU_SESSION_ESTABLISHMENT_REQUEST = 0xC1,
     PDU_SESSION_ESTABLISHMENT_ACCEPT = 0xC2,
     PDU_SESSION_ESTABLISHMENT_REJECT = 0xC3,
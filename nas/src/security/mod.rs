@@ -0,0 +1,273 @@
+//! NAS security, see TS 33.501 section 6.4 and Annex A.
+
+use aes::Aes128;
+use cmac::Cmac;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use log::{trace, warn};
+use sha2::Sha256;
+
+use crate::{MobilityManagementCause, SecurityHeader};
+
+#[cfg(test)]
+mod tests;
+
+/// Access type distinguisher for 3GPP access, TS 33.501 Annex A.9.
+const ACCESS_TYPE_3GPP: u8 = 0x01;
+
+/// Algorithm type distinguishers, TS 33.501 Annex A.8.
+const N_NAS_ENC_ALG: u8 = 0x01;
+const N_NAS_INT_ALG: u8 = 0x02;
+
+/// The BEARER input of the NAS algorithms is the NAS connection identifier,
+/// which is 0 for 3GPP access.
+const NAS_BEARER_3GPP: u8 = 0;
+
+const DIRECTION_UPLINK: u8 = 0;
+const DIRECTION_DOWNLINK: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityAlgorithm {
+    NIA0 = 0,
+    NIA2 = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CipheringAlgorithm {
+    NEA0 = 0,
+    NEA2 = 2,
+}
+
+/// Key derivation function, see TS 33.220 Annex B.2.
+pub fn kdf(key: &[u8], fc: u8, params: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&[fc]);
+    for param in params {
+        mac.update(param);
+        mac.update(&(param.len() as u16).to_be_bytes());
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Derive KgNB from KAMF, see TS 33.501 Annex A.9.
+pub fn derive_kgnb(kamf: &[u8; 32], ul_count: u32) -> [u8; 32] {
+    kdf(kamf, 0x6E, &[&ul_count.to_be_bytes(), &[ACCESS_TYPE_3GPP]])
+}
+
+/// Derive the next hop parameter NH from KAMF and the previous NH, or the
+/// initial KgNB for the first NH, see TS 33.501 Annex A.10.
+pub fn derive_nh(kamf: &[u8; 32], sync_input: &[u8; 32]) -> [u8; 32] {
+    kdf(kamf, 0x6F, &[sync_input])
+}
+
+/// Derive a NAS integrity or ciphering key from KAMF, see TS 33.501 Annex A.8.
+pub fn derive_nas_key(kamf: &[u8; 32], algorithm_type: u8, algorithm: u8) -> [u8; 16] {
+    let key = kdf(kamf, 0x69, &[&[algorithm_type], &[algorithm]]);
+    key[16..].try_into().unwrap()
+}
+
+/// 128-NIA2 (AES-CMAC) integrity algorithm, see TS 33.501 Annex D.3.1.3.
+pub fn nia2(key: &[u8; 16], count: u32, bearer: u8, direction: u8, message: &[u8]) -> [u8; 4] {
+    let mut mac = <Cmac<Aes128> as Mac>::new_from_slice(key).expect("key is 128 bits");
+    mac.update(&count.to_be_bytes());
+    mac.update(&[(bearer << 3) | (direction << 2), 0, 0, 0]);
+    mac.update(message);
+    mac.finalize().into_bytes()[..4].try_into().unwrap()
+}
+
+/// 128-NEA2 (AES-CTR) ciphering algorithm, see TS 33.501 Annex D.2.1.3.
+/// Ciphering and deciphering are the same operation.
+pub fn nea2(key: &[u8; 16], count: u32, bearer: u8, direction: u8, data: &mut [u8]) {
+    let mut iv = [0u8; 16];
+    iv[..4].copy_from_slice(&count.to_be_bytes());
+    iv[4] = (bearer << 3) | (direction << 2);
+    let mut cipher = ctr::Ctr128BE::<Aes128>::new(key.into(), &iv.into());
+    cipher.apply_keystream(data);
+}
+
+/// A 5G NAS security context of a UE, see TS 24.501 section 4.4.2.
+#[derive(Clone, Debug)]
+pub struct SecurityContext {
+    pub ngksi: u8,
+    pub kamf: [u8; 32],
+    pub integrity_algorithm: IntegrityAlgorithm,
+    pub ciphering_algorithm: CipheringAlgorithm,
+    /// NAS COUNT expected for the next message received from the UE, any
+    /// message with a lower one is a replay
+    pub next_ul_count: u32,
+    /// NAS COUNT to use for the next message sent to the UE
+    pub dl_count: u32,
+    /// The value of the UE security capability IE sent by the UE
+    pub ue_security_capability: Vec<u8>,
+    /// The latest next hop parameter, or the initial KgNB while NCC is 0
+    pub nh: [u8; 32],
+    /// Next hop chaining count of `nh`
+    pub ncc: u8,
+}
+
+impl SecurityContext {
+    /// Create a new security context, selecting the algorithms from the
+    /// UE security capability. Returns `None` if the UE does not support any
+    /// of the integrity algorithms implemented here.
+    pub fn new(ngksi: u8, kamf: [u8; 32], ue_security_capability: Vec<u8>) -> Option<Self> {
+        let ea = *ue_security_capability.first()?;
+        let ia = *ue_security_capability.get(1)?;
+
+        // Bit 8 of each octet is algorithm 0, bit 7 algorithm 1 and so on
+        if ia & 0x20 == 0 {
+            warn!("UE does not support 128-NIA2");
+            return None;
+        }
+        let ciphering_algorithm = if ea & 0x20 != 0 {
+            CipheringAlgorithm::NEA2
+        } else {
+            CipheringAlgorithm::NEA0
+        };
+
+        Some(SecurityContext {
+            ngksi,
+            kamf,
+            integrity_algorithm: IntegrityAlgorithm::NIA2,
+            ciphering_algorithm,
+            next_ul_count: 0,
+            dl_count: 0,
+            ue_security_capability,
+            nh: [0; 32],
+            ncc: 0,
+        })
+    }
+
+    /// Create the security context of a UE that registered for emergency
+    /// services without being authenticated, which has no KAMF and uses the
+    /// null algorithms, see TS 33.501 section 10.2.2.
+    pub fn null(ngksi: u8, ue_security_capability: Vec<u8>) -> Self {
+        SecurityContext {
+            ngksi,
+            kamf: [0; 32],
+            integrity_algorithm: IntegrityAlgorithm::NIA0,
+            ciphering_algorithm: CipheringAlgorithm::NEA0,
+            next_ul_count: 0,
+            dl_count: 0,
+            ue_security_capability,
+            nh: [0; 32],
+            ncc: 0,
+        }
+    }
+
+    /// The NAS COUNT of the last message received from the UE.
+    pub fn last_ul_count(&self) -> u32 {
+        self.next_ul_count.saturating_sub(1)
+    }
+
+    /// Derive the KgNB of a new AS security context from the latest uplink
+    /// NAS COUNT. This starts a new NH chain, see TS 33.501 section 6.9.2.1.1.
+    pub fn initial_kgnb(&mut self) -> [u8; 32] {
+        let kgnb = derive_kgnb(&self.kamf, self.last_ul_count());
+        self.nh = kgnb;
+        self.ncc = 0;
+        kgnb
+    }
+
+    /// Advance the NH chain for a handover, returning the new NCC and NH.
+    pub fn next_hop(&mut self) -> (u8, [u8; 32]) {
+        self.nh = derive_nh(&self.kamf, &self.nh);
+        self.ncc = (self.ncc + 1) & 0x07;
+        (self.ncc, self.nh)
+    }
+
+    fn knas_int(&self) -> [u8; 16] {
+        derive_nas_key(&self.kamf, N_NAS_INT_ALG, self.integrity_algorithm as u8)
+    }
+
+    fn knas_enc(&self) -> [u8; 16] {
+        derive_nas_key(&self.kamf, N_NAS_ENC_ALG, self.ciphering_algorithm as u8)
+    }
+
+    fn mac(&self, count: u32, direction: u8, message: &[u8]) -> [u8; 4] {
+        match self.integrity_algorithm {
+            IntegrityAlgorithm::NIA0 => [0; 4],
+            IntegrityAlgorithm::NIA2 => {
+                nia2(&self.knas_int(), count, NAS_BEARER_3GPP, direction, message)
+            }
+        }
+    }
+
+    fn cipher(&self, count: u32, direction: u8, data: &mut [u8]) {
+        match self.ciphering_algorithm {
+            CipheringAlgorithm::NEA0 => {}
+            CipheringAlgorithm::NEA2 => {
+                nea2(&self.knas_enc(), count, NAS_BEARER_3GPP, direction, data)
+            }
+        }
+    }
+
+    /// Wrap a plain 5GMM message in a security protected message for the UE,
+    /// advancing the downlink NAS COUNT.
+    pub fn protect(&mut self, plain: &[u8], sec_hdr_type: SecurityHeader) -> Vec<u8> {
+        let count = self.dl_count;
+        self.dl_count = self.dl_count.wrapping_add(1) & 0x00FF_FFFF;
+
+        let mut payload = vec![count as u8];
+        payload.extend_from_slice(plain);
+        if matches!(
+            sec_hdr_type,
+            SecurityHeader::IntegrityProtectedAndCiphered
+                | SecurityHeader::IntegrityProtectedAndCipheredWithNewSecurityContext
+        ) {
+            self.cipher(count, DIRECTION_DOWNLINK, &mut payload[1..]);
+        }
+
+        let mac = self.mac(count, DIRECTION_DOWNLINK, &payload);
+        let mut buf = vec![0x7E, sec_hdr_type as u8];
+        buf.extend_from_slice(&mac);
+        buf.extend_from_slice(&payload);
+        buf
+    }
+
+    /// Verify and, if needed, decipher a security protected 5GMM message from
+    /// the UE, returning the plain message inside it. The uplink NAS COUNT is
+    /// only advanced if the MAC is valid, so a replayed message is rejected,
+    /// see TS 33.501 section 6.4.3.2.
+    pub fn unprotect(&mut self, buf: &[u8]) -> Result<Vec<u8>, MobilityManagementCause> {
+        if buf.len() < 10 {
+            return Err(MobilityManagementCause::INVALID_MANDATORY_INFORMATION);
+        }
+        let sec_hdr_type = SecurityHeader::from_u8(buf[1] & 0x0F)
+            .ok_or(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED)?;
+        if sec_hdr_type == SecurityHeader::NotProtected {
+            return Err(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED);
+        }
+
+        // Estimate the full NAS COUNT from the 8 bit sequence number as the
+        // lowest one not below the expected NAS COUNT, assuming the overflow
+        // counter went up if the sequence number wrapped around. A replayed
+        // message then gets a NAS COUNT it was not protected with.
+        let sqn = buf[6] as u32;
+        let mut count = (self.next_ul_count & !0xFF) | sqn;
+        if count < self.next_ul_count {
+            count += 0x100;
+        }
+        if count > 0x00FF_FFFF {
+            warn!("Uplink NAS COUNT wrapped around");
+            return Err(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED);
+        }
+
+        let mac = self.mac(count, DIRECTION_UPLINK, &buf[6..]);
+        if self.integrity_algorithm != IntegrityAlgorithm::NIA0 && mac != buf[2..6] {
+            warn!("NAS MAC verification failed for uplink NAS COUNT {}", count);
+            return Err(MobilityManagementCause::PROTOCOL_ERROR_UNSPECIFIED);
+        }
+        trace!("NAS MAC verified for uplink NAS COUNT {}", count);
+        self.next_ul_count = count + 1;
+
+        let mut plain = buf[7..].to_vec();
+        if matches!(
+            sec_hdr_type,
+            SecurityHeader::IntegrityProtectedAndCiphered
+                | SecurityHeader::IntegrityProtectedAndCipheredWithNewSecurityContext
+        ) {
+            self.cipher(count, DIRECTION_UPLINK, &mut plain);
+        }
+        Ok(plain)
+    }
+}
@@ -0,0 +1,131 @@
+use super::*;
+
+// Test data from TS 33.401 Annex C, which 128-NIA2 and 128-NEA2 share with
+// 128-EIA2 and 128-EEA2.
+
+#[test]
+fn test_nia2() {
+    let key: [u8; 16] = hex::decode("d3c5d592327fb11c4035c6680af8c6d1")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let message = hex::decode("484583d5afe082ae").unwrap();
+    let mac = nia2(&key, 0x398a59b4, 0x1a, 1, &message);
+    assert_eq!(mac.to_vec(), hex::decode("b93787e6").unwrap());
+}
+
+#[test]
+fn test_nea2() {
+    let key: [u8; 16] = hex::decode("d3c5d592327fb11c4035c6680af8c6d1")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let mut data =
+        hex::decode("981ba6824c1bfb1ab485472029b71d808ce33e2cc3c0b5fc1f3de8a6dc66b1f0").unwrap();
+    nea2(&key, 0x398a59b4, 0x15, 1, &mut data);
+    // The test vector is 253 bits long, so the last three bits are not compared
+    let expected =
+        hex::decode("e9fed8a63d155304d71df20bf3e82214b20ed7dad2f233dc3c22d7bdeeed8e78").unwrap();
+    assert_eq!(data[..31], expected[..31]);
+    assert_eq!(data[31] & 0xF8, expected[31] & 0xF8);
+}
+
+#[test]
+fn test_protect_unprotect() {
+    let mut amf_side = SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+    assert_eq!(amf_side.ciphering_algorithm, CipheringAlgorithm::NEA2);
+
+    // Check the UE can decipher what the AMF sent
+    let plain = vec![0x7E, 0x00, 0x5E];
+    let protected = amf_side.protect(&plain, SecurityHeader::IntegrityProtectedAndCiphered);
+    assert_eq!(
+        protected[1],
+        SecurityHeader::IntegrityProtectedAndCiphered as u8
+    );
+    assert_eq!(amf_side.dl_count, 1);
+
+    let mut ue_payload = protected[7..].to_vec();
+    nea2(
+        &amf_side.knas_enc(),
+        0,
+        NAS_BEARER_3GPP,
+        DIRECTION_DOWNLINK,
+        &mut ue_payload,
+    );
+    assert_eq!(ue_payload, plain);
+}
+
+#[test]
+fn test_unprotect_rejects_bad_mac() {
+    let mut ctx = SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+    let msg = vec![0x7E, 0x01, 0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x7E, 0x00, 0x5E];
+    assert!(ctx.unprotect(&msg).is_err());
+    assert_eq!(ctx.next_ul_count, 0);
+}
+
+#[test]
+fn test_unprotect_uplink() {
+    let mut ctx = SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+
+    // Build an integrity protected uplink message the way the UE would
+    let mut payload = vec![0x00, 0x7E, 0x00, 0x5E];
+    let mac = nia2(
+        &ctx.knas_int(),
+        0,
+        NAS_BEARER_3GPP,
+        DIRECTION_UPLINK,
+        &payload,
+    );
+    let mut msg = vec![0x7E, 0x01];
+    msg.extend_from_slice(&mac);
+    msg.append(&mut payload);
+
+    assert_eq!(ctx.unprotect(&msg), Ok(vec![0x7E, 0x00, 0x5E]));
+    assert_eq!(ctx.next_ul_count, 1);
+    assert_eq!(ctx.last_ul_count(), 0);
+
+    // The same message again is a replay
+    assert!(ctx.unprotect(&msg).is_err());
+    assert_eq!(ctx.next_ul_count, 1);
+}
+
+#[test]
+fn test_no_nia2_support() {
+    assert!(SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xC0]).is_none());
+}
+
+#[test]
+fn test_null_security_context() {
+    let mut ctx = SecurityContext::null(0, vec![0x80, 0x80]);
+    let msg = ctx.protect(&[0x7E, 0x00, 0x42], SecurityHeader::IntegrityProtected);
+    assert_eq!(msg[2..6], [0, 0, 0, 0]);
+    assert_eq!(msg[7..], [0x7E, 0x00, 0x42]);
+
+    // Any MAC is accepted
+    let uplink = [0x7E, 0x01, 0x12, 0x34, 0x56, 0x78, 0x00, 0x7E, 0x00, 0x43];
+    assert_eq!(ctx.unprotect(&uplink).unwrap(), vec![0x7E, 0x00, 0x43]);
+    assert_eq!(ctx.next_ul_count, 1);
+}
+
+#[test]
+fn test_next_hop_chain() {
+    let mut ctx = SecurityContext::new(1, [0x42; 32], vec![0xE0, 0xE0]).unwrap();
+    ctx.next_ul_count = 4;
+    let kgnb = ctx.initial_kgnb();
+    assert_eq!(kgnb, derive_kgnb(&ctx.kamf, 3));
+    assert_eq!(ctx.ncc, 0);
+
+    // The first NH is derived from the initial KgNB, later ones from the last NH
+    let (ncc, nh) = ctx.next_hop();
+    assert_eq!(ncc, 1);
+    assert_eq!(nh, derive_nh(&ctx.kamf, &kgnb));
+    let (ncc, second_nh) = ctx.next_hop();
+    assert_eq!(ncc, 2);
+    assert_eq!(second_nh, derive_nh(&ctx.kamf, &nh));
+
+    // NCC is a 3 bit counter
+    for _ in 0..6 {
+        ctx.next_hop();
+    }
+    assert_eq!(ctx.ncc, 0);
+}
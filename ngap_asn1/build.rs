@@ -2,7 +2,8 @@
 //!
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use asn1_compiler::{
     generator::{Codec, Derive, Visibility},
@@ -16,18 +17,89 @@ fn main() -> std::io::Result<()> {
         .join("specs")
         .join("ngap")
         .join("ngap.asn");
-    let spec_files = vec![spec_file_name];
+    let spec_files = vec![spec_file_name.clone()];
     let rs_module = PathBuf::from(env::var("OUT_DIR").unwrap()).join(module);
     let rs_module = rs_module.to_str().unwrap();
 
-    let mut compiler = Asn1Compiler::new(
-        rs_module,
-        &Visibility::Public,
-        vec![Codec::Aper],
-        vec![Derive::Debug],
-    );
+    let mut compiler =
+        Asn1Compiler::new(rs_module, &Visibility::Public, vec![Codec::Aper], derives());
 
     compiler.compile_files(&spec_files)?;
 
+    if env::var_os("CARGO_FEATURE_JSON").is_some() {
+        let field_names = PathBuf::from(env::var("OUT_DIR").unwrap()).join("ngap_field_names.rs");
+        write_field_names(&spec_file_name, &field_names)?;
+    }
+
     Ok(())
 }
+
+/// The traits to derive for the generated types, Debug always and the others
+/// depending on the enabled features.
+fn derives() -> Vec<Derive> {
+    let mut derives = vec![Derive::Debug];
+    if env::var_os("CARGO_FEATURE_CLONE").is_some() {
+        derives.push(Derive::Clone);
+    }
+    if env::var_os("CARGO_FEATURE_EQ").is_some() {
+        derives.push(Derive::PartialEq);
+        derives.push(Derive::Eq);
+    }
+    if env::var_os("CARGO_FEATURE_HASH").is_some() {
+        derives.push(Derive::Hash);
+    }
+    if env::var_os("CARGO_FEATURE_SERDE").is_some() {
+        derives.push(Derive::Serialize);
+        derives.push(Derive::Deserialize);
+    }
+    derives
+}
+
+/// Write the table from the Rust name of each SEQUENCE field to its name in
+/// the ASN.1, sorted by the Rust name, so that the JSON form can use the
+/// names from the specification.
+fn write_field_names(spec_file: &Path, out_file: &Path) -> std::io::Result<()> {
+    use heck::ToSnakeCase;
+
+    let spec = fs::read_to_string(spec_file)?;
+    let mut field_names = vec![];
+    for line in spec.lines() {
+        let line = line.trim();
+        if line.starts_with("--") || line.contains("::=") {
+            continue;
+        }
+        // A field starts with an identifier in lower camel case, followed by
+        // its type
+        let mut tokens = line.split_whitespace();
+        let (Some(identifier), Some(_)) = (tokens.next(), tokens.next()) else {
+            continue;
+        };
+        let is_identifier = identifier.starts_with(|c: char| c.is_ascii_lowercase())
+            && identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !is_identifier {
+            continue;
+        }
+        // The compiler names fields after the identifier with its first
+        // letter in upper case, e.g. pLMNIdentity becomes plmn_identity
+        let mut chars = identifier.chars();
+        let capitalized: String = chars
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .into_iter()
+            .chain(chars)
+            .collect();
+        field_names.push((capitalized.to_snake_case(), identifier.to_string()));
+    }
+    // Keep the first identifier for each Rust name
+    field_names.sort_by(|(a, _), (b, _)| a.cmp(b));
+    field_names.dedup_by(|(a, _), (b, _)| a == b);
+
+    let mut out = String::from("static FIELD_NAMES: &[(&str, &str)] = &[\n");
+    for (rust_name, asn_name) in field_names {
+        out.push_str(&format!("    ({:?}, {:?}),\n", rust_name, asn_name));
+    }
+    out.push_str("];\n");
+    fs::write(out_file, out)
+}
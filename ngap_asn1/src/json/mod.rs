@@ -0,0 +1,771 @@
+//! A human-readable JSON form of the NGAP types, for inspecting captures and
+//! crafting test messages:
+//!
+//! - SEQUENCE fields are named as in the ASN.1, e.g. `protocolIEs`.
+//! - CHOICE alternatives and open type values are named after the ASN.1
+//!   identifier of the alternative or of the ID, e.g. `globalGNB-ID` or
+//!   `id-NGSetup`, and are objects with that single key.
+//! - OCTET STRINGs are hex strings, e.g. `"02f839"`.
+//! - BIT STRINGs are hex strings followed by their length in bits, e.g.
+//!   `"000001/22"`, with the unused trailing bits set to zero.
+//! - INTEGERs and ENUMERATEDs are numbers, absent OPTIONAL fields are omitted
+//!   and NULL is `"NULL"`.
+
+use bitvec::prelude::*;
+use serde::de::{self, DeserializeOwned, IntoDeserializer};
+use serde::ser::{self, Impossible};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use serde_json::Error;
+
+#[cfg(test)]
+mod tests;
+
+include!(concat!(env!("OUT_DIR"), "/ngap_field_names.rs"));
+
+/// The name serde gives to a BIT STRING, which the compiler maps to a BitVec.
+const BIT_STRING: &str = "BitSeq";
+
+/// Convert an NGAP value, typically an `NGAP_PDU`, to its JSON form.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(Serializer)
+}
+
+/// Convert the JSON form of an NGAP value back to the value.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(Deserializer(value))
+}
+
+/// The ASN.1 name of a SEQUENCE field, e.g. `pLMNIdentity` for `plmn_identity`.
+fn asn_field_name(field: &'static str) -> &'static str {
+    match FIELD_NAMES.binary_search_by(|(rust_name, _)| rust_name.cmp(&field)) {
+        Ok(index) => FIELD_NAMES[index].1,
+        Err(_) => field,
+    }
+}
+
+/// The ASN.1 name of an enum variant, e.g. `globalGNB-ID` for `GlobalGNB_ID`
+/// or `id-NGSetup` for `Id_NGSetup`.
+fn asn_variant_name(variant: &str) -> String {
+    let mut chars = variant.chars();
+    chars
+        .next()
+        .map(|c| c.to_ascii_lowercase())
+        .into_iter()
+        .chain(chars.map(|c| if c == '_' { '-' } else { c }))
+        .collect()
+}
+
+fn bit_string_to_hex(bits: &BitVec<u8, Msb0>) -> String {
+    format!("{}/{}", hex::encode(bits.as_raw_slice()), bits.len())
+}
+
+fn bit_string_from_hex(s: &str) -> Result<BitVec<u8, Msb0>, Error> {
+    let invalid =
+        || de::Error::invalid_value(de::Unexpected::Str(s), &"a hex BIT STRING like 000001/22");
+    let (data, len) = s.split_once('/').ok_or_else(invalid)?;
+    let data = hex::decode(data).map_err(|_| invalid())?;
+    let len: usize = len.parse().map_err(|_| invalid())?;
+    if len > data.len() * 8 {
+        return Err(invalid());
+    }
+    let mut bits = BitVec::from_vec(data);
+    bits.truncate(len);
+    Ok(bits)
+}
+
+fn octet_string_from_hex(s: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(s)
+        .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(s), &"a hex OCTET STRING"))
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SerializeSeq;
+    type SerializeTuple = SerializeSeq;
+    type SerializeTupleStruct = SerializeSeq;
+    type SerializeTupleVariant = SerializeVariant<SerializeSeq>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeVariant<SerializeStruct>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::String(hex::encode(v)))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::String("NULL".to_string()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::String(asn_variant_name(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let mut map = Map::new();
+        map.insert(asn_variant_name(variant), value.serialize(self)?);
+        Ok(Value::Object(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeSeq, Error> {
+        Ok(SerializeSeq {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            octets: true,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeSeq, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeSeq, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeSeq>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            map: Map::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<SerializeStruct, Error> {
+        Ok(SerializeStruct {
+            bit_string: name == BIT_STRING,
+            map: Map::new(),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeStruct>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_struct(variant, len)?,
+        })
+    }
+}
+
+/// Serializes a SEQUENCE OF as an array, or an OCTET STRING, the only
+/// sequence of bare bytes, as a hex string.
+struct SerializeSeq {
+    items: Vec<Value>,
+    octets: bool,
+}
+
+impl ser::SerializeSeq for SerializeSeq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        if self.octets {
+            if let Ok(octet) = value.serialize(OctetSerializer) {
+                self.items.push(Value::from(octet));
+                return Ok(());
+            }
+            self.octets = false;
+        }
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        if self.octets {
+            let octets: Vec<u8> = self
+                .items
+                .iter()
+                .filter_map(|item| item.as_u64().map(|octet| octet as u8))
+                .collect();
+            return Ok(Value::String(hex::encode(octets)));
+        }
+        Ok(Value::Array(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SerializeSeq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeSeq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    map: Map<String, Value>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(Serializer)? {
+            Value::String(key) => self.key = Some(key),
+            key => self.key = Some(key.to_string()),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value without a key"))?;
+        self.map.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Object(self.map))
+    }
+}
+
+/// Serializes a SEQUENCE as an object with the ASN.1 field names, or a BIT
+/// STRING as a hex string.
+struct SerializeStruct {
+    bit_string: bool,
+    map: Map<String, Value>,
+}
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        if self.bit_string {
+            // Let the BitVec read back its own serialization
+            self.map
+                .insert(key.to_string(), serde_json::to_value(value)?);
+            return Ok(());
+        }
+        match value.serialize(Serializer)? {
+            // An absent OPTIONAL field
+            Value::Null => {}
+            value => {
+                self.map.insert(asn_field_name(key).to_string(), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        if self.bit_string {
+            let bits: BitVec<u8, Msb0> = serde_json::from_value(Value::Object(self.map))?;
+            return Ok(Value::String(bit_string_to_hex(&bits)));
+        }
+        Ok(Value::Object(self.map))
+    }
+}
+
+/// Serializes the content of a tuple or struct variant, under the name of the
+/// variant.
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeSeq> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        let mut map = Map::new();
+        map.insert(
+            asn_variant_name(self.variant),
+            ser::SerializeSeq::end(self.inner)?,
+        );
+        Ok(Value::Object(map))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeStruct> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        let mut map = Map::new();
+        map.insert(
+            asn_variant_name(self.variant),
+            ser::SerializeStruct::end(self.inner)?,
+        );
+        Ok(Value::Object(map))
+    }
+}
+
+/// Accepts only a bare byte, to tell the elements of an OCTET STRING from
+/// those of a SEQUENCE OF, which are all named types.
+struct OctetSerializer;
+
+macro_rules! not_an_octet {
+    ($($method:ident($($arg:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ok, Error> {
+                Err(ser::Error::custom("not an octet"))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for OctetSerializer {
+    type Ok = u8;
+    type Error = Error;
+    type SerializeSeq = Impossible<u8, Error>;
+    type SerializeTuple = Impossible<u8, Error>;
+    type SerializeTupleStruct = Impossible<u8, Error>;
+    type SerializeTupleVariant = Impossible<u8, Error>;
+    type SerializeMap = Impossible<u8, Error>;
+    type SerializeStruct = Impossible<u8, Error>;
+    type SerializeStructVariant = Impossible<u8, Error>;
+
+    fn serialize_u8(self, v: u8) -> Result<u8, Error> {
+        Ok(v)
+    }
+
+    not_an_octet! {
+        serialize_bool(bool) -> u8;
+        serialize_i8(i8) -> u8;
+        serialize_i16(i16) -> u8;
+        serialize_i32(i32) -> u8;
+        serialize_i64(i64) -> u8;
+        serialize_u16(u16) -> u8;
+        serialize_u32(u32) -> u8;
+        serialize_u64(u64) -> u8;
+        serialize_f32(f32) -> u8;
+        serialize_f64(f64) -> u8;
+        serialize_char(char) -> u8;
+        serialize_str(&str) -> u8;
+        serialize_bytes(&[u8]) -> u8;
+        serialize_none() -> u8;
+        serialize_unit() -> u8;
+        serialize_unit_struct(&'static str) -> u8;
+        serialize_unit_variant(&'static str, u32, &'static str) -> u8;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<u8, Error> {
+        Err(ser::Error::custom("not an octet"))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<u8, Error> {
+        Err(ser::Error::custom("not an octet"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u8, Error> {
+        Err(ser::Error::custom("not an octet"))
+    }
+}
+
+/// Reads the JSON form back, guided by the type being deserialized to map
+/// the ASN.1 names and the hex strings.
+struct Deserializer(Value);
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::Array(items) => visitor.visit_seq(SeqAccess(items.into_iter())),
+            Value::Object(map) => visitor.visit_map(MapAccess {
+                entries: map.into_iter().collect::<Vec<_>>().into_iter(),
+                value: None,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(Deserializer(value)),
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::String(s) if s == "NULL" => visitor.visit_unit(),
+            value => value.deserialize_unit(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::String(s) => {
+                let octets = octet_string_from_hex(&s)?;
+                visitor.visit_seq(SeqAccess(
+                    octets
+                        .into_iter()
+                        .map(Value::from)
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                ))
+            }
+            value => Deserializer(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::String(s) => visitor.visit_byte_buf(octet_string_from_hex(&s)?),
+            value => Deserializer(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::String(s) if name == BIT_STRING => {
+                // Let the BitVec read its own serialization
+                let bits = bit_string_from_hex(&s)?;
+                serde_json::to_value(bits)?.deserialize_struct(name, fields, visitor)
+            }
+            Value::Object(map) => {
+                let entries = map
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let field = fields
+                            .iter()
+                            .find(|field| asn_field_name(field) == key || **field == key)
+                            .ok_or_else(|| {
+                                de::Error::custom(format!("unknown field `{}` in {}", key, name))
+                            })?;
+                        Ok((field.to_string(), value))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                visitor.visit_map(MapAccess {
+                    entries: entries.into_iter(),
+                    value: None,
+                })
+            }
+            value => Deserializer(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let (key, value) = match self.0 {
+            Value::String(key) => (key, None),
+            Value::Object(map) if map.len() == 1 => {
+                let (key, value) = map.into_iter().next().unwrap();
+                (key, Some(value))
+            }
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "expected a string or an object with a single key for {}",
+                    name
+                )))
+            }
+        };
+        let variant = variants
+            .iter()
+            .find(|variant| asn_variant_name(variant) == key || **variant == key)
+            .ok_or_else(|| de::Error::custom(format!("unknown variant `{}` of {}", key, name)))?;
+        visitor.visit_enum(EnumAccess { variant, value })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        tuple tuple_struct map identifier ignored_any
+    }
+}
+
+struct SeqAccess(std::vec::IntoIter<Value>);
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0
+            .next()
+            .map(|value| seed.deserialize(Deserializer(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapAccess {
+    entries: std::vec::IntoIter<(String, Value)>,
+    value: Option<Value>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("map value without a key"))?;
+        seed.deserialize(Deserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess {
+    variant: &'static str,
+    value: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess), Error> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, VariantAccess(self.value)))
+    }
+}
+
+struct VariantAccess(Option<Value>);
+
+impl VariantAccess {
+    fn value(self) -> Result<Value, Error> {
+        self.0.ok_or_else(|| {
+            de::Error::invalid_type(de::Unexpected::UnitVariant, &"a variant with a value")
+        })
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None => Ok(()),
+            Some(value) => <()>::deserialize(Deserializer(value)),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(Deserializer(self.value()?))
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(Deserializer(self.value()?), visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_struct(Deserializer(self.value()?), "", fields, visitor)
+    }
+}
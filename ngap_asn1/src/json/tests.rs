@@ -0,0 +1,51 @@
+use super::*;
+use crate as ngap;
+use asn1_codecs::{aper::AperCodec, PerCodecData};
+
+const NG_SETUP_REQUEST: [u8; 57] = [
+    0x00, 0x15, 0x00, 0x35, 0x00, 0x00, 0x04, 0x00, 0x1b, 0x00, 0x08, 0x00, 0x02, 0xf8, 0x39, 0x03,
+    0x80, 0x00, 0x04, 0x00, 0x52, 0x40, 0x09, 0x03, 0x00, 0x4e, 0x65, 0x72, 0x76, 0x69, 0x6f, 0x6e,
+    0x00, 0x66, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0xf8, 0x39, 0x00, 0x00, 0x10,
+    0x08, 0x00, 0x00, 0x01, 0x00, 0x15, 0x40, 0x01, 0x40,
+];
+
+#[test]
+fn test_names() {
+    assert_eq!(asn_field_name("protocol_i_es"), "protocolIEs");
+    assert_eq!(asn_field_name("plmn_identity"), "pLMNIdentity");
+    assert_eq!(asn_field_name("ie_extensions"), "iE-Extensions");
+    assert_eq!(asn_variant_name("GlobalGNB_ID"), "globalGNB-ID");
+    assert_eq!(asn_variant_name("Id_NGSetup"), "id-NGSetup");
+}
+
+#[test]
+fn test_bit_string() {
+    let bits = bit_string_from_hex("000004/22").unwrap();
+    assert_eq!(bits.len(), 22);
+    assert_eq!(bit_string_to_hex(&bits), "000004/22");
+    assert!(bit_string_from_hex("0004/22").is_err());
+    assert!(bit_string_from_hex("000004").is_err());
+}
+
+#[test]
+fn test_round_trip() {
+    let mut codec_data = PerCodecData::from_slice_aper(&NG_SETUP_REQUEST);
+    let pdu = ngap::NGAP_PDU::aper_decode(&mut codec_data).unwrap();
+
+    let json = to_value(&pdu).unwrap();
+    let request = &json["initiatingMessage"]["value"]["id-NGSetup"];
+    assert_eq!(json["initiatingMessage"]["procedureCode"], 21);
+    assert_eq!(
+        request["protocolIEs"][0]["value"]["id-GlobalRANNodeID"]["globalGNB-ID"]["pLMNIdentity"],
+        "02f839"
+    );
+    assert_eq!(
+        request["protocolIEs"][1]["value"]["id-RANNodeName"],
+        "Nervion"
+    );
+
+    let pdu: ngap::NGAP_PDU = from_value(json).unwrap();
+    let mut codec_data = PerCodecData::default();
+    pdu.aper_encode(&mut codec_data).unwrap();
+    assert_eq!(codec_data.get_inner().unwrap(), NG_SETUP_REQUEST.to_vec());
+}
@@ -1,2 +1,5 @@
 #![allow(non_camel_case_types, dead_code)]
 include!(concat!(env!("OUT_DIR"), "/ngap_generated.rs"));
+
+#[cfg(feature = "json")]
+pub mod json;
@@ -0,0 +1,95 @@
+//! Convert NGAP PDUs between their APER encoding and a human-readable form,
+//! see `ngap_asn1::json` for the format.
+//!
+//! ```text
+//! ngap-tool decode [--yaml] <hex>
+//! ngap-tool encode <json or yaml>
+//! ```
+//!
+//! The input is read from stdin when it is omitted or `-`.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use asn1_codecs::{aper::AperCodec, PerCodecData};
+use ngap_asn1 as ngap;
+
+const USAGE: &str =
+    "usage: ngap-tool decode [--yaml] <hex>\n       ngap-tool encode <json or yaml>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args[..] {
+        ["decode", "--yaml", ref input @ ..] => decode(input, true),
+        ["decode", ref input @ ..] => decode(input, false),
+        ["encode", ref input @ ..] => encode(input),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The single input argument, or stdin if there is none or it is `-`.
+fn read_input(args: &[&str]) -> Result<String, String> {
+    match args {
+        [] | ["-"] => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("could not read stdin: {}", e))?;
+            Ok(input)
+        }
+        [input] => Ok(input.to_string()),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Decode a hex APER-encoded NGAP PDU to JSON, or YAML.
+fn decode(args: &[&str], yaml: bool) -> Result<String, String> {
+    // Accept the prefixes, spacing and separators of hex dumps
+    let hex: String = read_input(args)?
+        .replace("0x", "")
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect();
+    let buf = hex::decode(hex).map_err(|e| format!("invalid hex: {}", e))?;
+
+    let mut codec_data = PerCodecData::from_slice_aper(&buf);
+    let pdu = ngap::NGAP_PDU::aper_decode(&mut codec_data)
+        .map_err(|e| format!("could not decode NGAP PDU: {:?}", e))?;
+    let value = ngap::json::to_value(&pdu).map_err(|e| e.to_string())?;
+
+    if yaml {
+        serde_yaml::to_string(&value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+    }
+}
+
+/// Encode an NGAP PDU in JSON, or YAML, to hex APER.
+fn encode(args: &[&str]) -> Result<String, String> {
+    // YAML is a superset of JSON
+    let value: serde_json::Value = serde_yaml::from_str(&read_input(args)?)
+        .map_err(|e| format!("invalid JSON or YAML: {}", e))?;
+    let pdu: ngap::NGAP_PDU =
+        ngap::json::from_value(value).map_err(|e| format!("invalid NGAP PDU: {}", e))?;
+
+    let mut codec_data = PerCodecData::default();
+    pdu.aper_encode(&mut codec_data)
+        .map_err(|e| format!("could not encode NGAP PDU: {:?}", e))?;
+    let buf = codec_data
+        .get_inner()
+        .map_err(|e| format!("could not encode NGAP PDU: {:?}", e))?;
+    Ok(hex::encode(buf))
+}
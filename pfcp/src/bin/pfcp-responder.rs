@@ -0,0 +1,37 @@
+//! Run a PFCP responder standing in for a UPF, for testing the SMF-lite of
+//! CoreKube on a single host.
+//!
+//! Usage: pfcp-responder [BIND_ADDR] [N3_ADDR]
+
+use flexi_logger::Logger;
+use log::info;
+use std::net::{Ipv4Addr, UdpSocket};
+
+use pfcp::responder::Responder;
+
+fn main() {
+    let _logger = Logger::try_with_env_or_str("info")
+        .expect("could not retrieve log level")
+        .format_for_stderr(flexi_logger::colored_default_format)
+        .start()
+        .expect("could not start logger");
+
+    let mut args = std::env::args().skip(1);
+    let bind_addr: Ipv4Addr = args
+        .next()
+        .map(|a| a.parse().expect("invalid bind address"))
+        .unwrap_or(Ipv4Addr::LOCALHOST);
+    let n3_addr: Ipv4Addr = args
+        .next()
+        .map(|a| a.parse().expect("invalid N3 address"))
+        .unwrap_or(bind_addr);
+
+    let socket = match UdpSocket::bind((bind_addr, pfcp::PFCP_PORT)) {
+        Ok(s) => s,
+        Err(e) => panic!("couldn't bind socket: {}", e),
+    };
+    info!("Listening on {}:{}", bind_addr, pfcp::PFCP_PORT);
+
+    let mut responder = Responder::new(bind_addr, n3_addr);
+    responder.run(&socket).expect("PFCP socket failed");
+}
@@ -0,0 +1,164 @@
+//! A blocking PFCP client for the CP function side of the N4 interface.
+
+use log::{trace, warn};
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Condvar, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::Message;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of times a request is sent before giving up, N1 in TS 29.244
+/// section 6.4.
+const MAX_TRANSMISSIONS: u32 = 3;
+
+/// Time to wait for a response before retransmitting, T1 in TS 29.244.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const BUFFER_LEN: usize = 2048;
+
+/// Sends requests to a single UP function and waits for their responses.
+///
+/// Requests from several threads can be outstanding at once. One of the
+/// waiting threads reads from the socket at a time, and hands the responses
+/// to the others by sequence number.
+pub struct Client {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    next_sequence_number: Mutex<u32>,
+    /// The response to each outstanding request, once it arrived
+    responses: Mutex<HashMap<u32, Option<Message>>>,
+    response_received: Condvar,
+    /// Held by the thread that is reading from the socket
+    receiver: Mutex<()>,
+}
+
+impl Client {
+    pub fn connect(bind_addr: impl ToSocketAddrs, peer: SocketAddr) -> io::Result<Client> {
+        Ok(Client {
+            socket: UdpSocket::bind(bind_addr)?,
+            peer,
+            next_sequence_number: Mutex::new(1),
+            responses: Mutex::new(HashMap::new()),
+            response_received: Condvar::new(),
+            receiver: Mutex::new(()),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    fn allocate_sequence_number(&self) -> u32 {
+        let mut next = self
+            .next_sequence_number
+            .lock()
+            .expect("PFCP sequence number lock poisoned");
+        let sequence_number = *next;
+        *next = (*next + 1) & 0x00FF_FFFF;
+        sequence_number
+    }
+
+    fn lock_responses(&self) -> MutexGuard<'_, HashMap<u32, Option<Message>>> {
+        self.responses.lock().expect("PFCP response lock poisoned")
+    }
+
+    /// Send a request and wait for the response with the same sequence
+    /// number, retransmitting the request if none arrives in time.
+    pub fn request(&self, mut request: Message) -> io::Result<Message> {
+        request.sequence_number = self.allocate_sequence_number();
+        self.lock_responses().insert(request.sequence_number, None);
+        let result = self.exchange(&request);
+        self.lock_responses().remove(&request.sequence_number);
+        result
+    }
+
+    fn exchange(&self, request: &Message) -> io::Result<Message> {
+        let buf = request.encode();
+        let start = Instant::now();
+        let mut transmissions = 0;
+
+        loop {
+            let now = Instant::now();
+            if transmissions < MAX_TRANSMISSIONS && now >= start + RESPONSE_TIMEOUT * transmissions
+            {
+                trace!(
+                    "Sending PFCP message of type {} to {}",
+                    request.message_type,
+                    self.peer
+                );
+                self.socket.send_to(&buf, self.peer)?;
+                transmissions += 1;
+            }
+
+            // Datagrams that arrive meanwhile do not postpone the next
+            // retransmission, nor the final timeout
+            let timeout = (start + RESPONSE_TIMEOUT * transmissions).saturating_duration_since(now);
+            let mut responses = self.lock_responses();
+            if let Some(response) = responses
+                .get_mut(&request.sequence_number)
+                .and_then(Option::take)
+            {
+                return Ok(response);
+            }
+            if timeout.is_zero() {
+                if transmissions < MAX_TRANSMISSIONS {
+                    continue;
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "no response from PFCP peer",
+                ));
+            }
+
+            match self.receiver.try_lock() {
+                Ok(receiver) => {
+                    drop(responses);
+                    let result = self.receive(timeout);
+                    drop(receiver);
+                    // Let a waiting thread take over reading from the socket
+                    let _responses = self.lock_responses();
+                    self.response_received.notify_all();
+                    result?;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    let _ = self
+                        .response_received
+                        .wait_timeout(responses, timeout)
+                        .expect("PFCP response lock poisoned");
+                }
+                Err(TryLockError::Poisoned(_)) => panic!("PFCP receiver lock poisoned"),
+            }
+        }
+    }
+
+    /// Wait for a datagram, and keep it if it is the response to an
+    /// outstanding request.
+    fn receive(&self, timeout: Duration) -> io::Result<()> {
+        self.socket.set_read_timeout(Some(timeout))?;
+        let mut buf = [0; BUFFER_LEN];
+        let (size, src) = match self.socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                return Ok(())
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(response) = Message::decode(&buf[..size]).filter(|_| src == self.peer) {
+            let mut responses = self.lock_responses();
+            if let Some(slot) = responses.get_mut(&response.sequence_number) {
+                *slot = Some(response);
+                return Ok(());
+            }
+        }
+        warn!("Dropped unexpected PFCP message from {}", src);
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use super::*;
+use crate::ie;
+use crate::message::*;
+use crate::responder::Responder;
+use std::net::Ipv4Addr;
+use std::thread;
+
+#[test]
+fn test_request_to_responder() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let peer = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut responder = Responder::new(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST);
+        responder.run(&socket)
+    });
+
+    let client = Client::connect("127.0.0.1:0", peer).unwrap();
+    let response = client
+        .request(Message::new(
+            ASSOCIATION_SETUP_REQUEST,
+            None,
+            vec![ie::node_id(Ipv4Addr::LOCALHOST)],
+        ))
+        .unwrap();
+    assert_eq!(response.message_type, ASSOCIATION_SETUP_RESPONSE);
+    assert_eq!(response.sequence_number, 1);
+
+    let response = client
+        .request(Message::new(HEARTBEAT_REQUEST, None, vec![]))
+        .unwrap();
+    assert_eq!(response.message_type, HEARTBEAT_RESPONSE);
+    assert_eq!(response.sequence_number, 2);
+}
+
+#[test]
+fn test_request_times_out() {
+    // Nothing ever answers on this socket
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = Client::connect("127.0.0.1:0", silent.local_addr().unwrap()).unwrap();
+
+    let result = client.request(Message::new(HEARTBEAT_REQUEST, None, vec![]));
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+}
+
+#[test]
+fn test_concurrent_requests() {
+    // A peer that answers two requests in the reverse order
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let peer = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut responder = Responder::new(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST);
+        let mut buf = [0; BUFFER_LEN];
+        let mut requests = vec![];
+        while requests.len() < 2 {
+            let (size, src) = socket.recv_from(&mut buf).unwrap();
+            let request = Message::decode(&buf[..size]).unwrap();
+            if !requests
+                .iter()
+                .any(|(r, _): &(Message, _)| r.sequence_number == request.sequence_number)
+            {
+                requests.push((request, src));
+            }
+        }
+        for (request, src) in requests.iter().rev() {
+            let response = responder.handle(request).unwrap();
+            socket.send_to(&response.encode(), src).unwrap();
+        }
+    });
+
+    let client = Client::connect("127.0.0.1:0", peer).unwrap();
+    thread::scope(|scope| {
+        let heartbeat =
+            scope.spawn(|| client.request(Message::new(HEARTBEAT_REQUEST, None, vec![])));
+        let association = scope.spawn(|| {
+            client.request(Message::new(
+                ASSOCIATION_SETUP_REQUEST,
+                None,
+                vec![ie::node_id(Ipv4Addr::LOCALHOST)],
+            ))
+        });
+        let heartbeat = heartbeat.join().unwrap().unwrap();
+        let association = association.join().unwrap().unwrap();
+        assert_eq!(heartbeat.message_type, HEARTBEAT_RESPONSE);
+        assert_eq!(association.message_type, ASSOCIATION_SETUP_RESPONSE);
+        assert_ne!(heartbeat.sequence_number, association.sequence_number);
+    });
+}
+
+#[test]
+fn test_unrelated_datagrams_do_not_postpone_timeout() {
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = Client::connect("127.0.0.1:0", silent.local_addr().unwrap()).unwrap();
+
+    // Another node keeps sending to the client while it waits
+    let client_addr = client.local_addr().unwrap();
+    let other = UdpSocket::bind("127.0.0.1:0").unwrap();
+    thread::spawn(move || loop {
+        if other.send_to(&[0; 4], client_addr).is_err() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    });
+
+    let start = Instant::now();
+    let result = client.request(Message::new(HEARTBEAT_REQUEST, None, vec![]));
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    assert!(start.elapsed() < RESPONSE_TIMEOUT * (MAX_TRANSMISSIONS + 1));
+}
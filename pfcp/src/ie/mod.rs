@@ -0,0 +1,244 @@
+//! PFCP information elements, see TS 29.244 section 8.
+
+use std::net::Ipv4Addr;
+
+#[cfg(test)]
+mod tests;
+
+pub const CREATE_PDR: u16 = 1;
+pub const PDI: u16 = 2;
+pub const CREATE_FAR: u16 = 3;
+pub const FORWARDING_PARAMETERS: u16 = 4;
+pub const CREATE_QER: u16 = 7;
+pub const CREATED_PDR: u16 = 8;
+pub const UPDATE_FAR: u16 = 10;
+pub const UPDATE_FORWARDING_PARAMETERS: u16 = 11;
+pub const CAUSE: u16 = 19;
+pub const SOURCE_INTERFACE: u16 = 20;
+pub const F_TEID: u16 = 21;
+pub const GATE_STATUS: u16 = 25;
+pub const MBR: u16 = 26;
+pub const PRECEDENCE: u16 = 29;
+pub const DESTINATION_INTERFACE: u16 = 42;
+pub const APPLY_ACTION: u16 = 44;
+pub const PDR_ID: u16 = 56;
+pub const F_SEID: u16 = 57;
+pub const NODE_ID: u16 = 60;
+pub const OUTER_HEADER_CREATION: u16 = 84;
+pub const UE_IP_ADDRESS: u16 = 93;
+pub const OUTER_HEADER_REMOVAL: u16 = 95;
+pub const RECOVERY_TIME_STAMP: u16 = 96;
+pub const FAR_ID: u16 = 108;
+pub const QER_ID: u16 = 109;
+pub const QFI: u16 = 124;
+
+/// Cause values, TS 29.244 section 8.2.1.
+pub const CAUSE_REQUEST_ACCEPTED: u8 = 1;
+pub const CAUSE_REQUEST_REJECTED: u8 = 64;
+pub const CAUSE_SESSION_CONTEXT_NOT_FOUND: u8 = 65;
+pub const CAUSE_MANDATORY_IE_MISSING: u8 = 66;
+pub const CAUSE_NO_ESTABLISHED_PFCP_ASSOCIATION: u8 = 72;
+
+/// Interface values, TS 29.244 section 8.2.2.
+pub const INTERFACE_ACCESS: u8 = 0;
+pub const INTERFACE_CORE: u8 = 1;
+
+/// Apply Action flags, TS 29.244 section 8.2.26.
+pub const APPLY_ACTION_DROP: u8 = 0x01;
+pub const APPLY_ACTION_FORW: u8 = 0x02;
+pub const APPLY_ACTION_BUFF: u8 = 0x04;
+
+/// An information element as a type and its raw value. Grouped IEs carry
+/// their encoded children as the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ie {
+    pub ie_type: u16,
+    pub value: Vec<u8>,
+}
+
+impl Ie {
+    pub fn new(ie_type: u16, value: Vec<u8>) -> Self {
+        Ie { ie_type, value }
+    }
+
+    pub fn grouped(ie_type: u16, ies: Vec<Ie>) -> Self {
+        let mut value = vec![];
+        for ie in &ies {
+            ie.encode_into(&mut value);
+        }
+        Ie { ie_type, value }
+    }
+
+    /// Decode the children of a grouped IE.
+    pub fn children(&self) -> Option<Vec<Ie>> {
+        Ie::decode_all(&self.value)
+    }
+
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.ie_type.to_be_bytes());
+        buf.extend_from_slice(&(self.value.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&self.value);
+    }
+
+    /// Decode a sequence of IEs filling the whole buffer.
+    pub fn decode_all(mut buf: &[u8]) -> Option<Vec<Ie>> {
+        let mut ies = vec![];
+        while !buf.is_empty() {
+            let header = buf.get(..4)?;
+            let ie_type = u16::from_be_bytes([header[0], header[1]]);
+            let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+            let value = buf.get(4..4 + length)?;
+            ies.push(Ie::new(ie_type, value.to_vec()));
+            buf = &buf[4 + length..];
+        }
+        Some(ies)
+    }
+}
+
+/// Find the first IE of the given type.
+pub fn find(ies: &[Ie], ie_type: u16) -> Option<&Ie> {
+    ies.iter().find(|ie| ie.ie_type == ie_type)
+}
+
+/// A Fully qualified TEID, TS 29.244 section 8.2.3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FTeid {
+    /// Asks the UP function to allocate a TEID and IPv4 address (CH flag)
+    Choose,
+    Assigned {
+        teid: u32,
+        address: Ipv4Addr,
+    },
+}
+
+pub fn node_id(address: Ipv4Addr) -> Ie {
+    let mut value = vec![0];
+    value.extend_from_slice(&address.octets());
+    Ie::new(NODE_ID, value)
+}
+
+pub fn cause(cause: u8) -> Ie {
+    Ie::new(CAUSE, vec![cause])
+}
+
+pub fn parse_cause(ie: &Ie) -> Option<u8> {
+    ie.value.first().copied()
+}
+
+/// Seconds since 1900, as in NTP.
+pub fn recovery_time_stamp(timestamp: u32) -> Ie {
+    Ie::new(RECOVERY_TIME_STAMP, timestamp.to_be_bytes().to_vec())
+}
+
+pub fn f_seid(seid: u64, address: Ipv4Addr) -> Ie {
+    let mut value = vec![0x02];
+    value.extend_from_slice(&seid.to_be_bytes());
+    value.extend_from_slice(&address.octets());
+    Ie::new(F_SEID, value)
+}
+
+pub fn parse_f_seid(ie: &Ie) -> Option<(u64, Ipv4Addr)> {
+    if ie.value.first()? & 0x02 == 0 {
+        return None;
+    }
+    let seid = u64::from_be_bytes(ie.value.get(1..9)?.try_into().unwrap());
+    let address: [u8; 4] = ie.value.get(9..13)?.try_into().unwrap();
+    Some((seid, address.into()))
+}
+
+pub fn f_teid(f_teid: FTeid) -> Ie {
+    let value = match f_teid {
+        FTeid::Choose => vec![0x05],
+        FTeid::Assigned { teid, address } => {
+            let mut value = vec![0x01];
+            value.extend_from_slice(&teid.to_be_bytes());
+            value.extend_from_slice(&address.octets());
+            value
+        }
+    };
+    Ie::new(F_TEID, value)
+}
+
+pub fn parse_f_teid(ie: &Ie) -> Option<FTeid> {
+    let flags = *ie.value.first()?;
+    if flags & 0x04 != 0 {
+        return Some(FTeid::Choose);
+    }
+    if flags & 0x01 == 0 {
+        return None;
+    }
+    let teid = u32::from_be_bytes(ie.value.get(1..5)?.try_into().unwrap());
+    let address: [u8; 4] = ie.value.get(5..9)?.try_into().unwrap();
+    Some(FTeid::Assigned {
+        teid,
+        address: address.into(),
+    })
+}
+
+pub fn pdr_id(id: u16) -> Ie {
+    Ie::new(PDR_ID, id.to_be_bytes().to_vec())
+}
+
+pub fn parse_pdr_id(ie: &Ie) -> Option<u16> {
+    Some(u16::from_be_bytes(ie.value.get(..2)?.try_into().unwrap()))
+}
+
+pub fn precedence(precedence: u32) -> Ie {
+    Ie::new(PRECEDENCE, precedence.to_be_bytes().to_vec())
+}
+
+pub fn source_interface(interface: u8) -> Ie {
+    Ie::new(SOURCE_INTERFACE, vec![interface])
+}
+
+pub fn destination_interface(interface: u8) -> Ie {
+    Ie::new(DESTINATION_INTERFACE, vec![interface])
+}
+
+/// The UE IP address, as the source of uplink or destination of downlink
+/// packets.
+pub fn ue_ip_address(address: Ipv4Addr, destination: bool) -> Ie {
+    let mut value = vec![0x02 | (destination as u8) << 2];
+    value.extend_from_slice(&address.octets());
+    Ie::new(UE_IP_ADDRESS, value)
+}
+
+/// Remove the GTP-U/UDP/IPv4 header of uplink packets.
+pub fn outer_header_removal() -> Ie {
+    Ie::new(OUTER_HEADER_REMOVAL, vec![0])
+}
+
+pub fn far_id(id: u32) -> Ie {
+    Ie::new(FAR_ID, id.to_be_bytes().to_vec())
+}
+
+pub fn qer_id(id: u32) -> Ie {
+    Ie::new(QER_ID, id.to_be_bytes().to_vec())
+}
+
+pub fn apply_action(flags: u8) -> Ie {
+    Ie::new(APPLY_ACTION, vec![flags])
+}
+
+/// Add a GTP-U/UDP/IPv4 header towards the given tunnel endpoint.
+pub fn outer_header_creation(teid: u32, address: Ipv4Addr) -> Ie {
+    let mut value = vec![0x01, 0x00];
+    value.extend_from_slice(&teid.to_be_bytes());
+    value.extend_from_slice(&address.octets());
+    Ie::new(OUTER_HEADER_CREATION, value)
+}
+
+pub fn gate_status_open() -> Ie {
+    Ie::new(GATE_STATUS, vec![0])
+}
+
+/// Maximum bit rates in kbps, each encoded in 5 octets.
+pub fn mbr(uplink: u64, downlink: u64) -> Ie {
+    let mut value = uplink.to_be_bytes()[3..].to_vec();
+    value.extend_from_slice(&downlink.to_be_bytes()[3..]);
+    Ie::new(MBR, value)
+}
+
+pub fn qfi(qfi: u8) -> Ie {
+    Ie::new(QFI, vec![qfi & 0x3F])
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+#[test]
+fn test_grouped_ie_roundtrip() {
+    let create_far = Ie::grouped(
+        CREATE_FAR,
+        vec![
+            far_id(1),
+            apply_action(APPLY_ACTION_FORW),
+            Ie::grouped(
+                FORWARDING_PARAMETERS,
+                vec![destination_interface(INTERFACE_CORE)],
+            ),
+        ],
+    );
+
+    let mut buf = vec![];
+    create_far.encode_into(&mut buf);
+    assert_eq!(&buf[..4], &[0x00, 0x03, 0x00, 0x16]);
+
+    let decoded = Ie::decode_all(&buf).unwrap();
+    assert_eq!(decoded, vec![create_far.clone()]);
+    let children = decoded[0].children().unwrap();
+    assert_eq!(children[0], far_id(1));
+    assert_eq!(
+        find(&children, FORWARDING_PARAMETERS)
+            .unwrap()
+            .children()
+            .unwrap(),
+        vec![destination_interface(INTERFACE_CORE)]
+    );
+}
+
+#[test]
+fn test_truncated_ie_is_rejected() {
+    assert_eq!(Ie::decode_all(&[0x00, 0x13, 0x00, 0x02, 0x01]), None);
+}
+
+#[test]
+fn test_f_seid() {
+    let ie = f_seid(0x0102030405060708, Ipv4Addr::new(10, 0, 0, 1));
+    assert_eq!(ie.value, vec![0x02, 1, 2, 3, 4, 5, 6, 7, 8, 10, 0, 0, 1]);
+    assert_eq!(
+        parse_f_seid(&ie),
+        Some((0x0102030405060708, Ipv4Addr::new(10, 0, 0, 1)))
+    );
+}
+
+#[test]
+fn test_f_teid() {
+    assert_eq!(parse_f_teid(&f_teid(FTeid::Choose)), Some(FTeid::Choose));
+
+    let assigned = FTeid::Assigned {
+        teid: 0x11223344,
+        address: Ipv4Addr::new(192, 168, 0, 1),
+    };
+    let ie = f_teid(assigned);
+    assert_eq!(ie.value, vec![0x01, 0x11, 0x22, 0x33, 0x44, 192, 168, 0, 1]);
+    assert_eq!(parse_f_teid(&ie), Some(assigned));
+}
+
+#[test]
+fn test_mbr() {
+    assert_eq!(
+        mbr(1_000_000, 2).value,
+        vec![0x00, 0x00, 0x0F, 0x42, 0x40, 0x00, 0x00, 0x00, 0x00, 0x02]
+    );
+}
@@ -0,0 +1,16 @@
+//! A minimal implementation of the Packet Forwarding Control Protocol used on
+//! the N4 interface between the SMF and the UPF, see TS 29.244.
+//!
+//! Only the messages and IEs needed to set up a single GTP-U tunnel pair per
+//! PDU session are supported.
+
+pub mod client;
+pub mod ie;
+pub mod message;
+pub mod responder;
+
+pub use ie::Ie;
+pub use message::Message;
+
+/// UDP port PFCP entities listen on, TS 29.244 section 4.2.2.
+pub const PFCP_PORT: u16 = 8805;
@@ -0,0 +1,105 @@
+//! PFCP message header and message types, see TS 29.244 section 7.
+
+use crate::ie::{self, Ie};
+
+#[cfg(test)]
+mod tests;
+
+const PFCP_VERSION: u8 = 1;
+
+pub const HEARTBEAT_REQUEST: u8 = 1;
+pub const HEARTBEAT_RESPONSE: u8 = 2;
+pub const ASSOCIATION_SETUP_REQUEST: u8 = 5;
+pub const ASSOCIATION_SETUP_RESPONSE: u8 = 6;
+pub const SESSION_ESTABLISHMENT_REQUEST: u8 = 50;
+pub const SESSION_ESTABLISHMENT_RESPONSE: u8 = 51;
+pub const SESSION_MODIFICATION_REQUEST: u8 = 52;
+pub const SESSION_MODIFICATION_RESPONSE: u8 = 53;
+pub const SESSION_DELETION_REQUEST: u8 = 54;
+pub const SESSION_DELETION_RESPONSE: u8 = 55;
+
+/// A PFCP message. Node related messages carry no SEID, session related
+/// messages always do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub message_type: u8,
+    pub seid: Option<u64>,
+    /// Only the lower 24 bits are used
+    pub sequence_number: u32,
+    pub ies: Vec<Ie>,
+}
+
+impl Message {
+    pub fn new(message_type: u8, seid: Option<u64>, ies: Vec<Ie>) -> Self {
+        Message {
+            message_type,
+            seid,
+            sequence_number: 0,
+            ies,
+        }
+    }
+
+    /// Build the response to this message, copying its sequence number.
+    pub fn response(&self, message_type: u8, seid: Option<u64>, ies: Vec<Ie>) -> Message {
+        Message {
+            message_type,
+            seid,
+            sequence_number: self.sequence_number,
+            ies,
+        }
+    }
+
+    /// Find the first IE of the given type.
+    pub fn find(&self, ie_type: u16) -> Option<&Ie> {
+        ie::find(&self.ies, ie_type)
+    }
+
+    /// Encode the message including its header, TS 29.244 section 7.2.2.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = vec![];
+        if let Some(seid) = self.seid {
+            body.extend_from_slice(&seid.to_be_bytes());
+        }
+        body.extend_from_slice(&self.sequence_number.to_be_bytes()[1..]);
+        // Spare, or the message priority which we never set
+        body.push(0);
+        for ie in &self.ies {
+            ie.encode_into(&mut body);
+        }
+
+        let mut buf = vec![
+            PFCP_VERSION << 5 | self.seid.is_some() as u8,
+            self.message_type,
+        ];
+        buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&body);
+        buf
+    }
+
+    /// Decode a message, ignoring any trailing bytes after its length.
+    pub fn decode(buf: &[u8]) -> Option<Message> {
+        if buf.len() < 8 || buf[0] >> 5 != PFCP_VERSION {
+            return None;
+        }
+        let has_seid = buf[0] & 0x01 != 0;
+        let message_type = buf[1];
+        let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        let body = buf.get(4..4 + length)?;
+
+        let (seid, body) = if has_seid {
+            let seid = u64::from_be_bytes(body.get(..8)?.try_into().unwrap());
+            (Some(seid), &body[8..])
+        } else {
+            (None, body)
+        };
+        let header = body.get(..4)?;
+        let sequence_number = u32::from_be_bytes([0, header[0], header[1], header[2]]);
+
+        Some(Message {
+            message_type,
+            seid,
+            sequence_number,
+            ies: Ie::decode_all(&body[4..])?,
+        })
+    }
+}
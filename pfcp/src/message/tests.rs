@@ -0,0 +1,66 @@
+use super::*;
+use std::net::Ipv4Addr;
+
+#[test]
+fn test_node_message_roundtrip() {
+    let mut message = Message::new(
+        ASSOCIATION_SETUP_REQUEST,
+        None,
+        vec![
+            ie::node_id(Ipv4Addr::new(127, 0, 0, 1)),
+            ie::recovery_time_stamp(0xE000_0000),
+        ],
+    );
+    message.sequence_number = 0x010203;
+
+    let buf = message.encode();
+    assert_eq!(
+        &buf[..8],
+        &[
+            0x20,
+            ASSOCIATION_SETUP_REQUEST,
+            0x00,
+            0x15,
+            0x01,
+            0x02,
+            0x03,
+            0x00
+        ]
+    );
+    assert_eq!(Message::decode(&buf), Some(message));
+}
+
+#[test]
+fn test_session_message_roundtrip() {
+    let mut message = Message::new(SESSION_DELETION_REQUEST, Some(0x0A0B), vec![]);
+    message.sequence_number = 7;
+
+    let buf = message.encode();
+    assert_eq!(
+        buf,
+        vec![
+            0x21,
+            SESSION_DELETION_REQUEST,
+            0x00,
+            0x0C,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0x0A,
+            0x0B,
+            0,
+            0,
+            7,
+            0
+        ]
+    );
+    assert_eq!(Message::decode(&buf), Some(message));
+}
+
+#[test]
+fn test_decode_rejects_other_versions() {
+    assert_eq!(Message::decode(&[0x40, 1, 0, 4, 0, 0, 1, 0]), None);
+}
@@ -0,0 +1,189 @@
+//! A minimal UP function stand-in that answers PFCP requests, so that the
+//! control plane can be tested without a real UPF. It keeps track of
+//! sessions and allocates F-TEIDs, but does not forward any traffic.
+
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, UdpSocket};
+
+use crate::ie::{self, FTeid, Ie};
+use crate::message::*;
+use crate::Message;
+
+#[cfg(test)]
+mod tests;
+
+const BUFFER_LEN: usize = 2048;
+
+struct Session {
+    cp_seid: u64,
+}
+
+pub struct Responder {
+    node_id: Ipv4Addr,
+    /// Address advertised in the F-TEIDs allocated for the N3 interface
+    n3_address: Ipv4Addr,
+    recovery_time_stamp: u32,
+    associated: bool,
+    next_seid: u64,
+    next_teid: u32,
+    sessions: HashMap<u64, Session>,
+}
+
+impl Responder {
+    pub fn new(node_id: Ipv4Addr, n3_address: Ipv4Addr) -> Self {
+        Responder {
+            node_id,
+            n3_address,
+            recovery_time_stamp: 0,
+            associated: false,
+            next_seid: 1,
+            next_teid: 1,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Number of sessions currently established.
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Answer requests on the socket until an I/O error occurs.
+    pub fn run(&mut self, socket: &UdpSocket) -> io::Result<()> {
+        let mut buf = [0; BUFFER_LEN];
+        loop {
+            let (size, src) = socket.recv_from(&mut buf)?;
+            let Some(request) = Message::decode(&buf[..size]) else {
+                warn!("Dropped undecodable PFCP message from {}", src);
+                continue;
+            };
+            if let Some(response) = self.handle(&request) {
+                socket.send_to(&response.encode(), src)?;
+            }
+        }
+    }
+
+    /// Build the response to a single request. Responses and unsupported
+    /// messages are dropped.
+    pub fn handle(&mut self, request: &Message) -> Option<Message> {
+        debug!("Handling PFCP message of type {}", request.message_type);
+        match request.message_type {
+            HEARTBEAT_REQUEST => Some(request.response(
+                HEARTBEAT_RESPONSE,
+                None,
+                vec![ie::recovery_time_stamp(self.recovery_time_stamp)],
+            )),
+            ASSOCIATION_SETUP_REQUEST => {
+                info!("PFCP association set up");
+                self.associated = true;
+                Some(request.response(
+                    ASSOCIATION_SETUP_RESPONSE,
+                    None,
+                    vec![
+                        ie::node_id(self.node_id),
+                        ie::cause(ie::CAUSE_REQUEST_ACCEPTED),
+                        ie::recovery_time_stamp(self.recovery_time_stamp),
+                    ],
+                ))
+            }
+            SESSION_ESTABLISHMENT_REQUEST => Some(self.handle_session_establishment(request)),
+            SESSION_MODIFICATION_REQUEST => Some(self.handle_session_modification(request)),
+            SESSION_DELETION_REQUEST => Some(self.handle_session_deletion(request)),
+            unsupported => {
+                warn!("Unsupported PFCP message type {}", unsupported);
+                None
+            }
+        }
+    }
+
+    fn handle_session_establishment(&mut self, request: &Message) -> Message {
+        let reject = |cause| {
+            request.response(
+                SESSION_ESTABLISHMENT_RESPONSE,
+                Some(0),
+                vec![ie::node_id(self.node_id), ie::cause(cause)],
+            )
+        };
+
+        if !self.associated {
+            return reject(ie::CAUSE_NO_ESTABLISHED_PFCP_ASSOCIATION);
+        }
+        let Some((cp_seid, _)) = request.find(ie::F_SEID).and_then(ie::parse_f_seid) else {
+            return reject(ie::CAUSE_MANDATORY_IE_MISSING);
+        };
+
+        // Allocate a TEID for every PDR that asks us to choose one
+        let mut created_pdrs = vec![];
+        for create_pdr in request.ies.iter().filter(|i| i.ie_type == ie::CREATE_PDR) {
+            let Some(children) = create_pdr.children() else {
+                return reject(ie::CAUSE_REQUEST_REJECTED);
+            };
+            let Some(pdr_id) = ie::find(&children, ie::PDR_ID) else {
+                return reject(ie::CAUSE_MANDATORY_IE_MISSING);
+            };
+            let chooses_f_teid = ie::find(&children, ie::PDI)
+                .and_then(Ie::children)
+                .and_then(|pdi| ie::find(&pdi, ie::F_TEID).and_then(ie::parse_f_teid))
+                == Some(FTeid::Choose);
+            if chooses_f_teid {
+                let teid = self.next_teid;
+                self.next_teid += 1;
+                created_pdrs.push(Ie::grouped(
+                    ie::CREATED_PDR,
+                    vec![
+                        pdr_id.clone(),
+                        ie::f_teid(FTeid::Assigned {
+                            teid,
+                            address: self.n3_address,
+                        }),
+                    ],
+                ));
+            }
+        }
+
+        let up_seid = self.next_seid;
+        self.next_seid += 1;
+        self.sessions.insert(up_seid, Session { cp_seid });
+        info!("PFCP session {} established", up_seid);
+
+        let mut ies = vec![
+            ie::node_id(self.node_id),
+            ie::cause(ie::CAUSE_REQUEST_ACCEPTED),
+            ie::f_seid(up_seid, self.node_id),
+        ];
+        ies.extend(created_pdrs);
+        request.response(SESSION_ESTABLISHMENT_RESPONSE, Some(cp_seid), ies)
+    }
+
+    fn handle_session_modification(&mut self, request: &Message) -> Message {
+        let Some(session) = request.seid.and_then(|seid| self.sessions.get(&seid)) else {
+            return request.response(
+                SESSION_MODIFICATION_RESPONSE,
+                Some(0),
+                vec![ie::cause(ie::CAUSE_SESSION_CONTEXT_NOT_FOUND)],
+            );
+        };
+        request.response(
+            SESSION_MODIFICATION_RESPONSE,
+            Some(session.cp_seid),
+            vec![ie::cause(ie::CAUSE_REQUEST_ACCEPTED)],
+        )
+    }
+
+    fn handle_session_deletion(&mut self, request: &Message) -> Message {
+        let Some(session) = request.seid.and_then(|seid| self.sessions.remove(&seid)) else {
+            return request.response(
+                SESSION_DELETION_RESPONSE,
+                Some(0),
+                vec![ie::cause(ie::CAUSE_SESSION_CONTEXT_NOT_FOUND)],
+            );
+        };
+        info!("PFCP session {} deleted", request.seid.unwrap_or_default());
+        request.response(
+            SESSION_DELETION_RESPONSE,
+            Some(session.cp_seid),
+            vec![ie::cause(ie::CAUSE_REQUEST_ACCEPTED)],
+        )
+    }
+}
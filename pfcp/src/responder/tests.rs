@@ -0,0 +1,105 @@
+use super::*;
+
+fn establishment_request() -> Message {
+    Message::new(
+        SESSION_ESTABLISHMENT_REQUEST,
+        Some(0),
+        vec![
+            ie::node_id(Ipv4Addr::LOCALHOST),
+            ie::f_seid(42, Ipv4Addr::LOCALHOST),
+            Ie::grouped(
+                ie::CREATE_PDR,
+                vec![
+                    ie::pdr_id(1),
+                    Ie::grouped(
+                        ie::PDI,
+                        vec![
+                            ie::source_interface(ie::INTERFACE_ACCESS),
+                            ie::f_teid(FTeid::Choose),
+                        ],
+                    ),
+                ],
+            ),
+            Ie::grouped(
+                ie::CREATE_PDR,
+                vec![
+                    ie::pdr_id(2),
+                    Ie::grouped(ie::PDI, vec![ie::source_interface(ie::INTERFACE_CORE)]),
+                ],
+            ),
+        ],
+    )
+}
+
+#[test]
+fn test_session_establishment_requires_association() {
+    let mut responder = Responder::new(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST);
+    let response = responder.handle(&establishment_request()).unwrap();
+    assert_eq!(
+        response.find(ie::CAUSE).and_then(ie::parse_cause),
+        Some(ie::CAUSE_NO_ESTABLISHED_PFCP_ASSOCIATION)
+    );
+    assert_eq!(responder.session_count(), 0);
+}
+
+#[test]
+fn test_session_lifecycle() {
+    let n3_address = Ipv4Addr::new(10, 0, 0, 1);
+    let mut responder = Responder::new(Ipv4Addr::LOCALHOST, n3_address);
+    responder.handle(&Message::new(
+        ASSOCIATION_SETUP_REQUEST,
+        None,
+        vec![ie::node_id(Ipv4Addr::LOCALHOST)],
+    ));
+
+    let response = responder.handle(&establishment_request()).unwrap();
+    assert_eq!(response.message_type, SESSION_ESTABLISHMENT_RESPONSE);
+    assert_eq!(response.seid, Some(42));
+    assert_eq!(
+        response.find(ie::CAUSE).and_then(ie::parse_cause),
+        Some(ie::CAUSE_REQUEST_ACCEPTED)
+    );
+
+    // Only the uplink PDR asked for an F-TEID
+    let created_pdrs: Vec<_> = response
+        .ies
+        .iter()
+        .filter(|i| i.ie_type == ie::CREATED_PDR)
+        .collect();
+    assert_eq!(created_pdrs.len(), 1);
+    let children = created_pdrs[0].children().unwrap();
+    assert_eq!(ie::find(&children, ie::PDR_ID), Some(&ie::pdr_id(1)));
+    assert_eq!(
+        ie::find(&children, ie::F_TEID).and_then(ie::parse_f_teid),
+        Some(FTeid::Assigned {
+            teid: 1,
+            address: n3_address
+        })
+    );
+
+    let (up_seid, _) = response
+        .find(ie::F_SEID)
+        .and_then(ie::parse_f_seid)
+        .unwrap();
+    let response = responder
+        .handle(&Message::new(
+            SESSION_DELETION_REQUEST,
+            Some(up_seid),
+            vec![],
+        ))
+        .unwrap();
+    assert_eq!(response.seid, Some(42));
+    assert_eq!(responder.session_count(), 0);
+}
+
+#[test]
+fn test_unknown_session_modification() {
+    let mut responder = Responder::new(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST);
+    let response = responder
+        .handle(&Message::new(SESSION_MODIFICATION_REQUEST, Some(7), vec![]))
+        .unwrap();
+    assert_eq!(
+        response.find(ie::CAUSE).and_then(ie::parse_cause),
+        Some(ie::CAUSE_SESSION_CONTEXT_NOT_FOUND)
+    );
+}